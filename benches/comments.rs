@@ -0,0 +1,73 @@
+//! Benchmarks for sorting large comment threads.
+//!
+//! Run with `cargo bench --bench comments`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use temi::comments::{Comment, CommentResponse, CommentSort, CommentTree};
+
+/// Number of comments in the generated thread.
+const NUM_COMMENTS: u64 = 3000;
+
+/// Generates a thread of comments, in threads of up to five levels, in reverse download order.
+///
+/// Path IDs are cached when `finalize` is set, as for deserialized comments.
+fn thread(finalize: bool) -> Vec<CommentResponse> {
+    let mut comments = Vec::with_capacity(NUM_COMMENTS as usize);
+    let mut path = String::new();
+
+    for id in 1..=NUM_COMMENTS {
+        path = if id % 5 == 1 {
+            format!("0.{id}")
+        } else {
+            format!("{path}.{id}")
+        };
+
+        let mut c = CommentResponse {
+            comment: Comment {
+                id,
+                path: path.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        if finalize {
+            c.finalize();
+        }
+
+        comments.push(c);
+    }
+
+    comments.reverse();
+    comments
+}
+
+fn bench_sort(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort_3k_comments");
+
+    for (name, finalize) in [("parse_paths", false), ("cached_paths", true)] {
+        let comments = thread(finalize);
+
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || comments.clone(),
+                |mut comments| comments.sort(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    let comments = thread(true);
+    group.bench_function("tree", |b| {
+        b.iter_batched(
+            || comments.clone(),
+            |comments| CommentTree::new(comments).into_vec(CommentSort::Old),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sort);
+criterion_main!(benches);
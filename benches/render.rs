@@ -0,0 +1,91 @@
+//! Benchmarks for drawing the Post screen with a large comment thread.
+//!
+//! Run with `cargo bench --bench render`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use tui::{backend::TestBackend, Terminal};
+
+use temi::{
+    app::App,
+    comments::{CommentResponseTable, CommentResponses},
+    config::Config,
+    posts::{PostResponseTable, PostResponses},
+    screen::render_post_screen,
+};
+
+/// Number of comments in the generated thread.
+const NUM_COMMENTS: u64 = 2000;
+
+/// Number of frames drawn in each iteration, as while scrolling.
+const FRAMES: usize = 100;
+
+/// Creates an [App] on the first fixture post, with a thread of [NUM_COMMENTS] copies of the
+/// fixture comment thread.
+fn fixture_app() -> App {
+    let posts: PostResponses = serde_json::from_str(include_str!(
+        "../tests/fixtures/lemmy-0.19.3/post_list.json"
+    ))
+    .unwrap();
+    let thread: CommentResponses = serde_json::from_str(include_str!(
+        "../tests/fixtures/lemmy-0.19.3/comment_thread.json"
+    ))
+    .unwrap();
+
+    let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+    app.set_posts(posts);
+    app.posts.next();
+
+    // each copy of the thread has new IDs, replacing the fixture ones in the paths
+    let mut comments = Vec::with_capacity(NUM_COMMENTS as usize);
+    for copy in 0..NUM_COMMENTS / thread.comments.len() as u64 {
+        for c in &thread.comments {
+            let mut c = c.clone();
+            let path: Vec<String> = c
+                .comment
+                .path
+                .split('.')
+                .map(|id| match id.parse::<u64>() {
+                    Ok(0) | Err(_) => id.to_owned(),
+                    Ok(id) => (id + copy * 1000).to_string(),
+                })
+                .collect();
+            c.comment.id += copy * 1000;
+            c.comment.path = path.join(".");
+            comments.push(c);
+        }
+    }
+
+    let post_id = app.posts.current().unwrap().post.id();
+    app.comments_insert(post_id, CommentResponseTable::new(comments));
+
+    app
+}
+
+fn bench_draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw_2k_comments");
+    group.sample_size(10);
+
+    // the cached lines are reused while scrolling, or rendered again for every frame
+    for (name, cached) in [("cached_lines", true), ("rendered_lines", false)] {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let mut terminal = Terminal::new(TestBackend::new(120, 50)).unwrap();
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for frame in 0..FRAMES {
+                    if !cached {
+                        app.comments_get_mut(post_id).unwrap().lines = None;
+                    }
+                    app.comment_scroll.set_position(frame * 10);
+                    terminal.draw(|f| render_post_screen(f, &mut app)).unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_draw);
+criterion_main!(benches);
@@ -0,0 +1,325 @@
+//! Abstraction over the API backend used by the [App](crate::app::App).
+//!
+//! [Client] implements [ApiClient] for Lemmy instances. Tests use an in-memory client serving
+//! canned responses, so the application logic can be tested without a network.
+
+use futures::future::BoxFuture;
+
+use crate::{
+    client::Client,
+    comments::{CommentResponse, CommentResponses},
+    community::{BlockCommunityResponse, CommunityResponse, ListCommunitiesResponse},
+    diagnostics::RequestLog,
+    login::Credentials,
+    modlog::ModlogResponse,
+    posts::{
+        BlockPersonResponse, GetPostResponse, ListingType, PostResponse, PostResponses, PostsQuery,
+    },
+    report::{CommentReportResponse, PostReportResponse},
+    site::GetSiteResponse,
+    task::Progress,
+    Result,
+};
+
+#[cfg(test)]
+pub(crate) mod fake;
+
+/// API backend for fetching posts and comments, and performing user actions.
+///
+/// Methods return boxed futures, so the backend can be used as a `Box<dyn ApiClient>`.
+pub trait ApiClient: Send + Sync {
+    /// Gets a page of [PostResponses].
+    fn list_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>>;
+
+    /// Gets the page of [PostResponses] at a cursor from [PostResponses::next_page].
+    fn list_posts_cursor<'a>(&'a self, cursor: &'a str) -> BoxFuture<'a, Result<PostResponses>>;
+
+    /// Gets a page of [CommentResponses] for a [Post](crate::posts::Post), adding the bytes
+    /// received to `progress`, if any.
+    fn list_comments<'a>(
+        &'a self,
+        post_id: u64,
+        page: u64,
+        limit: u64,
+        progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<CommentResponses>>;
+
+    /// Gets the replies to a comment, up to `max_depth` levels below it, as [CommentResponses].
+    fn list_replies(
+        &self,
+        parent_id: u64,
+        max_depth: u64,
+    ) -> BoxFuture<'_, Result<CommentResponses>>;
+
+    /// Gets a single [PostResponse].
+    fn get_post(&self, post_id: u64) -> BoxFuture<'_, Result<PostResponse>>;
+
+    /// Gets a single [PostResponse], with its crossposts, as a [GetPostResponse].
+    fn get_post_view(&self, post_id: u64) -> BoxFuture<'_, Result<GetPostResponse>>;
+
+    /// Gets a single [CommentResponse].
+    fn get_comment(&self, comment_id: u64) -> BoxFuture<'_, Result<CommentResponse>>;
+
+    /// Gets a page of the [PostResponses] saved by the logged-in user.
+    fn list_saved_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>>;
+
+    /// Gets a page of the [CommentResponses] saved by the logged-in user.
+    fn list_saved_comments(&self, page: u64) -> BoxFuture<'_, Result<CommentResponses>>;
+
+    /// Gets a page of the [ModlogResponse] of a community.
+    fn get_modlog(&self, community_id: u64, page: u64) -> BoxFuture<'_, Result<ModlogResponse>>;
+
+    /// Gets a page of the communities most active over the last month.
+    fn list_communities(
+        &self,
+        listing_type: ListingType,
+        page: u64,
+    ) -> BoxFuture<'_, Result<ListCommunitiesResponse>>;
+
+    /// Gets the bytes of an image, adding the bytes received to `progress`, if any.
+    fn get_image<'a>(
+        &'a self,
+        url: &'a str,
+        progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<Vec<u8>>>;
+
+    /// Gets the site information, e.g. the backend version.
+    fn get_site(&self) -> BoxFuture<'_, Result<GetSiteResponse>>;
+
+    /// Follow, or unfollow, a [Community](crate::community::Community).
+    fn follow_community(
+        &self,
+        community_id: u64,
+        follow: bool,
+    ) -> BoxFuture<'_, Result<CommunityResponse>>;
+
+    /// Block, or unblock, a [Community](crate::community::Community).
+    fn block_community(
+        &self,
+        community_id: u64,
+        block: bool,
+    ) -> BoxFuture<'_, Result<BlockCommunityResponse>>;
+
+    /// Block, or unblock, a person.
+    fn block_person(
+        &self,
+        person_id: u64,
+        block: bool,
+    ) -> BoxFuture<'_, Result<BlockPersonResponse>>;
+
+    /// Save, or unsave, a [Post](crate::posts::Post).
+    fn save_post(&self, post_id: u64, save: bool) -> BoxFuture<'_, Result<PostResponse>>;
+
+    /// Save, or unsave, a [Comment](crate::comments::Comment).
+    fn save_comment(&self, comment_id: u64, save: bool) -> BoxFuture<'_, Result<CommentResponse>>;
+
+    /// Report a [Post](crate::posts::Post) to the moderators.
+    fn report_post<'a>(
+        &'a self,
+        post_id: u64,
+        reason: &'a str,
+    ) -> BoxFuture<'a, Result<PostReportResponse>>;
+
+    /// Report a [Comment](crate::comments::Comment) to the moderators.
+    fn report_comment<'a>(
+        &'a self,
+        comment_id: u64,
+        reason: &'a str,
+    ) -> BoxFuture<'a, Result<CommentReportResponse>>;
+
+    /// Gets the base URL of the backend instance.
+    fn instance_url(&self) -> &str;
+
+    /// Gets the JWT for the logged in user, if any.
+    fn jwt(&self) -> Option<String>;
+
+    /// Sets the JWT for the logged in user.
+    fn set_jwt(&mut self, jwt: Option<String>);
+
+    /// Sets the [Credentials] to log in again with once the JWT expires.
+    fn set_credentials(&mut self, credentials: Option<Credentials>);
+
+    /// Sets the [PostsQuery] for [list_posts](Self::list_posts).
+    fn set_posts_query(&mut self, posts_query: PostsQuery);
+
+    /// Gets the maximum number of concurrent requests for multi-page downloads.
+    fn concurrency(&self) -> usize;
+
+    /// Gets the request status, e.g. while retrying, if any.
+    fn status(&self) -> Option<String>;
+
+    /// Gets whether certificate verification is disabled.
+    fn accepts_invalid_certs(&self) -> bool;
+
+    /// Sets the [RequestLog] to record finished requests to, for diagnostics.
+    fn set_request_log(&mut self, requests: RequestLog);
+
+    /// Clones the [ApiClient] into a new [Box], e.g. to move into a background task.
+    fn clone_box(&self) -> Box<dyn ApiClient>;
+}
+
+impl Clone for Box<dyn ApiClient> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl ApiClient for Client {
+    fn list_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>> {
+        Box::pin(Client::get_posts(self, page))
+    }
+
+    fn list_posts_cursor<'a>(&'a self, cursor: &'a str) -> BoxFuture<'a, Result<PostResponses>> {
+        Box::pin(Client::get_posts_cursor(self, cursor))
+    }
+
+    fn list_comments<'a>(
+        &'a self,
+        post_id: u64,
+        page: u64,
+        limit: u64,
+        progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<CommentResponses>> {
+        Box::pin(Client::get_comments(self, post_id, page, limit, progress))
+    }
+
+    fn list_replies(
+        &self,
+        parent_id: u64,
+        max_depth: u64,
+    ) -> BoxFuture<'_, Result<CommentResponses>> {
+        Box::pin(Client::get_replies(self, parent_id, max_depth))
+    }
+
+    fn get_post(&self, post_id: u64) -> BoxFuture<'_, Result<PostResponse>> {
+        Box::pin(Client::get_post(self, post_id))
+    }
+
+    fn get_post_view(&self, post_id: u64) -> BoxFuture<'_, Result<GetPostResponse>> {
+        Box::pin(Client::get_post_view(self, post_id))
+    }
+
+    fn get_comment(&self, comment_id: u64) -> BoxFuture<'_, Result<CommentResponse>> {
+        Box::pin(Client::get_comment(self, comment_id))
+    }
+
+    fn list_saved_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>> {
+        Box::pin(Client::get_saved_posts(self, page))
+    }
+
+    fn list_saved_comments(&self, page: u64) -> BoxFuture<'_, Result<CommentResponses>> {
+        Box::pin(Client::get_saved_comments(self, page))
+    }
+
+    fn get_modlog(&self, community_id: u64, page: u64) -> BoxFuture<'_, Result<ModlogResponse>> {
+        Box::pin(Client::get_modlog(self, community_id, page))
+    }
+
+    fn list_communities(
+        &self,
+        listing_type: ListingType,
+        page: u64,
+    ) -> BoxFuture<'_, Result<ListCommunitiesResponse>> {
+        Box::pin(Client::list_communities(self, listing_type, page))
+    }
+
+    fn get_image<'a>(
+        &'a self,
+        url: &'a str,
+        progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Box::pin(Client::get_image(self, url, progress))
+    }
+
+    fn get_site(&self) -> BoxFuture<'_, Result<GetSiteResponse>> {
+        Box::pin(Client::get_site(self))
+    }
+
+    fn follow_community(
+        &self,
+        community_id: u64,
+        follow: bool,
+    ) -> BoxFuture<'_, Result<CommunityResponse>> {
+        Box::pin(Client::follow_community(self, community_id, follow))
+    }
+
+    fn block_community(
+        &self,
+        community_id: u64,
+        block: bool,
+    ) -> BoxFuture<'_, Result<BlockCommunityResponse>> {
+        Box::pin(Client::block_community(self, community_id, block))
+    }
+
+    fn block_person(
+        &self,
+        person_id: u64,
+        block: bool,
+    ) -> BoxFuture<'_, Result<BlockPersonResponse>> {
+        Box::pin(Client::block_person(self, person_id, block))
+    }
+
+    fn save_post(&self, post_id: u64, save: bool) -> BoxFuture<'_, Result<PostResponse>> {
+        Box::pin(Client::save_post(self, post_id, save))
+    }
+
+    fn save_comment(&self, comment_id: u64, save: bool) -> BoxFuture<'_, Result<CommentResponse>> {
+        Box::pin(Client::save_comment(self, comment_id, save))
+    }
+
+    fn report_post<'a>(
+        &'a self,
+        post_id: u64,
+        reason: &'a str,
+    ) -> BoxFuture<'a, Result<PostReportResponse>> {
+        Box::pin(Client::report_post(self, post_id, reason))
+    }
+
+    fn report_comment<'a>(
+        &'a self,
+        comment_id: u64,
+        reason: &'a str,
+    ) -> BoxFuture<'a, Result<CommentReportResponse>> {
+        Box::pin(Client::report_comment(self, comment_id, reason))
+    }
+
+    fn instance_url(&self) -> &str {
+        Client::instance_url(self)
+    }
+
+    fn jwt(&self) -> Option<String> {
+        Client::jwt(self)
+    }
+
+    fn set_jwt(&mut self, jwt: Option<String>) {
+        Client::set_jwt(self, jwt)
+    }
+
+    fn set_credentials(&mut self, credentials: Option<Credentials>) {
+        Client::set_credentials(self, credentials)
+    }
+
+    fn set_posts_query(&mut self, posts_query: PostsQuery) {
+        Client::set_posts_query(self, posts_query)
+    }
+
+    fn concurrency(&self) -> usize {
+        Client::concurrency(self)
+    }
+
+    fn status(&self) -> Option<String> {
+        Client::status(self)
+    }
+
+    fn set_request_log(&mut self, requests: RequestLog) {
+        Client::set_request_log(self, Some(requests))
+    }
+
+    fn accepts_invalid_certs(&self) -> bool {
+        Client::accepts_invalid_certs(self)
+    }
+
+    fn clone_box(&self) -> Box<dyn ApiClient> {
+        Box::new(self.clone())
+    }
+}
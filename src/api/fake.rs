@@ -0,0 +1,324 @@
+//! In-memory [ApiClient] serving canned responses for tests.
+
+use futures::future::{self, BoxFuture};
+
+use super::ApiClient;
+use crate::{
+    comments::{CommentResponse, CommentResponses},
+    community::{
+        BlockCommunityResponse, CommunityResponse, CommunityView, ListCommunitiesResponse,
+    },
+    diagnostics::RequestLog,
+    login::Credentials,
+    modlog::ModlogResponse,
+    posts::{
+        BlockPersonResponse, GetPostResponse, ListingType, PostResponse, PostResponses, PostsQuery,
+    },
+    report::{CommentReportResponse, PostReportResponse},
+    site::GetSiteResponse,
+    task::Progress,
+    Error, Result,
+};
+
+/// [ApiClient] serving pages of canned [PostResponse]s, and their [CommentResponse]s.
+///
+/// The saved lists serve the canned responses marked as saved, communities are those of the
+/// canned posts, and the modlog is empty. User actions are rejected with an [Error::Api].
+#[derive(Clone, Debug, Default)]
+pub struct FakeClient {
+    posts: Vec<PostResponse>,
+    comments: Vec<CommentResponse>,
+    page_limit: usize,
+    jwt: Option<String>,
+}
+
+impl FakeClient {
+    /// Creates a new [FakeClient] serving `page_limit` posts per page.
+    pub fn new(
+        posts: Vec<PostResponse>,
+        comments: Vec<CommentResponse>,
+        page_limit: usize,
+    ) -> Self {
+        Self {
+            posts,
+            comments,
+            page_limit: page_limit.max(1),
+            jwt: None,
+        }
+    }
+
+    /// Gets the number of responses before the page.
+    fn page_offset(&self, page: u64) -> usize {
+        (page.saturating_sub(1) as usize).saturating_mul(self.page_limit)
+    }
+
+    fn unsupported<'a, T: Send + 'a>(action: &str) -> BoxFuture<'a, Result<T>> {
+        Box::pin(future::ready(Err(Error::Api(format!(
+            "{action} unsupported by FakeClient"
+        )))))
+    }
+}
+
+impl ApiClient for FakeClient {
+    fn list_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>> {
+        let posts = self
+            .posts
+            .chunks(self.page_limit)
+            .nth(page.saturating_sub(1) as usize)
+            .map(<[PostResponse]>::to_vec)
+            .unwrap_or_default();
+
+        Box::pin(future::ready(Ok(PostResponses {
+            posts,
+            next_page: None,
+        })))
+    }
+
+    fn list_posts_cursor<'a>(&'a self, _cursor: &'a str) -> BoxFuture<'a, Result<PostResponses>> {
+        Self::unsupported("cursor paging")
+    }
+
+    fn list_comments<'a>(
+        &'a self,
+        post_id: u64,
+        page: u64,
+        limit: u64,
+        _progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<CommentResponses>> {
+        let comments = self
+            .comments
+            .iter()
+            .filter(|c| c.post.id() == post_id)
+            .skip(page.saturating_sub(1).saturating_mul(limit) as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+
+        Box::pin(future::ready(Ok(CommentResponses::new(comments))))
+    }
+
+    fn get_post(&self, post_id: u64) -> BoxFuture<'_, Result<PostResponse>> {
+        let post = self
+            .posts
+            .iter()
+            .find(|p| p.post.id() == post_id)
+            .cloned()
+            .ok_or(Error::Api("couldnt_find_post".into()));
+
+        Box::pin(future::ready(post))
+    }
+
+    fn get_post_view(&self, post_id: u64) -> BoxFuture<'_, Result<GetPostResponse>> {
+        // crossposts are the other canned posts of the same URL
+        let res = self
+            .posts
+            .iter()
+            .find(|p| p.post.id() == post_id)
+            .map(|post_view| GetPostResponse {
+                post_view: post_view.clone(),
+                cross_posts: self
+                    .posts
+                    .iter()
+                    .filter(|p| {
+                        p.post.id() != post_id
+                            && !p.post.url().is_empty()
+                            && p.post.url() == post_view.post.url()
+                    })
+                    .cloned()
+                    .collect(),
+            })
+            .ok_or(Error::Api("couldnt_find_post".into()));
+
+        Box::pin(future::ready(res))
+    }
+
+    fn list_replies(
+        &self,
+        parent_id: u64,
+        max_depth: u64,
+    ) -> BoxFuture<'_, Result<CommentResponses>> {
+        let comments = self
+            .comments
+            .iter()
+            .filter(|c| {
+                let ids = c.path_ids();
+                ids.iter()
+                    .position(|&id| id == parent_id)
+                    .is_some_and(|i| (1..=max_depth as usize).contains(&(ids.len() - 1 - i)))
+            })
+            .cloned()
+            .collect();
+
+        Box::pin(future::ready(Ok(CommentResponses::new(comments))))
+    }
+
+    fn get_comment(&self, comment_id: u64) -> BoxFuture<'_, Result<CommentResponse>> {
+        let comment = self
+            .comments
+            .iter()
+            .find(|c| c.comment.id() == comment_id)
+            .cloned()
+            .ok_or(Error::Api("couldnt_find_comment".into()));
+
+        Box::pin(future::ready(comment))
+    }
+
+    fn list_saved_posts(&self, page: u64) -> BoxFuture<'_, Result<PostResponses>> {
+        let posts = self
+            .posts
+            .iter()
+            .filter(|p| p.saved)
+            .skip(self.page_offset(page))
+            .take(self.page_limit)
+            .cloned()
+            .collect();
+
+        Box::pin(future::ready(Ok(PostResponses {
+            posts,
+            next_page: None,
+        })))
+    }
+
+    fn list_saved_comments(&self, page: u64) -> BoxFuture<'_, Result<CommentResponses>> {
+        let comments = self
+            .comments
+            .iter()
+            .filter(|c| c.saved)
+            .skip(self.page_offset(page))
+            .take(self.page_limit)
+            .cloned()
+            .collect();
+
+        Box::pin(future::ready(Ok(CommentResponses::new(comments))))
+    }
+
+    fn get_modlog(&self, _: u64, _: u64) -> BoxFuture<'_, Result<ModlogResponse>> {
+        Box::pin(future::ready(Ok(ModlogResponse::default())))
+    }
+
+    fn list_communities(
+        &self,
+        listing_type: ListingType,
+        page: u64,
+    ) -> BoxFuture<'_, Result<ListCommunitiesResponse>> {
+        let mut communities: Vec<CommunityView> = Vec::new();
+        for p in &self.posts {
+            let listed = listing_type != ListingType::Local || p.community.local;
+            if listed && communities.iter().all(|c| c.community.id != p.community.id) {
+                communities.push(CommunityView {
+                    community: p.community.clone(),
+                    subscribed: p.subscribed,
+                    blocked: false,
+                    counts: Default::default(),
+                });
+            }
+        }
+        let communities = communities
+            .into_iter()
+            .skip(self.page_offset(page))
+            .take(self.page_limit)
+            .collect();
+
+        Box::pin(future::ready(Ok(ListCommunitiesResponse { communities })))
+    }
+
+    fn get_image<'a>(
+        &'a self,
+        _url: &'a str,
+        _progress: Option<&'a Progress>,
+    ) -> BoxFuture<'a, Result<Vec<u8>>> {
+        Self::unsupported("images")
+    }
+
+    fn get_site(&self) -> BoxFuture<'_, Result<GetSiteResponse>> {
+        Box::pin(future::ready(Ok(GetSiteResponse {
+            version: "0.18.5".into(),
+        })))
+    }
+
+    fn follow_community(
+        &self,
+        _community_id: u64,
+        _follow: bool,
+    ) -> BoxFuture<'_, Result<CommunityResponse>> {
+        Self::unsupported("follow community")
+    }
+
+    fn block_community(
+        &self,
+        _community_id: u64,
+        _block: bool,
+    ) -> BoxFuture<'_, Result<BlockCommunityResponse>> {
+        Self::unsupported("block community")
+    }
+
+    fn block_person(
+        &self,
+        _person_id: u64,
+        _block: bool,
+    ) -> BoxFuture<'_, Result<BlockPersonResponse>> {
+        Self::unsupported("block person")
+    }
+
+    fn save_post(&self, _post_id: u64, _save: bool) -> BoxFuture<'_, Result<PostResponse>> {
+        Self::unsupported("save post")
+    }
+
+    fn save_comment(
+        &self,
+        _comment_id: u64,
+        _save: bool,
+    ) -> BoxFuture<'_, Result<CommentResponse>> {
+        Self::unsupported("save comment")
+    }
+
+    fn report_post<'a>(
+        &'a self,
+        _post_id: u64,
+        _reason: &'a str,
+    ) -> BoxFuture<'a, Result<PostReportResponse>> {
+        Self::unsupported("report post")
+    }
+
+    fn report_comment<'a>(
+        &'a self,
+        _comment_id: u64,
+        _reason: &'a str,
+    ) -> BoxFuture<'a, Result<CommentReportResponse>> {
+        Self::unsupported("report comment")
+    }
+
+    fn instance_url(&self) -> &str {
+        "fake://"
+    }
+
+    fn jwt(&self) -> Option<String> {
+        self.jwt.clone()
+    }
+
+    fn set_jwt(&mut self, jwt: Option<String>) {
+        self.jwt = jwt;
+    }
+
+    fn set_credentials(&mut self, _credentials: Option<Credentials>) {}
+
+    fn set_posts_query(&mut self, _posts_query: PostsQuery) {}
+
+    fn concurrency(&self) -> usize {
+        1
+    }
+
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    fn accepts_invalid_certs(&self) -> bool {
+        false
+    }
+
+    fn set_request_log(&mut self, _requests: RequestLog) {}
+
+    fn clone_box(&self) -> Box<dyn ApiClient> {
+        Box::new(self.clone())
+    }
+}
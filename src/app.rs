@@ -1,31 +1,53 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{HashSet, VecDeque};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use hashbrown::HashMap;
 
 use tui::{backend::CrosstermBackend, prelude::*, widgets::*, Terminal};
 
-use crate::{comments::CommentResponseTable, posts::PostResponseTable};
+use crate::{
+    api::ApiClient,
+    client::InstanceUrl,
+    comments::{CommentCache, CommentResponseTable, CommentResponses, CommentSort},
+    community::{Community, Discover},
+    config::{Config, NsfwMode},
+    diagnostics::Diagnostics,
+    event::EventSender,
+    keymap::KeyMap,
+    modlog::Modlog,
+    posts::{
+        DiskCache, ImageCache, PostFilter, PostResponse, PostResponseTable, PostResponses,
+        PostsQuery,
+    },
+    saved::Saved,
+    screen::{Graphics, ImageView, PendingKeys, Screen, TextInput, DEFAULT_CELL_SIZE},
+    secrets::{Account, Secret},
+    session::Visits,
+    task::{CommentsTask, CrossPostsTask, ImageTask, PostsTask, RepliesTask},
+    theme::Theme,
+    utils::actor_host,
+    Error, Source,
+};
 
-static REFRESH: AtomicBool = AtomicBool::new(false);
-
-pub fn refresh() -> bool {
-    REFRESH.load(Ordering::Relaxed)
-}
-
-pub fn set_refresh(val: bool) {
-    REFRESH.store(val, Ordering::SeqCst);
-}
+/// HUD warning shown while certificate verification is disabled for the instance.
+pub const INSECURE_WARNING: &str = "WARNING: TLS CERTIFICATES NOT VERIFIED";
 
 /// Convenience alias for the [Terminal](tui::Terminal) type used in `temi`.
 pub type TemiTerminal = Terminal<CrosstermBackend<std::io::Stdout>>;
 
 /// State for a scrollbar.
+///
+/// Positions and lengths are in rows, as [usize], so content taller than [u16::MAX] rows, e.g. a
+/// long comment thread, scrolls to the end. Panes draw only the lines in view, so the
+/// [Paragraph](tui::widgets::Paragraph) scroll, see [Scroll::offset], stays small.
 #[derive(Clone, Default)]
 pub struct Scroll {
     pub state: ScrollbarState,
-    pub position: u16,
-    pub content_length: u16,
-    pub viewport_length: u16,
+    pub position: usize,
+    pub content_length: usize,
+    pub viewport_length: usize,
 }
 
 impl Scroll {
@@ -40,12 +62,12 @@ impl Scroll {
     }
 
     /// Creates a new [Scroll] with the provided parameters.
-    pub fn create(position: u16, content_length: u16, viewport_length: u16) -> Self {
+    pub fn create(position: usize, content_length: usize, viewport_length: usize) -> Self {
         Self {
             state: ScrollbarState::default()
-                .position(position as usize)
-                .content_length(content_length as usize)
-                .viewport_content_length(viewport_length as usize),
+                .position(position)
+                .content_length(content_length)
+                .viewport_content_length(viewport_length),
             position,
             content_length,
             viewport_length,
@@ -53,37 +75,47 @@ impl Scroll {
     }
 
     /// Gets the [Scroll] position.
-    pub fn position(&self) -> u16 {
+    pub fn position(&self) -> usize {
         self.position
     }
 
     /// Sets the [Scroll] position.
-    pub fn set_position(&mut self, pos: u16) {
+    pub fn set_position(&mut self, pos: usize) {
         self.position = pos;
-        self.state = self.state.position(pos as usize);
+        self.state = self.state.position(pos);
     }
 
     /// Gets the [Scroll] content length.
-    pub fn content_length(&self) -> u16 {
+    pub fn content_length(&self) -> usize {
         self.content_length
     }
 
-    /// Sets the [Scroll] content length.
-    pub fn set_content_length(&mut self, len: u16) {
+    /// Sets the [Scroll] content length, clamping the position into the new content.
+    pub fn set_content_length(&mut self, len: usize) {
         self.content_length = len;
-        self.state = self.state.content_length(len as usize);
+        self.state = self.state.content_length(len);
+
+        if self.position >= len {
+            self.set_position(len.saturating_sub(1));
+        }
     }
 
     /// Gets the [Scroll] viewport length.
-    pub fn viewport_length(&self) -> u16 {
+    pub fn viewport_length(&self) -> usize {
         self.viewport_length
     }
 
     /// Sets the [Scroll] viewport length.
-    pub fn set_viewport_length(&mut self, len: u16) {
+    pub fn set_viewport_length(&mut self, len: usize) {
         self.viewport_length = len;
 
-        self.state = self.state.viewport_content_length(len as usize);
+        self.state = self.state.viewport_content_length(len);
+    }
+
+    /// Gets the position as a [Paragraph](tui::widgets::Paragraph) scroll offset, saturating at
+    /// [u16::MAX] rows.
+    pub fn offset(&self) -> u16 {
+        u16::try_from(self.position).unwrap_or(u16::MAX)
     }
 
     /// Gets the [Margin] from the [Scroll] position.
@@ -117,64 +149,2543 @@ impl Scroll {
         self.state.first();
     }
 
-    /// Moves to the last scrollbar position.
+    /// Moves to the last scrollbar position, where the end of the content fills the viewport.
     pub fn last(&mut self) {
-        self.position = self.content_length.saturating_sub(1);
-        self.state.last();
+        self.set_position(self.last_position());
+    }
+
+    /// Moves down by a viewport length, stopping at the last position.
+    pub fn page_down(&mut self) {
+        let pos = self
+            .position
+            .saturating_add(self.page_length())
+            .min(self.last_position())
+            .max(self.position);
+
+        self.set_position(pos);
+    }
+
+    /// Moves up by a viewport length, stopping at the first position.
+    pub fn page_up(&mut self) {
+        self.set_position(self.position.saturating_sub(self.page_length()));
+    }
+
+    /// Scrolls the least distance to show the `rows`, showing the first row of `rows` taller than
+    /// the viewport.
+    pub fn show(&mut self, rows: Range<usize>) {
+        let page = self.page_length();
+
+        if rows.start < self.position || rows.end - rows.start > page {
+            self.set_position(rows.start);
+        } else if rows.end > self.position.saturating_add(page) {
+            self.set_position(rows.end - page);
+        }
+    }
+
+    /// Gets the last position, before the viewport length is known this is the last line.
+    fn last_position(&self) -> usize {
+        self.content_length.saturating_sub(self.page_length())
+    }
+
+    /// Gets the number of lines moved by a page, at least one.
+    fn page_length(&self) -> usize {
+        self.viewport_length.max(1)
+    }
+}
+
+/// Context saved when leaving a [Screen], restored when navigating back to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NavEntry {
+    pub screen: Screen,
+    /// Selected index in the post list.
+    pub selected: Option<usize>,
+    /// Page of posts.
+    pub page: u64,
+    pub post_scroll: usize,
+    pub comment_scroll: usize,
+    pub error_log_scroll: usize,
+}
+
+/// Maximum number of entries kept in the error log.
+pub const MAX_ERROR_LOG: usize = 100;
+
+/// Entry in the in-memory error log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorEntry {
+    time: SystemTime,
+    kind: &'static str,
+    message: String,
+}
+
+impl ErrorEntry {
+    /// Creates a new [ErrorEntry] for the [Error], timestamped with the current time.
+    pub fn new(err: &Error) -> Self {
+        Self {
+            time: SystemTime::now(),
+            kind: err.kind(),
+            message: format!("{err}"),
+        }
+    }
+
+    /// Gets the time the error occurred.
+    pub const fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// Gets the name of the [Error] variant.
+    pub const fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Gets the error message.
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+/// How long a [StatusMessage] is displayed by default.
+pub const STATUS_TIMEOUT: Duration = Duration::from_secs(4);
+/// How long error text is displayed in the status bar.
+pub const ERROR_STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+/// Minimum time between refreshes of the same [RefreshTarget].
+pub const REFRESH_DEBOUNCE: Duration = Duration::from_secs(5);
+/// How long to wait before prefetching a page again after it failed.
+pub const PREFETCH_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Consecutive failed refreshes after which [Live] mode turns off.
+pub const MAX_LIVE_FAILURES: u32 = 3;
+
+/// Transient message in the status bar, displayed until it expires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusMessage {
+    text: String,
+    expires: SystemTime,
+}
+
+impl StatusMessage {
+    /// Creates a new [StatusMessage], expiring `timeout` after `now`.
+    pub fn new<S: Into<String>>(text: S, now: SystemTime, timeout: Duration) -> Self {
+        Self {
+            text: text.into(),
+            expires: now + timeout,
+        }
+    }
+
+    /// Gets the message text.
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Gets the time the message expires.
+    pub const fn expires(&self) -> SystemTime {
+        self.expires
+    }
+
+    /// Gets whether the message has expired at `now`.
+    pub fn expired(&self, now: SystemTime) -> bool {
+        now >= self.expires
+    }
+}
+
+/// Represents a request from a [Screen](crate::screen::Screen) for the main loop to perform
+/// an API call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Follow, or unfollow, a [Community](crate::community::Community).
+    FollowCommunity { community_id: u64, follow: bool },
+    /// Block, or unblock, a [Community](crate::community::Community).
+    BlockCommunity { community_id: u64, block: bool },
+    /// Block, or unblock, a person, e.g. a [Creator](crate::posts::Creator).
+    BlockPerson { person_id: u64, block: bool },
+    /// Report a [Post](crate::posts::Post) to moderators.
+    ReportPost { post_id: u64, reason: String },
+    /// Report a [Comment](crate::comments::Comment) to moderators.
+    ReportComment { comment_id: u64, reason: String },
+    /// Save, or unsave, a [Post](crate::posts::Post).
+    SavePost { post_id: u64, save: bool },
+    /// Save, or unsave, a [Comment](crate::comments::Comment).
+    SaveComment { comment_id: u64, save: bool },
+    /// Export a [Post](crate::posts::Post), and its comments, to a markdown file.
+    ExportPost { post_id: u64, path: String },
+    /// Forget the JWT, and delete the stored login for the instance.
+    Logout,
+    /// Quit, once confirmed, see [App::quit].
+    Quit,
+}
+
+/// Represents a request from a [Screen](crate::screen::Screen) input handler for the main loop
+/// to perform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Exit the application.
+    Quit,
+    /// Suspend to the shell, until continued, e.g. by `fg`.
+    Suspend,
+    /// Refresh the selected [Post](crate::posts::Post), and download its comments again.
+    Refresh,
+    /// Download the current page of posts.
+    DownloadPosts,
+    /// Detect the paging of the instance switched to, and download its first page of posts.
+    SwitchInstance,
+    /// Log in as the account switched to, and download the page of posts again.
+    SwitchAccount,
+    /// Download the current page of the current tab of [Saved] posts and comments.
+    DownloadSaved,
+    /// Download the current page of the [Modlog].
+    DownloadModlog,
+    /// Download the current page of communities to [Discover].
+    DownloadCommunities,
+}
+
+/// Page of posts, or post, to refresh, debounced separately, see [App::debounce_refresh].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RefreshTarget {
+    /// Page of posts, by page number.
+    Page(u64),
+    /// [Post](crate::posts::Post) and its comments, by post ID.
+    Post(u64),
+}
+
+/// Live mode on the Post screen, refetching the comments of the post periodically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Live {
+    /// ID of the [Post](crate::posts::Post) being refreshed.
+    pub post_id: u64,
+    /// Time of the next refresh.
+    pub next: SystemTime,
+    /// Number of consecutive failed refreshes.
+    pub failures: u32,
+}
+
+/// Represents an [Action] waiting for a yes/no confirmation from the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Confirm {
+    pub prompt: String,
+    pub action: Action,
+}
+
+impl Confirm {
+    /// Creates a new [Confirm].
+    pub fn new<S: Into<String>>(prompt: S, action: Action) -> Self {
+        Self {
+            prompt: prompt.into(),
+            action,
+        }
+    }
+}
+
+/// Represents what the text from an [Input] is used for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputKind {
+    /// Reason for reporting a [Post](crate::posts::Post).
+    ReportPost { post_id: u64 },
+    /// Reason for reporting a [Comment](crate::comments::Comment).
+    ReportComment { comment_id: u64 },
+    /// Number of the page of posts to go to.
+    GoToPage,
+    /// Number of the root comment to select, from one.
+    GoToComment,
+    /// Path of the markdown file to export a [Post](crate::posts::Post) to.
+    ExportPost { post_id: u64 },
+}
+
+/// Represents a single-line text input from the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Input {
+    pub kind: InputKind,
+    pub text: TextInput,
+}
+
+impl Input {
+    /// Creates a new [Input], accepting only digits for numbers.
+    pub fn new(kind: InputKind) -> Self {
+        let text = match kind {
+            InputKind::GoToPage | InputKind::GoToComment => TextInput::numeric(),
+            InputKind::ReportPost { .. }
+            | InputKind::ReportComment { .. }
+            | InputKind::ExportPost { .. } => TextInput::new(),
+        };
+
+        Self { kind, text }
+    }
+
+    /// Gets the prompt to display for the [Input].
+    pub fn prompt(&self) -> &'static str {
+        match self.kind {
+            InputKind::ReportPost { .. } => "Report post, reason",
+            InputKind::ReportComment { .. } => "Report comment, reason",
+            InputKind::GoToPage => "Go to page",
+            InputKind::GoToComment => "Go to comment",
+            InputKind::ExportPost { .. } => "Export post to",
+        }
+    }
+
+    /// Gets the [Input] text.
+    pub fn text(&self) -> &str {
+        self.text.text()
+    }
+
+    /// Converts the [Input] into an [Action].
+    ///
+    /// Returns `None` if the text is empty, cancelling the [Input], or if the [InputKind] is not
+    /// sent to the instance.
+    pub fn submit(self) -> Option<Action> {
+        let text = self.text().trim();
+
+        if text.is_empty() {
+            None
+        } else {
+            let reason = text.to_owned();
+
+            match self.kind {
+                InputKind::ReportPost { post_id } => Some(Action::ReportPost { post_id, reason }),
+                InputKind::ReportComment { comment_id } => {
+                    Some(Action::ReportComment { comment_id, reason })
+                }
+                InputKind::ExportPost { post_id } => Some(Action::ExportPost {
+                    post_id,
+                    path: reason,
+                }),
+                InputKind::GoToPage | InputKind::GoToComment => None,
+            }
+        }
+    }
+}
+
+/// Represents the numbered list of links found in the post and the selected comment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkPicker {
+    pub links: Vec<String>,
+    /// Link number typed so far.
+    pub number: String,
+}
+
+impl LinkPicker {
+    /// Creates a new [LinkPicker].
+    pub const fn new(links: Vec<String>) -> Self {
+        Self {
+            links,
+            number: String::new(),
+        }
+    }
+
+    /// Adds a digit to the typed link number.
+    ///
+    /// Returns the link once no other link number starts with the typed number, e.g. `3` with
+    /// fewer than 30 links. Numbers without a link are discarded.
+    pub fn push_digit(&mut self, digit: char) -> Option<&str> {
+        self.number.push(digit);
+
+        match self.number.parse::<usize>() {
+            Ok(n) if (1..=self.links.len()).contains(&n) => {
+                if n.saturating_mul(10) > self.links.len() {
+                    self.links.get(n - 1).map(String::as_str)
+                } else {
+                    None
+                }
+            }
+            _ => {
+                self.number.clear();
+                None
+            }
+        }
+    }
+
+    /// Gets the link for the typed number, if any.
+    pub fn selected(&self) -> Option<&str> {
+        let n = self.number.parse::<usize>().ok()?;
+        self.links.get(n.checked_sub(1)?).map(String::as_str)
     }
 }
 
+/// Represents the list of instances from the [Config] to switch to, see
+/// [App::switch_instance].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstancePicker {
+    /// Index of the selected instance.
+    pub selected: usize,
+}
+
+/// Represents the list of accounts on the instance to switch to, see [App::switch_account].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountPicker {
+    /// Index of the selected account.
+    pub selected: usize,
+}
+
+/// State of an instance kept while another is active, restored when switching back.
+pub struct InstanceState {
+    /// Name of the account logged in as on the instance, see [Config::account].
+    pub account: Option<String>,
+    /// Cached [CommentResponseTable]s, post IDs are only unique on their instance.
+    pub comments: CommentCache,
+}
+
+/// Spoiler blocks revealed on the Post screen, reset when leaving the post.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Spoilers {
+    /// Whether the spoilers in the post body are revealed.
+    pub post: bool,
+    /// IDs of the comments with revealed spoilers.
+    pub comments: HashSet<u64>,
+}
+
 /// Represents the application state.
 pub struct App {
-    pub instance_url: String,
+    /// User [Config].
+    pub config: Config,
+    /// Keybindings from the [Config].
+    pub keymap: KeyMap,
+    /// Post list filters from the [Config].
+    pub post_filter: PostFilter,
+    /// Colors and styles from the [Config].
+    pub theme: Theme,
+    /// Whether the [PostFilter] is applied, can be turned off for the session.
+    pub filters_enabled: bool,
+    /// [ApiClient] for making requests to the Lemmy instance.
+    pub client: Box<dyn ApiClient>,
+    /// Requests, and render timings, for the diagnostics overlay.
+    pub diagnostics: Diagnostics,
+    /// [Screen] to display.
+    pub screen: Screen,
+    /// Previous [Screen]s, most recent last, see [App::push_screen].
+    pub nav: Vec<NavEntry>,
+    /// Pending [Action] for the main loop to perform.
+    pub action: Option<Action>,
+    /// URL for the main loop to open in the browser.
+    pub open_url: Option<String>,
+    /// Post for the main loop to download, and open on the Post screen.
+    pub open_post: Option<u64>,
+    /// Text for the main loop to copy to the clipboard.
+    pub clipboard: Option<String>,
+    /// Viewer command, and the URL to open with it, for the main loop to run.
+    pub viewer: Option<(String, String)>,
+    /// Markdown text, and whether its spoilers are revealed, for the main loop to show in the
+    /// pager.
+    pub pager: Option<(String, bool)>,
+    /// Transient messages for the status bar, oldest first, see [App::message].
+    pub status: VecDeque<StatusMessage>,
+    /// Error to display in the error banner.
+    pub error: Option<ErrorEntry>,
+    /// Recent errors, oldest first.
+    pub error_log: VecDeque<ErrorEntry>,
+    pub error_log_scroll: Scroll,
+    /// [Action] waiting for confirmation from the user.
+    pub confirm: Option<Confirm>,
+    /// Text [Input] from the user.
+    pub input: Option<Input>,
+    /// Count or prefix key typed before a [KeyAction](crate::keymap::KeyAction).
+    pub pending: PendingKeys,
+    /// Last visit to each post, for marking new comments.
+    pub visits: Visits,
+    /// Links to open or copy, see [LinkPicker].
+    pub links: Option<LinkPicker>,
+    /// Instances to switch to, see [InstancePicker].
+    pub instance_picker: Option<InstancePicker>,
+    /// State of the instances switched away from, keyed by instance URL.
+    pub instance_states: HashMap<InstanceUrl, InstanceState>,
+    /// Accounts to switch to, see [AccountPicker].
+    pub account_picker: Option<AccountPicker>,
+    /// JWTs of the accounts switched away from, keyed by [Account::key].
+    jwts: HashMap<String, String>,
+    /// Directory to read responses from instead of the network, see [offline](crate::offline).
+    pub offline: Option<PathBuf>,
+    /// Directory to save fetched responses to, see [offline](crate::offline).
+    pub dump: Option<PathBuf>,
     pub page: u64,
+    /// Whether the instance supports cursor pagination (Lemmy 0.19 and later).
+    pub cursor_paging: bool,
+    /// Page cursors from [PostResponses::next_page], keyed by the page number they fetch.
+    pub cursors: HashMap<u64, String>,
     pub posts: PostResponseTable,
-    pub comments: HashMap<u64, CommentResponseTable>,
+    /// [Saved] posts and comments of the logged-in user, for the Saved screen.
+    pub saved: Saved,
+    /// [Modlog] of a community, for the Modlog screen, `None` until opened.
+    pub modlog: Option<Modlog>,
+    /// Communities to [Discover], for the Discover screen.
+    pub discover: Discover,
+    /// Crossposts of the posts opened on the Post screen, keyed by post ID, as many as the
+    /// comments cached.
+    pub cross_posts: CommentCache<Vec<PostResponse>>,
+    /// Prefetched page of [PostResponses], and its page number.
+    pub prefetched: Option<(u64, PostResponses)>,
+    /// Background task prefetching a page of [PostResponses].
+    pub prefetch_task: Option<PostsTask>,
+    /// Page that failed to prefetch, and when to prefetch it again.
+    pub prefetch_failed: Option<(u64, SystemTime)>,
+    /// Cached [CommentResponseTable]s, keyed by [Post](crate::posts::Post) ID.
+    pub(crate) comments: CommentCache,
+    /// Background task downloading comments for a [Post](crate::posts::Post).
+    pub comments_task: Option<CommentsTask>,
+    /// Background task loading replies to a comment, beyond the depth returned with the post.
+    pub replies_task: Option<RepliesTask>,
+    /// Background task downloading the crossposts of the [Post](crate::posts::Post) opened.
+    pub cross_posts_task: Option<CrossPostsTask>,
+    /// Comment for the main loop to load the missing replies to.
+    pub load_replies: Option<u64>,
+    /// Whether the main loop should load the next batch of comments for the current post.
+    pub load_more_comments: bool,
+    /// Sender for notifying the main loop when background tasks finish.
+    pub events: Option<EventSender>,
+    /// Comment to scroll to once the comments are loaded.
+    pub focus_comment: Option<u64>,
+    /// Time of the last refresh of each [RefreshTarget], see [App::debounce_refresh].
+    pub refreshed: HashMap<RefreshTarget, SystemTime>,
+    /// [Live] mode for the current post, if on.
+    pub live: Option<Live>,
+    /// Comment at the top of the comment pane, and the rows scrolled past its start, kept in view
+    /// after merging refreshed comments.
+    pub comment_anchor: Option<(u64, usize)>,
+    /// Order of the comments on the Post screen, for every post in the session.
+    pub comment_sort: CommentSort,
+    /// Fixed current time for relative timestamps, e.g. for snapshots, otherwise the clock.
+    pub now: Option<SystemTime>,
+    /// IDs of NSFW posts revealed in [Blur](NsfwMode::Blur) mode.
+    pub nsfw_revealed: HashSet<u64>,
+    /// Revealed [Spoilers] of the current post.
+    pub spoilers: Spoilers,
+    /// Image on the Image screen, see [App::open_image].
+    pub image: Option<ImageView>,
+    /// Background task downloading the image on the Image screen.
+    pub image_task: Option<ImageTask>,
+    /// Recently viewed images, see [ImageCache].
+    pub images: ImageCache,
+    /// Downloaded images on disk, unless disabled in the [Config].
+    pub disk_cache: Option<DiskCache>,
+    /// [Graphics] protocol for drawing images.
+    pub graphics: Graphics,
+    /// Size of a terminal cell in pixels, for drawing images.
+    pub cell_size: (u16, u16),
     pub post_scroll: Scroll,
     pub comment_scroll: Scroll,
 }
 
 impl App {
     /// Creates a new [App] instance.
-    pub fn new(instance_url: String, posts: PostResponseTable) -> Self {
+    pub fn new(config: Config, posts: PostResponseTable) -> Self {
+        let diagnostics = Diagnostics::default();
+
         Self {
-            instance_url,
+            keymap: config.keymap().unwrap_or_default(),
+            post_filter: config.post_filter().unwrap_or_default(),
+            theme: config.theme().unwrap_or_default(),
+            filters_enabled: true,
+            client: Box::new(
+                config
+                    .client()
+                    .with_request_log(diagnostics.requests.clone()),
+            ),
+            diagnostics,
+            screen: Screen::PostList,
+            nav: Vec::new(),
+            action: None,
+            open_url: None,
+            open_post: None,
+            clipboard: None,
+            viewer: None,
+            pager: None,
+            status: VecDeque::new(),
+            error: None,
+            error_log: VecDeque::new(),
+            error_log_scroll: Scroll::new(),
+            confirm: None,
+            input: None,
+            pending: PendingKeys::default(),
+            visits: Visits::default(),
+            links: None,
+            instance_picker: None,
+            instance_states: HashMap::new(),
+            account_picker: None,
+            jwts: HashMap::new(),
+            offline: None,
+            dump: None,
             page: 1,
+            cursor_paging: false,
+            cursors: HashMap::new(),
             posts,
-            comments: HashMap::new(),
+            saved: Saved::new(),
+            modlog: None,
+            discover: Discover::new(),
+            cross_posts: CommentCache::new(config.comment_cache_size),
+            prefetched: None,
+            prefetch_task: None,
+            prefetch_failed: None,
+            comments: CommentCache::new(config.comment_cache_size),
+            comments_task: None,
+            replies_task: None,
+            cross_posts_task: None,
+            load_replies: None,
+            load_more_comments: false,
+            events: None,
+            focus_comment: None,
+            refreshed: HashMap::new(),
+            live: None,
+            comment_anchor: None,
+            comment_sort: CommentSort::default(),
+            now: None,
+            nsfw_revealed: HashSet::new(),
+            spoilers: Spoilers::default(),
+            image: None,
+            image_task: None,
+            images: ImageCache::new(config.image_cache_bytes()),
+            disk_cache: config.disk_cache(),
+            graphics: Graphics::default(),
+            cell_size: DEFAULT_CELL_SIZE,
             post_scroll: Scroll::new(),
             comment_scroll: Scroll::new(),
+            config,
         }
     }
 
-    /// Gets the current [PostList](crate::endpoint::Endpoint) endpoint page.
-    pub fn page(&self) -> u64 {
-        self.page
+    /// Builder function that sets the [ApiClient], e.g. to use a non-Lemmy backend.
+    pub fn with_client(mut self, client: Box<dyn ApiClient>) -> Self {
+        self.client = client;
+        self.client
+            .set_request_log(self.diagnostics.requests.clone());
+        self
     }
 
-    /// Increments the page number.
-    pub fn next_page(&mut self) -> u64 {
-        self.page = self.page.saturating_add(1);
-        self.page
+    /// Gets the base URL of the Lemmy instance.
+    pub fn instance_url(&self) -> &str {
+        self.client.instance_url()
     }
 
-    /// Decrements the page number.
-    pub fn previous_page(&mut self) -> u64 {
-        if self.page > 1 {
-            self.page = self.page.saturating_sub(1);
+    /// Gets the label of the instance from the [Config], or its host for instances not listed.
+    pub fn instance_label(&self) -> &str {
+        let url = self.instance_url();
+
+        match self.config.instance(url) {
+            Some(instance) => instance.label(),
+            None => actor_host(url).unwrap_or(url),
         }
-        self.page
     }
-}
 
-impl AsRef<App> for App {
-    fn as_ref(&self) -> &Self {
-        self
+    /// Gets whether the [InstancePicker] is displayed.
+    pub fn picking_instance(&self) -> bool {
+        self.instance_picker.is_some()
     }
-}
 
-impl AsMut<App> for App {
-    fn as_mut(&mut self) -> &mut Self {
-        self
+    /// Displays the [InstancePicker], with the current instance selected.
+    pub fn open_instance_picker(&mut self) {
+        if self.is_offline() {
+            self.set_message("offline mode: instances unavailable");
+            return;
+        } else if self.config.instances.is_empty() {
+            self.set_message("no instances configured, add [[instances]] to the config");
+            return;
+        }
+
+        let current = InstanceUrl::from(self.instance_url());
+        let selected = self
+            .config
+            .instances
+            .iter()
+            .position(|i| InstanceUrl::from(i.url.as_str()) == current)
+            .unwrap_or(0);
+
+        self.instance_picker = Some(InstancePicker { selected });
+    }
+
+    /// Closes the [InstancePicker].
+    pub fn close_instance_picker(&mut self) {
+        self.instance_picker = None;
+    }
+
+    /// Switches to the instance at the index in the [Config] instances.
+    ///
+    /// The posts, page, and filters are reset for the new instance. The account, its JWT, and
+    /// the cached comments of the current instance are kept for switching back. Returns
+    /// [Command::SwitchInstance] for the main loop to download the posts of the new instance.
+    pub fn switch_instance(&mut self, index: usize) -> Option<Command> {
+        self.close_instance_picker();
+        let instance = self.config.instances.get(index)?.clone();
+        let url = InstanceUrl::from(instance.url.as_str());
+        let current = InstanceUrl::from(self.instance_url());
+        if url == current {
+            self.set_message(format!("already on {}", instance.label()));
+            return None;
+        }
+
+        self.cancel_comments_task();
+        self.cancel_replies_task();
+        self.cancel_cross_posts_task();
+        self.cancel_image_task();
+        self.invalidate_prefetch();
+
+        self.stash_jwt();
+        let capacity = self.config.comment_cache_size;
+        let state = InstanceState {
+            account: self.config.account.take(),
+            comments: std::mem::replace(&mut self.comments, CommentCache::new(capacity)),
+        };
+        self.instance_states.insert(current, state);
+
+        self.config.instance_url = url.to_string();
+        self.client = Box::new(
+            self.config
+                .client()
+                .with_request_log(self.diagnostics.requests.clone()),
+        );
+        if let Some(state) = self.instance_states.remove(&url) {
+            self.config.account = state.account;
+            self.comments = state.comments;
+        }
+        self.restore_jwt();
+
+        self.nav.clear();
+        self.screen = Screen::PostList;
+        self.posts = PostResponseTable::new(Vec::new());
+        self.saved = Saved::new();
+        self.modlog = None;
+        self.discover = Discover::new();
+        self.cross_posts = CommentCache::new(self.config.comment_cache_size);
+        self.page = 1;
+        self.cursor_paging = false;
+        self.cursors.clear();
+        self.filters_enabled = true;
+        self.nsfw_revealed.clear();
+        self.live = None;
+        self.focus_comment = None;
+        self.image = None;
+        self.post_scroll = Scroll::new();
+        self.comment_scroll = Scroll::new();
+
+        self.set_message(format!("switched to {}", instance.label()));
+        Some(Command::SwitchInstance)
+    }
+
+    /// Gets the name of the account logged in as, e.g. `ferris@lemmy.ml`, if logged in.
+    ///
+    /// Accounts not named in the config are labelled by the instance.
+    pub fn account_label(&self) -> Option<String> {
+        self.jwt()?;
+
+        Some(match self.config.active_account() {
+            Some(account) => format!("{account}@{}", self.instance_label()),
+            None => self.instance_label().to_owned(),
+        })
+    }
+
+    /// Gets whether the [AccountPicker] is displayed.
+    pub fn picking_account(&self) -> bool {
+        self.account_picker.is_some()
+    }
+
+    /// Displays the [AccountPicker], with the active account selected.
+    pub fn open_account_picker(&mut self) {
+        let accounts = self.config.accounts();
+
+        if self.is_offline() {
+            self.set_message("offline mode: accounts unavailable");
+        } else if accounts.len() < 2 {
+            self.set_message(format!(
+                "no other accounts for {}, add accounts to its [[instances]] table",
+                self.instance_label()
+            ));
+        } else {
+            let selected = self
+                .config
+                .active_account()
+                .and_then(|active| accounts.iter().position(|&a| a == active))
+                .unwrap_or(0);
+
+            self.account_picker = Some(AccountPicker { selected });
+        }
+    }
+
+    /// Closes the [AccountPicker].
+    pub fn close_account_picker(&mut self) {
+        self.account_picker = None;
+    }
+
+    /// Switches to the account at the index in the accounts of the instance, see
+    /// [Config::accounts].
+    ///
+    /// Responses with fields for the logged in user, e.g. votes and subscriptions, are
+    /// invalidated. The JWT of the current account is kept for switching back. Returns
+    /// [Command::SwitchAccount] for the main loop to log in, and download the posts again.
+    pub fn switch_account(&mut self, index: usize) -> Option<Command> {
+        self.close_account_picker();
+        let account = self.config.accounts().get(index)?.to_string();
+        if self.config.active_account() == Some(account.as_str()) {
+            self.set_message(format!("already logged in as {account}"));
+            return None;
+        }
+
+        self.cancel_comments_task();
+        self.cancel_replies_task();
+        self.cancel_cross_posts_task();
+        self.invalidate_prefetch();
+
+        self.stash_jwt();
+        self.config.account = Some(account);
+        self.restore_jwt();
+
+        self.comments = CommentCache::new(self.config.comment_cache_size);
+        self.saved = Saved::new();
+        self.cross_posts = CommentCache::new(self.config.comment_cache_size);
+        self.live = None;
+        self.focus_comment = None;
+
+        let label = format!(
+            "{}@{}",
+            self.config.active_account()?,
+            self.instance_label()
+        );
+        self.set_message(format!("switched to {label}"));
+        Some(Command::SwitchAccount)
+    }
+
+    /// Keeps the JWT of the active account, for switching back to it.
+    fn stash_jwt(&mut self) {
+        let key = Account::from_config(&self.config).key(Secret::Jwt);
+
+        match self.jwt() {
+            Some(jwt) => self.jwts.insert(key, jwt),
+            None => self.jwts.remove(&key),
+        };
+    }
+
+    /// Sets the JWT kept for the active account, if any, see [stash_jwt](Self::stash_jwt).
+    fn restore_jwt(&mut self) {
+        let key = Account::from_config(&self.config).key(Secret::Jwt);
+        let jwt = self.jwts.remove(&key);
+
+        self.set_jwt(jwt);
+    }
+
+    /// Gets the JWT for the logged in user, if any.
+    pub fn jwt(&self) -> Option<String> {
+        self.client.jwt()
+    }
+
+    /// Sets the JWT for the logged in user.
+    pub fn set_jwt(&mut self, jwt: Option<String>) {
+        self.client.set_jwt(jwt);
+    }
+
+    /// Gets the [Screen] to display.
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    /// Switches to the [Screen], saving the context of the current [Screen] to return to with
+    /// [App::pop_screen].
+    pub fn push_screen(&mut self, screen: Screen) {
+        let entry = NavEntry {
+            screen: self.screen,
+            selected: self.posts.state.selected(),
+            page: self.page,
+            post_scroll: self.post_scroll.position(),
+            comment_scroll: self.comment_scroll.position(),
+            error_log_scroll: self.error_log_scroll.position(),
+        };
+
+        self.nav.push(entry);
+        self.screen = screen;
+    }
+
+    /// Returns to the previous [Screen], restoring its context.
+    ///
+    /// Does nothing on the first [Screen], backing out never quits. Returns
+    /// [Command::DownloadPosts] if the restored page differs from the current page.
+    pub fn pop_screen(&mut self) -> Option<Command> {
+        let entry = self.nav.pop()?;
+
+        self.screen = entry.screen;
+        self.posts.state.select(entry.selected);
+        self.post_scroll.set_position(entry.post_scroll);
+        self.comment_scroll.set_position(entry.comment_scroll);
+        self.error_log_scroll.set_position(entry.error_log_scroll);
+
+        if entry.page != self.page {
+            self.set_page(entry.page);
+            Some(Command::DownloadPosts)
+        } else {
+            None
+        }
+    }
+
+    /// Switches to the [Screen] in place of the current [Screen], without saving its context.
+    pub fn replace_screen(&mut self, screen: Screen) {
+        self.screen = screen;
+    }
+
+    /// Sets the pending [Action] for the main loop to perform.
+    pub fn set_action(&mut self, action: Action) {
+        self.action.replace(action);
+    }
+
+    /// Takes the pending [Action], if any.
+    pub fn take_action(&mut self) -> Option<Action> {
+        self.action.take()
+    }
+
+    /// Sets the comment for the main loop to load the missing replies to, unless already loading.
+    pub fn set_load_replies(&mut self, comment_id: u64) {
+        if !self.replies_loading(comment_id) {
+            self.load_replies = Some(comment_id);
+        }
+    }
+
+    /// Takes the comment to load the missing replies to, if any.
+    pub fn take_load_replies(&mut self) -> Option<u64> {
+        self.load_replies.take()
+    }
+
+    /// Sets the main loop to load the next batch of comments for the current post.
+    pub fn set_load_more_comments(&mut self) {
+        self.load_more_comments = true;
+    }
+
+    /// Takes whether to load the next batch of comments.
+    pub fn take_load_more_comments(&mut self) -> bool {
+        std::mem::take(&mut self.load_more_comments)
+    }
+
+    /// Sets the URL for the main loop to open in the browser.
+    pub fn set_open_url<S: Into<String>>(&mut self, url: S) {
+        self.open_url.replace(url.into());
+    }
+
+    /// Takes the URL to open in the browser, if any.
+    pub fn take_open_url(&mut self) -> Option<String> {
+        self.open_url.take()
+    }
+
+    /// Sets the post for the main loop to download, and open on the Post screen.
+    pub fn set_open_post(&mut self, post_id: u64) {
+        self.open_post.replace(post_id);
+    }
+
+    /// Takes the post to open, if any.
+    pub fn take_open_post(&mut self) -> Option<u64> {
+        self.open_post.take()
+    }
+
+    /// Sets the viewer command for the main loop to run with the URL.
+    pub fn set_viewer<C: Into<String>, U: Into<String>>(&mut self, command: C, url: U) {
+        self.viewer.replace((command.into(), url.into()));
+    }
+
+    /// Takes the viewer command, and its URL, to run, if any.
+    pub fn take_viewer(&mut self) -> Option<(String, String)> {
+        self.viewer.take()
+    }
+
+    /// Sets the markdown text for the main loop to show in the pager.
+    pub fn set_pager<S: Into<String>>(&mut self, text: S, reveal: bool) {
+        self.pager.replace((text.into(), reveal));
+    }
+
+    /// Takes the markdown text to show in the pager, and whether to reveal its spoilers, if any.
+    pub fn take_pager(&mut self) -> Option<(String, bool)> {
+        self.pager.take()
+    }
+
+    /// Sets the text for the main loop to copy to the clipboard.
+    pub fn set_clipboard<S: Into<String>>(&mut self, text: S) {
+        self.clipboard.replace(text.into());
+    }
+
+    /// Takes the text to copy to the clipboard, if any.
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// Gets whether an [Action] is waiting for confirmation from the user.
+    pub fn confirming(&self) -> bool {
+        self.confirm.is_some()
+    }
+
+    /// Sets an [Action] to wait for confirmation from the user.
+    pub fn set_confirm(&mut self, confirm: Confirm) {
+        self.confirm.replace(confirm);
+    }
+
+    /// Confirms the pending [Confirm], setting its [Action] for the main loop to perform.
+    ///
+    /// Returns [Command::Quit] for a confirmed [Action::Quit].
+    pub fn accept_confirm(&mut self) -> Option<Command> {
+        match self.confirm.take()?.action {
+            Action::Quit => return Some(Command::Quit),
+            action => self.set_action(action),
+        }
+
+        None
+    }
+
+    /// Returns [Command::Quit] to quit, or asks for confirmation first with `confirm_quit`.
+    pub fn quit(&mut self) -> Option<Command> {
+        if !self.config.confirm_quit {
+            return Some(Command::Quit);
+        }
+
+        self.set_confirm(Confirm::new("Quit temi?", Action::Quit));
+        None
+    }
+
+    /// Cancels the background tasks, before the session is saved on exit.
+    pub fn shutdown(&mut self) {
+        self.cancel_prefetch_task();
+        self.cancel_comments_task();
+        self.cancel_replies_task();
+        self.cancel_cross_posts_task();
+        self.cancel_image_task();
+    }
+
+    /// Cancels the pending [Confirm].
+    pub fn reject_confirm(&mut self) {
+        self.confirm.take();
+    }
+
+    /// Gets whether the user is entering text [Input].
+    pub fn inputting(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Starts a new text [Input] from the user.
+    pub fn set_input(&mut self, kind: InputKind) {
+        self.input.replace(Input::new(kind));
+    }
+
+    /// Starts a new text [Input] from the user, with the text typed in already, e.g. a default
+    /// path to edit.
+    pub fn set_input_text(&mut self, kind: InputKind, text: &str) {
+        let mut input = Input::new(kind);
+        text.chars().for_each(|c| {
+            input.text.push(c);
+        });
+
+        self.input.replace(input);
+    }
+
+    /// Submits the text [Input], setting the resulting [Action] for the main loop to perform.
+    ///
+    /// Page numbers instead return the [Command] to download the page. Empty text cancels the
+    /// [Input].
+    pub fn submit_input(&mut self) -> Option<Command> {
+        let input = self.input.take()?;
+
+        if input.text().is_empty() {
+            self.set_message("cancelled");
+            return None;
+        }
+
+        match input.kind {
+            InputKind::GoToPage => return self.go_to_page(input.text.number()),
+            InputKind::GoToComment => self.go_to_comment(input.text.number()),
+            InputKind::ReportPost { .. }
+            | InputKind::ReportComment { .. }
+            | InputKind::ExportPost { .. } => match input.submit() {
+                Some(action) => self.set_action(action),
+                None => self.set_message("cancelled"),
+            },
+        }
+
+        None
+    }
+
+    /// Goes to the page of posts, returning the [Command] to download it.
+    pub fn go_to_page(&mut self, page: Option<u64>) -> Option<Command> {
+        match page.filter(|&p| p > 0) {
+            Some(page) => {
+                self.set_page(page);
+                Some(Command::DownloadPosts)
+            }
+            None => {
+                self.set_message("invalid page number");
+                None
+            }
+        }
+    }
+
+    /// Selects the root comment `number`, counting from one, scrolling it into view.
+    pub fn go_to_comment(&mut self, number: Option<u64>) {
+        let Some(post_id) = self.posts.current().map(|p| p.post.id()) else {
+            return;
+        };
+
+        let selected = self.comments.get_mut(post_id).and_then(|c| {
+            let n = number?.checked_sub(1)?;
+            let i = c
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(_, cr)| cr.level() == 0)
+                .nth(usize::try_from(n).ok()?)
+                .map(|(i, _)| i)?;
+
+            c.state.select(Some(i));
+            Some(c.rows.get(i).cloned())
+        });
+
+        match selected {
+            Some(Some(rows)) => self.comment_scroll.show(rows),
+            // the rows are known after the next draw
+            Some(None) => (),
+            None => self.set_message("no such comment"),
+        }
+    }
+
+    /// Cancels the text [Input].
+    pub fn cancel_input(&mut self) {
+        self.input.take();
+    }
+
+    /// Gets whether the [LinkPicker] is displayed.
+    pub fn picking_link(&self) -> bool {
+        self.links.is_some()
+    }
+
+    /// Displays the [LinkPicker] for the links.
+    pub fn set_links(&mut self, links: Vec<String>) {
+        self.links.replace(LinkPicker::new(links));
+    }
+
+    /// Closes the [LinkPicker].
+    pub fn close_links(&mut self) {
+        self.links.take();
+    }
+
+    /// Gets the newest unexpired [StatusMessage] to display in the status bar.
+    ///
+    /// Older messages show again once newer ones expire, e.g. an error after `copied`.
+    pub fn message(&self) -> &str {
+        let now = self.now();
+
+        self.status
+            .iter()
+            .rev()
+            .find(|m| !m.expired(now))
+            .map_or("", StatusMessage::text)
+    }
+
+    /// Sets the message to display in the status bar, for the [STATUS_TIMEOUT].
+    pub fn set_message<S: Into<String>>(&mut self, message: S) {
+        self.push_status(StatusMessage::new(message, self.now(), STATUS_TIMEOUT));
+    }
+
+    /// Queues a [StatusMessage], dropping any expired messages.
+    pub fn push_status(&mut self, message: StatusMessage) {
+        self.expire_status();
+        self.status.push_back(message);
+    }
+
+    /// Drops the expired [StatusMessage]s, and pending keys.
+    ///
+    /// Returns whether any expired, changing the status bar.
+    pub fn expire_status(&mut self) -> bool {
+        let now = self.now();
+        let len = self.status.len();
+        self.status.retain(|m| !m.expired(now));
+
+        let pending = self.pending.expire(now);
+        pending || self.status.len() != len
+    }
+
+    /// Gets whether a download with its progress displayed is running, e.g. of the comments, so
+    /// the screen changes without any input.
+    pub fn busy(&self) -> bool {
+        self.comments_task.is_some() || self.image_task.is_some()
+    }
+
+    /// Gets whether there is an error to display in the error banner.
+    pub fn has_error(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// Gets the error to display in the error banner.
+    pub fn error(&self) -> &str {
+        self.error.as_ref().map(|e| e.message()).unwrap_or("")
+    }
+
+    /// Gets the [Error] variant name for the error banner.
+    pub fn error_kind(&self) -> &str {
+        self.error.as_ref().map(|e| e.kind()).unwrap_or("")
+    }
+
+    /// Appends an [Error] to the error log, without displaying it.
+    pub fn log_error(&mut self, err: &Error) {
+        if self.error_log.len() >= MAX_ERROR_LOG {
+            self.error_log.pop_front();
+        }
+        self.error_log.push_back(ErrorEntry::new(err));
+    }
+
+    /// Sets an [Error] to display in the error banner, and appends it to the error log.
+    pub fn set_error(&mut self, err: &Error) {
+        let entry = ErrorEntry::new(err);
+        self.log_error(err);
+
+        // the banner can be dismissed, the status bar keeps the error for a while
+        self.push_status(StatusMessage::new(
+            entry.message(),
+            self.now(),
+            ERROR_STATUS_TIMEOUT,
+        ));
+        self.error.replace(entry);
+    }
+
+    /// Switches to the error log [Screen], dismissing the error banner.
+    pub fn open_error_log(&mut self) {
+        self.clear_error();
+
+        if self.screen != Screen::ErrorLog {
+            self.push_screen(Screen::ErrorLog);
+        }
+
+        self.error_log_scroll.first();
+    }
+
+    /// Switches to the Saved [Screen], on the first page of the saved posts.
+    ///
+    /// Returns [Command::DownloadSaved] for the main loop to download them, unless logged out,
+    /// or offline.
+    pub fn open_saved(&mut self) -> Option<Command> {
+        if self.is_offline() {
+            self.set_message("offline mode: saved posts unavailable");
+            return None;
+        }
+
+        self.saved = Saved::new();
+        self.push_screen(Screen::Saved);
+
+        if self.jwt().is_none() {
+            self.set_message("login required: set LEMMY_JWT");
+            return None;
+        }
+
+        Some(Command::DownloadSaved)
+    }
+
+    /// Switches to the Modlog [Screen], on the first page of the moderation log of the
+    /// [Community].
+    ///
+    /// Returns [Command::DownloadModlog] for the main loop to download it, unless offline.
+    pub fn open_modlog(&mut self, community: Community) -> Option<Command> {
+        if self.is_offline() {
+            self.set_message("offline mode: modlog unavailable");
+            return None;
+        }
+
+        self.modlog = Some(Modlog::new(community));
+        self.push_screen(Screen::Modlog);
+
+        Some(Command::DownloadModlog)
+    }
+
+    /// Switches to the Discover [Screen], on the first page of communities from all instances.
+    ///
+    /// Returns [Command::DownloadCommunities] for the main loop to download them, unless
+    /// offline.
+    pub fn open_discover(&mut self) -> Option<Command> {
+        if self.is_offline() {
+            self.set_message("offline mode: communities unavailable");
+            return None;
+        }
+
+        self.discover = Discover::new();
+        self.push_screen(Screen::Discover);
+
+        Some(Command::DownloadCommunities)
+    }
+
+    /// Switches to the PostList [Screen], listing the first page of posts of the [Community].
+    ///
+    /// Returns [Command::DownloadPosts] for the main loop to download them.
+    pub fn open_community(&mut self, community: &Community) -> Option<Command> {
+        // `rust@lemmy.ml`, remote communities need the host
+        let name = community.handle().trim_start_matches('!').to_owned();
+        self.set_message(format!("showing posts of !{name}"));
+        self.config.community = Some(name);
+        self.set_posts_query(self.config.posts_query());
+
+        self.nav.clear();
+        self.screen = Screen::PostList;
+        self.posts = PostResponseTable::new(Vec::new());
+        self.set_page(1);
+
+        Some(Command::DownloadPosts)
+    }
+
+    /// Returns from the error log to the previous [Screen].
+    pub fn close_error_log(&mut self) -> Option<Command> {
+        self.pop_screen()
+    }
+
+    /// Clears the error banner.
+    pub fn clear_error(&mut self) {
+        self.error.take();
+    }
+
+    /// Gets whether the content of the NSFW post is hidden, in [Blur](NsfwMode::Blur) mode until
+    /// revealed.
+    pub fn nsfw_blurred(&self, post: &PostResponse) -> bool {
+        self.config.nsfw == NsfwMode::Blur
+            && post.nsfw()
+            && !self.nsfw_revealed.contains(&post.post.id())
+    }
+
+    /// Gets the current time, for relative timestamps.
+    pub fn now(&self) -> SystemTime {
+        self.now.unwrap_or_else(SystemTime::now)
+    }
+
+    /// Gets the status to display in the status bar.
+    ///
+    /// Request status from the [ApiClient], e.g. while retrying, takes precedence over the message.
+    /// A warning is always shown while certificate verification is disabled.
+    pub fn hud_message(&self) -> String {
+        let message = self
+            .client
+            .status()
+            .unwrap_or_else(|| self.message().to_owned());
+
+        if !self.client.accepts_invalid_certs() {
+            message
+        } else if message.is_empty() {
+            INSECURE_WARNING.into()
+        } else {
+            format!("{INSECURE_WARNING} | {message}")
+        }
+    }
+
+    /// Clears the messages displayed in the status bar.
+    pub fn clear_message(&mut self) {
+        self.status.clear();
+    }
+
+    /// Gets the crossposts of the [Post](crate::posts::Post), `None` until downloaded.
+    pub fn cross_posts(&self, post_id: u64) -> Option<&[PostResponse]> {
+        self.cross_posts.get(post_id).map(Vec::as_slice)
+    }
+
+    /// Sets the downloaded crossposts of the [Post](crate::posts::Post).
+    pub fn set_cross_posts(&mut self, post_id: u64, cross_posts: Vec<PostResponse>) {
+        self.cross_posts
+            .pin(self.posts.current().map(|p| p.post.id()));
+        self.cross_posts.insert(post_id, cross_posts);
+    }
+
+    /// Starts downloading the crossposts of the [Post](crate::posts::Post) in the background,
+    /// unless downloaded, or downloading, already.
+    pub fn load_cross_posts(&mut self, post_id: u64) {
+        let loading = self
+            .cross_posts_task
+            .as_ref()
+            .is_some_and(|t| t.post_id() == post_id);
+        if loading || self.cross_posts(post_id).is_some() {
+            return;
+        }
+
+        self.cancel_cross_posts_task();
+        self.cross_posts_task = Some(CrossPostsTask::spawn(
+            self.client.clone(),
+            post_id,
+            self.events.clone(),
+        ));
+    }
+
+    /// Cancels the [CrossPostsTask], if any.
+    pub fn cancel_cross_posts_task(&mut self) {
+        if let Some(task) = self.cross_posts_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Gets the cached [CommentResponseTable] for the [Post](crate::posts::Post).
+    pub fn comments_get(&self, post_id: u64) -> Option<&CommentResponseTable> {
+        self.comments.get(post_id)
+    }
+
+    /// Gets the mutable cached [CommentResponseTable] for the [Post](crate::posts::Post).
+    pub fn comments_get_mut(&mut self, post_id: u64) -> Option<&mut CommentResponseTable> {
+        self.comments.get_mut(post_id)
+    }
+
+    /// Caches the [CommentResponseTable] for the [Post](crate::posts::Post).
+    ///
+    /// Evicts the least-recently used tables over capacity, except for the current post. Bot
+    /// comments are hidden if configured.
+    pub fn comments_insert(&mut self, post_id: u64, mut table: CommentResponseTable) {
+        let orphans: Vec<String> = table
+            .orphans()
+            .map(|c| format!("{} `{}`", c.comment.id(), c.comment.path()))
+            .collect();
+        if !orphans.is_empty() {
+            self.log_error(&Error::Json(Source::msg(format!(
+                "comments of post {post_id} with malformed paths, shown after the threads: {}",
+                orphans.join(", ")
+            ))));
+        }
+
+        table.hide_bots = self.config.hide_bots;
+        self.comments.pin(self.posts.current().map(|p| p.post.id()));
+        self.comments.insert(post_id, table);
+    }
+
+    /// Marks the cached [CommentResponseTable], and crossposts, for the
+    /// [Post](crate::posts::Post) as recently used.
+    pub fn comments_touch(&mut self, post_id: u64) {
+        self.comments.touch(post_id);
+        self.cross_posts.touch(post_id);
+    }
+
+    /// Gets a mutable iterator over every cached [CommentResponseTable].
+    pub fn comments_iter_mut(&mut self) -> impl Iterator<Item = &mut CommentResponseTable> {
+        self.comments.values_mut()
+    }
+
+    /// Sets the maximum number of cached [CommentResponseTable]s, and crossposts.
+    pub fn set_comments_capacity(&mut self, capacity: usize) {
+        let current = self.posts.current().map(|p| p.post.id());
+
+        self.comments.pin(current);
+        self.comments.set_capacity(capacity);
+        self.cross_posts.pin(current);
+        self.cross_posts.set_capacity(capacity);
+    }
+
+    /// Gets the [CommentsTask] if it is downloading comments for the [Post](crate::posts::Post).
+    pub fn comments_loading(&self, post_id: u64) -> Option<&CommentsTask> {
+        self.comments_task
+            .as_ref()
+            .filter(|t| t.post_id() == post_id)
+    }
+
+    /// Gets whether the [RepliesTask] is loading replies to the comment.
+    pub fn replies_loading(&self, comment_id: u64) -> bool {
+        self.replies_task
+            .as_ref()
+            .is_some_and(|t| t.comment_id() == comment_id)
+    }
+
+    /// Gets the comment at the top of the comment pane of the post, and the rows scrolled past
+    /// its start, to keep in view after merging comments.
+    pub fn scroll_anchor(&self, post_id: u64) -> Option<(u64, usize)> {
+        let position = self.comment_scroll.position();
+        let c = self.comments_get(post_id)?;
+        let i = c.rows.iter().position(|rows| rows.end > position)?;
+
+        Some((c.items[i].comment.id(), position - c.rows[i].start))
+    }
+
+    /// Merges pages of comments streamed in by the [CommentsTask] into the post's comments,
+    /// keeping the scroll position.
+    pub fn merge_comment_pages(&mut self, post_id: u64, pages: Vec<CommentResponses>) {
+        self.comment_anchor = self.scroll_anchor(post_id);
+        for page in pages {
+            match self.comments_get_mut(post_id) {
+                Some(c) => {
+                    c.merge_loaded(page);
+                }
+                None => self.comments_insert(post_id, page.into()),
+            }
+        }
+    }
+
+    /// Stops the downloads for the current post, keeping the comments already downloaded.
+    ///
+    /// Returns whether a download was stopped. Comment pages finished before stopping are
+    /// merged, a post without any keeps an empty table, so the download is not restarted until
+    /// refreshed, or the rest is loaded like comments beyond the fetch cap.
+    pub fn stop_fetch(&mut self) -> bool {
+        let Some((post_id, total)) = self
+            .posts
+            .current()
+            .map(|p| (p.post.id(), p.counts.comments()))
+        else {
+            return false;
+        };
+
+        let mut stopped = false;
+        if let Some(task) = self.comments_task.take_if(|t| t.post_id() == post_id) {
+            task.abort();
+            let pages = task.take_pages();
+            self.merge_comment_pages(post_id, pages);
+            match self.comments_get_mut(post_id) {
+                Some(c) if task.streams() => c.set_fetched(c.items.len() as u64, total),
+                Some(_) => (),
+                None => self.comments_insert(post_id, CommentResponses::new(Vec::new()).into()),
+            }
+            stopped = true;
+        }
+        if let Some(task) = self.replies_task.take_if(|t| t.post_id() == post_id) {
+            task.abort();
+            stopped = true;
+        }
+
+        if stopped {
+            self.set_message("fetch cancelled");
+        }
+        stopped
+    }
+
+    /// Cancels the [CommentsTask], if any.
+    ///
+    /// Comments streamed in so far are dropped, so the post downloads them again when next
+    /// opened.
+    pub fn cancel_comments_task(&mut self) {
+        if let Some(task) = self.comments_task.take() {
+            task.abort();
+
+            if task.streams() {
+                self.comments.remove(task.post_id());
+            }
+        }
+    }
+
+    /// Cancels the [RepliesTask], if any.
+    pub fn cancel_replies_task(&mut self) {
+        if let Some(task) = self.replies_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Switches to the Image [Screen] for the image at the URL.
+    ///
+    /// Images missing from the [ImageCache] are downloaded by the main loop.
+    pub fn open_image<S: Into<String>>(&mut self, url: S) {
+        let mut view = ImageView::new(url);
+        if let Some(image) = self.images.get(view.url()) {
+            view.set_image(image);
+        }
+
+        self.cancel_image_task();
+        self.image = Some(view);
+        self.push_screen(Screen::Image);
+    }
+
+    /// Returns from the Image [Screen], cancelling the download, if any.
+    pub fn close_image(&mut self) -> Option<Command> {
+        if self.image_task.is_some() {
+            self.set_message("fetch cancelled");
+        }
+        self.cancel_image_task();
+        self.image = None;
+        self.pop_screen()
+    }
+
+    /// Cancels the [ImageTask], if any.
+    pub fn cancel_image_task(&mut self) {
+        if let Some(task) = self.image_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Gets the offline mode directory, if any.
+    pub fn offline(&self) -> Option<&Path> {
+        self.offline.as_deref()
+    }
+
+    /// Gets whether the [App] is in offline mode.
+    pub fn is_offline(&self) -> bool {
+        self.offline.is_some()
+    }
+
+    /// Sets the offline mode directory.
+    pub fn set_offline(&mut self, dir: Option<PathBuf>) {
+        self.offline = dir;
+    }
+
+    /// Gets the directory to save fetched responses to, if any.
+    pub fn dump(&self) -> Option<&Path> {
+        self.dump.as_deref()
+    }
+
+    /// Sets the directory to save fetched responses to.
+    pub fn set_dump(&mut self, dir: Option<PathBuf>) {
+        self.dump = dir;
+    }
+
+    /// Sets the current page of posts, filtering out NSFW posts in [Hide](NsfwMode::Hide) mode,
+    /// and posts matching the [PostFilter]. Reposts of the same link are hidden if
+    /// [dedupe_links](Config::dedupe_links) is set.
+    ///
+    /// Every page, including prefetched ones, passes through here.
+    ///
+    /// With cursor paging, the next page cursor is saved for [page_cursor](Self::page_cursor).
+    pub fn set_posts(&mut self, posts: PostResponses) {
+        let mut posts = posts;
+
+        if self.cursor_paging {
+            let next = self.page.saturating_add(1);
+
+            match posts.next_page.take() {
+                Some(cursor) => self.cursors.insert(next, cursor),
+                None => self.cursors.remove(&next),
+            };
+        }
+
+        if self.config.nsfw == NsfwMode::Hide {
+            posts.posts.retain(|p| !p.nsfw());
+        }
+        let filtered = self.apply_filters(&mut posts);
+
+        self.posts = posts.into();
+        self.posts.filtered = filtered;
+        if self.config.dedupe_links {
+            self.posts.dedupe_links();
+        }
+    }
+
+    /// Drops the posts matching the [PostFilter], unless filters are disabled.
+    ///
+    /// Returns the number of posts dropped.
+    pub fn apply_filters(&self, posts: &mut PostResponses) -> usize {
+        if !self.filters_enabled || self.post_filter.is_empty() {
+            return 0;
+        }
+
+        let len = posts.posts.len();
+        posts.posts.retain(|p| !self.post_filter.matches(p));
+        len - posts.posts.len()
+    }
+
+    /// Sets the post sort order, listing type, and page limit.
+    ///
+    /// Discards any prefetched posts and page cursors, since they were fetched with the old
+    /// parameters.
+    pub fn set_posts_query(&mut self, posts_query: PostsQuery) {
+        self.client.set_posts_query(posts_query);
+        self.invalidate_prefetch();
+        self.cursors.clear();
+    }
+
+    /// Gets whether the instance supports cursor pagination.
+    pub fn cursor_paging(&self) -> bool {
+        self.cursor_paging
+    }
+
+    /// Sets whether the instance supports cursor pagination, e.g. from the
+    /// [GetSite](crate::endpoint::Endpoint) version.
+    pub fn set_cursor_paging(&mut self, cursor_paging: bool) {
+        self.cursor_paging = cursor_paging;
+        self.cursors.clear();
+    }
+
+    /// Gets the cursor to fetch a page of posts with, if any.
+    ///
+    /// Falls back to the page number (returns `None`) for the first page, when cursor paging is
+    /// unsupported, or when no cursor is known for the page, e.g. after starting on a later page.
+    pub fn page_cursor(&self, page: u64) -> Option<&str> {
+        if self.cursor_paging && page > 1 {
+            self.cursors.get(&page).map(String::as_str)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the paging position to display in the status bar, e.g. `page 3`, or
+    /// `page 3 (cursor)` when fetched with a page cursor.
+    pub fn paging_label(&self) -> String {
+        if self.cursor_paging && (self.page == 1 || self.cursors.contains_key(&self.page)) {
+            format!("page {} (cursor)", self.page)
+        } else {
+            format!("page {}", self.page)
+        }
+    }
+
+    /// Gets whether the next page should be prefetched.
+    ///
+    /// Prefetches once the user scrolls past the halfway point of the current page, and the next
+    /// page is neither prefetched nor downloading, nor failed less than [PREFETCH_RETRY_DELAY]
+    /// ago.
+    pub fn should_prefetch(&self) -> bool {
+        let next = self.page.saturating_add(1);
+        let halfway = self
+            .posts
+            .state()
+            .selected()
+            .is_some_and(|i| i.saturating_mul(2) >= self.posts.items().len());
+
+        !self.is_offline()
+            && halfway
+            && self.prefetched.as_ref().map(|(p, _)| *p) != Some(next)
+            && self.prefetch_task.as_ref().map(|t| t.page()) != Some(next)
+            && !self
+                .prefetch_failed
+                .is_some_and(|(page, retry)| page == next && self.now() < retry)
+    }
+
+    /// Records a failed prefetch of the page, so it is not prefetched again until
+    /// [PREFETCH_RETRY_DELAY] has passed.
+    ///
+    /// The error is shown once, not again when prefetching the same page fails again.
+    pub fn set_prefetch_failed(&mut self, page: u64, err: &Error) {
+        if self.prefetch_failed.map(|(p, _)| p) != Some(page) {
+            self.log_error(err);
+            self.set_message(format!("prefetching page {page} failed: {err}"));
+        }
+
+        self.prefetch_failed = Some((page, self.now() + PREFETCH_RETRY_DELAY));
+    }
+
+    /// Starts prefetching the next page in the background, cancelling any previous prefetch.
+    pub fn prefetch_next_page(&mut self) {
+        self.cancel_prefetch_task();
+        let next = self.page.saturating_add(1);
+
+        self.prefetch_task = Some(PostsTask::spawn(
+            self.client.clone(),
+            next,
+            self.page_cursor(next).map(str::to_owned),
+            self.events.clone(),
+        ));
+    }
+
+    /// Takes the prefetched [PostResponses] if they match the requested page.
+    pub fn take_prefetched(&mut self, page: u64) -> Option<PostResponses> {
+        match self.prefetched.take() {
+            Some((p, posts)) if p == page => Some(posts),
+            other => {
+                self.prefetched = other;
+                None
+            }
+        }
+    }
+
+    /// Cancels the prefetch task, if any.
+    pub fn cancel_prefetch_task(&mut self) {
+        if let Some(task) = self.prefetch_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Discards prefetched posts, and cancels the prefetch task.
+    ///
+    /// Call whenever the post listing parameters (sort, listing type, community) change.
+    pub fn invalidate_prefetch(&mut self) {
+        self.prefetched = None;
+        self.prefetch_failed = None;
+        self.cancel_prefetch_task();
+    }
+
+    /// Records a refresh of the [RefreshTarget], unless it was refreshed less than
+    /// [REFRESH_DEBOUNCE] ago.
+    ///
+    /// Returns whether to refresh, showing the time left in the status bar otherwise.
+    pub fn debounce_refresh(&mut self, target: RefreshTarget) -> bool {
+        let now = self.now();
+        let wait = self
+            .refreshed
+            .get(&target)
+            .and_then(|&last| (last + REFRESH_DEBOUNCE).duration_since(now).ok())
+            .filter(|wait| !wait.is_zero());
+
+        if let Some(wait) = wait {
+            let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            self.set_message(format!("refreshed recently, try again in {secs}s"));
+            return false;
+        }
+
+        self.refreshed
+            .retain(|_, &mut last| last + REFRESH_DEBOUNCE > now);
+        self.refreshed.insert(target, now);
+
+        true
+    }
+
+    /// Turns [Live] mode on, or off, for the current post.
+    pub fn toggle_live(&mut self) {
+        if self.live.take().is_some() {
+            self.set_message("live mode off");
+            return;
+        }
+
+        if self.is_offline() {
+            self.set_message("live mode is unavailable offline");
+            return;
+        }
+
+        if let Some(post_id) = self.posts.current().map(|p| p.post.id()) {
+            let interval = self.config.live_interval();
+
+            self.live = Some(Live {
+                post_id,
+                next: self.now() + interval,
+                failures: 0,
+            });
+            self.set_message(format!("live mode on, every {}s", interval.as_secs()));
+        }
+    }
+
+    /// Turns [Live] mode off after leaving the Post screen, or its post.
+    pub fn expire_live(&mut self) {
+        let post_id = self.posts.current().map(|p| p.post.id());
+        let left = self
+            .live
+            .is_some_and(|l| self.screen != Screen::Post || post_id != Some(l.post_id));
+
+        if left {
+            self.live = None;
+            self.set_message("live mode off");
+        }
+    }
+
+    /// Gets whether a [Live] refresh of the post is due, scheduling the next one if so.
+    pub fn take_live_refresh(&mut self, post_id: u64) -> bool {
+        let now = self.now();
+        let interval = self.config.live_interval();
+
+        match self.live.as_mut() {
+            Some(live) if live.post_id == post_id && live.next <= now => {
+                live.next = now + interval;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the result of a [Live] refresh of the post, turning live mode off after
+    /// [MAX_LIVE_FAILURES] consecutive failures.
+    pub fn live_refreshed<E: std::fmt::Display>(&mut self, post_id: u64, res: Result<usize, E>) {
+        let Some(live) = self.live.as_mut().filter(|l| l.post_id == post_id) else {
+            return;
+        };
+
+        match res {
+            Ok(added) => {
+                live.failures = 0;
+                match added {
+                    0 => (),
+                    1 => self.set_message("1 new comment"),
+                    n => self.set_message(format!("{n} new comments")),
+                }
+            }
+            Err(err) => {
+                live.failures += 1;
+                if live.failures >= MAX_LIVE_FAILURES {
+                    self.live = None;
+                    self.set_message(format!(
+                        "live mode off after {MAX_LIVE_FAILURES} failed refreshes: {err}"
+                    ));
+                } else {
+                    self.set_message(format!("live refresh failed: {err}"));
+                }
+            }
+        }
+    }
+
+    /// Gets the current [PostList](crate::endpoint::Endpoint) endpoint page.
+    pub fn page(&self) -> u64 {
+        self.page
+    }
+
+    /// Sets the page number, starting from 1.
+    pub fn set_page(&mut self, page: u64) {
+        self.page = page.max(1);
+    }
+
+    /// Increments the page number.
+    pub fn next_page(&mut self) -> u64 {
+        self.page = self.page.saturating_add(1);
+        self.page
+    }
+
+    /// Decrements the page number.
+    pub fn previous_page(&mut self) -> u64 {
+        if self.page > 1 {
+            self.page = self.page.saturating_sub(1);
+        }
+        self.page
+    }
+}
+
+impl AsRef<App> for App {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl AsMut<App> for App {
+    fn as_mut(&mut self) -> &mut Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::fake::FakeClient,
+        comments::{Comment, CommentResponse},
+        community::Community,
+        config::InstanceConfig,
+        posts::{Creator, Filters, Post, PostResponse},
+        screen::snapshot::fixture_app,
+        session::Session,
+        task::CommentsTask,
+    };
+
+    fn fake_client() -> FakeClient {
+        let posts = (1..=5)
+            .map(|id| PostResponse {
+                post: Post {
+                    id,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+
+        let comments = (1..=7)
+            .map(|id| CommentResponse {
+                comment: Comment {
+                    id,
+                    path: format!("0.{id}"),
+                    ..Default::default()
+                },
+                post: Post {
+                    id: 3,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+
+        FakeClient::new(posts, comments, 2)
+    }
+
+    #[tokio::test]
+    async fn test_post_comments_flow() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()))
+            .with_client(Box::new(fake_client()));
+
+        // first page, then prefetch the second
+        let posts = app.client.list_posts(app.page()).await.unwrap();
+        app.set_posts(posts);
+        app.posts.next();
+        app.posts.next();
+
+        assert!(app.should_prefetch());
+        app.prefetch_next_page();
+
+        let task = app.prefetch_task.take().unwrap();
+        let page = task.page();
+        app.prefetched = Some((page, task.join().await.unwrap()));
+
+        app.next_page();
+        let posts = app.take_prefetched(app.page()).unwrap();
+        app.set_posts(posts);
+        app.posts.next();
+
+        // comments for the selected post, downloaded in multiple pages
+        let post_id = app.posts.current().unwrap().post.id();
+        assert_eq!(post_id, 3);
+
+        let task = CommentsTask::spawn(app.client.clone(), post_id, 0..7, 3, false, None);
+        let comments = task.join().await.unwrap();
+        app.comments_insert(post_id, comments.into());
+
+        assert_eq!(app.comments_get(post_id).unwrap().items.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_load_cross_posts() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()))
+            .with_client(Box::new(fake_client()));
+
+        // downloaded in the background, once per post
+        app.load_cross_posts(1);
+        let task = app.cross_posts_task.take().unwrap();
+        assert_eq!(task.post_id(), 1);
+        app.set_cross_posts(1, task.join().await.unwrap().cross_posts);
+
+        app.load_cross_posts(1);
+        assert!(app.cross_posts_task.is_none());
+
+        // opening another post cancels the download for the previous one
+        app.load_cross_posts(2);
+        app.load_cross_posts(3);
+        assert_eq!(app.cross_posts_task.as_ref().map(|t| t.post_id()), Some(3));
+
+        app.shutdown();
+        assert!(app.cross_posts_task.is_none());
+
+        // kept for as many posts as the comments
+        app.set_comments_capacity(2);
+        (1..=4).for_each(|id| app.set_cross_posts(id, Vec::new()));
+        assert_eq!(app.cross_posts.len(), 2);
+        assert!(app.cross_posts(1).is_none());
+        assert!(app.cross_posts(4).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_failed() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()))
+            .with_client(Box::new(fake_client()));
+        let posts = app.client.list_posts(app.page()).await.unwrap();
+        app.set_posts(posts);
+        app.posts.last();
+        let now = app.now();
+        app.now = Some(now);
+        assert!(app.should_prefetch());
+        let next = app.page() + 1;
+
+        // the failed page is not requested again on every tick, and the error is shown once
+        let err = Error::Status {
+            code: 503,
+            message: "busy".into(),
+        };
+        app.set_prefetch_failed(next, &err);
+        assert!(!app.should_prefetch());
+        app.set_prefetch_failed(next, &err);
+        assert_eq!(app.error_log.len(), 1);
+
+        app.now = Some(now + PREFETCH_RETRY_DELAY);
+        assert!(app.should_prefetch());
+
+        // changing the listing forgets the failure
+        app.set_prefetch_failed(next, &err);
+        app.invalidate_prefetch();
+        assert!(app.should_prefetch());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()))
+            .with_client(Box::new(fake_client()));
+        let posts = app.client.list_posts(app.page()).await.unwrap();
+        app.set_posts(posts);
+        app.posts.last();
+
+        let post_id = app.posts.current().unwrap().post.id();
+        app.visits.visit(post_id, app.now());
+        app.prefetch_next_page();
+        app.comments_task = Some(CommentsTask::spawn(
+            Box::new(fake_client()),
+            post_id,
+            0..2,
+            1,
+            true,
+            None,
+        ));
+
+        // in-flight downloads are cancelled before the session is taken, and cannot land after
+        app.shutdown();
+        tokio::task::yield_now().await;
+        assert!(app.prefetch_task.is_none());
+        assert!(app.comments_task.is_none());
+        assert!(app.comments_get(post_id).is_none());
+
+        let session = Session::from_app(&app);
+        assert_eq!(session.post_id, Some(post_id));
+        assert!(session.visits.contains_key(&post_id));
+    }
+
+    #[test]
+    fn test_set_posts_nsfw() {
+        let posts = || {
+            let mut posts: Vec<PostResponse> = (1..=3)
+                .map(|id| PostResponse {
+                    post: Post {
+                        id,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .collect();
+            posts[1].post.nsfw = true;
+            // NSFW communities imply NSFW posts
+            posts[2].community.nsfw = true;
+
+            PostResponses {
+                posts,
+                next_page: None,
+            }
+        };
+        let ids = |app: &App| {
+            app.posts
+                .items
+                .iter()
+                .map(|p| p.post.id())
+                .collect::<Vec<_>>()
+        };
+
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        app.set_posts(posts());
+        assert_eq!(ids(&app), [1]);
+
+        app.config.nsfw = NsfwMode::Blur;
+        app.set_posts(posts());
+        assert_eq!(ids(&app), [1, 2, 3]);
+        assert!(!app.nsfw_blurred(&app.posts.items[0]));
+        assert!(app.nsfw_blurred(&app.posts.items[2]));
+
+        app.nsfw_revealed.insert(3);
+        assert!(!app.nsfw_blurred(&app.posts.items[2]));
+
+        app.config.nsfw = NsfwMode::Show;
+        assert!(!app.nsfw_blurred(&app.posts.items[1]));
+    }
+
+    #[test]
+    fn test_apply_filters() {
+        let posts = || {
+            let posts = [
+                (
+                    "[Meta] Site update",
+                    "https://lemmy.ml/post/1",
+                    "rust",
+                    "ferris",
+                ),
+                (
+                    "Meta analysis",
+                    "https://Blog.Example.com/a",
+                    "rust",
+                    "ferris",
+                ),
+                ("CRYPTO news", "", "rust", "ferris"),
+                ("Memes", "", "Memes", "ferris"),
+                ("Spam", "", "rust", "Spammer"),
+                ("Keep me", "https://notexample.com/", "rust", "ferris"),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, url, community, creator))| PostResponse {
+                post: Post {
+                    id: i as u64 + 1,
+                    name: name.into(),
+                    url: (!url.is_empty()).then(|| url.into()),
+                    ..Default::default()
+                },
+                community: Community {
+                    name: community.into(),
+                    actor_id: format!("https://lemmy.ml/c/{community}"),
+                    ..Default::default()
+                },
+                creator: Creator {
+                    name: creator.into(),
+                    actor_id: format!("https://sh.itjust.works/u/{creator}"),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+
+            PostResponses {
+                posts,
+                next_page: None,
+            }
+        };
+        let ids =
+            |posts: &PostResponses| posts.posts.iter().map(|p| p.post.id()).collect::<Vec<_>>();
+
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        let mut unfiltered = posts();
+        assert_eq!(app.apply_filters(&mut unfiltered), 0);
+        assert_eq!(unfiltered.posts.len(), 6);
+
+        app.post_filter = PostFilter::from_config(&Filters {
+            // the regex only matches at the start of the title, both ignore case
+            keywords: vec![r"/^\[meta\]/".into(), "crypto".into()],
+            domains: vec!["example.com".into()],
+            communities: vec!["!memes@LEMMY.ml".into(), "!other@lemmy.ml".into()],
+            creators: vec!["@spammer".into()],
+        })
+        .unwrap();
+
+        let mut filtered = posts();
+        assert_eq!(app.apply_filters(&mut filtered), 5);
+        assert_eq!(ids(&filtered), [6]);
+
+        // the community handle must match the instance
+        app.post_filter = PostFilter::from_config(&Filters {
+            communities: vec!["!memes@lemmy.world".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        let mut filtered = posts();
+        assert_eq!(app.apply_filters(&mut filtered), 0);
+
+        app.post_filter = PostFilter::from_config(&Filters {
+            keywords: vec!["META".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        app.set_posts(posts());
+        assert_eq!(app.posts.items.len(), 4);
+        assert_eq!(app.posts.filtered, 2);
+
+        app.filters_enabled = false;
+        app.set_posts(posts());
+        assert_eq!(app.posts.items.len(), 6);
+        assert_eq!(app.posts.filtered, 0);
+
+        assert!(PostFilter::from_config(&Filters {
+            keywords: vec!["/[/".into()],
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_link_picker() {
+        let links = |n| {
+            (1..=n)
+                .map(|i| format!("https://lemmy.ml/post/{i}"))
+                .collect()
+        };
+
+        let mut picker = LinkPicker::new(links(12));
+        // 1 could be 10 to 12
+        assert_eq!(picker.push_digit('1'), None);
+        assert_eq!(picker.selected(), Some("https://lemmy.ml/post/1"));
+        assert_eq!(picker.push_digit('2'), Some("https://lemmy.ml/post/12"));
+
+        let mut picker = LinkPicker::new(links(12));
+        assert_eq!(picker.push_digit('3'), Some("https://lemmy.ml/post/3"));
+
+        // numbers without a link start over
+        let mut picker = LinkPicker::new(links(3));
+        assert_eq!(picker.push_digit('0'), None);
+        assert_eq!(picker.push_digit('4'), None);
+        assert_eq!(picker.number, "");
+        assert_eq!(picker.selected(), None);
+        assert_eq!(picker.push_digit('2'), Some("https://lemmy.ml/post/2"));
+    }
+
+    #[test]
+    fn test_switch_instance() {
+        let mut app = fixture_app();
+        let home = app.instance_url().to_owned();
+        app.config.instances = vec![
+            InstanceConfig {
+                url: home.clone(),
+                account: Some("ferris".into()),
+                label: Some("home".into()),
+                ..Default::default()
+            },
+            InstanceConfig {
+                url: "https://programming.dev/".into(),
+                ..Default::default()
+            },
+        ];
+        app.set_jwt(Some("secret".into()));
+        app.set_page(3);
+        app.push_screen(Screen::Post);
+        let post_id = app.posts.current().unwrap().post.id();
+
+        app.open_instance_picker();
+        assert_eq!(app.instance_picker, Some(InstancePicker { selected: 0 }));
+        assert_eq!(app.instance_label(), "home");
+        assert_eq!(app.switch_instance(0), None);
+        assert_eq!(app.message(), "already on home");
+
+        // the posts, page, and screen are reset, the JWT is not sent to the other instance
+        assert_eq!(app.switch_instance(1), Some(Command::SwitchInstance));
+        assert!(!app.picking_instance());
+        assert_eq!(app.instance_url(), "https://programming.dev");
+        assert_eq!(app.instance_label(), "programming.dev");
+        assert_eq!(app.screen(), Screen::PostList);
+        assert!(app.posts.items.is_empty());
+        assert_eq!(app.page(), 1);
+        assert_eq!(app.jwt(), None);
+        assert!(app.comments_get(post_id).is_none());
+        assert_eq!(app.message(), "switched to programming.dev");
+
+        // switching back restores the JWT and comments
+        app.open_instance_picker();
+        assert_eq!(app.instance_picker, Some(InstancePicker { selected: 1 }));
+        assert_eq!(app.switch_instance(0), Some(Command::SwitchInstance));
+        assert_eq!(app.instance_url(), home);
+        assert_eq!(app.jwt().as_deref(), Some("secret"));
+        assert!(app.comments_get(post_id).is_some());
+
+        assert_eq!(app.switch_instance(5), None);
+    }
+
+    #[test]
+    fn test_switch_account() {
+        let mut app = fixture_app();
+        app.config.instances = vec![InstanceConfig {
+            url: app.instance_url().to_owned(),
+            accounts: vec!["ferris".into(), "ferris_mod".into()],
+            ..Default::default()
+        }];
+        app.set_jwt(Some("main".into()));
+        let post_id = app.posts.current().unwrap().post.id();
+        assert_eq!(
+            app.account_label().as_deref(),
+            Some("ferris@voyager.lemmy.ml")
+        );
+
+        app.open_account_picker();
+        assert_eq!(app.account_picker, Some(AccountPicker { selected: 0 }));
+        assert_eq!(app.switch_account(0), None);
+        assert_eq!(app.message(), "already logged in as ferris");
+
+        // responses for the other account are dropped, its JWT is loaded by the main loop
+        assert_eq!(app.switch_account(1), Some(Command::SwitchAccount));
+        assert!(!app.picking_account());
+        assert_eq!(app.config.active_account(), Some("ferris_mod"));
+        assert_eq!(app.jwt(), None);
+        assert_eq!(app.account_label(), None);
+        assert!(app.comments_get(post_id).is_none());
+        assert_eq!(app.message(), "switched to ferris_mod@voyager.lemmy.ml");
+
+        app.set_jwt(Some("mod".into()));
+        assert_eq!(
+            app.account_label().as_deref(),
+            Some("ferris_mod@voyager.lemmy.ml")
+        );
+
+        // each account keeps its own JWT
+        app.open_account_picker();
+        assert_eq!(app.account_picker, Some(AccountPicker { selected: 1 }));
+        app.switch_account(0);
+        assert_eq!(app.jwt().as_deref(), Some("main"));
+        app.switch_account(1);
+        assert_eq!(app.jwt().as_deref(), Some("mod"));
+
+        // a single account has nothing to switch to
+        app.config.instances[0].accounts.truncate(1);
+        app.open_account_picker();
+        assert!(!app.picking_account());
+    }
+
+    #[test]
+    fn test_error_log_screen() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        let other = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+
+        app.push_screen(Screen::Post);
+        app.set_error(&Error::Api("couldnt_find_post".into()));
+        app.open_error_log();
+
+        assert!(!app.has_error());
+        assert_eq!(app.screen(), Screen::ErrorLog);
+        assert_eq!(other.screen(), Screen::PostList, "each App owns its screen");
+
+        app.close_error_log();
+        assert_eq!(app.screen(), Screen::Post);
+    }
+
+    #[test]
+    fn test_log_orphaned_comments() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        let responses: CommentResponses = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/comment_malformed.json"
+        ))
+        .unwrap();
+
+        // logged without the error banner
+        app.comments_insert(10822441, CommentResponseTable::from(responses));
+        assert!(!app.has_error());
+        assert_eq!(app.error_log.len(), 1);
+        assert_eq!(
+            app.error_log[0].message(),
+            "JSON error: comments of post 10822441 with malformed paths, shown after the threads: \
+             8100600 ``, 8100610 `0.8100321.8100400`, 8100620 `0.8100321.x8100620`"
+        );
+
+        app.comments_insert(1, CommentResponseTable::new(Vec::new()));
+        assert_eq!(app.error_log.len(), 1);
+    }
+
+    #[test]
+    fn test_status_expiry() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        let start = SystemTime::UNIX_EPOCH;
+        let at = |secs: u64| Some(start + Duration::from_secs(secs));
+
+        app.now = at(0);
+        app.set_error(&Error::Api("couldnt_find_post".into()));
+        app.now = at(1);
+        app.set_message("copied");
+        app.now = at(2);
+        app.set_message("retrying");
+
+        // newest first
+        assert_eq!(app.message(), "retrying");
+        assert_eq!(app.status.len(), 3);
+
+        // older messages show again once newer ones expire
+        app.now = at(2 + STATUS_TIMEOUT.as_secs());
+        assert!(app.message().contains("couldnt_find_post"));
+
+        // expired messages are dropped, in any position
+        app.push_status(StatusMessage::new("short", start, Duration::from_secs(7)));
+        assert!(!app.expire_status());
+        assert_eq!(app.status.len(), 2);
+        assert_eq!(app.message(), "short");
+
+        // expiring changes the status bar, redrawn without input
+        app.now = at(ERROR_STATUS_TIMEOUT.as_secs());
+        assert_eq!(app.message(), "");
+        assert!(app.expire_status());
+        assert!(app.status.is_empty());
+        assert!(!app.expire_status());
+
+        app.set_message("copied");
+        app.clear_message();
+        assert_eq!(app.message(), "");
+    }
+
+    #[test]
+    fn test_debounce_refresh() {
+        let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+        let at = |millis: u64| Some(SystemTime::UNIX_EPOCH + Duration::from_millis(millis));
+
+        app.now = at(0);
+        assert!(app.debounce_refresh(RefreshTarget::Post(1)));
+        assert!(app.debounce_refresh(RefreshTarget::Post(2)), "other posts");
+        assert!(
+            app.debounce_refresh(RefreshTarget::Page(1)),
+            "other targets"
+        );
+
+        app.now = at(1500);
+        assert!(!app.debounce_refresh(RefreshTarget::Post(1)));
+        assert_eq!(app.message(), "refreshed recently, try again in 4s");
+
+        app.now = at(REFRESH_DEBOUNCE.as_millis() as u64);
+        assert!(app.debounce_refresh(RefreshTarget::Post(1)));
+        assert_eq!(app.refreshed.len(), 1, "expired refreshes are dropped");
+    }
+
+    #[test]
+    fn test_live() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let start = app.now();
+        let interval = app.config.live_interval();
+
+        app.toggle_live();
+        assert_eq!(app.message(), "live mode on, every 60s");
+        assert!(!app.take_live_refresh(post_id), "not due yet");
+
+        app.now = Some(start + interval);
+        assert!(!app.take_live_refresh(post_id + 1), "other posts");
+        assert!(app.take_live_refresh(post_id));
+        assert!(!app.take_live_refresh(post_id), "next one scheduled");
+        assert_eq!(app.live.map(|l| l.next), Some(start + interval * 2));
+
+        // failures in a row turn live mode off
+        app.live_refreshed(post_id, Err("timeout"));
+        assert_eq!(app.message(), "live refresh failed: timeout");
+        app.live_refreshed(post_id, Ok::<_, &str>(2));
+        assert_eq!(app.message(), "2 new comments");
+        for _ in 0..MAX_LIVE_FAILURES {
+            app.live_refreshed(post_id, Err("timeout"));
+        }
+        assert_eq!(app.live, None);
+        assert_eq!(
+            app.message(),
+            "live mode off after 3 failed refreshes: timeout"
+        );
+
+        app.toggle_live();
+        app.toggle_live();
+        assert_eq!((app.live, app.message()), (None, "live mode off"));
+
+        // leaving the post turns it off
+        app.toggle_live();
+        app.push_screen(Screen::Post);
+        app.expire_live();
+        assert!(app.live.is_some());
+        app.pop_screen();
+        app.expire_live();
+        assert_eq!(app.live, None);
+    }
+
+    #[test]
+    fn test_nav_stack() {
+        let posts = (1..=5)
+            .map(|id| PostResponse {
+                post: Post {
+                    id,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let mut app = App::new(Config::new(), PostResponseTable::new(posts));
+
+        // backing out of the first screen does nothing
+        assert_eq!(app.pop_screen(), None);
+        assert_eq!(app.screen(), Screen::PostList);
+
+        app.set_page(3);
+        app.posts.state.select(Some(2));
+        app.push_screen(Screen::Post);
+
+        app.posts.next();
+        app.post_scroll.set_content_length(10);
+        app.post_scroll.next();
+        app.comment_scroll.set_content_length(10);
+        app.comment_scroll.last();
+        app.push_screen(Screen::CommentList);
+
+        app.comment_scroll.first();
+        app.push_screen(Screen::ErrorLog);
+        assert_eq!(app.nav.len(), 3);
+
+        // each pop restores the context saved by the matching push
+        assert_eq!(app.pop_screen(), None);
+        assert_eq!(app.screen(), Screen::CommentList);
+        assert_eq!(app.comment_scroll.position(), 0);
+
+        assert_eq!(app.pop_screen(), None);
+        assert_eq!(app.screen(), Screen::Post);
+        assert_eq!(app.posts.state.selected(), Some(3));
+        assert_eq!(app.post_scroll.position(), 1);
+        assert_eq!(app.comment_scroll.position(), 9);
+
+        app.next_page();
+        assert_eq!(app.pop_screen(), Some(Command::DownloadPosts));
+        assert_eq!(app.screen(), Screen::PostList);
+        assert_eq!(app.posts.state.selected(), Some(2));
+        assert_eq!(app.post_scroll.position(), 0);
+        assert_eq!(app.page(), 3);
+
+        assert_eq!(app.pop_screen(), None);
+        assert_eq!(app.screen(), Screen::PostList);
+        assert!(app.nav.is_empty());
+    }
+
+    #[test]
+    fn test_scroll_content_length() {
+        let mut scroll = Scroll::create(8, 10, 5);
+
+        // e.g. after a resize, shorter content clamps the position
+        scroll.set_content_length(4);
+        assert_eq!(scroll.position(), 3);
+
+        scroll.set_content_length(20);
+        assert_eq!(scroll.position(), 3);
+
+        scroll.set_content_length(0);
+        assert_eq!(scroll.position(), 0);
+    }
+
+    #[test]
+    fn test_scroll_page() {
+        let mut scroll = Scroll::create(0, 23, 10);
+
+        scroll.page_down();
+        assert_eq!(scroll.position(), 10);
+
+        // the last page ends at the last line
+        scroll.page_down();
+        assert_eq!(scroll.position(), 13);
+        scroll.page_down();
+        assert_eq!(scroll.position(), 13);
+
+        scroll.page_up();
+        assert_eq!(scroll.position(), 3);
+        scroll.page_up();
+        assert_eq!(scroll.position(), 0);
+
+        scroll.last();
+        assert_eq!(scroll.position(), 13);
+
+        // content shorter than the viewport does not scroll
+        let mut scroll = Scroll::create(0, 4, 10);
+        scroll.last();
+        assert_eq!(scroll.position(), 0);
+        scroll.page_down();
+        assert_eq!(scroll.position(), 0);
     }
 }
@@ -0,0 +1,271 @@
+//! Command-line argument parsing.
+
+use std::path::PathBuf;
+
+use crate::{config::Config, posts::SortType};
+
+/// Usage text printed for `--help` and invalid arguments.
+pub const USAGE: &str = "\
+usage: temi [options] [<post or comment url>]
+
+options:
+  --instance <url>     Lemmy instance URL
+  --community <name>   only show posts from the community
+  --page <n>           start on page <n>
+  --sort <type>        post sort order, e.g. hot, new, top_day
+  --offline <dir>      read posts and comments from a directory instead of the network
+  --dump <dir>         save fetched posts and comments to a directory
+  --no-cache           only keep comments for the open post, and cache no images
+  --comment-limit <n>  comments per page, at most 50
+  --comment-fetch-cap <n>
+                       comments to download per post before loading more, 0 for all
+  --logout             delete the stored login for the instance and exit
+  --version            print the version and exit
+  -h, --help           print this help and exit";
+
+/// Kind of Lemmy object a [Link] points to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    /// Link to a post, e.g. `https://lemmy.world/post/1234`.
+    Post(u64),
+    /// Link to a comment, e.g. `https://lemmy.world/comment/1234`.
+    Comment(u64),
+    /// Link with an unrecognized path.
+    Unknown,
+}
+
+/// Link to a post or comment on a Lemmy instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    /// Instance base URL, e.g. `https://lemmy.world`.
+    pub instance_url: String,
+    /// Linked object.
+    pub kind: LinkKind,
+}
+
+impl Link {
+    /// Parses a [Link] from an `http(s)` URL.
+    pub fn parse(url: &str) -> Option<Self> {
+        let uri: hyper::Uri = url.parse().ok()?;
+
+        let scheme = uri
+            .scheme_str()
+            .filter(|s| matches!(*s, "http" | "https"))?;
+        let authority = uri.authority()?;
+
+        let mut segments = uri.path().split('/').filter(|s| !s.is_empty());
+
+        let kind = match (segments.next(), segments.next(), segments.next()) {
+            (Some("post"), Some(id), None) => id.parse().map_or(LinkKind::Unknown, LinkKind::Post),
+            (Some("comment"), Some(id), None) => {
+                id.parse().map_or(LinkKind::Unknown, LinkKind::Comment)
+            }
+            _ => LinkKind::Unknown,
+        };
+
+        Some(Self {
+            instance_url: format!("{scheme}://{authority}"),
+            kind,
+        })
+    }
+}
+
+/// Command-line arguments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    pub instance: Option<String>,
+    pub community: Option<String>,
+    pub page: Option<u64>,
+    pub sort: Option<SortType>,
+    pub offline: Option<PathBuf>,
+    pub dump: Option<PathBuf>,
+    pub no_cache: bool,
+    pub comment_limit: Option<u64>,
+    pub comment_fetch_cap: Option<u64>,
+    pub logout: bool,
+    pub version: bool,
+    pub help: bool,
+    pub link: Option<Link>,
+}
+
+impl Args {
+    /// Parses [Args] from the process arguments.
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Parses [Args], excluding the program name.
+    ///
+    /// Options accept their value as the next argument, or after an `=`, e.g. `--page=2`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.into_iter();
+
+        while let Some(arg) = iter.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, val)) if name.starts_with("--") => (name, Some(val.to_owned())),
+                _ => (arg.as_str(), None),
+            };
+
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| iter.next())
+                    .ok_or_else(|| format!("missing value for {name}"))
+            };
+
+            match name {
+                "--instance" => parsed.instance = Some(value()?),
+                "--community" => parsed.community = Some(value()?),
+                "--page" => {
+                    let page = value()?;
+                    parsed.page = Some(
+                        page.parse()
+                            .ok()
+                            .filter(|&p| p > 0)
+                            .ok_or_else(|| format!("invalid page: {page}"))?,
+                    );
+                }
+                "--sort" => parsed.sort = Some(value()?.parse().map_err(|err| format!("{err}"))?),
+                "--offline" => parsed.offline = Some(value()?.into()),
+                "--dump" => parsed.dump = Some(value()?.into()),
+                "--no-cache" => parsed.no_cache = true,
+                "--comment-limit" => {
+                    let limit = value()?;
+                    parsed.comment_limit = Some(
+                        limit
+                            .parse()
+                            .ok()
+                            .filter(|&l| l > 0)
+                            .ok_or_else(|| format!("invalid comment limit: {limit}"))?,
+                    );
+                }
+                "--comment-fetch-cap" => {
+                    let cap = value()?;
+                    parsed.comment_fetch_cap = Some(
+                        cap.parse()
+                            .map_err(|_| format!("invalid comment fetch cap: {cap}"))?,
+                    );
+                }
+                "--logout" => parsed.logout = true,
+                "--version" => parsed.version = true,
+                "-h" | "--help" => parsed.help = true,
+                url if !url.starts_with('-') && parsed.link.is_none() => {
+                    parsed.link =
+                        Some(Link::parse(url).ok_or_else(|| format!("invalid url: {url}"))?);
+                }
+                _ => return Err(format!("unknown argument: {arg}")),
+            }
+        }
+
+        if parsed.offline.is_some() && parsed.dump.is_some() {
+            return Err("--offline and --dump are mutually exclusive".into());
+        }
+
+        Ok(parsed)
+    }
+
+    /// Applies the [Args] to the [Config], overriding settings from the config file and
+    /// environment.
+    pub fn apply(&self, config: &mut Config) {
+        let link_instance = self
+            .link
+            .as_ref()
+            .filter(|l| l.kind != LinkKind::Unknown)
+            .map(|l| l.instance_url.clone());
+
+        if let Some(instance) = self.instance.clone().or(link_instance) {
+            config.instance_url = instance;
+        }
+        if let Some(community) = self.community.clone() {
+            config.community = Some(community);
+        }
+        if let Some(sort) = self.sort {
+            config.sort = Some(sort);
+        }
+        if let Some(limit) = self.comment_limit {
+            config.comment_limit = limit;
+        }
+        if let Some(cap) = self.comment_fetch_cap {
+            config.comment_fetch_cap = cap;
+        }
+        if self.no_cache {
+            config.comment_cache_size = 0;
+            config.image_cache_mb = 0;
+            config.image_disk_cache = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&[
+            "--instance",
+            "https://lemmy.ml",
+            "--page=3",
+            "--sort",
+            "new",
+            "--no-cache",
+            "--comment-limit=20",
+            "--comment-fetch-cap",
+            "0",
+            "--logout",
+            "https://lemmy.world/post/1234",
+        ])
+        .unwrap();
+
+        assert_eq!(args.instance.as_deref(), Some("https://lemmy.ml"));
+        assert_eq!(args.page, Some(3));
+        assert_eq!(args.sort, Some(SortType::New));
+        assert!(args.no_cache);
+        assert!(args.logout);
+        assert_eq!(
+            args.link,
+            Some(Link {
+                instance_url: "https://lemmy.world".into(),
+                kind: LinkKind::Post(1234),
+            })
+        );
+
+        let mut config = Config::new();
+        args.apply(&mut config);
+
+        // explicit instance wins over the link instance
+        assert_eq!(config.instance_url, "https://lemmy.ml");
+        assert_eq!(config.comment_cache_size, 0);
+        assert_eq!(config.image_cache_mb, 0);
+        assert!(!config.image_disk_cache);
+        assert_eq!(config.comment_limit(), 20);
+        assert_eq!(config.comment_fetch_cap(), None);
+
+        assert!(parse(&["--page", "0"]).is_err());
+        assert!(parse(&["--comment-limit", "0"]).is_err());
+        assert!(parse(&["--comment-fetch-cap", "-1"]).is_err());
+        assert!(parse(&["--sort", "sideways"]).is_err());
+        assert!(parse(&["--offline"]).is_err());
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["--offline", "a", "--dump", "b"]).is_err());
+        assert!(parse(&["not a url"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_link() {
+        assert_eq!(
+            Link::parse("https://lemmy.world/comment/987").map(|l| l.kind),
+            Some(LinkKind::Comment(987))
+        );
+        assert_eq!(
+            Link::parse("http://localhost:8536/c/rust").map(|l| l.kind),
+            Some(LinkKind::Unknown)
+        );
+        assert_eq!(Link::parse("ftp://lemmy.world/post/1"), None);
+    }
+}
@@ -0,0 +1,1238 @@
+//! HTTP client for making requests to a Lemmy instance.
+
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper::{
+    body::Bytes,
+    client::HttpConnector,
+    header::{ACCEPT_ENCODING, AUTHORIZATION, CONTENT_TYPE, LOCATION, USER_AGENT},
+    Body, Method, Response, StatusCode, Uri,
+};
+use hyper_tls::HttpsConnector;
+
+use crate::{
+    comments::CommentResponses,
+    diagnostics::{RequestLog, RequestRecord},
+    endpoint::Endpoint,
+    login::{Credentials, LoginRequest, LoginResponse},
+    posts::{PostResponses, PostsQuery},
+    task::Progress,
+    ApiError, Error, Result, Source,
+};
+
+mod body;
+mod encoding;
+#[cfg(test)]
+pub(crate) mod mock;
+mod retry;
+mod url;
+
+pub use body::*;
+pub use encoding::*;
+pub use retry::*;
+pub use url::*;
+
+/// Default timeout for a single request attempt.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Default maximum number of concurrent requests for multi-page downloads.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+/// Maximum number of redirects to follow for a single request.
+pub const MAX_REDIRECTS: usize = 5;
+/// `User-Agent` header sent with every request.
+pub const TEMI_USER_AGENT: &str = concat!("temi/", env!("CARGO_PKG_VERSION"));
+/// Maximum number of bytes from a response body to include in an [Error::Status] message.
+pub const STATUS_MESSAGE_LEN: usize = 200;
+
+/// Convenience alias for the [hyper::Client] type used in `temi`.
+pub type HttpsClient = hyper::Client<HttpsConnector<HttpConnector>>;
+
+/// Client for making requests to a Lemmy instance.
+///
+/// A single [HttpsClient] is shared across all requests, so connections are pooled, and only
+/// pay the TLS setup cost once.
+#[derive(Clone, Debug)]
+pub struct Client {
+    inner: HttpsClient,
+    /// Client that skips certificate verification, only used for the instance host.
+    insecure: Option<HttpsClient>,
+    instance_url: InstanceUrl,
+    /// Shared between clones, so a JWT renewed by one request is used by every clone.
+    jwt: Arc<Mutex<Option<String>>>,
+    /// Credentials to log in again with once the JWT expires.
+    credentials: Option<Credentials>,
+    /// Held while logging in again, so concurrent requests with the expired JWT log in once.
+    relogin: Arc<tokio::sync::Mutex<()>>,
+    retry: RetryPolicy,
+    timeout: Duration,
+    concurrency: usize,
+    posts_query: PostsQuery,
+    max_response_size: u64,
+    status: Arc<Mutex<Option<String>>>,
+    /// Log of the finished requests, for diagnostics, if any.
+    requests: Option<RequestLog>,
+}
+
+impl Client {
+    /// Creates a new [Client] for the Lemmy instance at `instance_url`.
+    ///
+    /// Both `http://` and `https://` instance URLs are supported, see [InstanceUrl].
+    pub fn new<S: Into<InstanceUrl>>(instance_url: S) -> Self {
+        let https = HttpsConnector::new();
+
+        Self {
+            inner: hyper::Client::builder().build::<_, Body>(https),
+            insecure: None,
+            instance_url: instance_url.into(),
+            jwt: Arc::new(Mutex::new(None)),
+            credentials: None,
+            relogin: Arc::new(tokio::sync::Mutex::new(())),
+            retry: RetryPolicy::new(),
+            timeout: DEFAULT_TIMEOUT,
+            concurrency: DEFAULT_CONCURRENCY,
+            posts_query: PostsQuery::new(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            status: Arc::new(Mutex::new(None)),
+            requests: None,
+        }
+    }
+
+    /// Builder function that sets the timeout for a single request attempt.
+    ///
+    /// The timeout covers waiting for the response headers, then waiting for each chunk of the
+    /// body, so downloads of any size complete as long as they keep making progress.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Gets the timeout for a single request attempt.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Builder function that sets the maximum number of concurrent requests for multi-page
+    /// downloads.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Gets the maximum number of concurrent requests for multi-page downloads.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Builder function that sets the maximum size of a response body, in bytes, before and
+    /// after decompression.
+    pub fn with_max_response_size(mut self, max_response_size: u64) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Gets the maximum size of a response body, in bytes.
+    pub fn max_response_size(&self) -> u64 {
+        self.max_response_size
+    }
+
+    /// Builder function that sets the [PostsQuery] for [get_posts](Self::get_posts).
+    pub fn with_posts_query(mut self, posts_query: PostsQuery) -> Self {
+        self.posts_query = posts_query;
+        self
+    }
+
+    /// Gets the [PostsQuery] for [get_posts](Self::get_posts).
+    pub fn posts_query(&self) -> &PostsQuery {
+        &self.posts_query
+    }
+
+    /// Sets the [PostsQuery] for [get_posts](Self::get_posts).
+    pub fn set_posts_query(&mut self, posts_query: PostsQuery) {
+        self.posts_query = posts_query;
+    }
+
+    /// Builder function that disables certificate verification for the instance host, e.g. for
+    /// instances with self-signed certificates.
+    ///
+    /// Other hosts, e.g. for images, are still verified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS connector could not be created, like [HttpsConnector::new].
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.insecure = accept.then(|| {
+            let tls = native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .build()
+                .unwrap_or_else(|err| panic!("insecure TLS connector failure: {err}"));
+
+            let mut http = HttpConnector::new();
+            http.enforce_http(false);
+
+            let https = HttpsConnector::from((http, tokio_native_tls::TlsConnector::from(tls)));
+
+            hyper::Client::builder().build::<_, Body>(https)
+        });
+        self
+    }
+
+    /// Gets whether certificate verification is disabled for the instance host.
+    pub fn accepts_invalid_certs(&self) -> bool {
+        self.insecure.is_some()
+    }
+
+    /// Builder function that sets the [RetryPolicy].
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Gets the [RetryPolicy].
+    pub fn retry(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// Gets the request status, e.g. `retrying in 3s…`, while a request is being retried.
+    ///
+    /// The status is shared between clones of the [Client].
+    pub fn status(&self) -> Option<String> {
+        self.status.lock().ok().and_then(|s| s.clone())
+    }
+
+    fn set_status(&self, status: Option<String>) {
+        if let Ok(mut s) = self.status.lock() {
+            *s = status;
+        }
+    }
+
+    /// Builder function that sets the [RequestLog] to record finished requests to.
+    pub fn with_request_log(mut self, requests: RequestLog) -> Self {
+        self.set_request_log(Some(requests));
+        self
+    }
+
+    /// Sets the [RequestLog] to record finished requests to, if any.
+    pub fn set_request_log(&mut self, requests: Option<RequestLog>) {
+        self.requests = requests;
+    }
+
+    /// Builder function that sets the JWT for the logged in user.
+    ///
+    /// The JWT is shared with clones of the [Client], see [set_jwt](Self::set_jwt).
+    pub fn with_jwt<S: Into<String>>(mut self, jwt: S) -> Self {
+        self.set_jwt(Some(jwt.into()));
+        self
+    }
+
+    /// Builder function that sets the [Credentials] to log in again with once the JWT expires.
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.set_credentials(Some(credentials));
+        self
+    }
+
+    /// Sets the [Credentials] to log in again with once the JWT expires.
+    pub fn set_credentials(&mut self, credentials: Option<Credentials>) {
+        self.credentials = credentials;
+    }
+
+    /// Gets the base URL of the Lemmy instance, without trailing slashes.
+    pub fn instance_url(&self) -> &str {
+        self.instance_url.as_str()
+    }
+
+    /// Gets the JWT for the logged in user, if any.
+    pub fn jwt(&self) -> Option<String> {
+        self.jwt.lock().ok().and_then(|jwt| jwt.clone())
+    }
+
+    /// Sets the JWT for the logged in user.
+    ///
+    /// The JWT is shared between clones of the [Client], e.g. background tasks log out too.
+    pub fn set_jwt(&mut self, jwt: Option<String>) {
+        self.replace_jwt(jwt);
+    }
+
+    fn replace_jwt(&self, jwt: Option<String>) {
+        if let Ok(mut j) = self.jwt.lock() {
+            *j = jwt;
+        }
+    }
+
+    /// Gets the JWT for the logged in user, returning an error if there is no logged in user.
+    pub fn auth(&self) -> Result<String> {
+        self.jwt()
+            .ok_or(Error::Auth("login required: set LEMMY_JWT".into()))
+    }
+
+    /// Gets the full URL for an [Endpoint] on the Lemmy instance, with the query `params`, if
+    /// any, see [InstanceUrl::join].
+    pub fn url(&self, endpoint: Endpoint, params: &[(&str, String)]) -> String {
+        self.instance_url.join(endpoint, params)
+    }
+
+    /// Sends a request, and returns the response body.
+    ///
+    /// Transient failures are retried according to the [RetryPolicy], and non-success
+    /// responses return an [Error::Status].
+    pub async fn send(&self, method: Method, url: &str, body: Option<&[u8]>) -> Result<Bytes> {
+        self.send_with_progress(method, url, body, None).await
+    }
+
+    /// Sends a request, and returns the response body, adding the bytes received to
+    /// `progress`, if any.
+    ///
+    /// Bodies over the [max_response_size](Self::max_response_size) fail with an
+    /// [Error::Http]. See [send](Self::send) for details.
+    ///
+    /// A request rejected for an expired JWT logs in again with the [Credentials], if any, and is
+    /// retried once with the new JWT, also replacing the expired JWT in the `auth` field of a JSON
+    /// body, as sent to Lemmy 0.18. Failing to log in again returns an [Error::Auth].
+    pub async fn send_with_progress(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&[u8]>,
+        progress: Option<&Progress>,
+    ) -> Result<Bytes> {
+        let sent_jwt = self.jwt();
+        let mut res = self.send_retry(method.clone(), url, body, progress).await;
+
+        if let Some(expired) = sent_jwt {
+            if self.credentials.is_some()
+                && !self.is_login(url)
+                && res.as_ref().is_err_and(Error::is_unauthorized)
+            {
+                res = match self.relogin(&expired).await {
+                    Ok(()) => {
+                        let body = body.map(|b| renew_auth(b, &expired, self.jwt().as_deref()));
+                        self.send_retry(method, url, body.as_deref(), progress)
+                            .await
+                    }
+                    Err(err) => Err(Error::Auth(format!(
+                        "login expired, logging in again failed: {err}"
+                    ))),
+                };
+            }
+        }
+
+        self.set_status(None);
+
+        res
+    }
+
+    /// Gets whether the URL is the [Login](Endpoint::Login) endpoint, which is sent no JWT, and
+    /// not retried by logging in again.
+    fn is_login(&self, url: &str) -> bool {
+        url == self.url(Endpoint::Login, &[])
+    }
+
+    /// Logs in again with the [Credentials], replacing the `expired` JWT.
+    ///
+    /// Requests failing at the same time log in once, the JWT renewed by the first is used by the
+    /// rest.
+    async fn relogin(&self, expired: &str) -> Result<()> {
+        let credentials = self
+            .credentials
+            .as_ref()
+            .ok_or(Error::Auth("no credentials to log in again with".into()))?;
+        let _guard = self.relogin.lock().await;
+
+        if self.jwt().as_deref() != Some(expired) {
+            return Ok(());
+        }
+
+        self.set_status(Some("logging in again…".into()));
+
+        // sent directly, a failed login is not retried by logging in again
+        let url = self.url(Endpoint::Login, &[]);
+        let body = serde_json::to_vec(&LoginRequest::from(credentials))?;
+        let res = self
+            .send_retry(Method::POST, url.as_str(), Some(&body), None)
+            .await?;
+        let jwt = parse_json::<LoginResponse>(&res)?.into_jwt()?;
+
+        self.replace_jwt(Some(jwt));
+
+        Ok(())
+    }
+
+    /// Sends a request with retries, see [send_attempts](Self::send_attempts), recording it to
+    /// the [RequestLog], if any.
+    async fn send_retry(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&[u8]>,
+        progress: Option<&Progress>,
+    ) -> Result<Bytes> {
+        let start = Instant::now();
+        let res = self
+            .send_attempts(method.clone(), url, body, progress)
+            .await;
+
+        if let Some(requests) = self.requests.as_ref() {
+            let (status, bytes) = match &res {
+                Ok((status, body)) => (Some(status.as_u16()), body.len() as u64),
+                Err(Error::Status { code, .. }) => (Some(*code), 0),
+                Err(_) => (None, 0),
+            };
+
+            requests.push(RequestRecord {
+                method: method.to_string(),
+                url: url.to_owned(),
+                status,
+                duration: start.elapsed(),
+                bytes,
+            });
+        }
+
+        res.map(|(_, body)| body)
+    }
+
+    /// Sends a request, retrying transient failures, and returns the status and body of a
+    /// successful response.
+    async fn send_attempts(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&[u8]>,
+        progress: Option<&Progress>,
+    ) -> Result<(StatusCode, Bytes)> {
+        let max_retries = self.retry.max_retries;
+        let mut attempt = 0;
+
+        loop {
+            let delay = match self
+                .with_deadline(url, self.request(method.clone(), url, body))
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+
+                    if is_retryable(&method, status) && attempt < max_retries {
+                        self.retry.delay(attempt, Some(response.headers()))
+                    } else {
+                        let headers = response.headers().clone();
+                        let max_len = self.max_response_size;
+                        let res_body =
+                            read_body(url, response.into_body(), max_len, self.timeout, progress)
+                                .await?;
+                        let res_body = decode_body(&headers, res_body, max_len)?;
+
+                        return if status.is_success() {
+                            Ok((status, res_body))
+                        } else {
+                            Err(status_error(status, &res_body))
+                        };
+                    }
+                }
+                Err(Error::Connect(_)) if attempt < max_retries => self.retry.delay(attempt, None),
+                Err(err) => return Err(err),
+            };
+
+            attempt += 1;
+
+            self.set_status(Some(format!(
+                "retrying in {}s… ({attempt}/{max_retries})",
+                delay.as_secs_f32().ceil()
+            )));
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Runs a request future, returning [Error::Timeout] if it does not complete within the
+    /// [Client] timeout.
+    ///
+    /// Only covers the response headers. [read_body] times out the body separately, per chunk.
+    async fn with_deadline<T, F>(&self, url: &str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        tokio::time::timeout(self.timeout, fut)
+            .await
+            .map_err(|_| Error::Timeout(url.into()))?
+    }
+
+    /// Sends a request, following up to [MAX_REDIRECTS] redirects.
+    ///
+    /// Redirects with `301`, `302`, or `303` continue with a GET, without the body. `307` and `308`
+    /// keep the method and body, and fail with an [Error::Http] when redirecting a body to a host
+    /// other than the instance, since request bodies may carry the JWT as `auth`.
+    ///
+    /// Every request sends a [TEMI_USER_AGENT] `User-Agent` header, an
+    /// [ACCEPT_ENCODING_VALUE] `Accept-Encoding` header, and the JWT, if any, as an
+    /// `Authorization: Bearer` header. The JWT is only sent to the instance host, not to image
+    /// hosts, or redirects to other hosts, and not to the [Login](Endpoint::Login) endpoint,
+    /// where an expired JWT would fail the login.
+    async fn request(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&[u8]>,
+    ) -> Result<Response<Body>> {
+        let mut method = method;
+        let mut body = body;
+        let mut uri = Uri::from_str(url)?;
+        let instance = self.instance_url.authority();
+        let jwt = self.jwt().filter(|_| !self.is_login(url));
+
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request = hyper::Request::builder()
+                .method(method.clone())
+                .uri(uri.clone())
+                .header(USER_AGENT, TEMI_USER_AGENT)
+                .header(ACCEPT_ENCODING, ACCEPT_ENCODING_VALUE);
+
+            if let Some(jwt) = jwt.as_deref() {
+                if instance.is_some() && uri.authority() == instance.as_ref() {
+                    request = request.header(AUTHORIZATION, format!("Bearer {jwt}"));
+                }
+            }
+
+            let req_body = match body {
+                Some(b) if method != Method::GET => {
+                    request = request.header(CONTENT_TYPE, "application/json");
+                    Body::from(b.to_vec())
+                }
+                _ => Body::empty(),
+            };
+
+            let client = match self.insecure.as_ref() {
+                Some(insecure) if uri.authority() == instance.as_ref() => insecure,
+                _ => &self.inner,
+            };
+
+            let response = client
+                .request(request.body(req_body)?)
+                .await
+                .map_err(|err| {
+                    if err.is_connect() {
+                        Error::Connect(Source::new(err))
+                    } else {
+                        err.into()
+                    }
+                })?;
+            let status = response.status();
+
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .ok_or(Error::Status {
+                        code: status.as_u16(),
+                        message: "redirect without a Location header".into(),
+                    })?;
+
+                uri = redirect_uri(&uri, location)?;
+
+                // only `307` and `308` require preserving the method and body
+                if !matches!(
+                    status,
+                    StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+                ) {
+                    method = Method::GET;
+                    body = None;
+                }
+
+                if body.is_some() && uri.authority() != instance.as_ref() {
+                    return Err(Error::Http(Source::msg(format!(
+                        "refusing to redirect a request body to another host: {uri}"
+                    ))));
+                }
+
+                continue;
+            }
+
+            return Ok(response);
+        }
+
+        Err(Error::Http(Source::msg(format!(
+            "too many redirects: {url}"
+        ))))
+    }
+
+    /// Sends a GET request, and returns the response body.
+    pub async fn get_bytes(&self, url: &str) -> Result<Bytes> {
+        self.send(Method::GET, url, None).await
+    }
+
+    /// Sends a GET request, and parses the JSON response.
+    ///
+    /// With the `debug_endpoints` feature, the response body is captured by the
+    /// [DebugWriter](crate::utils::debug_writer::DebugWriter).
+    pub async fn get_json<R>(&self, url: &str) -> Result<R>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        self.get_json_with_progress(url, None).await
+    }
+
+    /// Sends a GET request, and parses the JSON response, adding the bytes received to
+    /// `progress`, if any.
+    ///
+    /// See [get_json](Self::get_json) for details.
+    pub async fn get_json_with_progress<R>(
+        &self,
+        url: &str,
+        progress: Option<&Progress>,
+    ) -> Result<R>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let body = self
+            .send_with_progress(Method::GET, url, None, progress)
+            .await?;
+
+        #[cfg(feature = "debug_endpoints")]
+        crate::utils::debug_writer::DebugWriter::global().write("GET", url, &body)?;
+
+        parse_json(&body)
+    }
+
+    /// Sends a POST request with a JSON body, and parses the JSON response.
+    ///
+    /// With the `debug_endpoints` feature, the response body is captured by the
+    /// [DebugWriter](crate::utils::debug_writer::DebugWriter).
+    pub async fn post_json<T, R>(&self, url: &str, body: &T) -> Result<R>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let body = self
+            .send(Method::POST, url, Some(&serde_json::to_vec(body)?))
+            .await?;
+
+        #[cfg(feature = "debug_endpoints")]
+        crate::utils::debug_writer::DebugWriter::global().write("POST", url, &body)?;
+
+        parse_json(&body)
+    }
+
+    /// Sends a PUT request with a JSON body, and parses the JSON response.
+    ///
+    /// See [post_json](Self::post_json) for details.
+    pub async fn put_json<T, R>(&self, url: &str, body: &T) -> Result<R>
+    where
+        T: serde::Serialize,
+        R: serde::de::DeserializeOwned,
+    {
+        let body = self
+            .send(Method::PUT, url, Some(&serde_json::to_vec(body)?))
+            .await?;
+
+        #[cfg(feature = "debug_endpoints")]
+        crate::utils::debug_writer::DebugWriter::global().write("PUT", url, &body)?;
+
+        parse_json(&body)
+    }
+
+    /// Gets a page of responses from the [PostList](Endpoint::PostList) endpoint.
+    pub async fn get_posts(&self, page: u64) -> Result<PostResponses> {
+        self.get_posts_params(self.posts_query.params(page)).await
+    }
+
+    /// Gets the page of responses from the [PostList](Endpoint::PostList) endpoint at the
+    /// `cursor` returned in [PostResponses::next_page] (Lemmy 0.19 and later).
+    pub async fn get_posts_cursor(&self, cursor: &str) -> Result<PostResponses> {
+        self.get_posts_params(self.posts_query.cursor_params(cursor))
+            .await
+    }
+
+    async fn get_posts_params(&self, params: Vec<(&str, String)>) -> Result<PostResponses> {
+        let url = self.url(Endpoint::PostList, &params);
+
+        self.get_json(url.as_str()).await
+    }
+
+    /// Gets a page of responses from the [CommentList](Endpoint::CommentList) endpoint, adding
+    /// the bytes received to `progress`, if any.
+    pub async fn get_comments(
+        &self,
+        post_id: u64,
+        page: u64,
+        limit: u64,
+        progress: Option<&Progress>,
+    ) -> Result<CommentResponses> {
+        let url = self.url(
+            Endpoint::CommentList,
+            &[
+                ("post_id", post_id.to_string()),
+                ("page", page.to_string()),
+                ("limit", limit.to_string()),
+            ],
+        );
+
+        self.get_json_with_progress(url.as_str(), progress).await
+    }
+
+    /// Gets the replies to a comment, up to `max_depth` levels below it, from the
+    /// [CommentList](Endpoint::CommentList) endpoint.
+    pub async fn get_replies(&self, parent_id: u64, max_depth: u64) -> Result<CommentResponses> {
+        let url = self.url(
+            Endpoint::CommentList,
+            &[
+                ("parent_id", parent_id.to_string()),
+                ("max_depth", max_depth.to_string()),
+            ],
+        );
+
+        self.get_json(url.as_str()).await
+    }
+
+    /// Gets the bytes of a [Post](crate::posts::Post) image, adding the bytes received to
+    /// `progress`, if any.
+    pub async fn get_image(&self, url: &str, progress: Option<&Progress>) -> Result<Vec<u8>> {
+        Ok(self
+            .send_with_progress(Method::GET, url, None, progress)
+            .await?
+            .to_vec())
+    }
+}
+
+/// Gets the [Uri] for a redirect `location`, which may be relative to the current [Uri].
+fn redirect_uri(current: &Uri, location: &str) -> Result<Uri> {
+    let uri = Uri::from_str(location)?;
+
+    if uri.scheme().is_some() {
+        Ok(uri)
+    } else {
+        let mut parts = uri.into_parts();
+        parts.scheme = current.scheme().cloned();
+        parts.authority = current.authority().cloned();
+
+        Uri::from_parts(parts).map_err(|err| Error::Http(Source::new(err)))
+    }
+}
+
+/// Creates an [Error::Status] from a non-success response.
+///
+/// Lemmy API error bodies are used as the message, otherwise the first
+/// [STATUS_MESSAGE_LEN] bytes of the body are used.
+fn status_error(status: StatusCode, body: &[u8]) -> Error {
+    let message = match serde_json::from_slice::<ApiError>(body) {
+        Ok(api_err) => api_err.error,
+        Err(_) => {
+            let len = body.len().min(STATUS_MESSAGE_LEN);
+            String::from_utf8_lossy(&body[..len]).into_owned()
+        }
+    };
+
+    Error::Status {
+        code: status.as_u16(),
+        message,
+    }
+}
+
+/// Gets the JSON request body with the `expired` JWT in its `auth` field replaced by the renewed
+/// `jwt`.
+///
+/// Other bodies are returned as is.
+fn renew_auth(body: &[u8], expired: &str, jwt: Option<&str>) -> Vec<u8> {
+    let renewed = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|mut value| {
+            let auth = value.get_mut("auth").filter(|auth| *auth == expired)?;
+            *auth = jwt?.into();
+            serde_json::to_vec(&value).ok()
+        });
+
+    renewed.unwrap_or_else(|| body.to_vec())
+}
+
+/// Parses a JSON response body.
+///
+/// Lemmy API error bodies, e.g. `{"error": "couldnt_find_post"}`, are returned as
+/// [Error::Api].
+fn parse_json<R>(body: &[u8]) -> Result<R>
+where
+    R: serde::de::DeserializeOwned,
+{
+    serde_json::from_slice::<R>(body).map_err(|err| {
+        match serde_json::from_slice::<ApiError>(body) {
+            Ok(api_err) => Error::Api(api_err.error),
+            Err(_) => err.into(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use hyper::Response;
+
+    use super::mock::serve;
+    use super::*;
+
+    fn status_response(code: u16, body: &'static str) -> Response<Body> {
+        Response::builder()
+            .status(code)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connection_reuse() {
+        let (addr, connections) = serve(|_| Response::new(Body::from(r#"{"posts":[]}"#)));
+
+        let client = Client::new(format!("http://{addr}"));
+
+        for page in 1..=3 {
+            assert!(client.get_posts(page).await.unwrap().posts.is_empty());
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_headers() {
+        let (addr, _) = serve(|req| {
+            let header = |name| {
+                req.headers()
+                    .get(name)
+                    .and_then(|h| h.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned()
+            };
+
+            if header(USER_AGENT) == TEMI_USER_AGENT && header(AUTHORIZATION) == "Bearer token" {
+                Response::new(Body::from(r#"{"posts":[]}"#))
+            } else {
+                status_response(400, "missing headers")
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}")).with_jwt("token");
+
+        assert!(client.get_posts(1).await.unwrap().posts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response() {
+        use std::io::Write;
+
+        let (addr, _) = serve(|req| {
+            let accepts_gzip = req
+                .headers()
+                .get(ACCEPT_ENCODING)
+                .and_then(|h| h.to_str().ok())
+                .is_some_and(|h| h.contains("gzip"));
+
+            if accepts_gzip {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(br#"{"posts":[]}"#).unwrap();
+
+                Response::builder()
+                    .header(hyper::header::CONTENT_ENCODING, "gzip")
+                    .body(Body::from(encoder.finish().unwrap()))
+                    .unwrap()
+            } else {
+                Response::new(Body::from(r#"{"posts":[]}"#))
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+
+        assert!(client.get_posts(1).await.unwrap().posts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_posts_cursor() {
+        let (addr, _) = serve(|req| {
+            let body = match req.uri().query() {
+                Some("page_cursor=Pa42") => r#"{"posts":[]}"#,
+                _ => r#"{"posts":[],"next_page":"Pa42"}"#,
+            };
+
+            Response::new(Body::from(body))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+
+        let first = client.get_posts(1).await.unwrap();
+        assert_eq!(first.next_page.as_deref(), Some("Pa42"));
+
+        let next = client.get_posts_cursor("Pa42").await.unwrap();
+        assert_eq!(next.next_page, None);
+    }
+
+    #[tokio::test]
+    async fn test_status_html() {
+        let (addr, _) = serve(|_| {
+            status_response(
+                500,
+                "<html><head><title>500 Internal Server Error</title></head></html>",
+            )
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+
+        match client.get_posts(1).await {
+            Err(Error::Status { code, message }) => {
+                assert_eq!(code, 500);
+                assert!(message.starts_with("<html>"));
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_long_body() {
+        let (addr, _) = serve(|_| status_response(503, include_str!("../LICENSE")));
+
+        let client = Client::new(format!("http://{addr}")).with_retry(RetryPolicy::none());
+
+        match client.get_posts(1).await {
+            Err(Error::Status { code, message }) => {
+                assert_eq!(code, 503);
+                assert_eq!(message.len(), STATUS_MESSAGE_LEN);
+            }
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_api_error() {
+        let (addr, _) = serve(|_| status_response(400, r#"{"error":"couldnt_get_posts"}"#));
+
+        let client = Client::new(format!("http://{addr}"));
+
+        assert_eq!(
+            client.get_posts(1).await,
+            Err(Error::Status {
+                code: 400,
+                message: "couldnt_get_posts".into()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_deleted_post() {
+        let (addr, _) = serve(|_| status_response(404, r#"{"error":"couldnt_find_post"}"#));
+
+        let client = Client::new(format!("http://{addr}"));
+
+        // refreshing a deleted post marks it as deleted, instead of showing the error
+        let err = client.get_post_view(1).await.unwrap_err();
+        assert_eq!(err.api_message(), Some("couldnt_find_post"));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json() {
+        let (addr, _) = serve(|_| Response::new(Body::from(r#"{"posts":[{"post":"#)));
+
+        let client = Client::new(format!("http://{addr}"));
+
+        assert!(matches!(client.get_posts(1).await, Err(Error::Json(_))));
+    }
+
+    #[tokio::test]
+    async fn test_redirect() {
+        let (addr, _) = serve(|req| {
+            if req.uri().path() == "/old/api/v3/post/list" {
+                Response::builder()
+                    .status(301)
+                    .header(LOCATION, "/api/v3/post/list?page=1")
+                    .body(Body::empty())
+                    .unwrap()
+            } else {
+                Response::new(Body::from(r#"{"posts":[]}"#))
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}/old"));
+
+        assert!(client.get_posts(1).await.unwrap().posts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_other_host() {
+        static OTHER: std::sync::OnceLock<std::net::SocketAddr> = std::sync::OnceLock::new();
+        static BODIES: AtomicUsize = AtomicUsize::new(0);
+
+        let (other, _) = serve(|req| {
+            if req.method() != Method::GET || req.headers().contains_key(CONTENT_TYPE) {
+                BODIES.fetch_add(1, Ordering::SeqCst);
+            }
+            Response::new(Body::from(r#"{"posts":[]}"#))
+        });
+        OTHER.set(other).unwrap();
+
+        let (addr, _) = serve(|req| {
+            let status = match req.uri().path() {
+                "/found" => 302,
+                _ => 307,
+            };
+
+            Response::builder()
+                .status(status)
+                .header(LOCATION, format!("http://{}/api", OTHER.get().unwrap()))
+                .body(Body::empty())
+                .unwrap()
+        });
+
+        let client = Client::new(format!("http://{addr}")).with_jwt("token");
+        let body = serde_json::json!({ "auth": "token" });
+
+        // the body, with the JWT, is not sent on to the other host
+        let res = client
+            .post_json::<_, PostResponses>(&format!("http://{addr}/save"), &body)
+            .await;
+        assert!(matches!(res, Err(Error::Http(_))), "{res:?}");
+
+        // other redirects continue with a GET, without the body
+        let res = client
+            .post_json::<_, PostResponses>(&format!("http://{addr}/found"), &body)
+            .await;
+        assert!(res.unwrap().posts.is_empty());
+        assert_eq!(BODIES.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|_| {
+            if REQUESTS.fetch_add(1, Ordering::SeqCst) < 2 {
+                Response::builder()
+                    .status(429)
+                    .header(hyper::header::RETRY_AFTER, "0")
+                    .body(Body::empty())
+                    .unwrap()
+            } else {
+                Response::new(Body::from(r#"{"posts":[]}"#))
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+
+        assert!(client.get_posts(1).await.unwrap().posts.is_empty());
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 3);
+        assert_eq!(client.status(), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhausted() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|_| {
+            REQUESTS.fetch_add(1, Ordering::SeqCst);
+            status_response(503, "unavailable")
+        });
+
+        let retry = RetryPolicy::new()
+            .with_max_retries(2)
+            .with_base_delay(Duration::from_millis(1));
+        let client = Client::new(format!("http://{addr}")).with_retry(retry);
+
+        assert_eq!(
+            client.get_posts(1).await,
+            Err(Error::Status {
+                code: 503,
+                message: "unavailable".into()
+            })
+        );
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_post() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|_| {
+            REQUESTS.fetch_add(1, Ordering::SeqCst);
+            status_response(502, "bad gateway")
+        });
+
+        let retry = RetryPolicy::new()
+            .with_max_retries(2)
+            .with_base_delay(Duration::from_millis(1));
+        let client = Client::new(format!("http://{addr}")).with_retry(retry);
+
+        // the comment may have been posted behind the gateway, so is not posted again
+        let body = serde_json::json!({ "content": "hi" });
+        let res = client
+            .post_json::<_, PostResponses>(&format!("http://{addr}/api/v3/comment"), &body)
+            .await;
+        assert!(matches!(res, Err(Error::Status { code: 502, .. })));
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|_| {
+            REQUESTS.fetch_add(1, Ordering::SeqCst);
+            status_response(404, "not found")
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+
+        assert!(matches!(
+            client.get_posts(1).await,
+            Err(Error::Status { code: 404, .. })
+        ));
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // accept connections, but never respond
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}"))
+            .with_timeout(Duration::from_millis(50))
+            .with_retry(RetryPolicy::none());
+
+        assert_eq!(
+            client.get_posts(1).await,
+            Err(Error::Timeout(format!(
+                "http://{addr}/api/v3/post/list?page=1"
+            )))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_slow_body() {
+        // 5 chunks 30ms apart, taking longer than the timeout in total
+        let (addr, _) = serve(|_| {
+            let (mut tx, body) = Body::channel();
+            tokio::spawn(async move {
+                for chunk in ["{\"posts\"", ":", "[", "]", "}"] {
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                    tx.send_data(chunk.into()).await.unwrap();
+                }
+            });
+            Response::new(body)
+        });
+
+        let client = Client::new(format!("http://{addr}"))
+            .with_timeout(Duration::from_millis(100))
+            .with_retry(RetryPolicy::none());
+
+        let posts = client.get_posts(1).await.unwrap();
+        assert!(posts.posts.is_empty());
+    }
+
+    fn bearer(req: &hyper::Request<Body>) -> Option<&str> {
+        req.headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_relogin() {
+        static LOGINS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|req| match (req.uri().path(), bearer(&req)) {
+            // the expired JWT is not sent to the login endpoint
+            ("/api/v3/user/login", None) => {
+                LOGINS.fetch_add(1, Ordering::SeqCst);
+                Response::new(Body::from(r#"{"jwt":"fresh"}"#))
+            }
+            (_, Some("Bearer fresh")) => Response::new(Body::from(r#"{"posts":[]}"#)),
+            _ => status_response(400, r#"{"error":"not_logged_in"}"#),
+        });
+
+        let client = Client::new(format!("http://{addr}"))
+            .with_jwt("expired")
+            .with_credentials(Credentials::new("ferris", "hunter2"));
+        let task_client = client.clone();
+
+        let (first, second) = tokio::join!(client.get_posts(1), task_client.get_posts(2));
+        assert!(first.unwrap().posts.is_empty());
+        assert!(second.unwrap().posts.is_empty());
+
+        // logged in once, the renewed JWT is shared with clones
+        assert_eq!(LOGINS.load(Ordering::SeqCst), 1);
+        assert_eq!(client.jwt().as_deref(), Some("fresh"));
+        assert_eq!(task_client.jwt().as_deref(), Some("fresh"));
+        assert_eq!(client.status(), None);
+    }
+
+    #[tokio::test]
+    async fn test_relogin_body() {
+        let (addr, _) = serve(|req| match req.uri().path() {
+            "/api/v3/user/login" => Response::new(Body::from(r#"{"jwt":"fresh"}"#)),
+            _ => {
+                // Lemmy 0.18 reads the JWT from the body, already read in full by the mock server
+                let body = futures::executor::block_on(hyper::body::to_bytes(req.into_body()));
+                let body: serde_json::Value = serde_json::from_slice(&body.unwrap()).unwrap();
+
+                if body["auth"] == "fresh" && body["post_id"] == 7 {
+                    Response::new(Body::from(r#"{"posts":[]}"#))
+                } else {
+                    status_response(400, r#"{"error":"not_logged_in"}"#)
+                }
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}"))
+            .with_jwt("expired")
+            .with_credentials(Credentials::new("ferris", "hunter2"));
+        let body = serde_json::json!({ "post_id": 7, "save": true, "auth": "expired" });
+
+        let res = client
+            .post_json::<_, PostResponses>(&format!("http://{addr}/api/v3/post/save"), &body)
+            .await;
+        assert!(res.unwrap().posts.is_empty());
+        assert_eq!(client.jwt().as_deref(), Some("fresh"));
+
+        assert_eq!(
+            renew_auth(b"not json", "expired", Some("fresh")),
+            b"not json"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relogin_failed() {
+        static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+        let (addr, _) = serve(|req| {
+            REQUESTS.fetch_add(1, Ordering::SeqCst);
+
+            match req.uri().path() {
+                "/api/v3/user/login" => status_response(400, r#"{"error":"incorrect_login"}"#),
+                _ => status_response(401, r#"{"error":"not_logged_in"}"#),
+            }
+        });
+
+        let client = Client::new(format!("http://{addr}"))
+            .with_jwt("expired")
+            .with_credentials(Credentials::new("ferris", "wrong"));
+
+        // the request, and one login, without retrying the request
+        match client.get_posts(1).await {
+            Err(Error::Auth(message)) => assert!(message.contains("incorrect_login")),
+            res => panic!("unexpected result: {res:?}"),
+        }
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 2);
+        assert_eq!(client.jwt().as_deref(), Some("expired"));
+
+        // logging in directly is not retried by logging in again
+        let res = client.login(&Credentials::new("ferris", "wrong")).await;
+        assert!(matches!(res, Err(Error::Status { code: 400, .. })));
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 3);
+
+        // without credentials, the error is returned as is
+        let client = Client::new(format!("http://{addr}")).with_jwt("expired");
+        let res = client.get_posts(1).await;
+        assert!(matches!(res, Err(Error::Status { code: 401, .. })));
+        assert_eq!(REQUESTS.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,134 @@
+//! Types and functions for reading response bodies in chunks.
+
+use std::time::Duration;
+
+use hyper::body::{Bytes, HttpBody};
+
+use crate::{task::Progress, Error, Result, Source};
+
+/// Default maximum size of a response body, before and after decompression.
+pub const DEFAULT_MAX_RESPONSE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Reads a response body chunk by chunk, adding the bytes received to `progress`, if any.
+///
+/// Bodies over `max_len` bytes fail with an [Error::Http], without reading the rest of the body.
+/// A `Content-Length` over the limit fails before reading any of it.
+///
+/// Waiting over `timeout` for the next chunk of the body from `url` fails with an
+/// [Error::Timeout]. The timeout restarts with every chunk, so a slow but steady download of a
+/// large body does not time out.
+pub async fn read_body<B>(
+    url: &str,
+    mut body: B,
+    max_len: u64,
+    timeout: Duration,
+    progress: Option<&Progress>,
+) -> Result<Bytes>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+    B::Error: Into<Error>,
+{
+    let expected = body.size_hint().exact();
+    if expected.is_some_and(|len| len > max_len) {
+        return Err(too_large(max_len));
+    }
+    if let (Some(progress), Some(len)) = (progress, expected) {
+        progress.add_bytes_total(len);
+    }
+
+    let mut buf = Vec::with_capacity(expected.unwrap_or(0) as usize);
+    while let Some(chunk) = tokio::time::timeout(timeout, body.data())
+        .await
+        .map_err(|_| Error::Timeout(url.into()))?
+    {
+        let chunk = chunk.map_err(Into::into)?;
+        if (buf.len() + chunk.len()) as u64 > max_len {
+            return Err(too_large(max_len));
+        }
+
+        if let Some(progress) = progress {
+            progress.add_bytes(chunk.len() as u64);
+        }
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(buf.into())
+}
+
+/// Creates the [Error] for a response body over `max_len` bytes.
+pub(crate) fn too_large(max_len: u64) -> Error {
+    Error::Http(Source::msg(format!(
+        "response body over the {max_len} byte limit"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::Body;
+
+    use super::*;
+
+    const URL: &str = "http://localhost/api/v3/post/list";
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[tokio::test]
+    async fn test_read_body() {
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            for chunk in ["{\"posts\"", ":", "[]}"] {
+                tx.send_data(Bytes::from_static(chunk.as_bytes()))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let progress = Progress::new();
+        let body = read_body(URL, body, 100, TIMEOUT, Some(&progress))
+            .await
+            .unwrap();
+        assert_eq!(body, br#"{"posts":[]}"#.as_ref());
+        assert_eq!(progress.bytes(), 12);
+        assert_eq!(progress.bytes_total(), 0, "no Content-Length");
+
+        let progress = Progress::new();
+        read_body(URL, Body::from("12345"), 5, TIMEOUT, Some(&progress))
+            .await
+            .unwrap();
+        assert_eq!((progress.bytes(), progress.bytes_total()), (5, 5));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_limit() {
+        // the known length fails before reading
+        let progress = Progress::new();
+        let err = read_body(URL, Body::from("123456"), 5, TIMEOUT, Some(&progress))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), too_large(5).to_string());
+        assert_eq!(progress.bytes(), 0);
+
+        // an unknown length fails once the chunks exceed the limit
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(
+            async move { while tx.send_data(Bytes::from_static(b"1234")).await.is_ok() {} },
+        );
+
+        let err = read_body(URL, body, 10, TIMEOUT, None).await.unwrap_err();
+        assert!(err.to_string().contains("10 byte limit"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_read_body_stalled() {
+        // one chunk, then nothing until the sender is dropped
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from_static(b"{")).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+
+        let err = read_body(URL, body, 10, Duration::from_millis(50), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, Error::Timeout(URL.into()));
+    }
+}
@@ -0,0 +1,101 @@
+//! Types and functions for compressed response bodies.
+
+use std::io::Read;
+
+use hyper::{body::Bytes, header::CONTENT_ENCODING, HeaderMap};
+
+use super::too_large;
+use crate::Result;
+
+/// `Accept-Encoding` header sent with every request.
+pub const ACCEPT_ENCODING_VALUE: &str = "gzip, deflate, br";
+
+/// Buffer size for the `brotli` decoder.
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Decompresses a response body based on the `Content-Encoding` header.
+///
+/// Bodies without a `Content-Encoding`, or with an `identity` encoding, are returned unchanged.
+/// Unsupported encodings are also returned unchanged, and fail to parse as JSON later.
+/// Decompressed bodies over `max_len` bytes fail, e.g. a small body expanding to gigabytes.
+pub fn decode_body(headers: &HeaderMap, body: Bytes, max_len: u64) -> Result<Bytes> {
+    let Some(encoding) = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|e| e.to_str().ok())
+        .map(|e| e.trim().to_ascii_lowercase())
+    else {
+        return Ok(body);
+    };
+
+    let mut decoded = Vec::with_capacity(body.len().saturating_mul(4));
+    // one byte past the limit is enough to tell it was exceeded
+    let limit = max_len.saturating_add(1);
+
+    match encoding.as_str() {
+        "gzip" | "x-gzip" => flate2::read::GzDecoder::new(body.as_ref())
+            .take(limit)
+            .read_to_end(&mut decoded)?,
+        "deflate" => flate2::read::ZlibDecoder::new(body.as_ref())
+            .take(limit)
+            .read_to_end(&mut decoded)?,
+        "br" => brotli_decompressor::Decompressor::new(body.as_ref(), BROTLI_BUFFER_SIZE)
+            .take(limit)
+            .read_to_end(&mut decoded)?,
+        _ => return Ok(body),
+    };
+
+    if decoded.len() as u64 > max_len {
+        return Err(too_large(max_len));
+    }
+
+    Ok(decoded.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use hyper::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_body() {
+        let json = br#"{"posts":[]}"#;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        assert_eq!(
+            decode_body(&headers, gzipped.clone().into(), 100).unwrap(),
+            json.as_ref()
+        );
+
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+
+        assert_eq!(
+            decode_body(&headers, Bytes::from_static(json), 100).unwrap(),
+            json.as_ref()
+        );
+        assert_eq!(
+            decode_body(&HeaderMap::new(), Bytes::from_static(json), 100).unwrap(),
+            json.as_ref()
+        );
+
+        // the limit applies to the decompressed body
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+        assert!(decode_body(&headers, gzipped.into(), json.len() as u64 - 1).is_err());
+    }
+
+    #[test]
+    fn test_decode_body_corrupt() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        assert!(decode_body(&headers, Bytes::from_static(b"not gzip"), 100).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! Local mock server for testing requests.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use hyper::{
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+
+/// Spawns a local server responding to every request with `handler`.
+///
+/// Request bodies are read in full before calling `handler`, so it can read them without waiting
+/// on the connection.
+///
+/// Returns the server address, and a count of accepted connections.
+pub fn serve(handler: fn(Request<Body>) -> Response<Body>) -> (SocketAddr, Arc<AtomicUsize>) {
+    let connections = Arc::new(AtomicUsize::new(0));
+    let conns = Arc::clone(&connections);
+
+    let make_svc = make_service_fn(move |_: &AddrStream| {
+        conns.fetch_add(1, Ordering::SeqCst);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| async move {
+                let (parts, body) = req.into_parts();
+                let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+
+                Ok::<_, Infallible>(handler(Request::from_parts(parts, Body::from(body))))
+            }))
+        }
+    });
+
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    (addr, connections)
+}
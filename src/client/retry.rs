@@ -0,0 +1,133 @@
+//! Types and functions for retrying transient request failures.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+use hyper::{header::RETRY_AFTER, HeaderMap, Method, StatusCode};
+
+/// Default maximum number of retries for a single request.
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Default delay before the first retry.
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Default maximum delay between retries.
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Represents the policy for retrying transient request failures.
+///
+/// Retries use exponential backoff with jitter, unless the server sends a `Retry-After` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a new [RetryPolicy].
+    pub const fn new() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// Creates a [RetryPolicy] that never retries.
+    pub const fn none() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// Builder function that sets the maximum number of retries.
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Builder function that sets the delay before the first retry.
+    pub const fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Builder function that sets the maximum delay between retries.
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Gets the exponential backoff delay, with jitter, for the zero-indexed `attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        exp.saturating_add(jitter(self.base_delay))
+            .min(self.max_delay)
+    }
+
+    /// Gets the delay before the next retry.
+    ///
+    /// Honors the `Retry-After` header, if present, up to the maximum delay.
+    pub fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        headers
+            .and_then(retry_after)
+            .map(|d| d.min(self.max_delay))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gets whether a response with the [StatusCode] to a request with the [Method] should be
+/// retried.
+///
+/// `429` rejects the request unprocessed, so is always retried. Gateway errors are only retried
+/// for `GET` and `HEAD` requests, since the instance may have processed the request anyway, e.g.
+/// posting a comment twice.
+pub fn is_retryable(method: &Method, status: StatusCode) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => true,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => {
+            matches!(*method, Method::GET | Method::HEAD)
+        }
+        _ => false,
+    }
+}
+
+/// Parses the `Retry-After` header, either as delay seconds, or an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => httpdate::parse_http_date(value).ok().map(|date| {
+            date.duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO)
+        }),
+    }
+}
+
+/// Gets a random [Duration] in the range `[0, max)`.
+fn jitter(max: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+
+    let max_ms = (max.as_millis() as u64).max(1);
+
+    Duration::from_millis(hasher.finish() % max_ms)
+}
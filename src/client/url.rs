@@ -0,0 +1,202 @@
+//! Types and functions for building URLs on a Lemmy instance.
+
+use std::fmt;
+use std::str::FromStr;
+
+use hyper::{http::uri::Authority, Uri};
+
+use crate::{endpoint::Endpoint, Error, Result};
+
+/// Base URL of a Lemmy instance, e.g. `https://lemmy.ml`, or `https://example.com/lemmy` for an
+/// instance hosted under a subpath.
+///
+/// The base is kept without trailing slashes, so [Endpoint] paths join without a `//`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct InstanceUrl(String);
+
+impl InstanceUrl {
+    /// Parses an [InstanceUrl], requiring an `http` or `https` scheme and a host.
+    ///
+    /// Any path prefix is kept, trailing slashes are stripped. Queries are rejected, there is
+    /// nowhere to keep them once an [Endpoint] is joined.
+    pub fn parse(url: &str) -> Result<Self> {
+        let instance = Self::from(url);
+        let uri = Uri::from_str(instance.as_str())
+            .map_err(|err| Error::Config(format!("invalid instance URL {url}: {err}")))?;
+
+        match uri.scheme_str() {
+            Some("http" | "https") if uri.host().is_none_or(str::is_empty) => {
+                Err(Error::Config(format!("instance URL has no host: {url}")))
+            }
+            Some("http" | "https") if uri.query().is_some() => Err(Error::Config(format!(
+                "instance URL must not have a query: {url}"
+            ))),
+            Some("http" | "https") => Ok(instance),
+            _ => Err(Error::Config(format!(
+                "instance URL must start with http:// or https://: {url}"
+            ))),
+        }
+    }
+
+    /// Gets the normalized base URL.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Gets the host, and any port, of the instance, e.g. for sending credentials only to the
+    /// instance.
+    pub fn authority(&self) -> Option<Authority> {
+        Uri::from_str(self.as_str())
+            .ok()
+            .and_then(|uri| uri.authority().cloned())
+    }
+
+    /// Gets the full URL for an [Endpoint], with the query `params`, if any.
+    ///
+    /// Parameter values are percent-encoded, e.g. `?community_name=rust%20lang`.
+    pub fn join(&self, endpoint: Endpoint, params: &[(&str, String)]) -> String {
+        let mut url = format!("{}{endpoint}", self.0);
+
+        for (i, (name, value)) in params.iter().enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.push_str(name);
+            url.push('=');
+            url.push_str(&encode_component(value));
+        }
+
+        url
+    }
+}
+
+/// Normalizes the URL without validating it, see [InstanceUrl::parse] to validate.
+impl From<&str> for InstanceUrl {
+    fn from(url: &str) -> Self {
+        Self(url.trim().trim_end_matches('/').to_owned())
+    }
+}
+
+impl From<String> for InstanceUrl {
+    fn from(url: String) -> Self {
+        url.as_str().into()
+    }
+}
+
+impl fmt::Display for InstanceUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Percent-encodes a query parameter value, keeping unreserved characters, and the `@` and `:`
+/// in community names, e.g. `rust@programming.dev`.
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' | b':' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(base: &str, params: &[(&str, String)]) -> String {
+        InstanceUrl::parse(base)
+            .unwrap()
+            .join(Endpoint::PostList, params)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            InstanceUrl::parse("https://lemmy.ml").unwrap().as_str(),
+            "https://lemmy.ml"
+        );
+        assert_eq!(
+            InstanceUrl::parse(" https://lemmy.ml// ").unwrap().as_str(),
+            "https://lemmy.ml"
+        );
+        assert!(InstanceUrl::parse("http://192.168.1.10:8536").is_ok());
+        assert!(InstanceUrl::parse("http://[::1]:8536").is_ok());
+
+        for url in [
+            "lemmy.ml",
+            "ftp://lemmy.ml",
+            "https://",
+            "",
+            "https://lemmy.ml/?sort=New",
+        ] {
+            assert!(
+                matches!(InstanceUrl::parse(url), Err(Error::Config(_))),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_join() {
+        let post_list = "/api/v3/post/list";
+
+        // trailing slashes
+        assert_eq!(
+            join("https://lemmy.ml", &[]),
+            format!("https://lemmy.ml{post_list}")
+        );
+        assert_eq!(
+            join("https://lemmy.ml/", &[]),
+            format!("https://lemmy.ml{post_list}")
+        );
+
+        // subpaths
+        assert_eq!(
+            join("https://example.com/lemmy/", &[]),
+            format!("https://example.com/lemmy{post_list}")
+        );
+
+        // ports, and IPv6 literal hosts
+        assert_eq!(
+            join("http://localhost:8536", &[("page", "2".into())]),
+            format!("http://localhost:8536{post_list}?page=2")
+        );
+        assert_eq!(
+            join("http://[::1]:8536/", &[("page", "2".into())]),
+            format!("http://[::1]:8536{post_list}?page=2")
+        );
+
+        // values are encoded
+        assert_eq!(
+            join(
+                "https://lemmy.ml",
+                &[
+                    ("community_name", "rust@programming.dev".into()),
+                    ("page_cursor", "a b&c=d".into()),
+                ]
+            ),
+            format!(
+                "https://lemmy.ml{post_list}?community_name=rust@programming.dev\
+                 &page_cursor=a%20b%26c%3Dd"
+            )
+        );
+    }
+
+    #[test]
+    fn test_authority() {
+        let authority = |url| InstanceUrl::from(url).authority().map(|a| a.to_string());
+
+        assert_eq!(authority("https://lemmy.ml/"), Some("lemmy.ml".into()));
+        assert_eq!(
+            authority("https://example.com:8443/lemmy"),
+            Some("example.com:8443".into())
+        );
+        assert_eq!(authority("http://[::1]:8536"), Some("[::1]:8536".into()));
+        assert_eq!(authority(""), None);
+    }
+}
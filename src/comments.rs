@@ -1,19 +1,36 @@
 //! Types and functions for post comments.
 
-use std::cmp;
+use std::{borrow::Cow, cmp, fmt, ops::Range, time::SystemTime};
 
-use tui::widgets::TableState;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use hashbrown::{HashMap, HashSet};
+
+use tui::{text::Line, widgets::TableState};
 
 use crate::{
-    community::Community,
+    api::ApiClient,
+    client::Client,
+    community::{Community, SubscribedType},
     counts::Counts,
+    endpoint::Endpoint,
     posts::{Creator, Post},
+    task::Progress,
     Result,
 };
 
+mod cache;
 mod comment;
+mod tree;
 
+pub use cache::*;
 pub use comment::*;
+pub use tree::*;
+
+/// Levels of replies to load below a comment with missing replies, see
+/// [CommentResponseTable::missing_replies].
+pub const REPLIES_MAX_DEPTH: u64 = 8;
+/// Largest page of comments Lemmy returns.
+pub const MAX_COMMENT_LIMIT: u64 = 50;
 
 /// Load comments from a file instead of making a call to an endpoint.
 ///
@@ -34,6 +51,7 @@ pub fn load_comments(file_name: &str) -> Result<CommentResponses> {
 /// Represents a response to a [Comment] API request.
 #[repr(C)]
 #[derive(Clone, Debug, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct CommentResponse {
     pub comment: Comment,
     pub creator: Creator,
@@ -41,10 +59,19 @@ pub struct CommentResponse {
     pub community: Community,
     pub counts: Counts,
     pub creator_banned_from_community: bool,
-    pub subscribed: String,
+    pub subscribed: SubscribedType,
     pub saved: bool,
     pub creator_blocked: bool,
+    /// Vote by the logged-in user: `1`, `-1`, or unset.
+    pub my_vote: Option<i64>,
     pub level: Option<usize>,
+    /// Comment IDs parsed from the [Comment] path, set by [finalize](Self::finalize).
+    #[serde(skip)]
+    pub path_ids: Vec<u64>,
+    /// Whether the [Comment] path is malformed, or missing the comment ID, so its place in the
+    /// thread is unknown. Set by [finalize](Self::finalize).
+    #[serde(skip)]
+    pub orphaned: bool,
 }
 
 impl CommentResponse {
@@ -57,10 +84,34 @@ impl CommentResponse {
             community: Community::new(),
             counts: Counts::new(),
             creator_banned_from_community: false,
-            subscribed: String::new(),
+            subscribed: SubscribedType::NotSubscribed,
             saved: false,
             creator_blocked: false,
+            my_vote: None,
             level: None,
+            path_ids: Vec::new(),
+            orphaned: false,
+        }
+    }
+
+    /// Caches the comment IDs parsed from the [Comment] path, for comparisons, and whether the
+    /// comment is [orphaned](Self::orphaned).
+    ///
+    /// Called after deserializing [CommentResponses], and creating a [CommentResponseTable].
+    /// Must be called again after changing the path.
+    pub fn finalize(&mut self) {
+        self.path_ids = parse_path(&self.comment.path);
+        self.orphaned = !self.comment.has_valid_path();
+    }
+
+    /// Gets the comment IDs in the [Comment] path, parsing the path if not cached.
+    ///
+    /// Malformed IDs are zero.
+    pub fn path_ids(&self) -> Cow<'_, [u64]> {
+        if self.path_ids.is_empty() {
+            Cow::Owned(parse_path(&self.comment.path))
+        } else {
+            Cow::Borrowed(&self.path_ids)
         }
     }
 
@@ -73,107 +124,50 @@ impl CommentResponse {
     pub fn set_level(&mut self, level: usize) {
         self.level.replace(level);
     }
+
+    /// Creates a [CommentResponse] at the comment `path`, e.g. `0.1.2`, with the last path
+    /// element as its ID, or zero if malformed.
+    ///
+    /// The path IDs are not cached, see [finalize](Self::finalize).
+    #[cfg(test)]
+    pub fn test_at(path: &str) -> Self {
+        let id = path.rsplit('.').next().and_then(|id| id.parse().ok());
+
+        Self {
+            comment: Comment {
+                id: id.unwrap_or(0),
+                path: path.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
 }
 
+/// Equal when [Ord] compares [Equal](cmp::Ordering::Equal): the same path, published time, and
+/// ID.
 impl PartialEq for CommentResponse {
     fn eq(&self, rhs: &Self) -> bool {
-        let self_ids_len = self.comment.path.split('.').count();
-        let rhs_ids_len = rhs.comment.path.split('.').count();
-        let min_level = cmp::min(self_ids_len - 1, rhs_ids_len - 1);
-
-        let self_id = self
-            .comment
-            .path
-            .split('.')
-            .nth(min_level)
-            .map(|i| i.parse::<u64>().unwrap_or(0));
-        let rhs_id = rhs
-            .comment
-            .path
-            .split('.')
-            .nth(min_level)
-            .map(|i| i.parse::<u64>().unwrap_or(0));
-
-        self.level == rhs.level
-            && self_id == rhs_id
-            && self.comment.published == rhs.comment.published
+        self.cmp(rhs) == cmp::Ordering::Equal
     }
 }
 
 impl PartialOrd for CommentResponse {
     fn partial_cmp(&self, rhs: &Self) -> Option<cmp::Ordering> {
-        let self_id = self.comment.id;
-
-        let self_pos = self
-            .comment
-            .path
-            .split('.')
-            .map(|c| c.parse::<u64>().unwrap_or(0))
-            .position(|c| c == self_id)
-            .unwrap_or(0);
-
-        let self_root = self
-            .comment
-            .path
-            .split('.')
-            .map(|c| c.parse::<u64>().unwrap_or(0))
-            .nth(1);
-
-        let rhs_id = rhs.comment.id;
-
-        let rhs_pos = rhs
-            .comment
-            .path
-            .split('.')
-            .map(|c| c.parse::<u64>().unwrap_or(0))
-            .position(|c| c == rhs_id)
-            .unwrap_or(0);
-
-        let rhs_root = rhs
-            .comment
-            .path
-            .split('.')
-            .map(|c| c.parse::<u64>().unwrap_or(0))
-            .nth(1);
-
-        let level = cmp::min(self_pos.saturating_sub(1), rhs_pos.saturating_sub(1));
-
-        let ancestor_ord = self
-            .comment
-            .path
-            .split('.')
-            .skip(2)
-            .take(level.saturating_sub(2))
-            .map(|c| c.parse::<u64>().unwrap_or(0))
-            .zip(
-                rhs.comment
-                    .path
-                    .split('.')
-                    .skip(2)
-                    .take(level.saturating_sub(2))
-                    .map(|c| c.parse::<u64>().unwrap_or(0)),
-            )
-            .fold(self_root.cmp(&rhs_root), |acc, (s, r)| acc.then(s.cmp(&r)));
-
-        let self_child = self.counts.child_count();
-        let rhs_child = rhs.counts.child_count();
-
-        let published = self.comment.published.as_str();
-        let rhs_published = rhs.comment.published.as_str();
-
-        Some(
-            ancestor_ord
-                .then(self_pos.cmp(&rhs_pos))
-                .then(self_child.cmp(&rhs_child))
-                .then(self_id.cmp(&rhs_id))
-                .then(published.cmp(&rhs_published)),
-        )
+        Some(self.cmp(rhs))
     }
 }
 
+/// Orders comments in thread order, comparing their path IDs lexicographically, so replies
+/// follow their parent, and siblings are ordered by ID.
+///
+/// Equal paths, e.g. with malformed IDs, are ordered by published time, then comment ID.
 impl Ord for CommentResponse {
     fn cmp(&self, rhs: &Self) -> cmp::Ordering {
-        self.partial_cmp(rhs).unwrap_or(cmp::Ordering::Equal)
+        self.path_ids()
+            .cmp(&rhs.path_ids())
+            .then_with(|| self.comment.published.cmp(&rhs.comment.published))
+            .then_with(|| self.comment.id.cmp(&rhs.comment.id))
     }
 }
 
@@ -183,12 +177,35 @@ impl Default for CommentResponse {
     }
 }
 
+/// Parses a comment path, e.g. `0.12.34`, into comment IDs.
+///
+/// Malformed IDs parse as zero.
+fn parse_path(path: &str) -> Vec<u64> {
+    path.split('.').map(|id| id.parse().unwrap_or(0)).collect()
+}
+
 /// Represents list of responses to a [Comment] API request.
-#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
 pub struct CommentResponses {
     pub comments: Vec<CommentResponse>,
 }
 
+impl<'de> serde::Deserialize<'de> for CommentResponses {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            comments: Vec<CommentResponse>,
+        }
+
+        let mut raw = Raw::deserialize(deserializer)?;
+        raw.comments.iter_mut().for_each(CommentResponse::finalize);
+
+        Ok(Self::new(raw.comments))
+    }
+}
+
 impl CommentResponses {
     /// Creates a new [CommentResponses] list.
     pub const fn new(comments: Vec<CommentResponse>) -> Self {
@@ -199,23 +216,185 @@ impl CommentResponses {
     pub fn comments(&self) -> &[CommentResponse] {
         self.comments.as_ref()
     }
+
+    /// Merges `other` into the list by [Comment] ID, e.g. overlapping pages, or a refresh.
+    ///
+    /// Known comments are replaced, keeping the [Comment] itself if the known copy was edited
+    /// more recently. Comments missing from `other` are kept, as it may be a single page.
+    pub fn merge(&mut self, other: CommentResponses) -> MergeStats {
+        let mut index: HashMap<u64, usize> = self
+            .comments
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.comment.id(), i))
+            .collect();
+        let mut stats = MergeStats::default();
+
+        for mut c in other.comments {
+            let Some(&i) = index.get(&c.comment.id()) else {
+                index.insert(c.comment.id(), self.comments.len());
+                self.comments.push(c);
+                stats.added += 1;
+                continue;
+            };
+
+            let known = &mut self.comments[i];
+            if known.comment.edited_at() > c.comment.edited_at() {
+                c.comment = known.comment.clone();
+            }
+
+            let gone = |c: &CommentResponse| c.comment.deleted || c.comment.removed;
+            if gone(&c) && !gone(known) {
+                stats.removed += 1;
+            } else if c.comment != known.comment || c.counts != known.counts {
+                stats.updated += 1;
+            }
+
+            // the path is unchanged, so keep the level set for the table
+            c.level = known.level;
+            *known = c;
+        }
+
+        stats
+    }
+}
+
+/// Numbers of comments changed by [CommentResponses::merge].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Comments not seen before.
+    pub added: usize,
+    /// Comments with edited content, or changed [Counts].
+    pub updated: usize,
+    /// Comments newly deleted by their creator, or removed by a moderator.
+    pub removed: usize,
+}
+
+impl MergeStats {
+    /// Gets whether the merge changed nothing.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for MergeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} new, {} updated, {} removed",
+            self.added, self.updated, self.removed
+        )
+    }
 }
 
 /// Download a response to the [CommentList](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::get_comments to share connections between requests")]
 pub async fn dl_comments(url: &str) -> Result<CommentResponses> {
-    use std::str::FromStr;
+    Client::new("").get_json(url).await
+}
 
-    let https = hyper_tls::HttpsConnector::new();
-    let client = hyper::Client::builder().build::<_, hyper::Body>(https);
+/// Download every page of [CommentResponse]s for a [Post] from the
+/// [CommentList](crate::endpoint::Endpoint) endpoint.
+///
+/// Pages are requested concurrently, up to the [Client] concurrency limit. Pages may shift
+/// while downloading, so results are merged in page order, and deduplicated by [Comment] ID.
+pub async fn dl_all_comments(
+    client: &dyn ApiClient,
+    post_id: u64,
+    num_comments: u64,
+    limit: u64,
+) -> Result<CommentResponses> {
+    let progress = Progress::new();
+
+    dl_all_comments_with_progress(client, post_id, 0..num_comments, limit, &progress, &|_| ()).await
+}
+
+/// Download the pages of [CommentResponse]s covering the `comments` range for a [Post], e.g.
+/// `500..1000` for the second batch of 500, tracking the number of downloaded pages in
+/// `progress`, and passing each page to `on_page` as it arrives.
+///
+/// The first page is downloaded before the rest, so it can be shown while the rest download.
+/// See [dl_all_comments] for details.
+pub async fn dl_all_comments_with_progress(
+    client: &dyn ApiClient,
+    post_id: u64,
+    comments: Range<u64>,
+    limit: u64,
+    progress: &Progress,
+    on_page: &(dyn Fn(&CommentResponses) + Sync),
+) -> Result<CommentResponses> {
+    let limit = limit.max(1);
+    let first_page = comments.start / limit + 1;
+    let last_page = comments.end.div_ceil(limit);
+    let num_pages = (last_page + 1).saturating_sub(first_page);
+
+    progress.set_total(num_pages);
+
+    let fetch = |page| async move {
+        let res = client
+            .list_comments(post_id, page, limit, Some(progress))
+            .await;
+        progress.inc();
+
+        res.map(|res| {
+            on_page(&res);
+            (page, res)
+        })
+    };
+
+    let mut pages: Vec<(u64, CommentResponses)> = Vec::with_capacity(num_pages as usize);
+    if num_pages > 0 {
+        pages.push(fetch(first_page).await?);
+    }
 
-    let response = client.get(hyper::Uri::from_str(url)?).await?;
+    let rest: Vec<(u64, CommentResponses)> = stream::iter(first_page + 1..=last_page)
+        .map(fetch)
+        .buffer_unordered(client.concurrency())
+        .try_collect()
+        .await?;
+    pages.extend(rest);
+
+    pages.sort_by_key(|(page, _)| *page);
+
+    // comments added, or deleted, while downloading shift the pages, so they can overlap
+    let mut comments = CommentResponses::new(Vec::with_capacity(
+        comments.end.saturating_sub(comments.start) as usize,
+    ));
+    for (_, page) in pages {
+        comments.merge(page);
+    }
 
-    let body = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(comments)
+}
 
-    #[cfg(feature = "debug_endpoints")]
-    crate::utils::write_to_file("comments.json", &body)?;
+/// Inputs of the [CommentLines] drawn in the comment pane, besides the comments.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommentLinesKey {
+    /// Width of the comment pane, inside its borders.
+    pub width: u16,
+    /// Last visit to the post, comments published, or edited, after it are new.
+    pub since: Option<SystemTime>,
+    /// Comment whose replies are loading.
+    pub loading_replies: Option<u64>,
+    /// IDs of the comments with revealed spoilers, sorted.
+    pub revealed: Vec<u64>,
+    /// Minutes since the epoch, so relative comment ages stay current.
+    pub minute: u64,
+}
 
-    serde_json::from_slice::<CommentResponses>(&body).map_err(|err| err.into())
+/// Lines of the comments in the comment pane, cached between draws, since rendering the markdown
+/// of every comment is slow for large threads.
+#[derive(Clone, Debug, Default)]
+pub struct CommentLines {
+    pub key: CommentLinesKey,
+    /// Lines of every comment, each followed by its spacing.
+    pub lines: Vec<Line<'static>>,
+    /// Lines of each comment, excluding the spacing after it, empty for hidden comments.
+    pub comments: Vec<Range<usize>>,
+    /// Row each of the lines starts at when wrapped, followed by the total number of rows.
+    pub offsets: Vec<usize>,
+    /// Number of new comments, see [CommentResponseTable::is_new].
+    pub new_count: usize,
 }
 
 /// Table of [CommentResponse]s for displaying in the TUI.
@@ -230,25 +409,75 @@ pub struct CommentResponseTable {
     pub level: usize,
     /// [TableState] for drawing [CommentResponseTable].
     pub state: TableState,
+    /// Rows of each comment in the comment pane, set when drawing.
+    pub rows: Vec<Range<usize>>,
+    /// Lines of the comments in the comment pane, set when drawing, and cleared when the
+    /// comments, their order, or collapsed comments change.
+    pub lines: Option<CommentLines>,
+    /// IDs of comments with their replies hidden.
+    pub collapsed: HashSet<u64>,
+    /// Current order of the comments, `None` until sorted.
+    pub sort: Option<CommentSort>,
+    /// Whether comments from bot accounts are hidden, along with their replies.
+    pub hide_bots: bool,
+    /// IDs of comments added by [merge](Self::merge), shown as new.
+    pub added: HashSet<u64>,
+    /// Number of comments downloaded, when limited by the
+    /// [comment_fetch_cap](crate::config::Config::comment_fetch_cap), `None` for every comment.
+    pub fetched: Option<u64>,
 }
 
 impl CommentResponseTable {
-    /// Creates a new [CommentResponseTable].
-    pub fn new(items: Vec<CommentResponse>) -> Self {
+    /// Creates a new [CommentResponseTable], setting each comment's level from its path.
+    ///
+    /// Comments with malformed paths are at level zero.
+    pub fn new(mut items: Vec<CommentResponse>) -> Self {
+        items.iter_mut().for_each(|c| {
+            c.finalize();
+            c.set_level(c.comment.path_level().unwrap_or(0));
+        });
+
         Self {
             items,
             comment_id: 0,
             page: 1,
             level: 0,
             state: TableState::default(),
+            rows: Vec::new(),
+            lines: None,
+            collapsed: HashSet::new(),
+            sort: None,
+            hide_bots: false,
+            added: HashSet::new(),
+            fetched: None,
         }
     }
 
+    /// Records the comments downloaded, up to `end` of the post's `total`, see
+    /// [fetched](Self::fetched).
+    ///
+    /// Earlier batches stay downloaded, e.g. after a refresh of the first batch.
+    pub fn set_fetched(&mut self, end: u64, total: u64) {
+        let end = end.max(self.fetched.unwrap_or(0));
+        self.fetched = (end < total).then_some(end);
+    }
+
+    /// Gets the number of comments left to download beyond the comment fetch cap.
+    pub fn remaining(&self, total: u64) -> u64 {
+        self.fetched
+            .map_or(0, |fetched| total.saturating_sub(fetched))
+    }
+
     /// Gets the list of [Comment] items.
     pub fn items(&self) -> &[CommentResponse] {
         self.items.as_ref()
     }
 
+    /// Gets the [orphaned](CommentResponse::orphaned) comments, whose path is malformed.
+    pub fn orphans(&self) -> impl Iterator<Item = &CommentResponse> {
+        self.items.iter().filter(|c| c.orphaned)
+    }
+
     /// Gets the current level in the [CommentResponseTable].
     pub fn level(&self) -> usize {
         self.level
@@ -330,6 +559,16 @@ impl CommentResponseTable {
         self.state.select(Some(i));
     }
 
+    /// Updates the [TableState] to select the first item at the current level, if any.
+    pub fn first(&mut self) {
+        self.state.select((!self.items.is_empty()).then_some(0));
+    }
+
+    /// Updates the [TableState] to select the last item at the current level, if any.
+    pub fn last(&mut self) {
+        self.state.select(self.items.len().checked_sub(1));
+    }
+
     /// Updates the [TableState] to select the previous item at the current level.
     pub fn previous(&mut self) {
         let len = self.items.len();
@@ -342,40 +581,237 @@ impl CommentResponseTable {
         self.state.select(Some(i));
     }
 
-    /// Sorts comments by ID, and path length.
+    /// Hides the replies to the comment, or shows them if already hidden.
+    pub fn toggle_collapsed(&mut self, comment_id: u64) {
+        if !self.collapsed.remove(&comment_id) {
+            self.collapsed.insert(comment_id);
+        }
+        self.lines = None;
+    }
+
+    /// Hides the replies to every root level comment.
     ///
-    /// This recursively sorts comments:
+    /// A selected reply moves the selection to its root comment.
+    pub fn collapse_roots(&mut self) {
+        let roots = self
+            .items
+            .iter()
+            .filter(|c| c.level() == 0)
+            .map(|c| c.comment.id());
+        self.collapsed.extend(roots);
+        self.lines = None;
+
+        if let Some(i) = self.state.selected() {
+            // the nearest visible comment above a hidden reply is its collapsed ancestor
+            let hidden = self.hidden();
+            let visible = (0..=i).rev().find(|&i| !hidden[i]);
+            self.state.select(visible);
+        }
+    }
+
+    /// Shows the replies to every comment.
+    pub fn expand_all(&mut self) {
+        self.collapsed.clear();
+        self.lines = None;
+    }
+
+    /// Gets whether each comment is hidden by a collapsed ancestor, or as a bot comment when
+    /// [hide_bots](Self::hide_bots) is set.
     ///
-    /// - first by parent ID (indicated by `level` parameter)
-    /// - grouping child posts under parents
-    /// - smaller IDs are considered earlier than larger IDs
-    ///   - future releases may require explicity checking comment date-time
+    /// Expects sorted comments, with replies following their parents.
+    pub fn hidden(&self) -> Vec<bool> {
+        let mut collapsed: Option<&str> = None;
+
+        self.items
+            .iter()
+            .map(|c| {
+                let path = c.comment.path.as_str();
+                if collapsed.is_some_and(|parent| is_reply(parent, path)) {
+                    return true;
+                }
+
+                // hidden bot comments collapse their replies too
+                let bot = self.hide_bots && c.creator.bot_account;
+                collapsed = (bot || self.collapsed.contains(&c.comment.id())).then_some(path);
+                bot
+            })
+            .collect()
+    }
+
+    /// Gets the number of replies, at any depth, to the comment at index `i`.
     ///
-    /// Callers should always start with level `1`, unless a special-case dictates something else.
+    /// Expects sorted comments, with replies following their parents.
+    pub fn reply_count(&self, i: usize) -> usize {
+        let Some(c) = self.items.get(i) else {
+            return 0;
+        };
+
+        self.items[i + 1..]
+            .iter()
+            .take_while(|r| is_reply(&c.comment.path, &r.comment.path))
+            .count()
+    }
+
+    /// Sets the [SubscribedType] for every [CommentResponse] in the given
+    /// [Community](crate::community::Community).
+    pub fn set_subscribed(&mut self, community_id: u64, subscribed: SubscribedType) {
+        self.items
+            .iter_mut()
+            .filter(|c| c.community.id == community_id)
+            .for_each(|c| c.subscribed = subscribed);
+    }
+
+    /// Sets whether the [Creator] is blocked for every [CommentResponse] by the given
+    /// [Creator].
+    pub fn set_creator_blocked(&mut self, creator_id: u64, blocked: bool) {
+        self.items
+            .iter_mut()
+            .filter(|c| c.creator.id == creator_id)
+            .for_each(|c| c.creator_blocked = blocked);
+        self.lines = None;
+    }
+
+    /// Sets whether the logged-in user saved the comment.
+    pub fn set_saved(&mut self, comment_id: u64, saved: bool) {
+        self.items
+            .iter_mut()
+            .filter(|c| c.comment.id() == comment_id)
+            .for_each(|c| c.saved = saved);
+        self.lines = None;
+    }
+
+    /// Removes the comment, keeping its replies, e.g. after unsaving it from the saved comments.
+    pub fn remove_comment(&mut self, comment_id: u64) {
+        self.items.retain(|c| c.comment.id() != comment_id);
+        self.lines = None;
+
+        if let Some(i) = self.state.selected() {
+            if self.items.is_empty() {
+                self.deselect();
+            } else if i >= self.items.len() {
+                self.state.select(Some(self.items.len() - 1));
+            }
+        }
+    }
+
+    /// Merges refetched comments, e.g. on refresh, or in live mode, keeping the selection and
+    /// collapsed comments.
     ///
-    /// Parameters:
+    /// New comments are added to [added](Self::added), see [CommentResponses::merge]. Any change
+    /// sorts the comments again on the next draw.
+    pub fn merge(&mut self, other: CommentResponses) -> MergeStats {
+        let known: HashSet<u64> = self.items.iter().map(|c| c.comment.id()).collect();
+
+        let mut comments = CommentResponses::new(std::mem::take(&mut self.items));
+        let stats = comments.merge(other);
+        self.items = comments.comments;
+
+        for c in self
+            .items
+            .iter_mut()
+            .filter(|c| !known.contains(&c.comment.id()))
+        {
+            c.finalize();
+            c.set_level(c.comment.path_level().unwrap_or(0));
+            self.added.insert(c.comment.id());
+        }
+
+        if !stats.is_empty() {
+            self.sort = None;
+            self.lines = None;
+        }
+
+        stats
+    }
+
+    /// Merges comments loaded on demand, or streamed in while loading, see [merge](Self::merge),
+    /// without marking them as new.
+    pub fn merge_loaded(&mut self, other: CommentResponses) -> MergeStats {
+        let added = std::mem::take(&mut self.added);
+        let stats = self.merge(other);
+        self.added = added;
+
+        stats
+    }
+
+    /// Gets the number of replies to the comment at `i` counted by the instance, but missing
+    /// from the table, e.g. below the depth returned with the post.
     ///
-    /// `level`: comment path level for sorting comparison
-    pub fn sort_comments(&mut self) {
-        self.items.sort();
-        /*
-        let max_len = self.items
+    /// Replies missing further down are left to the deepest loaded reply above them, so a deep
+    /// chain has a single comment with missing replies. Expects sorted comments.
+    pub fn missing_replies(&self, i: usize) -> u64 {
+        let Some(c) = self.items.get(i) else {
+            return 0;
+        };
+        let depth = c.path_ids().len();
+
+        let loaded: u64 = self.items[i + 1..]
             .iter()
-            .map(|l| l.comment.path.split('.').count())
-            .max()
-            .unwrap_or(0);
+            .take_while(|r| is_reply(&c.comment.path, &r.comment.path))
+            .filter(|r| r.path_ids().len() == depth + 1)
+            .map(|r| 1 + r.counts.child_count())
+            .sum();
 
-        (0..=max_len).for_each(|_| self.items.sort());
-        */
+        c.counts.child_count().saturating_sub(loaded)
+    }
+
+    /// Gets whether the comment at `i` is new: added by [merge](Self::merge), or published, or
+    /// edited, after `since`.
+    pub fn is_new(&self, i: usize, since: Option<SystemTime>) -> bool {
+        self.items.get(i).is_some_and(|c| {
+            self.added.contains(&c.comment.id())
+                || since.is_some_and(|since| c.comment.is_newer_than(since))
+        })
+    }
+
+    /// Sorts comments into threads, with replies following their parents, and sets each
+    /// comment's level.
+    ///
+    /// The selection stays on the same comment. See [CommentTree] for details.
+    pub fn sort_comments(&mut self, sort: CommentSort) {
+        let selected = self.current().map(|c| c.comment.id());
+
+        let tree = CommentTree::new(std::mem::take(&mut self.items));
+        self.items = tree.into_vec(sort);
+        self.sort = Some(sort);
+        self.lines = None;
+
+        if let Some(id) = selected {
+            let i = self.items.iter().position(|c| c.comment.id() == id);
+            self.state.select(i);
+        }
     }
 }
 
+/// Gets whether the comment at `path` is a reply, at any depth, to the comment at `parent`.
+fn is_reply(parent: &str, path: &str) -> bool {
+    path.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
 impl From<Vec<CommentResponse>> for CommentResponseTable {
     fn from(val: Vec<CommentResponse>) -> Self {
         Self::new(val)
     }
 }
 
+/// Represents a response from the [GetComment](crate::endpoint::Endpoint) endpoint.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GetCommentResponse {
+    pub comment_view: CommentResponse,
+}
+
+impl Client {
+    /// Gets a single [CommentResponse] from the [GetComment](crate::endpoint::Endpoint) endpoint.
+    pub async fn get_comment(&self, comment_id: u64) -> Result<CommentResponse> {
+        let url = self.url(Endpoint::GetComment, &[("id", comment_id.to_string())]);
+
+        self.get_json::<GetCommentResponse>(url.as_str())
+            .await
+            .map(|res| res.comment_view)
+    }
+}
+
 impl From<CommentResponses> for CommentResponseTable {
     fn from(val: CommentResponses) -> Self {
         Self::new(val.comments)
@@ -396,7 +832,11 @@ impl AsMut<CommentResponseTable> for CommentResponseTable {
 
 #[cfg(test)]
 mod tests {
+    use hyper::{Body, Request, Response};
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::client::mock::serve;
 
     #[test]
     fn test_sort_comments() {
@@ -550,8 +990,15 @@ mod tests {
             },
         ];
 
+        let tree = CommentTree::new(comments.clone());
+        let tree_paths: Vec<String> = tree
+            .flatten(CommentSort::Old)
+            .iter()
+            .map(|c| c.comment.path.clone())
+            .collect();
+
         let mut comment_responses = CommentResponseTable::new(comments);
-        comment_responses.sort_comments();
+        comment_responses.sort_comments(CommentSort::Old);
 
         let response_paths: Vec<String> = comment_responses
             .items()
@@ -565,5 +1012,461 @@ mod tests {
             .collect();
 
         assert_eq!(response_paths, exp_paths);
+        assert_eq!(tree_paths, exp_paths);
+    }
+
+    #[test]
+    fn test_comment_path_ids() {
+        let responses: CommentResponses = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/comment_list.json"
+        ))
+        .unwrap();
+        assert_eq!(responses.comments[0].path_ids, [0, 8100321]);
+
+        // comparisons give the same result with and without the cache
+        let mut uncached: Vec<CommentResponse> = ["0.3.5", "0.1.2", "0.3", "0.1", "0.x.4"]
+            .into_iter()
+            .map(CommentResponse::test_at)
+            .collect();
+        let mut cached = uncached.clone();
+        cached.iter_mut().for_each(CommentResponse::finalize);
+        assert_eq!(cached[4].path_ids(), [0, 0, 4].as_slice());
+
+        uncached.sort();
+        cached.sort();
+
+        let paths = |comments: &[CommentResponse]| {
+            comments
+                .iter()
+                .map(|c| c.comment.path.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&cached), paths(&uncached));
+    }
+
+    /// Generates a valid comment tree, each comment a reply to the post or an earlier comment,
+    /// with few distinct IDs and published times to exercise the tiebreaks. Half are cached.
+    fn comment_tree() -> impl Strategy<Value = Vec<CommentResponse>> {
+        prop::collection::vec(
+            (any::<prop::sample::Index>(), 0..8u64, 0..3u8, any::<bool>()),
+            1..24,
+        )
+        .prop_map(|nodes| {
+            let mut comments: Vec<CommentResponse> = Vec::new();
+
+            for (parent, id, published, cached) in nodes {
+                let parent = parent.index(comments.len() + 1);
+                let path = match parent.checked_sub(1) {
+                    Some(i) => format!("{}.{id}", comments[i].comment.path),
+                    None => format!("0.{id}"),
+                };
+                let mut c = CommentResponse {
+                    comment: Comment {
+                        id,
+                        path,
+                        published: format!("2024-02-0{published}T12:00:00Z"),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                if cached {
+                    c.finalize();
+                }
+                comments.push(c);
+            }
+
+            comments
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_comment_order_total(comments in comment_tree()) {
+            for a in &comments {
+                prop_assert_eq!(a.cmp(a), cmp::Ordering::Equal);
+
+                for b in &comments {
+                    let ord = a.cmp(b);
+                    prop_assert_eq!(ord, b.cmp(a).reverse());
+                    prop_assert_eq!(a == b, ord == cmp::Ordering::Equal);
+                    prop_assert_eq!(a.partial_cmp(b), Some(ord));
+
+                    for c in &comments {
+                        if a <= b && b <= c {
+                            prop_assert!(a <= c, "{} <= {} <= {}", a.comment.path, b.comment.path, c.comment.path);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn test_comment_order_replies(mut comments in comment_tree()) {
+            comments.sort();
+
+            // each reply follows its parent
+            for (i, c) in comments.iter().enumerate() {
+                let Some((parent, _)) = c.comment.path.rsplit_once('.') else {
+                    continue;
+                };
+                if parent != "0" {
+                    let pos = comments.iter().position(|p| p.comment.path == parent);
+                    prop_assert!(pos.is_some_and(|pos| pos < i), "{}", c.comment.path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_collapse_comments() {
+        let comments = ["0.1", "0.1.2", "0.1.2.3", "0.10", "0.4", "0.4.5"]
+            .into_iter()
+            .map(CommentResponse::test_at)
+            .collect::<Vec<_>>();
+        let mut table = CommentResponseTable::new(comments);
+
+        assert_eq!(table.reply_count(0), 2);
+        assert_eq!(table.reply_count(1), 1);
+        assert_eq!(table.reply_count(3), 0, "0.10 is not a reply to 0.1");
+        assert_eq!(table.hidden(), [false; 6]);
+
+        table.toggle_collapsed(2);
+        assert_eq!(table.hidden(), [false, false, true, false, false, false]);
+
+        table.toggle_collapsed(1);
+        assert_eq!(table.hidden(), [false, true, true, false, false, false]);
+        table.toggle_collapsed(1);
+        assert_eq!(table.hidden(), [false, false, true, false, false, false]);
+
+        // the selection moves from a hidden reply to its root
+        table.expand_all();
+        table.state.select(Some(5));
+        table.collapse_roots();
+        assert_eq!(table.hidden(), [false, true, true, false, false, true]);
+        assert_eq!(table.current().map(|c| c.comment.id), Some(4));
+
+        table.expand_all();
+        assert!(table.collapsed.is_empty());
+        assert_eq!(table.hidden(), [false; 6]);
+    }
+
+    #[test]
+    fn test_merge_comments() {
+        let comment = |path: &str, score| CommentResponse {
+            counts: Counts {
+                score,
+                ..Default::default()
+            },
+            ..CommentResponse::test_at(path)
+        };
+        let ids = |table: &CommentResponseTable| {
+            table.items.iter().map(|c| c.comment.id).collect::<Vec<_>>()
+        };
+
+        let mut table = CommentResponseTable::new(vec![
+            comment("0.1", 0),
+            comment("0.1.2", 0),
+            comment("0.3", 0),
+        ]);
+        table.sort_comments(CommentSort::Old);
+        table.toggle_collapsed(1);
+        table.state.select(Some(2));
+
+        let stats = table.merge(CommentResponses::new(vec![
+            comment("0.1", 5),
+            comment("0.3.5", 0),
+            comment("0.1.2.4", 0),
+            comment("0.3", 0),
+        ]));
+        assert_eq!((stats.added, stats.updated), (2, 1));
+        assert_eq!(table.items[0].counts.score, 5, "updated in place");
+        assert_eq!(table.items[4].level(), 2);
+        assert_eq!(table.sort, None);
+
+        table.sort_comments(CommentSort::Old);
+        assert_eq!(ids(&table), [1, 2, 4, 3, 5], "tree order");
+        assert_eq!(table.current().map(|c| c.comment.id), Some(3));
+        assert!(table.collapsed.contains(&1));
+        assert_eq!(
+            (0..5).map(|i| table.is_new(i, None)).collect::<Vec<_>>(),
+            [false, false, true, false, true]
+        );
+
+        // no changes keep the order
+        let stats = table.merge(CommentResponses::new(vec![comment("0.3", 0)]));
+        assert!(stats.is_empty());
+        assert_eq!(table.sort, Some(CommentSort::Old));
+    }
+
+    #[test]
+    fn test_merge_orphans() {
+        let comment = CommentResponse::test_at;
+
+        // the reply arrives on an earlier page than its parent
+        let mut table = CommentResponseTable::new(vec![comment("0.3"), comment("0.1.2")]);
+        table.sort_comments(CommentSort::Old);
+        assert_eq!(table.items[0].comment.id, 2, "next to its lost thread");
+        assert_eq!(table.items[0].level(), 0, "orphans are at the root");
+
+        table.merge_loaded(CommentResponses::new(vec![comment("0.1")]));
+        table.sort_comments(CommentSort::Old);
+        let ids: Vec<(u64, usize)> = table
+            .items
+            .iter()
+            .map(|c| (c.comment.id, c.level()))
+            .collect();
+        assert_eq!(ids, [(1, 0), (2, 1), (3, 0)], "reparented");
+    }
+
+    #[test]
+    fn test_missing_replies() {
+        let comment = |path: &str, child_count| CommentResponse {
+            counts: Counts {
+                child_count: Some(child_count),
+                ..Default::default()
+            },
+            ..CommentResponse::test_at(path)
+        };
+
+        // 0.1.2.3 has 2 replies below the returned depth, and 0.5 has 1
+        let mut table = CommentResponseTable::new(vec![
+            comment("0.1", 4),
+            comment("0.1.2", 3),
+            comment("0.1.2.3", 2),
+            comment("0.5", 1),
+        ]);
+        table.sort_comments(CommentSort::Old);
+        table.state.select(Some(1));
+        assert_eq!(
+            (0..4).map(|i| table.missing_replies(i)).collect::<Vec<_>>(),
+            [0, 0, 2, 1]
+        );
+
+        // loading twice adds each reply once, without marking them as new
+        let replies = CommentResponses::new(vec![
+            comment("0.1.2.3", 2),
+            comment("0.1.2.3.6", 1),
+            comment("0.1.2.3.6.7", 0),
+        ]);
+        assert_eq!(table.merge_loaded(replies.clone()).added, 2);
+        assert_eq!(table.merge_loaded(replies).added, 0);
+        assert!(table.added.is_empty());
+
+        table.sort_comments(CommentSort::Old);
+        assert_eq!(table.items.len(), 6);
+        assert_eq!(table.current().map(|c| c.comment.id), Some(2));
+        assert_eq!(table.missing_replies(2), 0);
+        assert_eq!(table.items[4].level(), 4);
+    }
+
+    #[test]
+    fn test_merge_comment_responses() {
+        let comment = |id, content: &str, updated: Option<&str>| CommentResponse {
+            comment: Comment {
+                id,
+                content: content.into(),
+                published: "2023-08-04T18:00:00.000000".into(),
+                updated: updated.map(Into::into),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let edited = Some("2023-08-04T19:00:00.000000");
+
+        let mut comments = CommentResponses::new(vec![
+            comment(1, "one", None),
+            comment(2, "two, edited", edited),
+            comment(3, "three", None),
+        ]);
+
+        let mut deleted = comment(3, "", None);
+        deleted.comment.deleted = true;
+        let mut voted = comment(2, "two", None);
+        voted.counts.score = 3;
+
+        let stats = comments.merge(CommentResponses::new(vec![
+            comment(1, "one", None),
+            comment(1, "one, edited", edited),
+            voted,
+            deleted.clone(),
+            comment(4, "four", None),
+            comment(4, "four", None),
+        ]));
+        assert_eq!(
+            stats,
+            MergeStats {
+                added: 1,
+                updated: 2,
+                removed: 1
+            }
+        );
+        assert_eq!(stats.to_string(), "1 new, 2 updated, 1 removed");
+
+        let ids: Vec<u64> = comments.comments().iter().map(|c| c.comment.id).collect();
+        assert_eq!(ids, [1, 2, 3, 4], "no duplicates");
+
+        let [one, two, three, _] = comments.comments() else {
+            panic!("expected 4 comments");
+        };
+        assert_eq!(one.comment.content, "one, edited");
+        assert_eq!(
+            (two.comment.content.as_str(), two.counts.score),
+            ("two, edited", 3),
+            "the newer edit, with the new counts"
+        );
+        assert!(three.comment.deleted);
+
+        // already deleted
+        let stats = comments.merge(CommentResponses::new(vec![deleted]));
+        assert!(stats.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dl_all_comments() {
+        // page 2 overlaps page 1, as if a new comment shifted the pages while downloading
+        let (addr, _) = serve(|req: Request<Body>| {
+            let page = req
+                .uri()
+                .query()
+                .unwrap_or("")
+                .split('&')
+                .find_map(|q| q.strip_prefix("page="))
+                .and_then(|p| p.parse::<u64>().ok())
+                .unwrap_or(1);
+
+            let ids: &[u64] = match page {
+                1 => &[1, 2],
+                2 => &[2, 3],
+                _ => &[4, 5],
+            };
+
+            let comments = ids
+                .iter()
+                .map(|&id| CommentResponse {
+                    comment: Comment {
+                        id,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .collect();
+
+            Response::new(Body::from(
+                serde_json::to_vec(&CommentResponses::new(comments)).unwrap(),
+            ))
+        });
+
+        let client = Client::new(format!("http://{addr}")).with_concurrency(3);
+
+        let mut ids: Vec<u64> = dl_all_comments(&client, 1, 6, 2)
+            .await
+            .unwrap()
+            .comments()
+            .iter()
+            .map(|c| c.comment.id())
+            .collect();
+        ids.sort();
+
+        assert_eq!(ids, [1, 2, 3, 4, 5]);
+
+        // the first page arrives before the rest are requested
+        let pages = std::sync::Mutex::new(Vec::new());
+        let on_page = |page: &CommentResponses| {
+            let ids: Vec<u64> = page.comments().iter().map(|c| c.comment.id()).collect();
+            pages.lock().unwrap().push(ids);
+        };
+        dl_all_comments_with_progress(&client, 1, 0..6, 2, &Progress::new(), &on_page)
+            .await
+            .unwrap();
+
+        let pages = pages.into_inner().unwrap();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0], [1, 2]);
+
+        // a later batch starts at its own page
+        let progress = Progress::new();
+        let mut ids: Vec<u64> =
+            dl_all_comments_with_progress(&client, 1, 2..6, 2, &progress, &|_| ())
+                .await
+                .unwrap()
+                .comments()
+                .iter()
+                .map(|c| c.comment.id())
+                .collect();
+        ids.sort();
+
+        assert_eq!(ids, [2, 3, 4, 5]);
+        assert_eq!((progress.done(), progress.total()), (2, 2));
+    }
+
+    #[test]
+    fn test_comments_lemmy_versions() {
+        for (json, len) in [
+            (
+                include_str!("../tests/fixtures/lemmy-0.18.5/comment_list.json"),
+                2,
+            ),
+            (
+                include_str!("../tests/fixtures/lemmy-0.19.3/comment_list.json"),
+                1,
+            ),
+        ] {
+            let comments: CommentResponses = serde_json::from_str(json).unwrap();
+            let reserialized = serde_json::to_string(&comments).unwrap();
+
+            assert_eq!(
+                serde_json::from_str::<CommentResponses>(&reserialized).unwrap(),
+                comments
+            );
+            assert_eq!(comments.comments.len(), len);
+            assert!(comments
+                .comments
+                .iter()
+                .all(|c| c.creator.name() == "crab" || c.creator.name() == "ferris"));
+        }
+    }
+
+    #[test]
+    fn test_comments_logged_in_fields() {
+        let authenticated: CommentResponses = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/comment_list.json"
+        ))
+        .unwrap();
+        let c = &authenticated.comments[0];
+        assert_eq!(c.my_vote, Some(1));
+        assert_eq!(c.subscribed, SubscribedType::Subscribed);
+        assert!(!c.saved);
+
+        // anonymous responses leave out the vote
+        let anonymous: CommentResponses = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/comment_list_anonymous.json"
+        ))
+        .unwrap();
+        let c = &anonymous.comments[0];
+        assert_eq!(c.my_vote, None);
+        assert_eq!(c.subscribed, SubscribedType::NotSubscribed);
+        assert_eq!(c.comment, authenticated.comments[0].comment);
+    }
+
+    #[tokio::test]
+    async fn test_get_comments_table() {
+        let (addr, _) = serve(|_| {
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.18.5/comment_list.json"
+            )))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let mut table =
+            CommentResponseTable::from(client.get_comments(4312019, 1, 50, None).await.unwrap());
+
+        table.sort_comments(CommentSort::Old);
+
+        let paths: Vec<&str> = table
+            .items
+            .iter()
+            .map(|c| c.comment.path.as_str())
+            .collect();
+        assert_eq!(paths, ["0.3551200", "0.3551200.3551377"]);
     }
 }
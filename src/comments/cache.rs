@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+
+use super::CommentResponseTable;
+
+/// Default number of [CommentResponseTable]s to keep in a [CommentCache].
+pub const DEFAULT_CACHE_CAPACITY: usize = 20;
+
+/// Least-recently-used cache of [CommentResponseTable]s, or other data loaded per post, e.g. its
+/// crossposts, keyed by [Post](crate::posts::Post) ID.
+///
+/// The pinned entry, e.g. for the currently-open post, is never evicted, and does not count
+/// against the capacity.
+#[derive(Clone, Debug)]
+pub struct CommentCache<T = CommentResponseTable> {
+    capacity: usize,
+    pinned: Option<u64>,
+    entries: HashMap<u64, T>,
+    // least-recently used first
+    order: VecDeque<u64>,
+}
+
+impl<T> CommentCache<T> {
+    /// Creates a new [CommentCache] holding up to `capacity` entries, plus the pinned entry.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pinned: None,
+            entries: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Gets the capacity.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Sets the capacity, evicting entries if necessary.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Gets whether the [CommentCache] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets whether the [CommentCache] contains an entry for the post.
+    pub fn contains(&self, post_id: u64) -> bool {
+        self.entries.contains_key(&post_id)
+    }
+
+    /// Pins the entry for the post, protecting it from eviction.
+    pub fn pin(&mut self, post_id: Option<u64>) {
+        self.pinned = post_id;
+    }
+
+    /// Gets the entry for the post, without updating its recency.
+    pub fn get(&self, post_id: u64) -> Option<&T> {
+        self.entries.get(&post_id)
+    }
+
+    /// Gets a mutable entry for the post, without updating its recency.
+    pub fn get_mut(&mut self, post_id: u64) -> Option<&mut T> {
+        self.entries.get_mut(&post_id)
+    }
+
+    /// Marks the entry for the post as most-recently used.
+    pub fn touch(&mut self, post_id: u64) {
+        if let Some(i) = self.order.iter().position(|&id| id == post_id) {
+            self.order.remove(i);
+            self.order.push_back(post_id);
+        }
+    }
+
+    /// Inserts an entry for the post, evicting the least-recently used entries over capacity.
+    pub fn insert(&mut self, post_id: u64, entry: T) {
+        if self.entries.insert(post_id, entry).is_some() {
+            self.touch(post_id);
+        } else {
+            self.order.push_back(post_id);
+        }
+
+        self.evict();
+    }
+
+    /// Removes the entry for the post, e.g. comments left incomplete by a cancelled download.
+    pub fn remove(&mut self, post_id: u64) -> Option<T> {
+        self.order.retain(|&id| id != post_id);
+        self.entries.remove(&post_id)
+    }
+
+    /// Gets a mutable iterator over all entries.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.values_mut()
+    }
+
+    fn evict(&mut self) {
+        let pinned = self.pinned.filter(|id| self.entries.contains_key(id));
+
+        while self.entries.len() - usize::from(pinned.is_some()) > self.capacity {
+            match self.order.iter().position(|&id| Some(id) != pinned) {
+                Some(i) => {
+                    if let Some(id) = self.order.remove(i) {
+                        self.entries.remove(&id);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl Default for CommentCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comments::CommentResponses;
+
+    #[test]
+    fn test_comment_cache_eviction() {
+        let mut cache = CommentCache::default();
+        cache.pin(Some(0));
+
+        for id in 0..25 {
+            cache.insert(id, CommentResponses::new(Vec::new()).into());
+        }
+
+        assert_eq!(cache.len(), DEFAULT_CACHE_CAPACITY + 1);
+        assert!(cache.contains(0));
+        (1..5).for_each(|id| assert!(!cache.contains(id)));
+        (5..25).for_each(|id| assert!(cache.contains(id)));
+
+        assert!(cache.remove(5).is_some());
+        assert!(cache.remove(5).is_none());
+        cache.insert(25, CommentResponses::new(Vec::new()).into());
+        assert!(cache.contains(6), "removed entries free up capacity");
+    }
+}
@@ -1,9 +1,16 @@
 //! Types and functions for [Post](crate::posts::Post) comments.
 
+use std::time::SystemTime;
+
 use tui::widgets::ListState;
 
+use crate::utils::parse_timestamp;
+
 /// Represents a comment on a [Post](crate::posts::Post).
+///
+/// Missing fields are defaulted, to tolerate differences between Lemmy versions.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Comment {
     pub id: u64,
     pub creator_id: u64,
@@ -11,6 +18,8 @@ pub struct Comment {
     pub content: String,
     pub removed: bool,
     pub published: String,
+    /// Time the comment was last edited, if ever.
+    pub updated: Option<String>,
     pub deleted: bool,
     pub ap_id: String,
     pub local: bool,
@@ -29,6 +38,7 @@ impl Comment {
             content: String::new(),
             removed: false,
             published: String::new(),
+            updated: None,
             deleted: false,
             ap_id: String::new(),
             local: false,
@@ -58,6 +68,34 @@ impl Comment {
         self.content.as_str()
     }
 
+    /// Gets the time the [Comment] was published.
+    pub fn published(&self) -> &str {
+        self.published.as_str()
+    }
+
+    /// Gets whether the [Comment] has been edited.
+    pub const fn edited(&self) -> bool {
+        self.updated.is_some()
+    }
+
+    /// Gets whether the [Comment] was published, or edited, after `since`.
+    ///
+    /// Timestamps that fail to parse are never newer.
+    pub fn is_newer_than(&self, since: SystemTime) -> bool {
+        self.edited_at().is_some_and(|time| time > since)
+    }
+
+    /// Gets the time the [Comment] was last edited, or published if never edited.
+    ///
+    /// Timestamps that fail to parse are ignored.
+    pub fn edited_at(&self) -> Option<SystemTime> {
+        [Some(self.published.as_str()), self.updated.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(parse_timestamp)
+            .max()
+    }
+
     /// Gets whether the [Comment] is deleted.
     pub const fn deleted(&self) -> bool {
         self.deleted
@@ -73,6 +111,34 @@ impl Comment {
         self.local
     }
 
+    /// Gets the depth of the [Comment] from its path, zero for root comments.
+    ///
+    /// Returns `None` for a malformed or truncated path, which should be the `0` root followed
+    /// by numeric comment IDs, e.g. `0.12.34`.
+    pub fn path_level(&self) -> Option<usize> {
+        let mut ids = self.path.split('.');
+        if ids.next() != Some("0") {
+            return None;
+        }
+
+        let mut count = 0usize;
+        for id in ids {
+            id.parse::<u64>().ok()?;
+            count += 1;
+        }
+
+        count.checked_sub(1)
+    }
+
+    /// Gets whether the path of the [Comment] is well-formed, see
+    /// [path_level](Self::path_level), and ends with the comment ID.
+    ///
+    /// The ID is not checked when unset, i.e. zero.
+    pub fn has_valid_path(&self) -> bool {
+        self.path_level().is_some()
+            && (self.id == 0 || self.path.rsplit('.').next() == Some(self.id.to_string().as_str()))
+    }
+
     /// Gets the path of the [Comment].
     pub fn path(&self) -> &str {
         self.path.as_str()
@@ -0,0 +1,365 @@
+use std::{cmp, fmt};
+
+use hashbrown::HashMap;
+
+use super::{CommentResponse, CommentResponses};
+
+/// Order of sibling comments in a [CommentTree], like the lemmy-ui comment sorts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommentSort {
+    /// Highest hot rank first, from the server, falling back to score.
+    Hot,
+    /// Highest score first.
+    Top,
+    /// Newest first.
+    New,
+    /// Oldest first, by the comment IDs in the path, which increase over time.
+    #[default]
+    Old,
+    /// Most evenly split votes first, weighted by the number of votes.
+    Controversial,
+}
+
+impl CommentSort {
+    /// Gets the next [CommentSort], for cycling through the sorts.
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Hot => Self::Top,
+            Self::Top => Self::New,
+            Self::New => Self::Old,
+            Self::Old => Self::Controversial,
+            Self::Controversial => Self::Hot,
+        }
+    }
+
+    /// Compares sibling comments, with the comment first in this order being less.
+    fn compare(self, lhs: &CommentResponse, rhs: &CommentResponse) -> cmp::Ordering {
+        let (lhs_counts, rhs_counts) = (&lhs.counts, &rhs.counts);
+
+        match self {
+            Self::Hot => rhs_counts
+                .hot_rank
+                .cmp(&lhs_counts.hot_rank)
+                .then(rhs_counts.score.cmp(&lhs_counts.score)),
+            Self::Top => rhs_counts.score.cmp(&lhs_counts.score),
+            Self::New => rhs.comment.published.cmp(&lhs.comment.published),
+            // sorted by path
+            Self::Old => cmp::Ordering::Equal,
+            Self::Controversial => controversy(rhs).total_cmp(&controversy(lhs)),
+        }
+    }
+}
+
+impl fmt::Display for CommentSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Hot => "Hot",
+            Self::Top => "Top",
+            Self::New => "New",
+            Self::Old => "Old",
+            Self::Controversial => "Controversial",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Gets the controversy rank of the comment, as calculated by Lemmy.
+fn controversy(comment: &CommentResponse) -> f64 {
+    let (up, down) = (comment.counts.upvotes, comment.counts.downvotes);
+
+    if up == 0 || down == 0 {
+        0.0
+    } else {
+        let balance = up.min(down) as f64 / up.max(down) as f64;
+        ((up + down) as f64).powf(balance)
+    }
+}
+
+/// Tree of [CommentResponse]s, linked by their comment paths.
+///
+/// Paths are the [path IDs](CommentResponse::path_ids) cached by
+/// [finalize](CommentResponse::finalize), as in sorting and levels, instead of parsed on every
+/// comparison. Comments whose parent is missing, e.g. on a page that failed to download, are attached at the root level.
+///
+/// [Orphaned](CommentResponse::orphaned) comments, whose path is malformed, follow the threads at
+/// the root level, oldest first, without replies.
+#[derive(Clone, Debug, Default)]
+pub struct CommentTree {
+    comments: Vec<CommentResponse>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+impl CommentTree {
+    /// Creates a new [CommentTree], setting the level of each [CommentResponse] to its depth in
+    /// the tree, and whether it is [orphaned](CommentResponse::orphaned).
+    pub fn new(mut comments: Vec<CommentResponse>) -> Self {
+        // comments not from deserialized responses are parsed once here
+        for c in comments.iter_mut().filter(|c| c.path_ids.is_empty()) {
+            c.finalize();
+        }
+
+        // the last path element is the comment ID
+        let index: HashMap<u64, usize> = comments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| tree_path(c).last().map(|&id| (id, i)))
+            .collect();
+
+        let mut children = vec![Vec::new(); comments.len()];
+        let mut roots = Vec::new();
+
+        for (i, c) in comments.iter().enumerate() {
+            let path = tree_path(c);
+            let parent = path
+                .len()
+                .checked_sub(2)
+                .and_then(|p| index.get(&path[p]))
+                // parents have shorter paths, so malformed paths cannot form cycles
+                .filter(|&&p| tree_path(&comments[p]).len() < path.len());
+
+            match parent {
+                Some(&p) => children[p].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        let mut stack: Vec<(usize, usize)> = roots.iter().map(|&i| (i, 0)).collect();
+        while let Some((i, level)) = stack.pop() {
+            comments[i].set_level(level);
+            stack.extend(children[i].iter().map(|&c| (c, level + 1)));
+        }
+
+        Self {
+            comments,
+            children,
+            roots,
+        }
+    }
+
+    /// Gets the number of comments in the [CommentTree].
+    pub fn len(&self) -> usize {
+        self.comments.len()
+    }
+
+    /// Gets whether the [CommentTree] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.comments.is_empty()
+    }
+
+    /// Gets the comments depth-first, with replies following their parents, and siblings in
+    /// `order`.
+    pub fn flatten(&self, order: CommentSort) -> Vec<&CommentResponse> {
+        self.order(order)
+            .into_iter()
+            .map(|i| &self.comments[i])
+            .collect()
+    }
+
+    /// Converts the [CommentTree] into a list of comments, in the same order as
+    /// [flatten](Self::flatten).
+    pub fn into_vec(self, order: CommentSort) -> Vec<CommentResponse> {
+        let order = self.order(order);
+        let mut comments: Vec<Option<CommentResponse>> =
+            self.comments.into_iter().map(Some).collect();
+
+        order
+            .into_iter()
+            .filter_map(|i| comments[i].take())
+            .collect()
+    }
+
+    /// Gets the comment indices depth-first, followed by the orphaned comments.
+    fn order(&self, order: CommentSort) -> Vec<usize> {
+        let mut flat = Vec::with_capacity(self.comments.len());
+
+        let (mut orphans, roots): (Vec<usize>, Vec<usize>) =
+            self.roots.iter().partition(|&&i| self.comments[i].orphaned);
+        orphans.sort_by(|&a, &b| {
+            let (a, b) = (&self.comments[a].comment, &self.comments[b].comment);
+            a.published.cmp(&b.published).then(a.id.cmp(&b.id))
+        });
+
+        let mut stack = self.sorted(&roots, order);
+        stack.reverse();
+
+        while let Some(i) = stack.pop() {
+            flat.push(i);
+            stack.extend(self.sorted(&self.children[i], order).into_iter().rev());
+        }
+        flat.extend(orphans);
+
+        flat
+    }
+
+    /// Gets the sibling comment indices in `order`.
+    fn sorted(&self, siblings: &[usize], order: CommentSort) -> Vec<usize> {
+        let mut sorted = siblings.to_vec();
+
+        // ties, and replies whose parent is missing at the root level, sort by their full path,
+        // next to their lost thread
+        sorted.sort_by(|&a, &b| {
+            order
+                .compare(&self.comments[a], &self.comments[b])
+                .then_with(|| tree_path(&self.comments[a]).cmp(tree_path(&self.comments[b])))
+        });
+
+        sorted
+    }
+}
+
+/// Gets the cached path IDs of the comment in the [CommentTree], empty for
+/// [orphaned](CommentResponse::orphaned) comments, so they are roots, and cannot be parents.
+fn tree_path(comment: &CommentResponse) -> &[u64] {
+    if comment.orphaned {
+        &[]
+    } else {
+        &comment.path_ids
+    }
+}
+
+impl From<Vec<CommentResponse>> for CommentTree {
+    fn from(val: Vec<CommentResponse>) -> Self {
+        Self::new(val)
+    }
+}
+
+impl From<CommentResponses> for CommentTree {
+    fn from(val: CommentResponses) -> Self {
+        Self::new(val.comments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(path: &str) -> CommentResponse {
+        CommentResponse::test_at(path)
+    }
+
+    fn paths(comments: &[&CommentResponse]) -> Vec<String> {
+        comments.iter().map(|c| c.comment.path.clone()).collect()
+    }
+
+    #[test]
+    fn test_comment_tree() {
+        let tree = CommentTree::new(
+            ["0.5.6", "0.5", "0.1.2.3", "0.1", "0.1.2", "0.9.8.7", "0.4"]
+                .map(comment)
+                .to_vec(),
+        );
+
+        let flat = tree.flatten(CommentSort::Old);
+        assert_eq!(
+            paths(&flat),
+            ["0.1", "0.1.2", "0.1.2.3", "0.4", "0.5", "0.5.6", "0.9.8.7"]
+        );
+
+        // the orphan, whose parent is missing, is at the root level
+        let levels: Vec<usize> = flat.iter().map(|c| c.level()).collect();
+        assert_eq!(levels, [0, 1, 2, 0, 0, 1, 0]);
+
+        let comments = tree.clone().into_vec(CommentSort::Old);
+        assert_eq!(comments.iter().collect::<Vec<_>>(), flat);
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn test_comment_sort() {
+        let mut comments = ["0.1", "0.1.2", "0.1.3", "0.4", "0.5"]
+            .map(comment)
+            .to_vec();
+        for (c, (score, up, down, published)) in comments.iter_mut().zip([
+            (5, 6, 1, "2024-01-01"),
+            (1, 1, 0, "2024-01-03"),
+            (3, 4, 1, "2024-01-02"),
+            (0, 5, 5, "2024-01-05"),
+            (9, 9, 0, "2024-01-04"),
+        ]) {
+            c.counts.score = score;
+            c.counts.upvotes = up;
+            c.counts.downvotes = down;
+            c.comment.published = published.into();
+        }
+        let tree = CommentTree::new(comments);
+        let order = |sort| paths(&tree.flatten(sort));
+
+        // replies stay under their parents
+        assert_eq!(
+            order(CommentSort::Top),
+            ["0.5", "0.1", "0.1.3", "0.1.2", "0.4"]
+        );
+        assert_eq!(order(CommentSort::Hot), order(CommentSort::Top));
+        assert_eq!(
+            order(CommentSort::New),
+            ["0.4", "0.5", "0.1", "0.1.2", "0.1.3"]
+        );
+        assert_eq!(
+            order(CommentSort::Old),
+            ["0.1", "0.1.2", "0.1.3", "0.4", "0.5"]
+        );
+        assert_eq!(
+            order(CommentSort::Controversial),
+            ["0.4", "0.1", "0.1.3", "0.1.2", "0.5"]
+        );
+
+        let mut sort = CommentSort::Hot;
+        for _ in 0..5 {
+            sort = sort.next();
+        }
+        assert_eq!(sort, CommentSort::Hot);
+        assert_eq!(CommentSort::Controversial.to_string(), "Controversial");
+    }
+
+    #[test]
+    fn test_comment_tree_malformed() {
+        let tree = CommentTree::new(["", "0.x", "0.1.1", "0.2.3", "0.3.2"].map(comment).to_vec());
+
+        // every comment is kept, even without a valid parent
+        let flat = tree.flatten(CommentSort::Old);
+        assert_eq!(flat.len(), 5);
+        assert!(flat.iter().all(|c| c.level() == 0));
+
+        let levels = ["0.1", "0.1.2.3", "", "0", "1.2", "0.1.x", "0..2"]
+            .map(|path| comment(path).comment.path_level());
+        assert_eq!(levels, [Some(0), Some(2), None, None, None, None, None]);
+        assert!(CommentTree::default().flatten(CommentSort::Old).is_empty());
+
+        // the path must end with the comment ID, when set
+        let mut c = comment("0.1.2");
+        assert!(c.comment.has_valid_path());
+        c.comment.id = 3;
+        assert!(!c.comment.has_valid_path());
+        c.comment.id = 0;
+        assert!(c.comment.has_valid_path());
+    }
+
+    #[test]
+    fn test_comment_tree_orphans() {
+        let responses: CommentResponses = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lemmy-0.19.3/comment_malformed.json"
+        ))
+        .unwrap();
+        let ids = |comments: &[CommentResponse]| -> Vec<(u64, usize)> {
+            comments.iter().map(|c| (c.comment.id, c.level())).collect()
+        };
+
+        // the thread is untouched, with orphans after it, oldest first, and the comment taking
+        // the path of another does not take its replies
+        for sort in [CommentSort::Old, CommentSort::New, CommentSort::Top] {
+            let comments = CommentTree::from(responses.clone()).into_vec(sort);
+            assert_eq!(
+                ids(&comments[4..]),
+                [(8100600, 0), (8100620, 0), (8100610, 0)]
+            );
+            assert!(comments[4..].iter().all(|c| c.orphaned));
+            assert!(comments[..4].iter().all(|c| !c.orphaned));
+        }
+        assert_eq!(
+            ids(&CommentTree::from(responses).into_vec(CommentSort::Old)[..4]),
+            [(8100321, 0), (8100400, 1), (8100455, 2), (8100502, 0)]
+        );
+    }
+}
@@ -1,7 +1,19 @@
-use tui::widgets::ListState;
+//! Types and functions for communities.
+
+use std::fmt;
+
+use tui::widgets::{ListState, TableState};
+
+use crate::{
+    client::Client, counts::CommunityCounts, endpoint::Endpoint, posts::ListingType,
+    utils::actor_host, Result,
+};
 
 /// Represents a response to an API request that presents a `community` field.
+///
+/// Flags like `hidden` come and go between Lemmy versions, so missing fields are defaulted.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Community {
     pub id: u64,
     pub name: String,
@@ -12,6 +24,7 @@ pub struct Community {
     pub updated: Option<String>,
     pub deleted: bool,
     pub nsfw: bool,
+    #[serde(alias = "ap_id")]
     pub actor_id: String,
     pub local: bool,
     pub icon: Option<String>,
@@ -41,6 +54,305 @@ impl Community {
             instance_id: 0,
         }
     }
+
+    /// Gets the full [Community] handle, `!name@host`, with the host from the actor ID.
+    ///
+    /// The host is left out if the actor ID has none.
+    pub fn handle(&self) -> String {
+        match actor_host(&self.actor_id) {
+            Some(host) => format!("!{}@{}", self.name, host.to_ascii_lowercase()),
+            None => format!("!{}", self.name),
+        }
+    }
+}
+
+impl Default for Community {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the subscription state of the user for a [Community].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum SubscribedType {
+    #[serde(alias = "subscribed")]
+    Subscribed,
+    #[default]
+    #[serde(alias = "not_subscribed", alias = "notSubscribed")]
+    NotSubscribed,
+    /// Subscription request sent to a remote instance, but not yet accepted.
+    #[serde(alias = "pending")]
+    Pending,
+}
+
+impl SubscribedType {
+    /// Gets whether the user is subscribed, or has a pending subscription.
+    pub const fn is_subscribed(&self) -> bool {
+        matches!(self, Self::Subscribed | Self::Pending)
+    }
+}
+
+impl From<SubscribedType> for &'static str {
+    fn from(val: SubscribedType) -> Self {
+        match val {
+            SubscribedType::Subscribed => "Subscribed",
+            SubscribedType::NotSubscribed => "NotSubscribed",
+            SubscribedType::Pending => "Pending",
+        }
+    }
+}
+
+impl fmt::Display for SubscribedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", <&str>::from(*self))
+    }
+}
+
+/// Represents the request body for the [FollowCommunity](Endpoint::FollowCommunity) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct FollowCommunity<'a> {
+    pub community_id: u64,
+    pub follow: bool,
+    pub auth: &'a str,
+}
+
+/// Represents a `community_view` field in a response to a [Community] API request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CommunityView {
+    pub community: Community,
+    pub subscribed: SubscribedType,
+    pub blocked: bool,
+    #[serde(default)]
+    pub counts: CommunityCounts,
+}
+
+/// Represents a response to the [CommunityList](Endpoint::CommunityList) endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ListCommunitiesResponse {
+    pub communities: Vec<CommunityView>,
+}
+
+/// Represents a response to a [Community] API request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CommunityResponse {
+    pub community_view: CommunityView,
+}
+
+/// Follow, or unfollow, a [Community] using the
+/// [FollowCommunity](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::follow_community to share connections between requests")]
+pub async fn follow_community(
+    instance_url: &str,
+    jwt: &str,
+    community_id: u64,
+    follow: bool,
+) -> Result<CommunityResponse> {
+    Client::new(instance_url)
+        .with_jwt(jwt)
+        .follow_community(community_id, follow)
+        .await
+}
+
+/// Represents the request body for the [BlockCommunity](Endpoint::BlockCommunity) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct BlockCommunity<'a> {
+    pub community_id: u64,
+    pub block: bool,
+    pub auth: &'a str,
+}
+
+/// Represents a response to the [BlockCommunity](Endpoint::BlockCommunity) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BlockCommunityResponse {
+    pub community_view: CommunityView,
+    pub blocked: bool,
+}
+
+/// Block, or unblock, a [Community] using the
+/// [BlockCommunity](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::block_community to share connections between requests")]
+pub async fn block_community(
+    instance_url: &str,
+    jwt: &str,
+    community_id: u64,
+    block: bool,
+) -> Result<BlockCommunityResponse> {
+    Client::new(instance_url)
+        .with_jwt(jwt)
+        .block_community(community_id, block)
+        .await
+}
+
+impl Client {
+    /// Follow, or unfollow, a [Community] using the
+    /// [FollowCommunity](crate::endpoint::Endpoint) endpoint.
+    pub async fn follow_community(
+        &self,
+        community_id: u64,
+        follow: bool,
+    ) -> Result<CommunityResponse> {
+        let auth = self.auth()?;
+        let body = FollowCommunity {
+            community_id,
+            follow,
+            auth: &auth,
+        };
+
+        self.post_json(self.url(Endpoint::FollowCommunity, &[]).as_str(), &body)
+            .await
+    }
+
+    /// Block, or unblock, a [Community] using the
+    /// [BlockCommunity](crate::endpoint::Endpoint) endpoint.
+    pub async fn block_community(
+        &self,
+        community_id: u64,
+        block: bool,
+    ) -> Result<BlockCommunityResponse> {
+        let auth = self.auth()?;
+        let body = BlockCommunity {
+            community_id,
+            block,
+            auth: &auth,
+        };
+
+        self.post_json(self.url(Endpoint::BlockCommunity, &[]).as_str(), &body)
+            .await
+    }
+
+    /// Gets a page of the communities most active over the last month from the
+    /// [CommunityList](Endpoint::CommunityList) endpoint, for the [ListingType].
+    pub async fn list_communities(
+        &self,
+        listing_type: ListingType,
+        page: u64,
+    ) -> Result<ListCommunitiesResponse> {
+        let mut params = vec![
+            ("sort", "TopMonth".into()),
+            ("type_", listing_type.to_string()),
+            ("page", page.to_string()),
+        ];
+        if let Some(limit) = self.posts_query().limit {
+            params.push(("limit", limit.to_string()));
+        }
+        let url = self.url(Endpoint::CommunityList, &params);
+
+        self.get_json(url.as_str()).await
+    }
+}
+
+/// Communities most active over the last month, shown on the Discover screen.
+pub struct Discover {
+    /// Listing type, either [ListingType::All], or [ListingType::Local].
+    pub listing_type: ListingType,
+    /// Page of communities.
+    pub page: u64,
+    /// Communities on the page, `None` until downloaded.
+    pub communities: Option<Vec<CommunityView>>,
+    /// Selected row of the table.
+    pub state: TableState,
+}
+
+impl Discover {
+    /// Creates a new [Discover], listing communities from all instances, with nothing
+    /// downloaded.
+    pub fn new() -> Self {
+        Self {
+            listing_type: ListingType::All,
+            page: 1,
+            communities: None,
+            state: TableState::default(),
+        }
+    }
+
+    /// Switches between local communities, and communities from all instances, back on the
+    /// first page.
+    pub fn toggle_listing_type(&mut self) {
+        self.listing_type = match self.listing_type {
+            ListingType::All => ListingType::Local,
+            _ => ListingType::All,
+        };
+        self.page = 1;
+        self.communities = None;
+        self.state = TableState::default();
+    }
+
+    /// Sets the page, starting from 1.
+    pub fn set_page(&mut self, page: u64) {
+        self.page = page.max(1);
+    }
+
+    /// Sets the downloaded page of communities, selecting the first.
+    pub fn set_communities(&mut self, res: ListCommunitiesResponse) {
+        self.state = TableState::default();
+        self.state
+            .select((!res.communities.is_empty()).then_some(0));
+        self.communities = Some(res.communities);
+    }
+
+    /// Gets the number of downloaded communities.
+    pub fn len(&self) -> usize {
+        self.communities.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Gets whether there are no downloaded communities.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the selected [CommunityView], if any.
+    pub fn current(&self) -> Option<&CommunityView> {
+        self.communities.as_ref()?.get(self.state.selected()?)
+    }
+
+    /// Selects the next community, if any, wrapping around.
+    pub fn next(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        let i = self.state.selected().map_or(0, |i| (i + 1) % self.len());
+        self.state.select(Some(i));
+    }
+
+    /// Selects the previous community, if any, wrapping around.
+    pub fn previous(&mut self) {
+        let Some(last) = self.len().checked_sub(1) else {
+            return;
+        };
+
+        let i = self
+            .state
+            .selected()
+            .map_or(last, |i| i.checked_sub(1).unwrap_or(last));
+        self.state.select(Some(i));
+    }
+
+    /// Selects the first community, if any.
+    pub fn first(&mut self) {
+        self.state.select((!self.is_empty()).then_some(0));
+    }
+
+    /// Selects the last community, if any.
+    pub fn last(&mut self) {
+        self.state.select(self.len().checked_sub(1));
+    }
+
+    /// Sets the [SubscribedType] of the community, e.g. after following it.
+    pub fn set_subscribed(&mut self, community_id: u64, subscribed: SubscribedType) {
+        self.communities
+            .iter_mut()
+            .flatten()
+            .filter(|c| c.community.id == community_id)
+            .for_each(|c| c.subscribed = subscribed);
+    }
+}
+
+impl Default for Discover {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// List of [Community] for displaying in the TUI.
@@ -126,3 +438,94 @@ impl AsMut<Communities> for Communities {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Response};
+
+    use super::*;
+    use crate::client::mock::serve;
+
+    #[test]
+    fn test_community_handle() {
+        let handle = |actor_id: &str| {
+            Community {
+                name: "rust".into(),
+                actor_id: actor_id.into(),
+                ..Default::default()
+            }
+            .handle()
+        };
+
+        assert_eq!(handle("https://lemmy.ml/c/rust"), "!rust@lemmy.ml");
+        assert_eq!(
+            handle("https://programming.dev:443/c/rust/"),
+            "!rust@programming.dev:443"
+        );
+        assert_eq!(handle("not a url"), "!rust@not a url");
+        assert_eq!(handle(""), "!rust");
+    }
+
+    #[tokio::test]
+    async fn test_list_communities() {
+        let (addr, _) = serve(|req| {
+            assert_eq!(req.uri().path(), "/api/v3/community/list");
+            assert_eq!(req.uri().query(), Some("sort=TopMonth&type_=Local&page=2"));
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.19.3/community_list.json"
+            )))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let res = client
+            .list_communities(ListingType::Local, 2)
+            .await
+            .unwrap();
+
+        assert_eq!(res.communities.len(), 2);
+        let c = &res.communities[0];
+        assert_eq!(c.community.handle(), "!rust@lemmy.ml");
+        assert_eq!(c.subscribed, SubscribedType::Subscribed);
+        assert_eq!(c.counts.subscribers, 48213);
+        assert_eq!(c.counts.posts, 3560);
+        assert_eq!(c.counts.users_active_week, 1280);
+
+        // responses to following a community may have no counts
+        let view: CommunityView = serde_json::from_value(serde_json::json!({
+            "community": c.community,
+            "subscribed": "Pending",
+            "blocked": false,
+        }))
+        .unwrap();
+        assert_eq!(view.counts, CommunityCounts::default());
+    }
+
+    #[test]
+    fn test_discover() {
+        let mut discover = Discover::new();
+        discover.next();
+        assert!(discover.current().is_none());
+
+        let res: ListCommunitiesResponse = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/community_list.json"
+        ))
+        .unwrap();
+        discover.set_communities(res);
+        discover.previous();
+        assert_eq!(discover.current().map(|c| c.community.id), Some(7710));
+
+        discover.set_subscribed(7710, SubscribedType::Pending);
+        assert_eq!(
+            discover.current().map(|c| c.subscribed),
+            Some(SubscribedType::Pending)
+        );
+
+        discover.set_page(3);
+        discover.toggle_listing_type();
+        assert_eq!(discover.listing_type, ListingType::Local);
+        assert_eq!(discover.page, 1);
+        assert!(discover.communities.is_none());
+        discover.toggle_listing_type();
+        assert_eq!(discover.listing_type, ListingType::All);
+    }
+}
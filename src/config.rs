@@ -0,0 +1,635 @@
+//! Configuration loaded from `$XDG_CONFIG_HOME/temi/config.toml`.
+//!
+//! Settings are merged in order of precedence: CLI flags > environment variables > config file >
+//! defaults.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use tui::style::Color;
+
+use crate::{
+    client::{Client, InstanceUrl, DEFAULT_TIMEOUT},
+    comments::{DEFAULT_CACHE_CAPACITY, MAX_COMMENT_LIMIT},
+    keymap::{KeyBinding, KeyMap},
+    posts::{
+        DiskCache, Filters, ListingType, PostFilter, PostsQuery, SortType, DEFAULT_IMAGE_CACHE_MB,
+    },
+    session::RestoreSession,
+    theme::{Theme, ThemeColor, ThemeConfig, ThemePreset},
+    utils::{actor_host, TimeFormat},
+    Error, Result,
+};
+
+/// Default Lemmy instance.
+pub const DEFAULT_INSTANCE: &str = "https://voyager.lemmy.ml";
+/// Default score for highlighting comments.
+pub const DEFAULT_HIGH_SCORE: i64 = 100;
+/// Default guide drawn for each comment indent level.
+pub const DEFAULT_INDENT_GUIDE: &str = "│ ";
+/// Default interval between live mode refreshes, in seconds.
+pub const DEFAULT_LIVE_INTERVAL: u64 = 60;
+/// Shortest interval between live mode refreshes, in seconds, to spare the instance.
+pub const MIN_LIVE_INTERVAL: u64 = 30;
+/// Default number of comments downloaded when opening a post, before loading more by hand.
+pub const DEFAULT_COMMENT_FETCH_CAP: u64 = 500;
+
+/// Display of NSFW posts, and posts in NSFW communities.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NsfwMode {
+    /// Filter NSFW posts out of the post list.
+    #[default]
+    Hide,
+    /// List NSFW posts by community, with their content hidden until revealed.
+    Blur,
+    /// Show NSFW posts like any other.
+    Show,
+}
+
+impl NsfwMode {
+    /// Gets the next [NsfwMode], for cycling through the modes.
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Hide => Self::Blur,
+            Self::Blur => Self::Show,
+            Self::Show => Self::Hide,
+        }
+    }
+}
+
+impl FromStr for NsfwMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "hide" => Ok(Self::Hide),
+            "blur" => Ok(Self::Blur),
+            "show" => Ok(Self::Show),
+            _ => Err(format!("invalid NSFW mode: {s}")),
+        }
+    }
+}
+
+impl std::fmt::Display for NsfwMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Hide => "hide",
+            Self::Blur => "blur",
+            Self::Show => "show",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Lemmy instance listed in the [Config], to switch to at runtime.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstanceConfig {
+    /// Instance URL, e.g. `https://lemmy.ml`.
+    pub url: String,
+    /// Name of the account on the instance, if any.
+    pub account: Option<String>,
+    /// More accounts on the instance to switch to at runtime, e.g. a moderation account.
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// Name shown in the status bar, and the instance picker, defaults to the host.
+    pub label: Option<String>,
+}
+
+impl InstanceConfig {
+    /// Gets the label of the instance, or its host if unlabelled.
+    pub fn label(&self) -> &str {
+        self.label
+            .as_deref()
+            .filter(|l| !l.trim().is_empty())
+            .or_else(|| actor_host(&self.url))
+            .unwrap_or(&self.url)
+    }
+
+    /// Gets the names of the accounts on the instance, the default [account](Self::account)
+    /// first.
+    pub fn accounts(&self) -> Vec<&str> {
+        let mut accounts: Vec<&str> = Vec::new();
+        for account in self.account.iter().chain(self.accounts.iter()) {
+            if !accounts.contains(&account.as_str()) {
+                accounts.push(account);
+            }
+        }
+
+        accounts
+    }
+}
+
+/// User configuration.
+///
+/// Missing keys use the defaults.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Lemmy instance URL, defaults to the first of the [instances](Self::instances), if any.
+    pub instance_url: String,
+    /// Instances to switch between at runtime, e.g. `[[instances]]` tables.
+    pub instances: Vec<InstanceConfig>,
+    /// Name of the account to log in as, defaults to the first account of the instance.
+    pub account: Option<String>,
+    /// Post sort order, uses the instance default if unset.
+    pub sort: Option<SortType>,
+    /// Post listing type, uses the instance default if unset.
+    pub listing_type: Option<ListingType>,
+    /// Only show posts from the community, e.g. `rust` or `rust@programming.dev`.
+    pub community: Option<String>,
+    /// Number of posts per page, uses the instance default if unset.
+    pub page_limit: Option<u64>,
+    /// Whether to show NSFW posts, same as `nsfw = "show"`.
+    pub show_nsfw: bool,
+    /// Display of NSFW posts: `hide`, `blur`, or `show`.
+    pub nsfw: NsfwMode,
+    /// Request timeout, in seconds.
+    pub timeout: u64,
+    /// Disable certificate verification for the instance, e.g. for self-signed certificates.
+    pub accept_invalid_certs: bool,
+    /// Number of previously viewed posts to keep comments for.
+    pub comment_cache_size: usize,
+    /// Number of comments per page, at most [MAX_COMMENT_LIMIT].
+    pub comment_limit: u64,
+    /// Number of comments downloaded when opening a post, and loaded by each request for more,
+    /// `0` downloads every comment.
+    pub comment_fetch_cap: u64,
+    /// Size of the in-memory cache of decoded images, in megabytes, `0` disables it.
+    pub image_cache_mb: usize,
+    /// Keep downloaded images under `$XDG_CACHE_HOME/temi/images`, disable to write no images
+    /// to disk.
+    pub image_disk_cache: bool,
+    /// Capture the mouse for selecting and scrolling, disable for native text selection.
+    pub mouse: bool,
+    /// Comments with at least this score are highlighted.
+    pub high_score: i64,
+    /// Hide comments from bot accounts, along with their replies.
+    pub hide_bots: bool,
+    /// Hide reposts of the same link in the post list, keeping the highest scoring post.
+    pub dedupe_links: bool,
+    /// Display format of timestamps, `relative` or `absolute`.
+    pub timestamps: TimeFormat,
+    /// Show local users and communities without the instance domain, e.g. `@ferris`.
+    pub omit_local_domain: bool,
+    /// Guide drawn for each comment indent level, e.g. `"| "` for terminals without box-drawing
+    /// characters.
+    pub indent_guide: String,
+    /// Colors of the comment indent guides, cycled by level, uses the default palette if empty.
+    pub indent_colors: Vec<String>,
+    /// Command for opening images instead of the Image screen, e.g. `swayimg %u`, see
+    /// [viewer_command](crate::utils::viewer_command).
+    pub image_viewer: Option<String>,
+    /// Command for playing videos, e.g. `mpv %u`.
+    pub video_player: Option<String>,
+    /// Interval between refreshes of the comments in live mode, in seconds, at least
+    /// [MIN_LIVE_INTERVAL].
+    pub live_interval: u64,
+    /// Restore the post list position from the last run: `always`, `ask`, or `never`.
+    pub restore_session: RestoreSession,
+    /// Ask before quitting with the quit key, `Ctrl+c` still quits at once.
+    pub confirm_quit: bool,
+    /// Post list filters, see [Filters].
+    pub filters: Filters,
+    /// Foreground color overrides, e.g. `error = "red"`, superseded by the `[theme]` table.
+    pub colors: BTreeMap<String, String>,
+    /// Theme preset and style overrides, see [theme](crate::theme).
+    pub theme: ThemeConfig,
+    /// Keybinding overrides, e.g. `next_post = "t"`, see [keymap](crate::keymap).
+    pub keys: BTreeMap<String, KeyBinding>,
+}
+
+impl Config {
+    /// Creates a new [Config] with the default settings.
+    pub fn new() -> Self {
+        Self {
+            instance_url: DEFAULT_INSTANCE.into(),
+            instances: Vec::new(),
+            account: None,
+            sort: None,
+            listing_type: None,
+            community: None,
+            page_limit: None,
+            show_nsfw: false,
+            nsfw: NsfwMode::Hide,
+            timeout: DEFAULT_TIMEOUT.as_secs(),
+            accept_invalid_certs: false,
+            comment_cache_size: DEFAULT_CACHE_CAPACITY,
+            comment_limit: MAX_COMMENT_LIMIT,
+            comment_fetch_cap: DEFAULT_COMMENT_FETCH_CAP,
+            image_cache_mb: DEFAULT_IMAGE_CACHE_MB,
+            image_disk_cache: true,
+            mouse: true,
+            high_score: DEFAULT_HIGH_SCORE,
+            hide_bots: false,
+            dedupe_links: false,
+            timestamps: TimeFormat::Relative,
+            omit_local_domain: false,
+            indent_guide: DEFAULT_INDENT_GUIDE.into(),
+            indent_colors: Vec::new(),
+            image_viewer: None,
+            video_player: None,
+            live_interval: DEFAULT_LIVE_INTERVAL,
+            restore_session: RestoreSession::Ask,
+            confirm_quit: false,
+            filters: Filters::default(),
+            colors: BTreeMap::new(),
+            theme: ThemeConfig::default(),
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Gets the default config file path, `$XDG_CONFIG_HOME/temi/config.toml`.
+    ///
+    /// Falls back to `$HOME/.config` if `XDG_CONFIG_HOME` is unset.
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|dir| dir.join("temi").join("config.toml"))
+    }
+
+    /// Loads the [Config] from the default path, merged with environment variables.
+    pub fn load() -> Result<Self> {
+        let config = match Self::path() {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::new(),
+        };
+
+        Ok(config.with_env())
+    }
+
+    /// Loads the [Config] from a file.
+    ///
+    /// A missing file results in the default [Config].
+    pub fn from_file(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents)
+                .map_err(|err| Error::Config(format!("{}: {err}", path.display()))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(err) => Err(Error::Config(format!("{}: {err}", path.display()))),
+        }
+    }
+
+    /// Parses the [Config] from a TOML string.
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Self::parse(contents).map_err(Error::Config)
+    }
+
+    fn parse(contents: &str) -> std::result::Result<Self, String> {
+        let mut config: Self = toml::from_str(contents).map_err(|err| err.to_string())?;
+
+        if config.show_nsfw {
+            config.nsfw = NsfwMode::Show;
+        }
+
+        for (name, color) in config.colors.iter() {
+            Color::from_str(color).map_err(|_| format!("invalid color for {name}: {color}"))?;
+        }
+
+        for instance in config.instances.iter() {
+            InstanceUrl::parse(&instance.url).map_err(|err| err.to_string())?;
+        }
+        if config.instance_url == DEFAULT_INSTANCE {
+            if let Some(instance) = config.instances.first() {
+                config.instance_url = instance.url.clone();
+            }
+        }
+
+        if config.live_interval < MIN_LIVE_INTERVAL {
+            return Err(format!(
+                "live_interval must be at least {MIN_LIVE_INTERVAL} seconds"
+            ));
+        }
+
+        Theme::from_config(&config.theme)?;
+        KeyMap::from_config(&config.keys)?;
+        PostFilter::from_config(&config.filters)?;
+
+        Ok(config)
+    }
+
+    /// Overrides settings with environment variables.
+    ///
+    /// - `LEMMY_INSTANCE`: instance URL
+    /// - `TEMI_NSFW`: [NsfwMode], invalid modes are ignored
+    /// - `NO_COLOR`: the monochrome theme preset, if set and not empty
+    pub fn with_env(mut self) -> Self {
+        if let Ok(instance_url) = std::env::var("LEMMY_INSTANCE") {
+            self.instance_url = instance_url;
+        }
+        if let Some(nsfw) = std::env::var("TEMI_NSFW").ok().and_then(|m| m.parse().ok()) {
+            self.nsfw = nsfw;
+        }
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            self.theme.name = ThemePreset::Mono;
+        }
+        self
+    }
+
+    /// Validates the merged settings, i.e. after applying environment variables and CLI flags.
+    ///
+    /// A zero timeout is rejected, since every request would time out at once.
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout == 0 {
+            return Err(Error::Config("timeout must be at least 1 second".into()));
+        }
+
+        InstanceUrl::parse(self.instance_url.as_str()).map(|_| ())
+    }
+
+    /// Gets the request timeout.
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+
+    /// Gets the interval between live mode refreshes.
+    pub const fn live_interval(&self) -> Duration {
+        Duration::from_secs(self.live_interval)
+    }
+
+    /// Gets the number of comments per page, clamped to `1..=`[MAX_COMMENT_LIMIT].
+    pub fn comment_limit(&self) -> u64 {
+        self.comment_limit.clamp(1, MAX_COMMENT_LIMIT)
+    }
+
+    /// Gets the number of comments downloaded at a time, `None` for every comment.
+    pub fn comment_fetch_cap(&self) -> Option<u64> {
+        (self.comment_fetch_cap > 0).then_some(self.comment_fetch_cap)
+    }
+
+    /// Gets the listed [InstanceConfig] for the instance URL, ignoring trailing slashes.
+    pub fn instance(&self, url: &str) -> Option<&InstanceConfig> {
+        let url = InstanceUrl::from(url);
+
+        self.instances
+            .iter()
+            .find(|i| InstanceUrl::from(i.url.as_str()) == url)
+    }
+
+    /// Gets the names of the accounts on the configured instance, see
+    /// [InstanceConfig::accounts].
+    pub fn accounts(&self) -> Vec<&str> {
+        self.instance(&self.instance_url)
+            .map(InstanceConfig::accounts)
+            .unwrap_or_default()
+    }
+
+    /// Gets the name of the account to log in as, if any, see [account](Self::account).
+    pub fn active_account(&self) -> Option<&str> {
+        self.account
+            .as_deref()
+            .or_else(|| self.accounts().first().copied())
+    }
+
+    /// Gets the [PostsQuery] for the configured sort, listing type, community, and page limit.
+    pub fn posts_query(&self) -> PostsQuery {
+        PostsQuery {
+            sort: self.sort,
+            listing_type: self.listing_type,
+            community_name: self.community.clone(),
+            limit: self.page_limit,
+        }
+    }
+
+    /// Gets the color override for `name`, if any.
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.colors
+            .get(name)
+            .and_then(|color| Color::from_str(color).ok())
+    }
+
+    /// Gets the comment indent guide colors, skipping invalid colors.
+    pub fn indent_colors(&self) -> Vec<Color> {
+        self.indent_colors
+            .iter()
+            .filter_map(|color| Color::from_str(color).ok())
+            .collect()
+    }
+
+    /// Gets the [Theme] for the configured preset, with the color overrides applied.
+    ///
+    /// Overrides in the `[theme]` table take precedence over the older `[colors]` table and
+    /// `indent_colors`.
+    pub fn theme(&self) -> Result<Theme> {
+        let mut theme = Theme::preset(self.theme.name);
+
+        for (name, color) in self.colors.iter() {
+            // unknown names were ignored before themes, so they still are
+            theme.apply(name, &ThemeColor::Fg(color.clone())).ok();
+        }
+        let indent_colors = self.indent_colors();
+        if !indent_colors.is_empty() {
+            theme.indent = indent_colors;
+        }
+        for (name, color) in self.theme.colors.iter() {
+            theme.apply(name, color).map_err(Error::Config)?;
+        }
+
+        Ok(theme)
+    }
+
+    /// Gets the [KeyMap] with the configured keybindings.
+    pub fn keymap(&self) -> Result<KeyMap> {
+        KeyMap::from_config(&self.keys).map_err(Error::Config)
+    }
+
+    /// Gets the [PostFilter] for the configured filters.
+    pub fn post_filter(&self) -> Result<PostFilter> {
+        PostFilter::from_config(&self.filters).map_err(Error::Config)
+    }
+
+    /// Gets the image viewer command, unless unset or blank.
+    pub fn image_viewer(&self) -> Option<&str> {
+        self.image_viewer
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+    }
+
+    /// Gets the video player command, unless unset or blank.
+    pub fn video_player(&self) -> Option<&str> {
+        self.video_player
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+    }
+
+    /// Gets the capacity of the in-memory image cache, in bytes.
+    pub const fn image_cache_bytes(&self) -> usize {
+        self.image_cache_mb.saturating_mul(1024 * 1024)
+    }
+
+    /// Gets the [DiskCache] for images, unless disabled, or there is no cache directory.
+    pub fn disk_cache(&self) -> Option<DiskCache> {
+        if self.image_disk_cache {
+            DiskCache::path().map(DiskCache::new)
+        } else {
+            None
+        }
+    }
+
+    /// Creates a [Client] for the configured instance.
+    pub fn client(&self) -> Client {
+        Client::new(self.instance_url.as_str())
+            .with_timeout(self.timeout())
+            .with_posts_query(self.posts_query())
+            .with_accept_invalid_certs(self.accept_invalid_certs)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeyAction;
+
+    #[test]
+    fn test_config_from_toml() {
+        let config = Config::from_toml(
+            r#"
+instance_url = "https://lemmy.world"
+sort = "top_day"
+listing_type = "local"
+page_limit = 30
+timeout = 5
+mouse = false
+high_score = 50
+hide_bots = true
+dedupe_links = true
+image_cache_mb = 16
+image_disk_cache = false
+image_viewer = "swayimg %u"
+video_player = " "
+omit_local_domain = true
+timestamps = "absolute"
+nsfw = "blur"
+indent_guide = "| "
+indent_colors = ["red", "nope", "green"]
+restore_session = "never"
+confirm_quit = true
+live_interval = 45
+comment_limit = 200
+comment_fetch_cap = 0
+
+[[instances]]
+url = "https://lemmy.ml"
+label = "ml"
+
+[[instances]]
+url = "https://programming.dev/"
+account = "ferris"
+accounts = ["ferris_mod", "ferris"]
+
+[colors]
+error = "magenta"
+unused = "red"
+
+[theme]
+name = "light"
+op = "cyan"
+
+[filters]
+keywords = ['/^\[meta\]/', "crypto"]
+communities = ["!memes@lemmy.ml"]
+
+[keys]
+next_post = ["t", "right"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.instance_url, "https://lemmy.world");
+        assert_eq!(
+            config.instance("https://lemmy.ml/").map(|i| i.label()),
+            Some("ml")
+        );
+        let instance = config.instance("https://programming.dev").unwrap();
+        assert_eq!(instance.label(), "programming.dev");
+        assert_eq!(instance.account.as_deref(), Some("ferris"));
+        assert_eq!(instance.accounts(), ["ferris", "ferris_mod"]);
+        assert_eq!(config.active_account(), None);
+        assert_eq!(config.instance("https://lemmy.world"), None);
+        assert_eq!(config.sort, Some(SortType::TopDay));
+        assert_eq!(config.listing_type, Some(ListingType::Local));
+        assert_eq!(config.page_limit, Some(30));
+        assert!(!config.show_nsfw);
+        assert_eq!(config.nsfw, NsfwMode::Blur);
+        assert!(!config.mouse);
+        assert_eq!(config.high_score, 50);
+        assert!(config.hide_bots);
+        assert!(config.dedupe_links);
+        assert!(!Config::new().dedupe_links);
+        assert_eq!(config.image_cache_bytes(), 16 * 1024 * 1024);
+        assert_eq!(config.disk_cache(), None);
+        assert_eq!(config.image_viewer(), Some("swayimg %u"));
+        assert_eq!(config.video_player(), None);
+        assert!(config.omit_local_domain);
+        assert_eq!(config.timestamps, TimeFormat::Absolute);
+        assert_eq!(config.indent_guide, "| ");
+        assert_eq!(config.indent_colors(), [Color::Red, Color::Green]);
+        assert_eq!(config.restore_session, RestoreSession::Never);
+        assert!(config.confirm_quit);
+        assert!(!Config::new().confirm_quit);
+        assert_eq!(config.live_interval(), Duration::from_secs(45));
+        assert_eq!(config.comment_limit(), MAX_COMMENT_LIMIT);
+        assert_eq!(config.comment_fetch_cap(), None);
+        assert_eq!(Config::new().comment_fetch_cap(), Some(500));
+        assert_eq!(config.timeout(), Duration::from_secs(5));
+        assert!(config.validate().is_ok());
+        assert!(Config::from_toml("timeout = 0")
+            .unwrap()
+            .validate()
+            .is_err());
+        assert_eq!(config.color("error"), Some(Color::Magenta));
+        let theme = config.theme().unwrap();
+        assert_eq!(theme.error.fg, Some(Color::Magenta));
+        assert_eq!(theme.error.bg, Theme::light().error.bg);
+        assert_eq!(theme.op.fg, Some(Color::Cyan));
+        assert_eq!(theme.indent, [Color::Red, Color::Green]);
+        assert_eq!(config.filters.communities, ["!memes@lemmy.ml"]);
+        assert!(!config.post_filter().unwrap().is_empty());
+        assert_eq!(
+            config.keymap().unwrap().keys(KeyAction::NextPost),
+            ["t".parse().unwrap(), "right".parse().unwrap()]
+        );
+
+        assert_eq!(Config::from_toml("").unwrap(), Config::new());
+
+        // without an instance URL, the first listed instance is used
+        let mut config = Config::from_toml(
+            "[[instances]]\nurl = \"https://lemmy.ml\"\naccounts = [\"ferris\", \"ferris_mod\"]",
+        )
+        .unwrap();
+        assert_eq!(config.instance_url, "https://lemmy.ml");
+        assert_eq!(config.active_account(), Some("ferris"));
+        config.account = Some("ferris_mod".into());
+        assert_eq!(config.active_account(), Some("ferris_mod"));
+        assert!(Config::from_toml("[[instances]]\nurl = \"lemmy.ml\"").is_err());
+        assert!(Config::from_toml("[[instances]]\nlabel = \"ml\"").is_err());
+
+        assert!(Config::from_toml("sort = \"sideways\"").is_err());
+        assert!(Config::from_toml("unknown_key = 1").is_err());
+        assert!(Config::from_toml("[colors]\nerror = \"not-a-color\"").is_err());
+        assert!(Config::from_toml("[keys]\nnext_post = \"j\"").is_err());
+        assert!(Config::from_toml("[filters]\nkeywords = [\"/(unclosed/\"]").is_err());
+        assert!(Config::from_toml("[filters]\nflairs = []").is_err());
+        assert!(Config::from_toml("[theme]\nname = \"sepia\"").is_err());
+        assert!(Config::from_toml("[theme]\nsidebar = \"red\"").is_err());
+
+        // the older flag still shows NSFW posts
+        let config = Config::from_toml("show_nsfw = true").unwrap();
+        assert_eq!(config.nsfw, NsfwMode::Show);
+        assert!(Config::from_toml("nsfw = \"peek\"").is_err());
+        assert_eq!("blur".parse(), Ok(NsfwMode::Blur));
+        assert_eq!(NsfwMode::Show.next(), NsfwMode::Hide);
+    }
+}
@@ -1,10 +1,20 @@
 //! Types for representing count statistics.
 
+use std::time::{Duration, SystemTime};
+
 use tui::widgets::ListState;
 
+use crate::utils::parse_timestamp;
+
+/// Length of a week, for [CommunityCounts::posts_per_week].
+const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 /// Represents the count statistics for a [Post](crate::posts::Post),
 /// [Comment](crate::comment::Comment), etc.
+///
+/// Fields missing from the response, e.g. on other Lemmy versions, use their default values.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Counts {
     pub id: Option<u64>,
     pub post_id: Option<u64>,
@@ -18,7 +28,9 @@ pub struct Counts {
     pub newest_comment_time: Option<String>,
     pub featured_community: Option<bool>,
     pub featured_local: Option<bool>,
+    #[serde(deserialize_with = "deserialize_rank")]
     pub hot_rank: Option<u64>,
+    #[serde(deserialize_with = "deserialize_rank")]
     pub hot_rank_active: Option<u64>,
     pub child_count: Option<u64>,
 }
@@ -47,7 +59,7 @@ impl Counts {
 
     /// Gets the [Counts] ID.
     pub fn id(&self) -> u64 {
-        self.id.clone().unwrap_or(0)
+        self.id.unwrap_or(0)
     }
 
     /// Gets whether the [Counts] are for a [Post](crate::posts::Post).
@@ -139,6 +151,72 @@ impl Counts {
     }
 }
 
+/// Formats a score compactly, e.g. `1.2k` above 1000, or `3.4M` above a million.
+///
+/// Scores are truncated, not rounded, so `999999` is `999.9k`, not `1000.0k`.
+pub fn format_score(score: i64) -> String {
+    let sign = if score < 0 { "-" } else { "" };
+    let abs = score.unsigned_abs();
+
+    match abs {
+        a if a < 1_000 => score.to_string(),
+        a if a < 1_000_000 => format!("{sign}{}.{}k", a / 1_000, a / 100 % 10),
+        a => format!("{sign}{}.{}M", a / 1_000_000, a / 100_000 % 10),
+    }
+}
+
+/// Deserializes a rank sent as an integer (Lemmy 0.18), or a float (Lemmy 0.19).
+///
+/// Float ranks are rounded to the nearest integer.
+fn deserialize_rank<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    let rank = Option::<f64>::deserialize(deserializer)?;
+
+    Ok(rank.map(|r| r.max(0.0).round() as u64))
+}
+
+impl Default for Counts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the count statistics for a [Community](crate::community::Community), returned
+/// alongside it in community views.
+///
+/// Fields missing from the response, e.g. on other Lemmy versions, use their default values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CommunityCounts {
+    pub community_id: u64,
+    pub subscribers: u64,
+    pub posts: u64,
+    pub comments: u64,
+    pub published: String,
+    pub users_active_day: u64,
+    pub users_active_week: u64,
+    pub users_active_month: u64,
+    pub users_active_half_year: u64,
+}
+
+impl CommunityCounts {
+    /// Gets the average number of posts per week since the community was created, at `now`.
+    ///
+    /// Communities younger than a week, or with an invalid creation date, count as a week old.
+    pub fn posts_per_week(&self, now: SystemTime) -> f64 {
+        let age = parse_timestamp(&self.published)
+            .and_then(|published| now.duration_since(published).ok())
+            .unwrap_or(WEEK)
+            .max(WEEK);
+
+        self.posts as f64 / age.div_duration_f64(WEEK)
+    }
+}
+
 /// List of [Counts] for displaying in the TUI.
 #[derive(Clone, Debug)]
 pub struct Countss {
@@ -222,3 +300,38 @@ impl AsMut<Counts> for Counts {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(format_score(0), "0");
+        assert_eq!(format_score(999), "999");
+        assert_eq!(format_score(-999), "-999");
+        assert_eq!(format_score(1_000), "1.0k");
+        assert_eq!(format_score(1_234), "1.2k");
+        assert_eq!(format_score(-1_250), "-1.2k");
+        assert_eq!(format_score(999_999), "999.9k");
+        assert_eq!(format_score(1_000_000), "1.0M");
+        assert_eq!(format_score(12_345_678), "12.3M");
+        assert_eq!(format_score(i64::MIN), "-9223372036854.7M");
+    }
+
+    #[test]
+    fn test_posts_per_week() {
+        let now = parse_timestamp("2024-02-29T00:00:00Z").unwrap();
+        let counts = |posts, published: &str| CommunityCounts {
+            posts,
+            published: published.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(counts(40, "2024-02-01T00:00:00Z").posts_per_week(now), 10.0);
+        // young communities, and invalid dates, count as a week old
+        assert_eq!(counts(3, "2024-02-28T00:00:00Z").posts_per_week(now), 3.0);
+        assert_eq!(counts(3, "").posts_per_week(now), 3.0);
+        assert_eq!(counts(0, "2023-02-28T00:00:00Z").posts_per_week(now), 0.0);
+    }
+}
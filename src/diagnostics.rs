@@ -0,0 +1,119 @@
+//! Diagnostics for the overlay toggled with F12: recent requests, and the frame render time.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::app::Scroll;
+
+/// Number of requests kept in the [RequestLog].
+pub const REQUEST_LOG_LEN: usize = 50;
+
+/// Represents a finished request, successful or not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestRecord {
+    /// HTTP method, e.g. `GET`.
+    pub method: String,
+    pub url: String,
+    /// Status of the final response, `None` without a response, e.g. on a timeout.
+    pub status: Option<u16>,
+    /// Time taken, including retries.
+    pub duration: Duration,
+    /// Size of the decoded response body, `0` for errors.
+    pub bytes: u64,
+}
+
+/// Ring buffer of the last [REQUEST_LOG_LEN] [RequestRecord]s.
+///
+/// The records are shared between clones, so the log can be handed to the
+/// [Client](crate::client::Client), and read by the [App](crate::app::App).
+#[derive(Clone, Debug, Default)]
+pub struct RequestLog {
+    records: Arc<Mutex<VecDeque<RequestRecord>>>,
+}
+
+impl RequestLog {
+    /// Creates a new, empty, [RequestLog].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a [RequestRecord], dropping the oldest once full.
+    pub fn push(&self, record: RequestRecord) {
+        if let Ok(mut records) = self.records.lock() {
+            if records.len() == REQUEST_LOG_LEN {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+
+    /// Gets the [RequestRecord]s, newest first.
+    pub fn recent(&self) -> Vec<RequestRecord> {
+        self.records
+            .lock()
+            .map(|records| records.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Gets the number of [RequestRecord]s.
+    pub fn len(&self) -> usize {
+        self.records.lock().map_or(0, |records| records.len())
+    }
+
+    /// Gets whether the [RequestLog] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// State of the diagnostics overlay.
+#[derive(Clone, Default)]
+pub struct Diagnostics {
+    /// Requests made by the [ApiClient](crate::api::ApiClient).
+    pub requests: RequestLog,
+    /// Time taken to render the last frame.
+    pub frame_time: Duration,
+    /// Whether the overlay is displayed.
+    pub visible: bool,
+    pub scroll: Scroll,
+}
+
+impl Diagnostics {
+    /// Shows, or hides, the overlay, scrolled to the top.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.scroll.first();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_log() {
+        let log = RequestLog::new();
+        let shared = log.clone();
+        assert!(log.is_empty());
+
+        for i in 0..REQUEST_LOG_LEN + 2 {
+            shared.push(RequestRecord {
+                method: "GET".into(),
+                url: format!("https://lemmy.ml/{i}"),
+                status: Some(200),
+                duration: Duration::from_millis(i as u64),
+                bytes: 0,
+            });
+        }
+
+        // the oldest are dropped, newest first
+        let recent = log.recent();
+        assert_eq!(log.len(), REQUEST_LOG_LEN);
+        assert_eq!(
+            recent[0].url,
+            format!("https://lemmy.ml/{}", REQUEST_LOG_LEN + 1)
+        );
+        assert_eq!(recent[REQUEST_LOG_LEN - 1].url, "https://lemmy.ml/2");
+    }
+}
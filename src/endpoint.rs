@@ -9,6 +9,19 @@ pub enum Endpoint {
     #[default]
     PostList,
     CommentList,
+    GetPost,
+    GetComment,
+    GetSite,
+    FollowCommunity,
+    BlockCommunity,
+    BlockPerson,
+    CreatePostReport,
+    CreateCommentReport,
+    Login,
+    SavePost,
+    SaveComment,
+    Modlog,
+    CommunityList,
 }
 
 impl From<Endpoint> for &'static str {
@@ -16,6 +29,19 @@ impl From<Endpoint> for &'static str {
         match val {
             Endpoint::PostList => "/api/v3/post/list",
             Endpoint::CommentList => "/api/v3/comment/list",
+            Endpoint::GetPost => "/api/v3/post",
+            Endpoint::GetComment => "/api/v3/comment",
+            Endpoint::GetSite => "/api/v3/site",
+            Endpoint::FollowCommunity => "/api/v3/community/follow",
+            Endpoint::BlockCommunity => "/api/v3/community/block",
+            Endpoint::BlockPerson => "/api/v3/user/block",
+            Endpoint::CreatePostReport => "/api/v3/post/report",
+            Endpoint::CreateCommentReport => "/api/v3/comment/report",
+            Endpoint::Login => "/api/v3/user/login",
+            Endpoint::SavePost => "/api/v3/post/save",
+            Endpoint::SaveComment => "/api/v3/comment/save",
+            Endpoint::Modlog => "/api/v3/modlog",
+            Endpoint::CommunityList => "/api/v3/community/list",
         }
     }
 }
@@ -1,39 +1,326 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Underlying cause of an [Error].
+///
+/// Wraps the original error in an [Arc], so [Error] stays [Clone]. Equality compares the error
+/// messages, since most error types do not implement [PartialEq].
+#[derive(Clone)]
+pub struct Source(SourceInner);
+
+#[derive(Clone)]
+enum SourceInner {
+    Error(Arc<dyn StdError + Send + Sync>),
+    Message(String),
+}
+
+impl Source {
+    /// Creates a new [Source] from the original error.
+    pub fn new<E: StdError + Send + Sync + 'static>(err: E) -> Self {
+        Self(SourceInner::Error(Arc::new(err)))
+    }
+
+    /// Creates a new [Source] from a message, for failures without an underlying error.
+    pub fn msg<S: Into<String>>(msg: S) -> Self {
+        Self(SourceInner::Message(msg.into()))
+    }
+
+    /// Gets the original error, if any.
+    pub fn error(&self) -> Option<&(dyn StdError + 'static)> {
+        match &self.0 {
+            SourceInner::Error(err) => Some(err.as_ref()),
+            SourceInner::Message(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            SourceInner::Error(err) => write!(f, "{err}"),
+            SourceInner::Message(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            SourceInner::Error(err) => write!(f, "{err:?}"),
+            SourceInner::Message(msg) => write!(f, "{msg:?}"),
+        }
+    }
+}
+
+impl PartialEq for Source {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.to_string() == rhs.to_string()
+    }
+}
+
+/// Errors returned by temi.
+///
+/// Variants wrapping a [Source] preserve the original error, available from
+/// [source](StdError::source).
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
-    Io(String),
-    Http(String),
-    Json(String),
-    Image(String),
+    /// File or terminal I/O failure.
+    Io(Source),
+    /// HTTP protocol failure, or an invalid request.
+    Http(Source),
+    /// Failure to connect to the Lemmy instance.
+    Connect(Source),
+    /// Request to the URL timed out.
+    Timeout(String),
+    /// Invalid JSON in a response body, or a file.
+    Json(Source),
+    /// Failure to decode an image.
+    Image(Source),
+    /// Error returned by the Lemmy API, e.g. `couldnt_create_report`.
+    Api(String),
+    /// Request requires a logged in user.
+    Auth(String),
+    /// Non-success HTTP status, with a message from the response body.
+    Status { code: u16, message: String },
+    /// Failure to run a background task.
+    Task(Source),
+    /// Invalid configuration.
+    Config(String),
+    /// Failure to access the OS keyring, see [secrets](crate::secrets).
+    Secret(Source),
+}
+
+impl Error {
+    /// Gets the name of the [Error] variant, e.g. `Timeout`.
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "Io",
+            Self::Http(_) => "Http",
+            Self::Connect(_) => "Connect",
+            Self::Timeout(_) => "Timeout",
+            Self::Json(_) => "Json",
+            Self::Image(_) => "Image",
+            Self::Api(_) => "Api",
+            Self::Auth(_) => "Auth",
+            Self::Status { .. } => "Status",
+            Self::Task(_) => "Task",
+            Self::Config(_) => "Config",
+            Self::Secret(_) => "Secret",
+        }
+    }
+
+    /// Gets whether the instance rejected the credentials of the request, e.g. an expired JWT.
+    pub fn is_unauthorized(&self) -> bool {
+        match self {
+            Self::Status { code: 401, .. } | Self::Auth(_) => true,
+            _ => matches!(
+                self.api_message(),
+                Some("not_logged_in" | "incorrect_login")
+            ),
+        }
+    }
+
+    /// Gets the Lemmy API error, e.g. `couldnt_find_post`, whether from a successful response, or
+    /// the body of an error status.
+    ///
+    /// Status messages are only API errors when they are a single `snake_case` word, other
+    /// messages are the start of the response body, e.g. an HTML error page.
+    pub fn api_message(&self) -> Option<&str> {
+        match self {
+            Self::Api(message) => Some(message),
+            Self::Status { message, .. }
+                if !message.is_empty()
+                    && message.bytes().all(|b| b.is_ascii_lowercase() || b == b'_') =>
+            {
+                Some(message)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Http(err) => write!(f, "HTTP error: {err}"),
+            Self::Connect(err) => write!(f, "connection error: {err}"),
+            Self::Timeout(url) => write!(f, "request timed out: {url}"),
+            Self::Json(err) => write!(f, "JSON error: {err}"),
+            Self::Image(err) => write!(f, "image error: {err}"),
+            Self::Api(err) => write!(f, "API error: {err}"),
+            Self::Auth(err) => write!(f, "authentication error: {err}"),
+            Self::Status { code, message } => write!(f, "HTTP status {code}: {message}"),
+            Self::Task(err) => write!(f, "background task error: {err}"),
+            Self::Config(err) => write!(f, "config error: {err}"),
+            Self::Secret(err) => write!(f, "keyring error: {err}"),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io(src)
+            | Self::Http(src)
+            | Self::Connect(src)
+            | Self::Json(src)
+            | Self::Image(src)
+            | Self::Task(src)
+            | Self::Secret(src) => src.error(),
+            _ => None,
+        }
+    }
+}
+
+/// Represents an error response body from the Lemmy API.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct ApiError {
+    pub error: String,
 }
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
-        Self::Io(format!("{err}"))
+        Self::Io(Source::new(err))
     }
 }
 
 impl From<hyper::Error> for Error {
     fn from(err: hyper::Error) -> Self {
-        Self::Http(format!("{err}"))
+        Self::Http(Source::new(err))
     }
 }
 
 impl From<http::uri::InvalidUri> for Error {
     fn from(err: http::uri::InvalidUri) -> Self {
-        Self::Http(format!("{err}"))
+        Self::Http(Source::new(err))
+    }
+}
+
+impl From<http::Error> for Error {
+    fn from(err: http::Error) -> Self {
+        Self::Http(Source::new(err))
     }
 }
 
 impl From<serde_json::Error> for Error {
     fn from(err: serde_json::Error) -> Self {
-        Self::Json(format!("{err}"))
+        Self::Json(Source::new(err))
     }
 }
 
 impl From<image::ImageError> for Error {
     fn from(err: image::ImageError) -> Self {
-        Self::Image(format!("{err}"))
+        Self::Image(Source::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts the [Error] Display output, and that the source is the original error type.
+    fn assert_error<E: StdError + 'static>(err: Error, display: String) {
+        assert_eq!(err.to_string(), display);
+        assert!(err.source().is_some_and(|src| src.is::<E>()), "{err:?}");
+        assert_eq!(err.clone(), err);
+    }
+
+    #[test]
+    fn test_from_io() {
+        let io_err = || std::io::Error::other("disk full");
+        assert_error::<std::io::Error>(io_err().into(), format!("I/O error: {}", io_err()));
+    }
+
+    #[test]
+    fn test_from_uri() {
+        let uri_err = || "not a uri".parse::<hyper::Uri>().unwrap_err();
+        assert_error::<http::uri::InvalidUri>(
+            uri_err().into(),
+            format!("HTTP error: {}", uri_err()),
+        );
+
+        let http_err = || hyper::Request::get("not a uri").body(()).unwrap_err();
+        assert_error::<http::Error>(http_err().into(), format!("HTTP error: {}", http_err()));
+    }
+
+    #[tokio::test]
+    async fn test_from_hyper() {
+        let (sender, body) = hyper::Body::channel();
+        sender.abort();
+
+        let hyper_err = hyper::body::to_bytes(body).await.unwrap_err();
+        let display = format!("HTTP error: {hyper_err}");
+
+        assert_error::<hyper::Error>(hyper_err.into(), display);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json_err = || serde_json::from_str::<u64>("nope").unwrap_err();
+        assert_error::<serde_json::Error>(json_err().into(), format!("JSON error: {}", json_err()));
+    }
+
+    #[test]
+    fn test_from_image() {
+        let image_err = || image::load_from_memory(b"nope").unwrap_err();
+        assert_error::<image::ImageError>(
+            image_err().into(),
+            format!("image error: {}", image_err()),
+        );
+    }
+
+    #[test]
+    fn test_message_source() {
+        let err = Error::Http(Source::msg("too many redirects"));
+
+        assert_eq!(err.to_string(), "HTTP error: too many redirects");
+        assert!(err.source().is_none());
+
+        let err = Error::Status {
+            code: 503,
+            message: "busy".into(),
+        };
+
+        assert_eq!(err.to_string(), "HTTP status 503: busy");
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn test_is_unauthorized() {
+        let status = |code, message: &str| Error::Status {
+            code,
+            message: message.into(),
+        };
+
+        assert!(status(401, "").is_unauthorized());
+        assert!(status(400, "not_logged_in").is_unauthorized());
+        assert!(Error::Api("incorrect_login".into()).is_unauthorized());
+        assert!(Error::Auth("login expired".into()).is_unauthorized());
+        assert!(!status(503, "busy").is_unauthorized());
+        assert!(!Error::Api("couldnt_find_post".into()).is_unauthorized());
+    }
+
+    #[test]
+    fn test_api_message() {
+        let status = |message: &str| Error::Status {
+            code: 404,
+            message: message.into(),
+        };
+
+        assert_eq!(
+            status("couldnt_find_post").api_message(),
+            Some("couldnt_find_post")
+        );
+        assert_eq!(
+            Error::Api("couldnt_find_post".into()).api_message(),
+            Some("couldnt_find_post")
+        );
+        assert_eq!(status("<html>Not Found</html>").api_message(), None);
+        assert_eq!(status("").api_message(), None);
+        assert_eq!(Error::Auth("login_required".into()).api_message(), None);
     }
 }
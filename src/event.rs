@@ -0,0 +1,271 @@
+//! Events driving the main loop.
+//!
+//! Terminal input is read on a dedicated thread, and a timer sends periodic [Event::Tick]s for
+//! timed updates, so input is never dropped while the main loop waits on a request. Frames are
+//! only drawn when something changed, see [Redraw].
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+
+use crossterm::event::{self as term, KeyEvent, MouseEvent};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+/// Interval between [Event::Tick]s.
+pub const TICK_RATE: Duration = Duration::from_millis(200);
+/// Interval for the input thread to check whether the [Events] were dropped.
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+/// Represents an event for the main loop to handle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Key press from the terminal.
+    Key(KeyEvent),
+    /// Mouse click or scroll from the terminal, only sent with mouse capture enabled.
+    Mouse(MouseEvent),
+    /// Terminal resized to the new width and height.
+    Resize(u16, u16),
+    /// Periodic check for timed updates, e.g. expired status messages, without a redraw by
+    /// itself.
+    Tick,
+    /// Background download finished, see [task](crate::task).
+    FetchComplete,
+}
+
+/// Convenience alias for sending [Event]s to the main loop, e.g. from background tasks.
+pub type EventSender = UnboundedSender<Event>;
+
+/// State of the input thread shared with the [Events].
+#[derive(Debug, Default)]
+struct Input {
+    /// Whether reading input is paused, see [Events::pause].
+    paused: AtomicBool,
+    /// Held while reading input.
+    reading: Mutex<()>,
+}
+
+/// Source of [Event]s for the main loop.
+#[derive(Debug)]
+pub struct Events {
+    tx: EventSender,
+    rx: UnboundedReceiver<Event>,
+    ticker: JoinHandle<()>,
+    input: Arc<Input>,
+}
+
+/// Guard pausing terminal input, see [Events::pause].
+#[derive(Debug)]
+pub struct InputPause<'a> {
+    input: &'a Input,
+}
+
+impl Drop for InputPause<'_> {
+    fn drop(&mut self) {
+        self.input.paused.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Events {
+    /// Creates a new [Events], starting the input thread and tick timer.
+    pub fn new(tick_rate: Duration) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let input = Arc::new(Input::default());
+        let input_tx = tx.clone();
+        let thread_input = input.clone();
+        std::thread::spawn(move || read_input(input_tx, &thread_input));
+
+        let tick_tx = tx.clone();
+        let ticker = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick_rate);
+
+            loop {
+                interval.tick().await;
+
+                if tick_tx.send(Event::Tick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            tx,
+            rx,
+            ticker,
+            input,
+        }
+    }
+
+    /// Pauses reading terminal input until the returned guard is dropped, leaving the input to
+    /// another program running in the terminal, e.g. a pager.
+    ///
+    /// Waits for a read in progress to finish.
+    pub fn pause(&self) -> InputPause<'_> {
+        self.input.paused.store(true, Ordering::SeqCst);
+        drop(self.input.reading.lock());
+
+        InputPause { input: &self.input }
+    }
+
+    /// Gets an [EventSender] for sending events to the main loop.
+    pub fn sender(&self) -> EventSender {
+        self.tx.clone()
+    }
+
+    /// Waits for the next [Event].
+    pub async fn next(&mut self) -> Option<Event> {
+        self.rx.recv().await
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.ticker.abort();
+    }
+}
+
+/// Tracks whether the screen changed since the last frame, so idle [Event::Tick]s draw nothing.
+#[derive(Debug)]
+pub struct Redraw {
+    dirty: bool,
+}
+
+impl Default for Redraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redraw {
+    /// Creates a new [Redraw], with the first frame still to draw.
+    pub const fn new() -> Self {
+        Self { dirty: true }
+    }
+
+    /// Marks the screen changed by the [Event], anything but a [Tick](Event::Tick).
+    pub fn event(&mut self, event: &Event) {
+        if *event != Event::Tick {
+            self.dirty = true;
+        }
+    }
+
+    /// Marks the screen changed, e.g. by a timed update.
+    pub fn mark(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Calls `draw` if the screen changed since the last frame.
+    ///
+    /// Returns whether a frame was drawn. A failed draw is tried again on the next call.
+    pub fn draw<E>(&mut self, draw: impl FnOnce() -> Result<(), E>) -> Result<bool, E> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        draw()?;
+        self.dirty = false;
+        Ok(true)
+    }
+}
+
+/// Forwards terminal input until the [Events] are dropped, or reading fails.
+fn read_input(tx: EventSender, input: &Input) {
+    while !tx.is_closed() {
+        let reading = input.reading.lock();
+        if input.paused.load(Ordering::SeqCst) {
+            drop(reading);
+            std::thread::sleep(INPUT_POLL);
+            continue;
+        }
+
+        match term::poll(INPUT_POLL) {
+            Ok(true) => {
+                let event = match term::read() {
+                    Ok(term::Event::Key(key)) => Event::Key(key),
+                    Ok(term::Event::Mouse(mouse)) => Event::Mouse(mouse),
+                    Ok(term::Event::Resize(width, height)) => Event::Resize(width, height),
+                    Ok(_) => continue,
+                    Err(_) => break,
+                };
+
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+            Ok(false) => (),
+            Err(_) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use tui::{backend::TestBackend, widgets::Paragraph, Terminal};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events() {
+        let mut events = Events::new(Duration::from_millis(10));
+
+        assert_eq!(events.next().await, Some(Event::Tick));
+
+        events.sender().send(Event::FetchComplete).unwrap();
+
+        // ticks may arrive before the sent event
+        loop {
+            match events.next().await {
+                Some(Event::FetchComplete) => break,
+                Some(Event::Tick) => (),
+                event => panic!("unexpected event: {event:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_ticks() {
+        let mut events = Events::new(Duration::from_millis(5));
+        let mut terminal = Terminal::new(TestBackend::new(20, 2)).unwrap();
+        let mut redraw = Redraw::new();
+        let mut frames = 0;
+
+        let mut next = |redraw: &mut Redraw, event: Event| {
+            redraw.event(&event);
+            redraw
+                .draw(|| {
+                    frames += 1;
+                    terminal
+                        .draw(|f| f.render_widget(Paragraph::new(format!("{event:?}")), f.area()))
+                        .map(|_| ())
+                })
+                .unwrap()
+        };
+
+        // the first frame is drawn, then idle ticks draw nothing
+        for _ in 0..5 {
+            let event = events.next().await.unwrap();
+            assert_eq!(event, Event::Tick);
+            next(&mut redraw, event);
+        }
+        assert!(next(&mut redraw, Event::Resize(20, 2)));
+        assert!(!next(&mut redraw, Event::Tick));
+        assert!(next(&mut redraw, Event::Key(KeyCode::Char('j').into())));
+
+        // timed updates are drawn on the next tick
+        redraw.mark();
+        assert!(next(&mut redraw, Event::Tick));
+        assert_eq!(frames, 4);
+
+        // failed frames are drawn again
+        assert!(redraw.draw(|| Err(())).is_ok_and(|drawn| !drawn));
+        redraw.mark();
+        assert!(redraw.draw(|| Err(())).is_err());
+        assert_eq!(redraw.draw(|| Ok::<_, ()>(())), Ok(true));
+    }
+}
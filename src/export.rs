@@ -0,0 +1,190 @@
+//! Exporting a post, and its comments, to a markdown file.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    comments::CommentResponse, posts::PostResponse, utils::format_count, utils::write_to_file,
+    Result,
+};
+
+/// Longest slug of the post title in the default export file name.
+const MAX_SLUG_LEN: usize = 50;
+
+/// Converts the post, and its comments in thread order, to markdown: the title, metadata, body,
+/// then each comment as a blockquote nested by its level, under an author, score, and time
+/// header.
+///
+/// Every comment is exported, replies hidden in the TUI included. Deleted, and removed, comments
+/// keep their place in the thread with a placeholder.
+pub fn post_to_markdown(post: &PostResponse, comments: &[CommentResponse]) -> String {
+    let p = &post.post;
+    let mut md = format!("# {}\n\n", p.name().trim());
+
+    let _ = writeln!(md, "- community: {}", post.community.handle());
+    let _ = writeln!(md, "- author: {}", post.creator.handle());
+    let _ = writeln!(
+        md,
+        "- score: {} | comments: {}",
+        post.counts.score(),
+        format_count(post.counts.comments())
+    );
+    let _ = writeln!(md, "- published: {}", p.published());
+    if !p.url().is_empty() {
+        let _ = writeln!(md, "- link: <{}>", p.url());
+    }
+    let _ = writeln!(md, "- source: <{}>", p.ap_id());
+
+    let body = if p.deleted {
+        "*[deleted]*"
+    } else {
+        p.body().trim()
+    };
+    if !body.is_empty() {
+        let _ = write!(md, "\n{body}\n");
+    }
+
+    if comments.is_empty() {
+        return md;
+    }
+    md.push_str("\n---\n\n## Comments\n");
+
+    for c in comments {
+        // `0.12.34` is a reply to the root comment 12
+        let level = c.path_ids().len().saturating_sub(2);
+        let quote = "> ".repeat(level);
+        let score = match c.counts.score() {
+            1 => "1 point".to_owned(),
+            n => format!("{n} points"),
+        };
+        let content = if c.comment.removed {
+            "*[removed by moderator]*"
+        } else if c.comment.deleted {
+            "*[deleted by creator]*"
+        } else {
+            c.comment.content().trim()
+        };
+
+        let header = format!(
+            "**{}** · {score} · {}",
+            c.creator.handle(),
+            c.comment.published()
+        );
+        md.push('\n');
+        for line in [header.as_str(), ""].into_iter().chain(content.lines()) {
+            let _ = writeln!(md, "{}", format!("{quote}{line}").trim_end());
+        }
+    }
+
+    md
+}
+
+/// Gets the default export path of the post, `~/Downloads/<post-id>-<slug>.md`.
+pub fn default_path(post: &PostResponse) -> String {
+    let slug = slug(post.post.name());
+
+    if slug.is_empty() {
+        format!("~/Downloads/{}.md", post.post.id())
+    } else {
+        format!("~/Downloads/{}-{slug}.md", post.post.id())
+    }
+}
+
+/// Gets the slug of a title, its lowercase letters and digits joined by dashes, e.g.
+/// `announcing-rust-1-76-0`.
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if slug.len() + word.len() >= MAX_SLUG_LEN {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_lowercase());
+    }
+
+    slug
+}
+
+/// Expands a leading `~` in the path to the home directory, if known.
+pub fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty());
+
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            Path::new(&home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Writes the markdown to the path, creating its directory if needed.
+pub fn write_markdown(path: &Path, markdown: &str) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    write_to_file(&path.to_string_lossy(), markdown.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comments::CommentResponses, posts::GetPostResponse};
+
+    fn thread() -> (PostResponse, Vec<CommentResponse>) {
+        let post: GetPostResponse =
+            serde_json::from_str(include_str!("../tests/fixtures/lemmy-0.19.3/post.json")).unwrap();
+        let comments: CommentResponses = serde_json::from_str(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/comment_thread.json"
+        ))
+        .unwrap();
+
+        (post.post_view, comments.comments)
+    }
+
+    #[test]
+    fn test_post_to_markdown() {
+        let (post, comments) = thread();
+
+        assert_eq!(
+            post_to_markdown(&post, &comments),
+            include_str!("../tests/fixtures/export/thread.md")
+        );
+
+        // no comments section without comments
+        let md = post_to_markdown(&post, &[]);
+        assert!(md.ends_with("`Option::inspect`, and more.\n"), "{md}");
+    }
+
+    #[test]
+    fn test_default_path() {
+        let (mut post, _) = thread();
+        assert_eq!(
+            default_path(&post),
+            "~/Downloads/10822441-announcing-rust-1-76-0.md"
+        );
+
+        post.post.name = "¿? !!".into();
+        assert_eq!(default_path(&post), "~/Downloads/10822441.md");
+        assert_eq!(slug(&"word ".repeat(20)).len(), 49);
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let dir = std::env::temp_dir().join(format!("temi-export-{}", std::process::id()));
+        let path = dir.join("nested").join("thread.md");
+
+        write_markdown(&path, "# title\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# title\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(expand_home("/tmp/a.md"), PathBuf::from("/tmp/a.md"));
+        assert_eq!(expand_home("~user/a.md"), PathBuf::from("~user/a.md"));
+    }
+}
@@ -0,0 +1,866 @@
+//! Keybindings, configured in the `[keys]` section of the [Config](crate::config::Config).
+//!
+//! Each [KeyAction] is bound to one or more [KeyChord]s, e.g.:
+//!
+//! ```toml
+//! [keys]
+//! scroll_comments_down = "h"
+//! next_page = ["n", "right"]
+//! quit = "ctrl+q"
+//! ```
+//!
+//! Configured bindings replace the defaults for that action. An empty list unbinds the action.
+//! `Ctrl+c` always quits, and the overlays, e.g. the confirmation prompt, are not remappable.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::screen::Screen;
+
+/// Represents a user action triggered by a key press on a [Screen].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyAction {
+    /// Exit the application.
+    Quit,
+    /// Return to the previous [Screen].
+    Back,
+    /// Select the next post in the post list.
+    SelectNext,
+    /// Select the previous post in the post list.
+    SelectPrev,
+    /// Select the first post in the post list.
+    FirstPost,
+    /// Select the last post in the post list.
+    LastPost,
+    /// Open the selected post.
+    OpenPost,
+    /// Go to the next page of posts.
+    NextPage,
+    /// Go to the previous page of posts.
+    PrevPage,
+    /// Block the community of the selected post.
+    BlockCommunity,
+    /// Change to the next NSFW display mode.
+    ToggleNsfw,
+    /// Turn the post filters off or on.
+    ToggleFilters,
+    /// Show, or hide, the reposts of the same link as the selected post.
+    ToggleDuplicates,
+    /// Pick an instance from the config to switch to.
+    SwitchInstance,
+    /// Log out of the instance, deleting the stored login.
+    Logout,
+    /// Pick an account on the instance to switch to.
+    SwitchAccount,
+    /// Show the posts and comments saved by the logged-in user.
+    ShowSaved,
+    /// Show the moderation log of the community.
+    ShowModlog,
+    /// Export the post, and its comments, to a markdown file.
+    ExportPost,
+    /// Show the selected comment, or the post, in the external pager.
+    Pager,
+    /// Show, or hide, the diagnostics overlay: recent requests, cache sizes, and render time.
+    ToggleDiagnostics,
+    /// Show the communities most active over the last month.
+    ShowDiscover,
+    /// Go to a page of posts, or a root comment of the post.
+    GoTo,
+    /// Scroll the post body up.
+    ScrollPostUp,
+    /// Scroll the post body down.
+    ScrollPostDown,
+    /// Scroll the post body up by a page.
+    PagePostUp,
+    /// Scroll the post body down by a page.
+    PagePostDown,
+    /// Jump to the top of the post body.
+    PostTop,
+    /// Jump to the bottom of the post body.
+    PostBottom,
+    /// Scroll the comments up.
+    ScrollCommentsUp,
+    /// Scroll the comments down.
+    ScrollCommentsDown,
+    /// Select the next comment.
+    NextComment,
+    /// Select the previous comment.
+    PrevComment,
+    /// Hide or show the replies to the selected comment.
+    ToggleReplies,
+    /// Hide the replies to every root level comment.
+    CollapseAll,
+    /// Show every hidden reply.
+    ExpandAll,
+    /// Change to the next comment sort.
+    SortComments,
+    /// Scroll the comments up by a page.
+    PageCommentsUp,
+    /// Scroll the comments down by a page.
+    PageCommentsDown,
+    /// Select the first comment, and jump to the top of the comments.
+    CommentsTop,
+    /// Select the last comment, and jump to the bottom of the comments.
+    CommentsBottom,
+    /// Select the next comment published since the last visit to the post.
+    NextNew,
+    /// Select the previous comment published since the last visit to the post.
+    PrevNew,
+    /// Show the next post.
+    NextPost,
+    /// Show the previous post.
+    PrevPost,
+    /// Open the comment list.
+    OpenComments,
+    /// Open the post image.
+    OpenImage,
+    /// Open the post link in the browser, or the post itself for posts without a link.
+    OpenLink,
+    /// Open the selected comment, or the post, on its home instance in the browser.
+    OpenApId,
+    /// Copy the selected comment text, or the post link, to the clipboard.
+    Yank,
+    /// Copy the ap_id of the selected comment, or the post, to the clipboard.
+    YankApId,
+    /// List the links in the post and the selected comment, to open or copy.
+    ShowLinks,
+    /// Refresh the post and its comments, or the page of posts.
+    Refresh,
+    /// Toggle live mode, refreshing the comments periodically.
+    ToggleLive,
+    /// Load the replies missing below the selected comment.
+    LoadReplies,
+    /// Load the next batch of comments, beyond the comment fetch cap.
+    LoadMoreComments,
+    /// Report the selected comment, or the post.
+    Report,
+    /// Block the author of the selected comment.
+    BlockUser,
+    /// Subscribe to, or unsubscribe from, the community of the post, or the selected community.
+    Subscribe,
+    /// Save, or unsave, the selected comment, or the post.
+    ToggleSave,
+    /// Switch between the saved posts and comments, or local and all communities.
+    NextTab,
+    /// Show the content of a hidden NSFW post.
+    RevealNsfw,
+    /// Open a crosspost of the post, by the number typed after the key.
+    OpenCrossPost,
+    /// Scroll the error log up.
+    ScrollUp,
+    /// Scroll the error log down.
+    ScrollDown,
+    /// Clear the error log.
+    ClearLog,
+    /// Zoom in on the image.
+    ZoomIn,
+    /// Zoom out of the image.
+    ZoomOut,
+    /// Pan the zoomed image to the left.
+    PanLeft,
+    /// Pan the zoomed image to the right.
+    PanRight,
+    /// Pan the zoomed image up.
+    PanUp,
+    /// Pan the zoomed image down.
+    PanDown,
+}
+
+impl KeyAction {
+    /// Every [KeyAction].
+    pub const ALL: [Self; 72] = [
+        Self::Quit,
+        Self::Back,
+        Self::SelectNext,
+        Self::SelectPrev,
+        Self::FirstPost,
+        Self::LastPost,
+        Self::OpenPost,
+        Self::NextPage,
+        Self::PrevPage,
+        Self::BlockCommunity,
+        Self::ToggleNsfw,
+        Self::ToggleFilters,
+        Self::ToggleDuplicates,
+        Self::SwitchInstance,
+        Self::Logout,
+        Self::SwitchAccount,
+        Self::ShowSaved,
+        Self::ShowModlog,
+        Self::ExportPost,
+        Self::Pager,
+        Self::ToggleDiagnostics,
+        Self::ShowDiscover,
+        Self::GoTo,
+        Self::ScrollPostUp,
+        Self::ScrollPostDown,
+        Self::PagePostUp,
+        Self::PagePostDown,
+        Self::PostTop,
+        Self::PostBottom,
+        Self::ScrollCommentsUp,
+        Self::ScrollCommentsDown,
+        Self::NextComment,
+        Self::PrevComment,
+        Self::ToggleReplies,
+        Self::CollapseAll,
+        Self::ExpandAll,
+        Self::SortComments,
+        Self::PageCommentsUp,
+        Self::PageCommentsDown,
+        Self::CommentsTop,
+        Self::CommentsBottom,
+        Self::NextNew,
+        Self::PrevNew,
+        Self::NextPost,
+        Self::PrevPost,
+        Self::OpenComments,
+        Self::OpenImage,
+        Self::OpenLink,
+        Self::OpenApId,
+        Self::Yank,
+        Self::YankApId,
+        Self::ShowLinks,
+        Self::Refresh,
+        Self::ToggleLive,
+        Self::LoadReplies,
+        Self::LoadMoreComments,
+        Self::Report,
+        Self::BlockUser,
+        Self::Subscribe,
+        Self::ToggleSave,
+        Self::NextTab,
+        Self::RevealNsfw,
+        Self::OpenCrossPost,
+        Self::ScrollUp,
+        Self::ScrollDown,
+        Self::ClearLog,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::PanLeft,
+        Self::PanRight,
+        Self::PanUp,
+        Self::PanDown,
+    ];
+
+    /// Gets the name of the [KeyAction] used in the config file.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Back => "back",
+            Self::SelectNext => "select_next",
+            Self::SelectPrev => "select_prev",
+            Self::FirstPost => "first_post",
+            Self::LastPost => "last_post",
+            Self::OpenPost => "open_post",
+            Self::NextPage => "next_page",
+            Self::PrevPage => "prev_page",
+            Self::BlockCommunity => "block_community",
+            Self::ToggleNsfw => "toggle_nsfw",
+            Self::ToggleFilters => "toggle_filters",
+            Self::ToggleDuplicates => "toggle_duplicates",
+            Self::SwitchInstance => "switch_instance",
+            Self::Logout => "logout",
+            Self::SwitchAccount => "switch_account",
+            Self::ShowSaved => "show_saved",
+            Self::ShowModlog => "show_modlog",
+            Self::ExportPost => "export_post",
+            Self::Pager => "pager",
+            Self::ToggleDiagnostics => "toggle_diagnostics",
+            Self::ShowDiscover => "show_communities",
+            Self::GoTo => "go_to",
+            Self::ScrollPostUp => "scroll_post_up",
+            Self::ScrollPostDown => "scroll_post_down",
+            Self::PagePostUp => "page_post_up",
+            Self::PagePostDown => "page_post_down",
+            Self::PostTop => "post_top",
+            Self::PostBottom => "post_bottom",
+            Self::ScrollCommentsUp => "scroll_comments_up",
+            Self::ScrollCommentsDown => "scroll_comments_down",
+            Self::NextComment => "next_comment",
+            Self::PrevComment => "prev_comment",
+            Self::ToggleReplies => "toggle_replies",
+            Self::CollapseAll => "collapse_all",
+            Self::ExpandAll => "expand_all",
+            Self::SortComments => "sort_comments",
+            Self::PageCommentsUp => "page_comments_up",
+            Self::PageCommentsDown => "page_comments_down",
+            Self::CommentsTop => "comments_top",
+            Self::CommentsBottom => "comments_bottom",
+            Self::NextNew => "next_new",
+            Self::PrevNew => "prev_new",
+            Self::NextPost => "next_post",
+            Self::PrevPost => "prev_post",
+            Self::OpenComments => "open_comments",
+            Self::OpenImage => "open_image",
+            Self::OpenLink => "open_link",
+            Self::OpenApId => "open_ap_id",
+            Self::Yank => "yank",
+            Self::YankApId => "yank_ap_id",
+            Self::ShowLinks => "show_links",
+            Self::Refresh => "refresh",
+            Self::ToggleLive => "toggle_live",
+            Self::LoadReplies => "load_replies",
+            Self::LoadMoreComments => "load_more_comments",
+            Self::Report => "report",
+            Self::BlockUser => "block_user",
+            Self::Subscribe => "subscribe",
+            Self::ToggleSave => "toggle_save",
+            Self::NextTab => "next_tab",
+            Self::RevealNsfw => "reveal_nsfw",
+            Self::OpenCrossPost => "open_crosspost",
+            Self::ScrollUp => "scroll_up",
+            Self::ScrollDown => "scroll_down",
+            Self::ClearLog => "clear_log",
+            Self::ZoomIn => "zoom_in",
+            Self::ZoomOut => "zoom_out",
+            Self::PanLeft => "pan_left",
+            Self::PanRight => "pan_right",
+            Self::PanUp => "pan_up",
+            Self::PanDown => "pan_down",
+        }
+    }
+
+    /// Gets the [Screen]s the [KeyAction] is available on.
+    pub const fn screens(self) -> &'static [Screen] {
+        match self {
+            Self::Quit | Self::Back | Self::ToggleDiagnostics => &[
+                Screen::PostList,
+                Screen::Post,
+                Screen::Image,
+                Screen::ErrorLog,
+                Screen::Saved,
+                Screen::Modlog,
+                Screen::Discover,
+            ],
+            Self::SelectNext
+            | Self::SelectPrev
+            | Self::FirstPost
+            | Self::LastPost
+            | Self::NextPage
+            | Self::PrevPage => &[
+                Screen::PostList,
+                Screen::Saved,
+                Screen::Modlog,
+                Screen::Discover,
+            ],
+            Self::OpenPost => &[Screen::PostList, Screen::Saved, Screen::Discover],
+            Self::BlockCommunity
+            | Self::ToggleNsfw
+            | Self::ToggleFilters
+            | Self::ToggleDuplicates
+            | Self::SwitchInstance
+            | Self::Logout
+            | Self::SwitchAccount
+            | Self::ShowSaved
+            | Self::ShowDiscover => &[Screen::PostList],
+            Self::GoTo | Self::ShowModlog => &[Screen::PostList, Screen::Post],
+            Self::Refresh => &[
+                Screen::PostList,
+                Screen::Post,
+                Screen::Saved,
+                Screen::Modlog,
+                Screen::Discover,
+            ],
+            Self::ToggleSave => &[Screen::Post, Screen::Saved],
+            Self::NextTab => &[Screen::Saved, Screen::Discover],
+            Self::Subscribe => &[Screen::Post, Screen::Discover],
+            Self::ScrollUp | Self::ScrollDown | Self::ClearLog => &[Screen::ErrorLog],
+            Self::ZoomIn
+            | Self::ZoomOut
+            | Self::PanLeft
+            | Self::PanRight
+            | Self::PanUp
+            | Self::PanDown => &[Screen::Image],
+            _ => &[Screen::Post],
+        }
+    }
+
+    /// Gets whether character keys for the [KeyAction] are pressed twice, like `gg` in vim.
+    pub const fn doubled(self) -> bool {
+        matches!(self, Self::FirstPost | Self::CommentsTop)
+    }
+
+    /// Gets whether the [KeyAction] waits for a digit after its key, like `x2`, used in place of
+    /// the count.
+    pub const fn numbered(self) -> bool {
+        matches!(self, Self::OpenCrossPost)
+    }
+
+    /// Gets the default [KeyChord]s for the [KeyAction].
+    pub fn default_keys(self) -> Vec<KeyChord> {
+        // same syntax as the config file
+        let keys: &[&str] = match self {
+            Self::Quit => &["q"],
+            Self::Back => &["esc"],
+            Self::SelectNext => &["down"],
+            Self::SelectPrev => &["up"],
+            Self::FirstPost => &["g", "home"],
+            Self::LastPost => &["G", "end"],
+            Self::OpenPost => &["enter"],
+            Self::NextPage => &["n", "right"],
+            Self::PrevPage => &["p", "left"],
+            Self::BlockCommunity => &["X"],
+            Self::ToggleNsfw => &["N"],
+            Self::ToggleFilters => &["F"],
+            Self::ToggleDuplicates => &["D"],
+            Self::SwitchInstance => &["@"],
+            Self::Logout => &["U"],
+            Self::SwitchAccount => &["A"],
+            Self::ShowSaved => &["B"],
+            Self::ShowModlog => &["M"],
+            Self::ExportPost => &["E"],
+            Self::Pager => &["|"],
+            Self::ToggleDiagnostics => &["f12"],
+            Self::ShowDiscover => &["C"],
+            Self::GoTo => &[":"],
+            Self::ScrollPostUp => &["up"],
+            Self::ScrollPostDown => &["down"],
+            Self::PagePostUp => &["shift+pageup"],
+            Self::PagePostDown => &["shift+pagedown"],
+            Self::PostTop => &["shift+home"],
+            Self::PostBottom => &["shift+end"],
+            Self::ScrollCommentsUp => &["shift+up"],
+            Self::ScrollCommentsDown => &["shift+down"],
+            Self::NextComment => &["j"],
+            Self::PrevComment => &["k"],
+            Self::ToggleReplies => &["space"],
+            Self::CollapseAll => &["z"],
+            Self::ExpandAll => &["Z"],
+            Self::SortComments => &["s"],
+            Self::PageCommentsUp => &["pageup", "ctrl+u"],
+            Self::PageCommentsDown => &["pagedown", "ctrl+d"],
+            Self::CommentsTop => &["g", "home"],
+            Self::CommentsBottom => &["G", "end"],
+            Self::NextNew => &["]"],
+            Self::PrevNew => &["["],
+            Self::NextPost => &["n"],
+            Self::PrevPost => &["p"],
+            Self::OpenComments => &["enter"],
+            Self::OpenImage => &["i"],
+            Self::OpenLink => &["o"],
+            Self::OpenApId => &["O"],
+            Self::Yank => &["y"],
+            Self::YankApId => &["Y"],
+            Self::ShowLinks => &["f"],
+            Self::Refresh => &["R"],
+            Self::ToggleLive => &["L"],
+            Self::LoadReplies => &["+"],
+            Self::LoadMoreComments => &["m"],
+            Self::Report => &["!"],
+            Self::BlockUser => &["X"],
+            Self::Subscribe => &["S"],
+            Self::ToggleSave => &["b"],
+            Self::NextTab => &["tab"],
+            Self::RevealNsfw => &["v"],
+            Self::OpenCrossPost => &["x"],
+            Self::ScrollUp => &["up", "k"],
+            Self::ScrollDown => &["down", "j"],
+            Self::ClearLog => &["c"],
+            Self::ZoomIn => &["+", "="],
+            Self::ZoomOut => &["-"],
+            Self::PanLeft => &["left", "h"],
+            Self::PanRight => &["right", "l"],
+            Self::PanUp => &["up", "k"],
+            Self::PanDown => &["down", "j"],
+        };
+
+        keys.iter()
+            .map(|k| k.parse().expect("valid default key"))
+            .collect()
+    }
+}
+
+impl FromStr for KeyAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| format!("unknown key action: {s}"))
+    }
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Represents a key, with optional modifiers, e.g. `ctrl+n`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Creates a new [KeyChord].
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Gets whether the key press matches the [KeyChord].
+    ///
+    /// `Shift` is ignored for characters, since it is already reflected in the character.
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let modifiers = match event.code {
+            KeyCode::Char(_) => event.modifiers - KeyModifiers::SHIFT,
+            _ => event.modifiers,
+        };
+
+        self.code == event.code && self.modifiers == modifiers
+    }
+}
+
+impl From<KeyCode> for KeyChord {
+    fn from(val: KeyCode) -> Self {
+        Self::new(val, KeyModifiers::NONE)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key: {s}");
+
+        // split on the last `+`, so `+` and `ctrl++` are valid keys
+        let (mods, key) = match s.rsplit_once('+') {
+            Some((mods, "")) if !mods.is_empty() => (mods.strip_suffix('+').unwrap_or(mods), "+"),
+            Some((mods, key)) if !mods.is_empty() => (mods, key),
+            _ => ("", s),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                f => match f.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+
+        // shifted characters are matched by the character itself
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            code => code,
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => write!(f, "▲"),
+            KeyCode::Down => write!(f, "▼"),
+            KeyCode::Left => write!(f, "◄"),
+            KeyCode::Right => write!(f, "►"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Key bindings for a [KeyAction] in the config file, either one key or a list of keys.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    /// Parses the [KeyChord]s.
+    pub fn keys(&self) -> Result<Vec<KeyChord>, String> {
+        match self {
+            Self::One(key) => Ok(vec![key.parse()?]),
+            Self::Many(keys) => keys.iter().map(|k| k.parse()).collect(),
+        }
+    }
+}
+
+/// Maps key presses to [KeyAction]s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+    bindings: BTreeMap<KeyAction, Vec<KeyChord>>,
+}
+
+impl KeyMap {
+    /// Creates a new [KeyMap] with the default bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: KeyAction::ALL
+                .into_iter()
+                .map(|a| (a, a.default_keys()))
+                .collect(),
+        }
+    }
+
+    /// Creates a [KeyMap] from the `[keys]` section of the config file, using the defaults for
+    /// actions that are not configured.
+    ///
+    /// Returns an error for unknown actions, invalid keys, and keys bound to more than one action
+    /// on the same [Screen].
+    pub fn from_config(keys: &BTreeMap<String, KeyBinding>) -> Result<Self, String> {
+        let mut keymap = Self::new();
+
+        for (name, binding) in keys.iter() {
+            let action = name.parse::<KeyAction>()?;
+            let keys = binding
+                .keys()
+                .map_err(|err| format!("{err} for {action}"))?;
+
+            keymap.bindings.insert(action, keys);
+        }
+
+        let conflicts = keymap.conflicts();
+        if conflicts.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(conflicts.join(", "))
+        }
+    }
+
+    /// Gets a description of every key bound to more than one [KeyAction] on the same [Screen].
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        for (i, (a, a_keys)) in self.bindings.iter().enumerate() {
+            for (b, b_keys) in self.bindings.iter().skip(i + 1) {
+                if !a.screens().iter().any(|s| b.screens().contains(s)) {
+                    continue;
+                }
+
+                for key in a_keys.iter().filter(|k| b_keys.contains(k)) {
+                    conflicts.push(format!("key {key} is bound to both {a} and {b}"));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Gets the [KeyChord]s bound to the [KeyAction].
+    pub fn keys(&self, action: KeyAction) -> &[KeyChord] {
+        self.bindings
+            .get(&action)
+            .map(|keys| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Gets the [KeyAction] bound to the key press on the [Screen], if any.
+    pub fn action_for(&self, screen: Screen, event: &KeyEvent) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .filter(|(action, _)| action.screens().contains(&screen))
+            .find(|(_, keys)| keys.iter().any(|k| k.matches(event)))
+            .map(|(&action, _)| action)
+    }
+
+    /// Gets the HUD hint for the [KeyAction]s, e.g. `(n, ►) next page`.
+    ///
+    /// Unbound actions have no hint.
+    pub fn hint(&self, actions: &[KeyAction], label: &str) -> Option<String> {
+        let keys = actions
+            .iter()
+            .flat_map(|&a| self.keys(a).iter().map(move |k| (a, k)))
+            .map(|(a, k)| match k.code {
+                // doubled keys are shown as typed, e.g. `gg`
+                KeyCode::Char(_) if a.doubled() => format!("{k}{k}"),
+                _ => k.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            None
+        } else {
+            Some(format!("({}) {label}", keys.join(", ")))
+        }
+    }
+
+    /// Gets the HUD line for the list of hints, e.g. `| (q) quit | (Enter) select |`.
+    pub fn hud(&self, hints: &[(&[KeyAction], &str)]) -> String {
+        let hints = hints
+            .iter()
+            .filter_map(|&(actions, label)| self.hint(actions, label))
+            .collect::<Vec<_>>();
+
+        format!("| {} |", hints.join(" | "))
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(toml: &str) -> BTreeMap<String, KeyBinding> {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_key_chord() {
+        assert_eq!("q".parse(), Ok(KeyChord::from(KeyCode::Char('q'))));
+        assert_eq!("Esc".parse(), Ok(KeyChord::from(KeyCode::Esc)));
+        assert_eq!("f12".parse(), Ok(KeyChord::from(KeyCode::F(12))));
+        assert_eq!("+".parse(), Ok(KeyChord::from(KeyCode::Char('+'))));
+        assert_eq!(
+            "ctrl++".parse(),
+            Ok(KeyChord::new(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!("shift+x".parse(), Ok(KeyChord::from(KeyCode::Char('X'))));
+        assert_eq!(
+            "ctrl+alt+left".parse(),
+            Ok(KeyChord::new(
+                KeyCode::Left,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("f99".parse::<KeyChord>().is_err());
+        assert!("".parse::<KeyChord>().is_err());
+
+        let chord: KeyChord = "ctrl+n".parse().unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+n");
+        assert!(chord.matches(&KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)));
+        assert!(!chord.matches(&KeyEvent::from(KeyCode::Char('n'))));
+
+        // terminals may report shift with uppercase characters
+        let chord = KeyChord::from(KeyCode::Char('X'));
+        assert!(chord.matches(&KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT)));
+    }
+
+    #[test]
+    fn test_keymap() {
+        let keymap = KeyMap::new();
+        assert!(keymap.conflicts().is_empty(), "{:?}", keymap.conflicts());
+
+        let down = KeyEvent::from(KeyCode::Down);
+        assert_eq!(
+            keymap.action_for(Screen::PostList, &down),
+            Some(KeyAction::SelectNext)
+        );
+        assert_eq!(
+            keymap.action_for(Screen::Post, &down),
+            Some(KeyAction::ScrollPostDown)
+        );
+        assert_eq!(
+            keymap.hud(&[
+                (&[KeyAction::Quit], "quit"),
+                (&[KeyAction::NextPage], "next page")
+            ]),
+            "| (q) quit | (n, ►) next page |"
+        );
+        assert_eq!(
+            keymap.hint(&[KeyAction::FirstPost], "first").as_deref(),
+            Some("(gg, Home) first")
+        );
+
+        let keymap = KeyMap::from_config(&keys(
+            r#"
+scroll_comments_down = "h"
+scroll_comments_up = ["t", "ctrl+up"]
+open_image = []
+"#,
+        ))
+        .unwrap();
+
+        let h = KeyEvent::from(KeyCode::Char('h'));
+        assert_eq!(
+            keymap.action_for(Screen::Post, &h),
+            Some(KeyAction::ScrollCommentsDown)
+        );
+        assert_eq!(keymap.action_for(Screen::PostList, &h), None);
+        let shift_down = KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action_for(Screen::Post, &shift_down), None);
+        assert_eq!(
+            keymap.action_for(Screen::Post, &KeyEvent::from(KeyCode::Char('i'))),
+            None
+        );
+        assert_eq!(keymap.hint(&[KeyAction::OpenImage], "image"), None);
+        assert_eq!(
+            keymap
+                .hint(
+                    &[KeyAction::ScrollCommentsDown, KeyAction::ScrollCommentsUp],
+                    "scroll"
+                )
+                .as_deref(),
+            Some("(h, t, Ctrl+▲) scroll")
+        );
+    }
+
+    #[test]
+    fn test_keymap_errors() {
+        assert_eq!(
+            KeyMap::from_config(&keys("next_post = \"j\"")),
+            Err("key j is bound to both next_comment and next_post".into())
+        );
+        assert_eq!(
+            KeyMap::from_config(&keys("fly = \"f\"")),
+            Err("unknown key action: fly".into())
+        );
+        assert_eq!(
+            KeyMap::from_config(&keys("quit = \"hyper+q\"")),
+            Err("invalid key: hyper+q for quit".into())
+        );
+
+        // the same key on different screens is not a conflict
+        assert!(KeyMap::from_config(&keys("clear_log = \"X\"")).is_ok());
+    }
+}
@@ -1,11 +1,32 @@
+pub mod api;
 pub mod app;
+pub mod cli;
+pub mod client;
 pub mod comments;
 pub mod community;
+pub mod config;
 pub mod counts;
+pub mod diagnostics;
 pub mod endpoint;
 mod error;
+pub mod event;
+pub mod export;
+pub mod keymap;
+pub mod login;
+pub mod markdown;
+pub mod modlog;
+pub mod offline;
+pub mod pager;
 pub mod posts;
+pub mod report;
+pub mod saved;
 pub mod screen;
+pub mod secrets;
+pub mod session;
+pub mod site;
+pub mod task;
+pub mod terminal;
+pub mod theme;
 pub mod utils;
 
 pub use error::*;
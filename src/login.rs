@@ -0,0 +1,102 @@
+//! Types and functions for logging in to a Lemmy instance.
+
+use std::fmt;
+
+use crate::{client::Client, endpoint::Endpoint, Error, Result};
+
+/// Username, or email, and password of an account, to log in again once its JWT expires.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// Username, or email, of the account.
+    pub username: String,
+    /// Password of the account.
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates new [Credentials].
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+/// Omits the password, e.g. from logs.
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Represents a request to the [Login](Endpoint::Login) endpoint.
+#[derive(Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LoginRequest<'a> {
+    pub username_or_email: &'a str,
+    pub password: &'a str,
+}
+
+/// Represents a response to the [Login](Endpoint::Login) endpoint.
+///
+/// Only the fields used by `temi` are deserialized.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LoginResponse {
+    /// JWT for the account, missing until e.g. the email is verified.
+    pub jwt: Option<String>,
+}
+
+impl<'a> From<&'a Credentials> for LoginRequest<'a> {
+    fn from(credentials: &'a Credentials) -> Self {
+        Self {
+            username_or_email: credentials.username.as_str(),
+            password: credentials.password.as_str(),
+        }
+    }
+}
+
+impl LoginResponse {
+    /// Gets the JWT, or an [Error::Auth] for logins without one.
+    pub fn into_jwt(self) -> Result<String> {
+        self.jwt.ok_or(Error::Auth(
+            "no JWT returned, e.g. the email is unverified".into(),
+        ))
+    }
+}
+
+impl Client {
+    /// Logs in to the [Login](Endpoint::Login) endpoint, returning the JWT for the account.
+    ///
+    /// The JWT is not set on the [Client], see [Client::set_jwt].
+    pub async fn login(&self, credentials: &Credentials) -> Result<String> {
+        let body = LoginRequest::from(credentials);
+
+        self.post_json::<_, LoginResponse>(self.url(Endpoint::Login, &[]).as_str(), &body)
+            .await?
+            .into_jwt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_debug() {
+        let credentials = Credentials::new("ferris", "hunter2");
+
+        assert!(!format!("{credentials:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn test_login_response() {
+        let res: LoginResponse = serde_json::from_str(r#"{"jwt":"token"}"#).unwrap();
+        assert_eq!(res.into_jwt().unwrap(), "token");
+
+        let res: LoginResponse = serde_json::from_str(r#"{"verify_email_sent":true}"#).unwrap();
+        assert!(matches!(res.into_jwt(), Err(Error::Auth(_))));
+    }
+}
@@ -1,113 +1,1167 @@
-use std::io;
+use std::io::Write;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::Instant;
 
-use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use tui::{backend::CrosstermBackend, Terminal};
+use tui::{backend::Backend, layout::Rect};
 
-use temi::{app::*, comments::*, endpoint::*, posts::*, screen::*, Result};
+use temi::{
+    app::*,
+    cli::{Args, Link, LinkKind, USAGE},
+    comments::*,
+    config::Config,
+    event::{Event, Events, Redraw, TICK_RATE},
+    export,
+    login::Credentials,
+    markdown, offline, pager,
+    posts::*,
+    saved::SavedTab,
+    screen::*,
+    secrets::{Account, Secret, SecretStore, Storage},
+    session::{RestoreSession, Session, Visits},
+    task::{CommentsTask, ImageTask, RepliesTask},
+    terminal::{install_panic_hook, TerminalGuard},
+    utils, Error, Result,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    terminal::enable_raw_mode()?;
+    let args = match Args::from_env() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("temi: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if args.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    if args.version {
+        println!("temi {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    // report config errors before entering the alternate screen
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("temi: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    args.apply(&mut config);
+
+    if let Err(err) = config.validate() {
+        eprintln!("temi: {err}");
+        std::process::exit(1);
+    }
+
+    if args.logout {
+        let account = Account::from_config(&config);
+        match SecretStore::new().delete_all(&account) {
+            Ok(true) => println!("logged out of {account}"),
+            Ok(false) => println!("no stored login for {account}"),
+            Err(err) => {
+                eprintln!("temi: error deleting the login for {account}: {err}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // asked before entering the alternate screen, CLI flags take precedence over the session
+    let saved = load_session(&config, &args);
+    let session = saved
+        .clone()
+        .filter(|session| restore_session(&config, &args, session));
+    if let Some(session) = session.as_ref() {
+        session.apply(&mut config);
+        args.apply(&mut config);
+    }
+    let jwt = login(&config, &args).await;
+    let password = std::env::var("LEMMY_PASSWORD").ok();
+    let credentials = load_credentials(&Account::from_config(&config), password);
 
     let stop = Arc::new(AtomicBool::new(false));
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&stop))?;
     signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&stop))?;
 
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // set after being stopped, by Ctrl+z or otherwise, the terminal needs setting up again
+    let cont = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGCONT, Arc::clone(&cont))?;
 
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    install_panic_hook();
 
-    let instance_url = std::env::var("LEMMY_INSTANCE").unwrap_or("https://voyager.lemmy.ml".into());
+    // restores the terminal when dropped, including on early returns
+    let mut terminal = TerminalGuard::new(config.mouse)?;
 
-    let post_ep = Endpoint::PostList;
-    let comment_ep = Endpoint::CommentList;
+    let mut app = App::new(config, PostResponseTable::new(Vec::new()));
+    app.set_jwt(jwt);
+    app.client.set_credentials(credentials);
+    app.set_offline(args.offline);
+    app.set_dump(args.dump);
+    app.set_page(args.page.or(session.as_ref().map(|s| s.page)).unwrap_or(1));
+    if let Some(saved) = saved {
+        app.visits = Visits::new(saved.visits);
+    }
+    app.graphics = Graphics::detect();
+    app.cell_size = cell_size(&mut terminal);
 
-    let posts_res = dl_posts(format!("{instance_url}{post_ep}?page=1").as_str()).await?;
-    let posts = PostResponseTable::from(posts_res);
+    if !app.is_offline() {
+        detect_paging(&mut app).await;
+    }
 
-    let mut app = App::new(instance_url, posts);
+    let page = app.page();
 
-    loop {
-        if stop.load(Ordering::Relaxed) {
+    match fetch_posts(&mut app, page).await {
+        Ok(posts) => {
+            app.set_posts(posts);
+            if let Some(session) = session.as_ref() {
+                session.select(&mut app);
+            }
+        }
+        Err(err) => app.set_error(&err),
+    }
+
+    if let Some(link) = args.link.as_ref() {
+        open_link(&mut app, link).await;
+    }
+
+    let mut events = Events::new(TICK_RATE);
+    app.events = Some(events.sender());
+
+    // whether an image was drawn with terminal graphics, and needs clearing after leaving
+    let mut graphics_drawn = false;
+    let mut redraw = Redraw::new();
+
+    while let Some(event) = events.next().await {
+        redraw.event(&event);
+        let command = match event {
+            Event::Key(key) => handle_key(key, &mut app),
+            Event::Mouse(mouse) => {
+                let size = terminal.size()?;
+                handle_mouse(mouse, &mut app, Rect::new(0, 0, size.width, size.height))
+            }
+            Event::Resize(..) => {
+                // scroll content lengths are recomputed, and clamped, by the draw for the new
+                // size, clearing forces a full redraw
+                terminal.autoresize()?;
+                terminal.clear()?;
+
+                app.cell_size = cell_size(&mut terminal);
+                if let Some(view) = app.image.as_mut() {
+                    view.redraw();
+                }
+                None
+            }
+            // ticks only redraw for timed updates below
+            Event::Tick | Event::FetchComplete => None,
+        };
+
+        if command == Some(Command::Quit) || stop.load(Ordering::Relaxed) {
             break;
         }
 
-        match current_screen() {
-            Screen::Post => {
-                if let Some(post) = app.posts.current() {
-                    let post_id = post.post.id();
-                    let num_comments = post.counts.comments() as usize;
-
-                    if app.comments.get(&post_id).is_none() || refresh() {
-                        let instance_url = app.instance_url.as_str();
-                        let mut responses = CommentResponses::new(Vec::with_capacity(num_comments));
-
-                        for page in 0..(num_comments / 50) {
-                            let page = page + 1;
-                            let comment_url = format!(
-                                "{instance_url}{comment_ep}?post_id={post_id}&page={page}&limit=50"
-                            );
-                            responses
-                                .comments
-                                .append(&mut dl_comments(comment_url.as_str()).await?.comments);
+        // input is left to the shell until continued
+        let pause = (command == Some(Command::Suspend)).then(|| events.pause());
+        #[cfg(unix)]
+        if pause.is_some() {
+            terminal.stop()?;
+        }
+
+        // resized while stopped, the new size is picked up after entering the alternate screen
+        if cont.swap(false, Ordering::Relaxed) {
+            redraw.mark();
+            terminal.resume()?;
+            if let Some(view) = app.image.as_mut() {
+                view.redraw();
+            }
+        }
+        drop(pause);
+
+        // status messages expire, and downloads progress, without any input
+        let busy = app.busy();
+        let expired = app.expire_status();
+        update(&mut app, command).await;
+        if command.is_some() || expired || busy || app.busy() {
+            redraw.mark();
+        }
+
+        if let Some((text, reveal)) = app.take_pager() {
+            let width = terminal.size()?.width as usize;
+            let text = pager::to_ansi(&markdown::render(&text, width, reveal, &app.theme));
+            let command = pager::pager_command();
+
+            // the pager takes over the terminal, and its input, until it exits
+            let pause = events.pause();
+            terminal.suspend()?;
+            let res = pager::run_pager(&command, &text);
+            terminal.resume()?;
+            drop(pause);
+            redraw.mark();
+
+            if let Err(err) = res {
+                app.set_message(format!("error running {command}: {err}"));
+            }
+        }
+
+        if graphics_drawn && app.screen() != Screen::Image {
+            write!(terminal.backend_mut(), "{}", app.graphics.clear_sequence())?;
+            terminal.clear()?;
+            graphics_drawn = false;
+            redraw.mark();
+
+            // e.g. back from the error log
+            if let Some(view) = app.image.as_mut() {
+                view.redraw();
+            }
+        }
+
+        let start = Instant::now();
+        let drawn = redraw.draw(|| terminal.draw(|f| render_screen(f, &mut app)).map(|_| ()))?;
+        if drawn {
+            app.diagnostics.frame_time = start.elapsed();
+        }
+
+        // terminal graphics are drawn over the frame
+        if let Some(output) = app.image.as_mut().and_then(|v| v.take_output()) {
+            let backend = terminal.backend_mut();
+            backend.write_all(output.as_bytes())?;
+            Backend::flush(backend)?;
+            graphics_drawn = true;
+        }
+    }
+
+    // background work stops, and the session is saved, before restoring the terminal, errors
+    // saving it are printed after
+    app.shutdown();
+    let saved = save_session(&app);
+    drop(terminal);
+    if let Err(err) = saved {
+        eprintln!("temi: {err}");
+    }
+
+    Ok(())
+}
+
+/// Loads the [Session] saved on the last clean exit.
+///
+/// Sessions for another instance are ignored, as is the session in offline mode.
+fn load_session(config: &Config, args: &Args) -> Option<Session> {
+    if args.offline.is_some() {
+        return None;
+    }
+
+    let path = Session::path()?;
+    match Session::load(&path) {
+        Ok(session) => session.filter(|s| s.matches(config)),
+        Err(err) => {
+            eprintln!("temi: error reading session {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Gets the JWT to log in with, from `LEMMY_JWT`, or stored by the [SecretStore].
+///
+/// A JWT from `LEMMY_JWT` that the instance accepts is offered to be stored, asked before
+/// entering the alternate screen. Stored JWTs are validated by [detect_paging].
+async fn login(config: &Config, args: &Args) -> Option<String> {
+    use std::io::IsTerminal;
+
+    let env = std::env::var("LEMMY_JWT")
+        .ok()
+        .filter(|jwt| !jwt.is_empty());
+    if args.offline.is_some() {
+        return env;
+    }
+
+    let store = SecretStore::new();
+    let account = Account::from_config(config);
+    let stored = store.load(&account, Secret::Jwt).unwrap_or_else(|err| {
+        eprintln!("temi: error loading the login for {account}: {err}");
+        None
+    });
+
+    let Some(jwt) = env else {
+        return stored;
+    };
+    if stored.as_deref() == Some(jwt.as_str()) || !std::io::stdin().is_terminal() {
+        return Some(jwt);
+    }
+
+    match config.client().with_jwt(jwt.as_str()).get_site().await {
+        Ok(_) if confirm(&format!("store the login for {account} in the keyring?")) => {
+            match store.store(&account, Secret::Jwt, &jwt) {
+                Ok(Storage::Keyring) => (),
+                Ok(Storage::File(path)) => eprintln!(
+                    "temi: warning: no keyring service, stored the login in {}",
+                    path.display()
+                ),
+                Err(err) => eprintln!("temi: error storing the login for {account}: {err}"),
+            }
+        }
+        Err(err) if err.is_unauthorized() => {
+            eprintln!("temi: warning: LEMMY_JWT was rejected by {account}: {err}");
+        }
+        _ => (),
+    }
+
+    Some(jwt)
+}
+
+/// Gets the [Credentials] to log in again with once the JWT expires, for accounts named in the
+/// config.
+///
+/// The `password`, e.g. from `LEMMY_PASSWORD`, takes precedence over the password stored by the
+/// [SecretStore].
+fn load_credentials(account: &Account, password: Option<String>) -> Option<Credentials> {
+    let username = account.username.clone()?;
+    let password = password.filter(|p| !p.is_empty()).or_else(|| {
+        SecretStore::new()
+            .load(account, Secret::Password)
+            .ok()
+            .flatten()
+    })?;
+
+    Some(Credentials::new(username, password))
+}
+
+/// Logs in as the account switched to, with its stored JWT, unless a JWT was kept from switching
+/// away from it.
+fn switch_login(app: &mut App) {
+    let account = Account::from_config(&app.config);
+
+    if app.jwt().is_none() {
+        match SecretStore::new().load(&account, Secret::Jwt) {
+            Ok(Some(jwt)) => app.set_jwt(Some(jwt)),
+            // browsing logged out is expected for instances without a named account
+            Ok(None) if account.username.is_some() => {
+                app.set_message(format!("no stored login for {account}"))
+            }
+            Ok(None) => (),
+            Err(err) => app.set_error(&err),
+        }
+    }
+
+    app.client.set_credentials(load_credentials(&account, None));
+}
+
+/// Detects whether the instance pages posts with cursors, i.e. Lemmy 0.19 and later, falling
+/// back to page numbers on failure.
+///
+/// Authenticated, so a JWT the instance rejects, e.g. once expired, is dropped to browse logged
+/// out.
+async fn detect_paging(app: &mut App) {
+    let mut site = app.client.get_site().await;
+
+    if app.jwt().is_some() && site.as_ref().is_err_and(Error::is_unauthorized) {
+        app.set_jwt(None);
+        app.set_message(format!(
+            "the login for {} has expired, set LEMMY_JWT to log in again",
+            app.instance_label()
+        ));
+        site = app.client.get_site().await;
+    }
+
+    if let Ok(site) = site {
+        app.set_cursor_paging(site.supports_cursor_paging());
+    }
+}
+
+/// Forgets the JWT, and deletes the stored login for the instance, see [Action::Logout].
+fn logout(app: &mut App) {
+    let account = Account::from_config(&app.config);
+    let logged_in = app.jwt().is_some();
+    app.set_jwt(None);
+
+    match SecretStore::new().delete_all(&account) {
+        Ok(deleted) if deleted || logged_in => app.set_message(format!("logged out of {account}")),
+        Ok(_) => app.set_message(format!("not logged in to {account}")),
+        Err(err) => app.set_error(&err),
+    }
+}
+
+/// Exports the post, and its downloaded comments, to a markdown file, see
+/// [Action::ExportPost].
+fn export_post(app: &mut App, post_id: u64, path: &str) {
+    let Some(post) = app.posts.items().iter().find(|p| p.post.id() == post_id) else {
+        app.set_message("no post to export");
+        return;
+    };
+    let comments = app
+        .comments_get(post_id)
+        .map_or(&[][..], |c| c.items.as_slice());
+    let markdown = export::post_to_markdown(post, comments);
+
+    let path = export::expand_home(path);
+    match export::write_markdown(&path, &markdown) {
+        Ok(()) => app.set_message(format!("exported to {}", path.display())),
+        Err(err) => app.set_message(format!("error exporting post: {err}")),
+    }
+}
+
+/// Gets whether to restore the post list position of the [Session], per the `restore_session`
+/// config option.
+///
+/// The position is not restored when starting on a page, or from a link.
+fn restore_session(config: &Config, args: &Args, session: &Session) -> bool {
+    if args.page.is_some() || args.link.is_some() {
+        return false;
+    }
+
+    match config.restore_session {
+        RestoreSession::Always => true,
+        RestoreSession::Ask => {
+            confirm(&format!("restore the last session, {}?", session.summary()))
+        }
+        RestoreSession::Never => false,
+    }
+}
+
+/// Asks a yes or no question on the terminal, defaulting to yes.
+///
+/// Returns `false` if stdin is not a terminal.
+fn confirm(question: &str) -> bool {
+    use std::io::IsTerminal;
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return false;
+    }
+
+    eprint!("temi: {question} [Y/n] ");
+    let mut answer = String::new();
+    if stdin.read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim(), "" | "y" | "Y" | "yes")
+}
+
+/// Saves the [Session] for the next run, unless in offline mode.
+///
+/// Failures, e.g. on a read-only filesystem, are returned for printing after the terminal is
+/// restored, and otherwise ignored.
+fn save_session(app: &App) -> std::result::Result<(), String> {
+    if app.is_offline() {
+        return Ok(());
+    }
+
+    match Session::path() {
+        Some(path) => Session::from_app(app)
+            .save(&path)
+            .map_err(|err| format!("error saving session {}: {err}", path.display())),
+        None => Ok(()),
+    }
+}
+
+/// Updates the [App] for the current [Screen], performing the [Command] and requested actions,
+/// and fetching posts and comments as needed.
+async fn update(app: &mut App, command: Option<Command>) {
+    app.expire_live();
+
+    if let Some(action) = app.take_action() {
+        handle_action(app, action).await;
+    }
+
+    if let Some(url) = app.take_open_url() {
+        // the URL is shown so it can be copied by hand
+        if let Err(err) = utils::open_in_browser(&url) {
+            app.set_message(format!("error opening browser: {err}: {url}"));
+        }
+    }
+
+    if let Some((command, url)) = app.take_viewer() {
+        if let Err(err) = utils::spawn_viewer(&command, &url) {
+            let command = utils::viewer_command(&command, &url).join(" ");
+            app.set_message(format!("error running {command}: {err}"));
+        }
+    }
+
+    if let Some(text) = app.take_clipboard() {
+        match utils::copy_to_clipboard(&text) {
+            Ok(false) => app.set_message("copied"),
+            Ok(true) => app.set_message(format!(
+                "copied, truncated to {} bytes",
+                utils::CLIPBOARD_MAX_BYTES
+            )),
+            Err(err) => app.set_message(format!("error copying: {err}")),
+        }
+    }
+
+    if let Some(post_id) = app.take_open_post() {
+        open_post(app, post_id).await;
+    }
+
+    poll_comments_task(app).await;
+    poll_replies_task(app).await;
+    poll_cross_posts_task(app).await;
+
+    let refresh = command == Some(Command::Refresh);
+
+    if matches!(
+        command,
+        Some(Command::SwitchInstance | Command::SwitchAccount)
+    ) {
+        switch_login(app);
+    }
+
+    // paging differs between instances, detected before downloading the first page
+    if command == Some(Command::SwitchInstance) {
+        detect_paging(app).await;
+    }
+
+    match app.screen() {
+        Screen::Post => {
+            if let Some(post) = app.posts.current() {
+                let post_id = post.post.id();
+                let num_comments = post.counts.comments();
+
+                let loading = app.comments_loading(post_id).is_some();
+                let live = !loading && app.take_live_refresh(post_id);
+                let load_more = !loading && app.take_load_more_comments();
+
+                app.comments_touch(post_id);
+                let now = app.now();
+                app.visits.visit(post_id, now);
+
+                if app.is_offline() {
+                    if refresh {
+                        app.set_message("offline mode: refresh unavailable");
+                    }
+
+                    if app.comments_get(post_id).is_none() {
+                        load_offline_comments(app, post_id);
+                    }
+                } else if refresh
+                    || live
+                    || load_more
+                    || (!loading && app.comments_get(post_id).is_none())
+                {
+                    if refresh {
+                        refresh_post(app, post_id).await;
+                    }
+
+                    let limit = app.config.comment_limit();
+                    let cap = app.config.comment_fetch_cap();
+                    let comments = match app.comments_get(post_id) {
+                        // the batch after the comments downloaded so far
+                        Some(c) if load_more => {
+                            let start = c.fetched.unwrap_or(num_comments);
+                            start..start.saturating_add(cap.unwrap_or(num_comments))
                         }
+                        // refreshes, and live mode, only download the batches loaded so far
+                        Some(c) if c.fetched.is_some() => 0..c.fetched.unwrap_or(0),
+                        // the comment count is from when the post was fetched, so live mode asks
+                        // for an extra page of comments posted since
+                        Some(c) if live => 0..num_comments.max(c.items.len() as u64) + limit,
+                        Some(_) => 0..num_comments,
+                        None => 0..cap.map_or(num_comments, |cap| cap.min(num_comments)),
+                    };
+
+                    // only one download at a time, cancel any download for another post
+                    app.cancel_comments_task();
+
+                    // a first load shows each page as it arrives
+                    let stream = app.comments_get(post_id).is_none();
+                    app.comments_task = Some(CommentsTask::spawn(
+                        app.client.clone(),
+                        post_id,
+                        comments,
+                        limit,
+                        stream,
+                        app.events.clone(),
+                    ));
+                }
+
+                // crossposts are only reported for a single post, downloaded once with it
+                if !app.is_offline() {
+                    app.load_cross_posts(post_id);
+                }
+
+                if let Some(comment_id) = app.take_load_replies() {
+                    if app.is_offline() {
+                        app.set_message("offline mode: replies unavailable");
+                    } else {
+                        app.cancel_replies_task();
+                        app.replies_task = Some(RepliesTask::spawn(
+                            app.client.clone(),
+                            post_id,
+                            comment_id,
+                            app.events.clone(),
+                        ));
+                    }
+                }
+            } else {
+                app.replace_screen(Screen::PostList);
+            }
+        }
+        Screen::PostList => {
+            poll_prefetch_task(app).await;
 
-                        if num_comments % 50 > 0 {
-                            let page = (num_comments / 50) + 1;
-                            let comment_url = format!(
-                                "{instance_url}{comment_ep}?post_id={post_id}&page={page}&limit=50"
-                            );
-                            responses
-                                .comments
-                                .append(&mut dl_comments(comment_url.as_str()).await?.comments);
+            if matches!(
+                command,
+                Some(Command::DownloadPosts | Command::SwitchInstance | Command::SwitchAccount)
+            ) {
+                let page = app.page();
+
+                if let Some(posts) = app.take_prefetched(page) {
+                    dump_posts(app, page, &posts);
+                    app.set_posts(posts);
+                    app.prefetch_next_page();
+                } else {
+                    match fetch_posts(app, page).await {
+                        Ok(posts) => app.set_posts(posts),
+                        Err(_) if app.is_offline() => {
+                            app.set_message(format!("offline mode: no saved page {page}"));
+                            app.previous_page();
                         }
+                        Err(err) => app.set_error(&err),
+                    }
+                }
+            } else if app.should_prefetch() {
+                app.prefetch_next_page();
+            }
+        }
+        Screen::Image => {
+            poll_image_task(app).await;
+
+            match app.image.as_mut() {
+                Some(view) if view.image().is_none() && app.image_task.is_none() => {
+                    if refresh || !view.failed() {
+                        view.set_failed(false);
+                        app.image_task = Some(ImageTask::spawn(
+                            app.client.clone(),
+                            view.url().to_owned(),
+                            app.disk_cache.clone(),
+                            app.events.clone(),
+                        ));
+                    }
+                }
+                Some(_) => (),
+                None => {
+                    app.pop_screen();
+                }
+            }
+        }
+        Screen::Saved if command == Some(Command::DownloadSaved) => download_saved(app).await,
+        Screen::Modlog if command == Some(Command::DownloadModlog) => download_modlog(app).await,
+        Screen::Discover if command == Some(Command::DownloadCommunities) => {
+            download_communities(app).await
+        }
+        _ => (),
+    }
+}
+
+/// Performs an [Action] requested by a [Screen].
+///
+/// Failures are displayed in the HUD, instead of exiting the application.
+async fn handle_action(app: &mut App, action: Action) {
+    let client = app.client.clone();
+
+    match action {
+        // the stored login is deleted offline too
+        Action::Logout => logout(app),
+        Action::ExportPost { post_id, path } => export_post(app, post_id, &path),
+        // returned as a Command by App::accept_confirm instead
+        Action::Quit => (),
+        _ if app.is_offline() => app.set_message("offline mode: network actions unavailable"),
+        _ if app.jwt().is_none() => app.set_message("login required: set LEMMY_JWT"),
+        Action::FollowCommunity {
+            community_id,
+            follow,
+        } => match client.follow_community(community_id, follow).await {
+            Ok(res) => {
+                let subscribed = res.community_view.subscribed;
+
+                app.posts.set_subscribed(community_id, subscribed);
+                app.discover.set_subscribed(community_id, subscribed);
+                app.comments_iter_mut()
+                    .for_each(|c| c.set_subscribed(community_id, subscribed));
+
+                app.set_message(format!(
+                    "{}: {subscribed}",
+                    res.community_view.community.name
+                ));
+            }
+            Err(err) => app.set_message(format!("error following community: {err}")),
+        },
+        Action::BlockCommunity {
+            community_id,
+            block,
+        } => match client.block_community(community_id, block).await {
+            Ok(res) => {
+                if res.blocked {
+                    app.posts.remove_community(community_id);
+                }
+
+                app.set_message(format!(
+                    "{}: {}",
+                    res.community_view.community.name,
+                    if res.blocked { "blocked" } else { "unblocked" }
+                ));
+            }
+            Err(err) => app.set_message(format!("error blocking community: {err}")),
+        },
+        Action::BlockPerson { person_id, block } => {
+            match client.block_person(person_id, block).await {
+                Ok(res) => {
+                    app.comments_iter_mut()
+                        .for_each(|c| c.set_creator_blocked(person_id, res.blocked));
+
+                    app.set_message(format!(
+                        "{}: {}",
+                        res.person_view.person.name(),
+                        if res.blocked { "blocked" } else { "unblocked" }
+                    ));
+                }
+                Err(err) => app.set_message(format!("error blocking user: {err}")),
+            }
+        }
+        Action::ReportPost { post_id, reason } => {
+            let res = client.report_post(post_id, &reason).await;
+            app.set_message(report_message(res.map(|_| ())));
+        }
+        Action::ReportComment { comment_id, reason } => {
+            let res = client.report_comment(comment_id, &reason).await;
+            app.set_message(report_message(res.map(|_| ())));
+        }
+        Action::SavePost { post_id, save } => match client.save_post(post_id, save).await {
+            Ok(res) => {
+                let saved = res.saved;
+                if !saved {
+                    app.saved.remove_post(post_id);
+                }
+                app.posts.update(res);
+
+                app.set_message(if saved { "post saved" } else { "post unsaved" });
+            }
+            Err(err) => app.set_message(format!("error saving post: {err}")),
+        },
+        Action::SaveComment { comment_id, save } => {
+            match client.save_comment(comment_id, save).await {
+                Ok(res) => {
+                    let saved = res.saved;
+                    if !saved {
+                        app.saved.remove_comment(comment_id);
+                    }
+                    app.comments_iter_mut()
+                        .for_each(|c| c.set_saved(comment_id, saved));
+
+                    app.set_message(if saved {
+                        "comment saved"
+                    } else {
+                        "comment unsaved"
+                    });
+                }
+                Err(err) => app.set_message(format!("error saving comment: {err}")),
+            }
+        }
+    }
+}
+
+/// Inserts the downloaded comments into the [App] once the [CommentsTask] finishes, merging them
+/// into the loaded comments on refresh, and in live mode.
+///
+/// Pages streamed in by the task are merged as they arrive.
+async fn poll_comments_task(app: &mut App) {
+    merge_comment_pages(app);
+
+    if !app.comments_task.as_ref().is_some_and(|t| t.is_finished()) {
+        return;
+    }
 
-                        app.comments.remove(&post_id);
-                        app.comments.insert(post_id, responses.into());
+    if let Some(task) = app.comments_task.take() {
+        let post_id = task.post_id();
+        let streamed = task.streams();
+        let comments = task.comments();
+        let total = app
+            .posts
+            .current()
+            .filter(|p| p.post.id() == post_id)
+            .map_or(0, |p| p.counts.comments());
 
-                        set_refresh(false);
+        match task.join().await {
+            Ok(responses) => {
+                if let Some(dir) = app.dump() {
+                    if let Err(err) = offline::dump_comments(dir, post_id, &responses) {
+                        app.set_message(format!("error saving comments: {err}"));
                     }
+                }
+
+                if app.comments_get(post_id).is_none() {
+                    // sorted when first drawn
+                    let mut c = CommentResponseTable::from(responses);
+                    c.set_fetched(comments.end, total);
+                    app.comments_insert(post_id, c);
+                    return;
+                }
+
+                app.comment_anchor = app.scroll_anchor(post_id);
+                let Some(c) = app.comments_get_mut(post_id) else {
+                    return;
+                };
+                c.set_fetched(comments.end, total);
 
-                    draw_post_screen(&mut terminal, app.as_mut(), Arc::clone(&stop))?;
+                if streamed {
+                    // every page was merged already, except for comments on shifted pages
+                    c.merge_loaded(responses);
+                    return;
+                }
+
+                if comments.start > 0 {
+                    let stats = c.merge_loaded(responses);
+                    app.set_message(format!("loaded {} more comments", stats.added));
+                    return;
+                }
+
+                // a refresh, or live mode, merges into the loaded comments, keeping the selection,
+                // collapsed comments, and scroll position
+
+                let stats = app
+                    .comments_get_mut(post_id)
+                    .map(|c| c.merge(responses))
+                    .unwrap_or_default();
+
+                if app.live.is_some_and(|l| l.post_id == post_id) {
+                    app.live_refreshed(post_id, Ok::<_, Error>(stats.added));
+                } else if stats.is_empty() {
+                    app.set_message("comments unchanged");
                 } else {
-                    set_current_screen(Screen::PostList);
+                    app.set_message(format!("comments: {stats}"));
                 }
             }
-            Screen::PostList => {
-                if download_posts() {
-                    let instance_url = app.instance_url.as_str();
-                    let page = app.page();
+            Err(err) if app.live.is_some_and(|l| l.post_id == post_id) => {
+                // failed live refreshes are retried, without interrupting in the error banner
+                app.live_refreshed(post_id, Err::<usize, _>(err));
+            }
+            Err(err) => {
+                app.set_error(&err);
 
-                    app.posts = dl_posts(format!("{instance_url}{post_ep}?page={page}").as_str())
-                        .await?
-                        .into();
+                // on failure, insert an empty table to avoid refetching every frame
+                if app.comments_get(post_id).is_none() {
+                    app.comments_insert(post_id, CommentResponses::new(Vec::new()).into());
+                }
+            }
+        }
+    }
+}
+
+/// Merges the pages streamed in by the [CommentsTask] so far, showing the comments as soon as
+/// the first page arrives.
+fn merge_comment_pages(app: &mut App) {
+    let Some(task) = app.comments_task.as_ref() else {
+        return;
+    };
+    let post_id = task.post_id();
+    let pages = task.take_pages();
+    if pages.is_empty() {
+        return;
+    }
+
+    app.merge_comment_pages(post_id, pages);
+}
+
+/// Merges the loaded replies into the comments once the [RepliesTask] finishes.
+async fn poll_replies_task(app: &mut App) {
+    if !app.replies_task.as_ref().is_some_and(|t| t.is_finished()) {
+        return;
+    }
+
+    if let Some(task) = app.replies_task.take() {
+        let post_id = task.post_id();
+
+        match task.join().await {
+            Ok(replies) => {
+                app.comment_anchor = app.scroll_anchor(post_id);
 
-                    set_download_posts(false);
+                let stats = app
+                    .comments_get_mut(post_id)
+                    .map(|c| c.merge_loaded(replies))
+                    .unwrap_or_default();
+                if stats.added == 0 {
+                    app.set_message("no more replies found");
                 }
-                draw_posts_screen(&mut terminal, app.as_mut(), Arc::clone(&stop))?
             }
-            _ => (),
+            Err(err) => app.set_error(&err),
         }
     }
+}
 
-    terminal::disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+/// Updates the opened post, and sets its crossposts, once the
+/// [CrossPostsTask](temi::task::CrossPostsTask) finishes.
+///
+/// Crossposts are optional, so failures are only logged, leaving the post without crossposts
+/// until refreshed. Posts deleted since the list was fetched are marked as deleted.
+async fn poll_cross_posts_task(app: &mut App) {
+    if !app
+        .cross_posts_task
+        .as_ref()
+        .is_some_and(|t| t.is_finished())
+    {
+        return;
+    }
 
-    Ok(())
+    if let Some(task) = app.cross_posts_task.take() {
+        let post_id = task.post_id();
+
+        match task.join().await {
+            Ok(res) => {
+                app.posts.update(res.post_view);
+                app.set_cross_posts(post_id, res.cross_posts);
+            }
+            Err(err) => {
+                if err.api_message() == Some("couldnt_find_post") {
+                    app.posts.set_deleted(post_id);
+                } else {
+                    app.log_error(&err);
+                }
+                app.set_cross_posts(post_id, Vec::new());
+            }
+        }
+    }
+}
+
+/// Sets the downloaded image on the Image screen once the [ImageTask] finishes.
+///
+/// Download, and decode, failures are shown in the error banner, and can be retried.
+async fn poll_image_task(app: &mut App) {
+    if !app.image_task.as_ref().is_some_and(|t| t.is_finished()) {
+        return;
+    }
+
+    if let Some(task) = app.image_task.take() {
+        let res = task.join().await;
+
+        if let Some(view) = app.image.as_mut() {
+            match res {
+                Ok(image) => {
+                    let image = Arc::new(image);
+
+                    app.images.insert(view.url(), Arc::clone(&image));
+                    view.set_image(image);
+                }
+                Err(err) => {
+                    view.set_failed(true);
+                    app.set_error(&err);
+                }
+            }
+        }
+    }
+}
+
+/// Gets the size of a terminal cell in pixels, or [DEFAULT_CELL_SIZE] if the terminal does not
+/// report it.
+fn cell_size(terminal: &mut TerminalGuard) -> (u16, u16) {
+    match terminal.backend_mut().window_size() {
+        Ok(size) if size.columns_rows.width > 0 && size.columns_rows.height > 0 => {
+            let width = size.pixels.width / size.columns_rows.width;
+            let height = size.pixels.height / size.columns_rows.height;
+
+            if width > 0 && height > 0 {
+                (width, height)
+            } else {
+                DEFAULT_CELL_SIZE
+            }
+        }
+        _ => DEFAULT_CELL_SIZE,
+    }
+}
+
+/// Replaces the post in the post list with an up-to-date copy from the server, and downloads its
+/// crossposts.
+///
+/// Posts deleted since the list was fetched are marked as deleted, instead of showing an error.
+/// Failed downloads leave the post without crossposts, until refreshed again.
+async fn refresh_post(app: &mut App, post_id: u64) {
+    match app.client.get_post_view(post_id).await {
+        Ok(res) => {
+            app.posts.update(res.post_view);
+            app.set_cross_posts(post_id, res.cross_posts);
+            return;
+        }
+        Err(err) if err.api_message() == Some("couldnt_find_post") => {
+            app.posts.set_deleted(post_id)
+        }
+        Err(err) => app.set_error(&err),
+    }
+
+    if app.cross_posts(post_id).is_none() {
+        app.set_cross_posts(post_id, Vec::new());
+    }
+}
+
+/// Opens the post for a post or comment [Link] on the Post screen.
+///
+/// Comment links scroll the comment pane to the linked comment once the comments are loaded.
+async fn open_link(app: &mut App, link: &Link) {
+    if app.is_offline() {
+        app.set_message("offline mode: links unavailable");
+        return;
+    }
+
+    let post_id = match link.kind {
+        LinkKind::Post(id) => id,
+        LinkKind::Comment(id) => match app.client.get_comment(id).await {
+            Ok(res) => {
+                app.focus_comment = Some(id);
+                res.post.id()
+            }
+            Err(err) => {
+                app.set_error(&err);
+                return;
+            }
+        },
+        LinkKind::Unknown => {
+            app.set_message("unrecognized link, showing the post list");
+            return;
+        }
+    };
+
+    open_post(app, post_id).await;
+}
+
+/// Downloads the post, and opens it on the Post screen.
+///
+/// A comment to scroll to, see [App::focus_comment], is dropped if the download fails.
+async fn open_post(app: &mut App, post_id: u64) {
+    match app.client.get_post_view(post_id).await {
+        Ok(res) => {
+            app.posts.select_or_insert(res.post_view);
+            app.set_cross_posts(post_id, res.cross_posts);
+            app.push_screen(Screen::Post);
+        }
+        Err(err) => {
+            app.focus_comment = None;
+            app.set_error(&err);
+        }
+    }
+}
+
+/// Downloads the current page of the current tab of saved posts, or comments.
+async fn download_saved(app: &mut App) {
+    let page = app.saved.page();
+
+    match app.saved.tab {
+        SavedTab::Posts => match app.client.list_saved_posts(page).await {
+            Ok(posts) => app.saved.set_posts(posts),
+            Err(err) => app.set_error(&err),
+        },
+        SavedTab::Comments => match app.client.list_saved_comments(page).await {
+            Ok(comments) => app.saved.set_comments(comments),
+            Err(err) => app.set_error(&err),
+        },
+    }
+}
+
+/// Downloads the current page of the moderation log of the community.
+async fn download_modlog(app: &mut App) {
+    let Some((community_id, page)) = app.modlog.as_ref().map(|m| (m.community.id, m.page)) else {
+        return;
+    };
+
+    match app.client.get_modlog(community_id, page).await {
+        Ok(res) => {
+            if let Some(modlog) = app.modlog.as_mut() {
+                modlog.set_entries(res);
+            }
+        }
+        Err(err) => app.set_error(&err),
+    }
+}
+
+/// Downloads the current page of communities to discover.
+async fn download_communities(app: &mut App) {
+    let (listing_type, page) = (app.discover.listing_type, app.discover.page);
+
+    match app.client.list_communities(listing_type, page).await {
+        Ok(res) => app.discover.set_communities(res),
+        Err(err) => app.set_error(&err),
+    }
+}
+
+/// Gets a page of posts, from the offline directory in offline mode, otherwise from the network.
+///
+/// Uses the page cursor when cursor paging, and one is known for the page. In dump mode, posts
+/// fetched from the network are saved to the dump directory.
+async fn fetch_posts(app: &mut App, page: u64) -> Result<PostResponses> {
+    if let Some(dir) = app.offline() {
+        return offline::read_posts(dir, page);
+    }
+
+    let posts = match app.page_cursor(page) {
+        Some(cursor) => app.client.list_posts_cursor(cursor).await?,
+        None => app.client.list_posts(page).await?,
+    };
+    dump_posts(app, page, &posts);
+
+    Ok(posts)
+}
+
+/// Saves a page of posts to the dump directory, if any.
+fn dump_posts(app: &mut App, page: u64, posts: &PostResponses) {
+    if let Some(dir) = app.dump() {
+        if let Err(err) = offline::dump_posts(dir, page, posts) {
+            app.set_message(format!("error saving posts: {err}"));
+        }
+    }
+}
+
+/// Loads the comments for a post from the offline directory.
+fn load_offline_comments(app: &mut App, post_id: u64) {
+    let Some(dir) = app.offline() else {
+        return;
+    };
+
+    let responses = match offline::read_comments(dir, post_id) {
+        Ok(responses) => responses,
+        Err(_) => {
+            app.set_message("offline mode: no saved comments for this post");
+            CommentResponses::new(Vec::new())
+        }
+    };
+
+    app.comments_insert(post_id, responses.into());
+}
+
+/// Stores the prefetched page once the prefetch task finishes.
+///
+/// Failed pages are not prefetched again for a while, see [App::set_prefetch_failed], and are
+/// downloaded normally when requested.
+async fn poll_prefetch_task(app: &mut App) {
+    if !app.prefetch_task.as_ref().is_some_and(|t| t.is_finished()) {
+        return;
+    }
+
+    if let Some(task) = app.prefetch_task.take() {
+        let page = task.page();
+
+        match task.join().await {
+            Ok(posts) => {
+                app.prefetched = Some((page, posts));
+                app.prefetch_failed = None;
+            }
+            Err(err) => app.set_prefetch_failed(page, &err),
+        }
+    }
+}
+
+/// Gets the HUD message for the result of a report request.
+fn report_message(res: Result<()>) -> String {
+    match res {
+        Ok(()) => "report sent to moderators".into(),
+        Err(err) => match err.api_message() {
+            Some(message) => format!("report rejected: {message}"),
+            None => format!("error sending report: {err}"),
+        },
+    }
 }
@@ -0,0 +1,1022 @@
+//! Basic markdown rendering for post bodies and comments.
+//!
+//! Supports paragraphs, headers, blockquotes, fenced code blocks, lists, rules, Lemmy spoilers,
+//! and inline bold, italic, strikethrough, code, and links. Anything else, including malformed
+//! markdown, is rendered as plain text.
+
+use tui::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{screen::split_width, theme::Theme};
+
+/// Prefix of blockquote lines.
+pub const QUOTE_PREFIX: &str = "▌ ";
+
+/// Display columns between tab stops.
+const TAB_WIDTH: usize = 4;
+
+/// Renders markdown into [Line]s of at most `width` display columns, styled by the [Theme].
+///
+/// Every block is followed by an empty line, so the [Line]s can be measured and scrolled without
+/// wrapping them again. Spoiler blocks are a single line unless `reveal` is set.
+pub fn render(text: &str, width: usize, reveal: bool, theme: &Theme) -> Vec<Line<'static>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = Vec::new();
+
+    render_blocks(&lines, width.max(1), reveal, theme, &mut out);
+    out
+}
+
+/// Gets whether the markdown has a spoiler block, i.e. whether revealing it changes anything.
+pub fn has_spoiler(text: &str) -> bool {
+    let lines: Vec<&str> = text.lines().collect();
+
+    (0..lines.len()).any(|i| spoiler_end(&lines, i).is_some())
+}
+
+/// Renders the block level markdown `lines`, appending the [Line]s to `out`.
+fn render_blocks(
+    lines: &[&str],
+    width: usize,
+    reveal: bool,
+    theme: &Theme,
+    out: &mut Vec<Line<'static>>,
+) {
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = fence(line) {
+            // unterminated blocks run to the end of the text
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with(fence))
+                .map_or(lines.len(), |p| i + 1 + p);
+
+            for code in &lines[i + 1..end] {
+                out.extend(code_lines(code, width, theme));
+            }
+            i = end + 1;
+        } else if let Some(end) = spoiler_end(lines, i) {
+            let title = spoiler_title(line).unwrap_or_default();
+            let (marker, style) = if reveal {
+                ("▼", Style::default().add_modifier(Modifier::BOLD))
+            } else {
+                ("▶", Style::default().add_modifier(Modifier::ITALIC))
+            };
+            let summary = match title {
+                "" => format!("{marker} spoiler"),
+                title => format!("{marker} spoiler: {title}"),
+            };
+
+            out.extend(wrap(vec![Span::styled(summary, style)], width, &[], &[]));
+            if reveal {
+                render_blocks(&lines[i + 1..end], width, reveal, theme, out);
+                // the spoiler is followed by its own empty line
+                if out.last().is_some_and(|l| l.spans.is_empty()) {
+                    out.pop();
+                }
+            }
+            i = end + 1;
+        } else if let Some((level, title)) = heading(line) {
+            let mut style = Style::default().add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+
+            out.extend(wrap(inline(title, style, theme), width, &[], &[]));
+            i += 1;
+        } else if is_rule(line) {
+            out.push(Line::styled(
+                "─".repeat(width),
+                Style::default().add_modifier(Modifier::DIM),
+            ));
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let end = lines[i..]
+                .iter()
+                .position(|l| !l.trim_start().starts_with('>'))
+                .map_or(lines.len(), |p| i + p);
+
+            let inner: Vec<&str> = lines[i..end]
+                .iter()
+                .map(|l| {
+                    let l = &l.trim_start()[1..];
+                    l.strip_prefix(' ').unwrap_or(l)
+                })
+                .collect();
+
+            let mut quoted = Vec::new();
+            let prefix_width = QUOTE_PREFIX.width();
+            render_blocks(
+                &inner,
+                width.saturating_sub(prefix_width).max(1),
+                reveal,
+                theme,
+                &mut quoted,
+            );
+            // the quote is followed by its own empty line
+            quoted.pop();
+
+            out.extend(quoted.into_iter().map(|line| {
+                let prefix = if line.spans.is_empty() {
+                    QUOTE_PREFIX.trim_end()
+                } else {
+                    QUOTE_PREFIX
+                };
+
+                Line::from(
+                    [Span::styled(prefix, theme.quote)]
+                        .into_iter()
+                        .chain(line.spans.into_iter().map(|span| {
+                            let style = theme.quote.patch(span.style);
+                            span.style(style)
+                        }))
+                        .collect::<Vec<_>>(),
+                )
+            }));
+            i = end;
+        } else if list_item(line).is_some() {
+            i = render_list(lines, i, width, theme, out);
+            continue;
+        } else {
+            // paragraphs end at an empty line, or the start of another block
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| starts_block(l))
+                .map_or(lines.len(), |p| i + 1 + p);
+
+            for row in paragraph_rows(&lines[i..end]) {
+                out.extend(wrap(inline(&row, Style::default(), theme), width, &[], &[]));
+            }
+            i = end;
+        }
+
+        out.push(Line::default());
+    }
+}
+
+/// Renders the list starting at `lines[start]`, appending the [Line]s to `out`.
+///
+/// Returns the index of the first line after the list.
+fn render_list(
+    lines: &[&str],
+    start: usize,
+    width: usize,
+    theme: &Theme,
+    out: &mut Vec<Line<'static>>,
+) -> usize {
+    let mut i = start;
+    // indent, marker, and text of each item
+    let mut items: Vec<(usize, String, String)> = Vec::new();
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some((indent, marker, text)) = list_item(line) {
+            items.push((indent, marker, text.to_owned()));
+        } else if line.trim().is_empty() {
+            // loose lists have empty lines between their items
+            let next = lines[i..].iter().position(|l| !l.trim().is_empty());
+            match next {
+                Some(p) if list_item(lines[i + p]).is_some() => {
+                    i += p;
+                    continue;
+                }
+                _ => break,
+            }
+        } else if starts_block(line) {
+            break;
+        } else if let Some((_, _, text)) = items.last_mut() {
+            // continuation of the item
+            text.push(' ');
+            text.push_str(line.trim());
+        }
+
+        i += 1;
+    }
+
+    for (indent, marker, text) in items {
+        // two columns per nesting level, capped to leave room for the text
+        let level = (indent / 2).min(width / 4);
+        let prefix = format!("{}{marker} ", "  ".repeat(level));
+        let hanging = " ".repeat(prefix.width());
+
+        out.extend(wrap(
+            inline(&text, Style::default(), theme),
+            width,
+            &[Span::raw(prefix)],
+            &[Span::raw(hanging)],
+        ));
+    }
+
+    out.push(Line::default());
+    i
+}
+
+/// Gets the fence of a fenced code block opening line, e.g. "```rust".
+fn fence(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+
+    if trimmed.starts_with("```") {
+        Some("```")
+    } else if trimmed.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+/// Gets the title of a spoiler block opening line, e.g. "::: spoiler title".
+fn spoiler_title(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix(":::")?.trim_start();
+    let title = rest.strip_prefix("spoiler")?;
+
+    (title.is_empty() || title.starts_with(char::is_whitespace)).then(|| title.trim())
+}
+
+/// Gets the index of the line closing the spoiler block opened at `lines[start]`, skipping nested
+/// spoilers.
+///
+/// Returns `None` if the line does not open a spoiler, or the spoiler is unterminated.
+fn spoiler_end(lines: &[&str], start: usize) -> Option<usize> {
+    spoiler_title(lines.get(start)?)?;
+    let mut depth = 0;
+
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if spoiler_title(line).is_some() {
+            depth += 1;
+        } else if line.trim() == ":::" {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+/// Gets the level and text of a header line, e.g. "## Title".
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+    let rest = &trimmed[level..];
+
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level, rest.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+/// Gets whether the line is a horizontal rule, e.g. "---".
+fn is_rule(line: &str) -> bool {
+    let rule: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+
+    rule.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| rule.chars().all(|r| r == c))
+}
+
+/// Gets the indent, display marker, and text of a list item line, e.g. "- item" or "2. item".
+fn list_item(line: &str) -> Option<(usize, String, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    if let Some(text) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        return Some((indent, "•".into(), text.trim()));
+    }
+
+    let digits = trimmed.len()
+        - trimmed
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let rest = &trimmed[digits..];
+    if (1..=9).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some((indent, trimmed[..digits + 1].into(), rest[2..].trim()));
+    }
+
+    None
+}
+
+/// Gets whether the line ends a paragraph, by being empty or starting another block.
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    trimmed.is_empty()
+        || trimmed.starts_with('>')
+        || spoiler_title(line).is_some()
+        || fence(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || list_item(line).is_some()
+}
+
+/// Joins the lines of a paragraph into rows to wrap, with tabs expanded.
+///
+/// Lines aligned with runs of spaces, e.g. a table, are rows of their own, instead of being
+/// joined to the lines around them. Lines are aligned with a run of three or more spaces, or with
+/// runs of two spaces ending at the same column as in the line before or after, unlike prose with
+/// two spaces after its periods.
+fn paragraph_rows(lines: &[&str]) -> Vec<String> {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|l| expand_tabs(l).trim().to_owned())
+        .collect();
+    let columns: Vec<Vec<usize>> = lines.iter().map(|l| gap_columns(l)).collect();
+    let shares_column = |i: usize, j: Option<usize>| {
+        j.and_then(|j| columns.get(j))
+            .is_some_and(|other| columns[i].iter().any(|c| other.contains(c)))
+    };
+
+    let mut rows: Vec<String> = Vec::new();
+    let mut joined = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let aligned = line.contains("   ")
+            || shares_column(i, i.checked_sub(1))
+            || shares_column(i, Some(i + 1));
+
+        match rows.last_mut() {
+            Some(row) if joined && !aligned => {
+                row.push(' ');
+                row.push_str(line);
+            }
+            _ => rows.push(line.clone()),
+        }
+        joined = !aligned;
+    }
+
+    rows
+}
+
+/// Gets the columns of the text following runs of two or more spaces in the line.
+fn gap_columns(line: &str) -> Vec<usize> {
+    let mut columns = Vec::new();
+    let mut column = 0;
+    let mut spaces = 0;
+
+    for grapheme in line.graphemes(true) {
+        if grapheme == " " {
+            spaces += 1;
+        } else {
+            if spaces >= 2 {
+                columns.push(column);
+            }
+            spaces = 0;
+        }
+        column += grapheme.width();
+    }
+
+    columns
+}
+
+/// Expands the tabs of a line into spaces, up to the next tab stop, and drops other control
+/// characters.
+///
+/// Line breaks, e.g. in text not split into lines, become single spaces.
+pub fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for grapheme in line.graphemes(true) {
+        match grapheme {
+            "\t" => {
+                let spaces = TAB_WIDTH - column % TAB_WIDTH;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            // control characters are graphemes of their own, apart from "\r\n"
+            g if g.chars().all(char::is_control) => {
+                if g.chars().all(char::is_whitespace) {
+                    expanded.push(' ');
+                    column += 1;
+                }
+            }
+            g => {
+                expanded.push_str(g);
+                column += g.width();
+            }
+        }
+    }
+
+    expanded
+}
+
+/// Gets the [Line]s of a code block line, keeping its whitespace, and breaking it at `width`.
+fn code_lines(code: &str, width: usize, theme: &Theme) -> Vec<Line<'static>> {
+    let code = expand_tabs(code);
+
+    // whitespace-only lines wrap to an extra row
+    if code.trim().is_empty() {
+        return vec![Line::default()];
+    }
+
+    split_width(&code, width)
+        .into_iter()
+        .map(|piece| Line::from(Span::styled(piece.to_owned(), theme.code)))
+        .collect()
+}
+
+/// Parses the inline markdown of a block into styled [Span]s, on top of the `base` style.
+///
+/// Delimiters without a closing delimiter are kept as text.
+fn inline(text: &str, base: Style, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut buf = String::new();
+    let (mut bold, mut italic, mut strike) = (false, false, false);
+
+    let style = |bold: bool, italic: bool, strike: bool| {
+        let mut style = base;
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if strike {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        style
+    };
+    let flush = |buf: &mut String, spans: &mut Vec<Span<'static>>, style: Style| {
+        if !buf.is_empty() {
+            spans.push(Span::styled(std::mem::take(buf), style));
+        }
+    };
+
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let prev = text[..i].chars().next_back();
+        let current = style(bold, italic, strike);
+
+        // escaped punctuation is literal
+        if let Some(escaped) = rest
+            .strip_prefix('\\')
+            .and_then(|r| r.chars().next())
+            .filter(char::is_ascii_punctuation)
+        {
+            buf.push(escaped);
+            i += 2;
+            continue;
+        }
+
+        if rest.starts_with('`') {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let fence = &rest[..ticks];
+
+            match rest[ticks..].find(fence) {
+                Some(end) => {
+                    flush(&mut buf, &mut spans, current);
+                    let code = rest[ticks..ticks + end].trim();
+                    spans.push(Span::styled(code.to_owned(), theme.code.patch(current)));
+                    i += ticks * 2 + end;
+                }
+                None => {
+                    buf.push_str(fence);
+                    i += ticks;
+                }
+            }
+            continue;
+        }
+
+        if let Some((label, url, len)) = link(rest) {
+            flush(&mut buf, &mut spans, current);
+
+            let underlined = current.add_modifier(Modifier::UNDERLINED);
+            if rest.starts_with('!') {
+                let alt = if label.is_empty() { url } else { label };
+                spans.push(Span::styled(format!("[image: {alt}]"), underlined));
+            } else if label.is_empty() {
+                spans.push(Span::styled(url.to_owned(), underlined));
+            } else {
+                spans.extend(inline(label, underlined, theme));
+            }
+
+            i += len;
+            continue;
+        }
+
+        if let Some(url) = autolink(rest) {
+            flush(&mut buf, &mut spans, current);
+            spans.push(Span::styled(
+                url.to_owned(),
+                current.add_modifier(Modifier::UNDERLINED),
+            ));
+            i += url.len() + 2;
+            continue;
+        }
+
+        let delim = ["**", "__", "~~", "*", "_"]
+            .into_iter()
+            .find(|d| rest.starts_with(d));
+        if let Some(delim) = delim {
+            let after = &rest[delim.len()..];
+            let next = after.chars().next();
+            let flag = match delim {
+                "**" | "__" => &mut bold,
+                "~~" => &mut strike,
+                _ => &mut italic,
+            };
+
+            // underscores inside words, e.g. snake_case, are literal
+            let intraword = delim.starts_with('_')
+                && (prev.is_some_and(char::is_alphanumeric)
+                    || next.is_some_and(char::is_alphanumeric));
+
+            let toggles = if *flag {
+                !delim.starts_with('_') || !next.is_some_and(char::is_alphanumeric)
+            } else {
+                !intraword && next.is_some_and(|c| !c.is_whitespace()) && after.contains(delim)
+            };
+
+            if toggles {
+                flush(&mut buf, &mut spans, current);
+                *flag = !*flag;
+            } else {
+                buf.push_str(delim);
+            }
+
+            i += delim.len();
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        buf.push(c);
+        i += c.len_utf8().max(1);
+    }
+
+    flush(&mut buf, &mut spans, style(bold, italic, strike));
+    spans
+}
+
+/// Parses a markdown link or image at the start of the text, e.g. `[label](url "title")`.
+///
+/// Returns the label, the URL, and the length of the link markdown.
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let start = if text.starts_with("![") {
+        2
+    } else if text.starts_with('[') {
+        1
+    } else {
+        return None;
+    };
+
+    let label_end = closing(&text[start..], '[', ']')? + start;
+    let rest = text[label_end + 1..].strip_prefix('(')?;
+    let target_end = closing(rest, '(', ')')?;
+
+    // the title, if any, follows the URL
+    let url = rest[..target_end].split_whitespace().next().unwrap_or("");
+    let len = label_end + 2 + target_end + 1;
+
+    Some((&text[start..label_end], url, len))
+}
+
+/// Gets the index of the bracket closing the already opened bracket, skipping nested pairs.
+fn closing(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+/// Parses an autolink at the start of the text, e.g. `<https://lemmy.ml>`, returning the URL.
+fn autolink(text: &str) -> Option<&str> {
+    let url = &text[1..text.find('>')?];
+
+    (text.starts_with('<')
+        && (url.starts_with("https://") || url.starts_with("http://"))
+        && !url.contains(char::is_whitespace))
+    .then_some(url)
+}
+
+/// Word of styled pieces, and the width and style of the whitespace before it.
+struct Word {
+    gap: usize,
+    space: Style,
+    pieces: Vec<(String, Style)>,
+}
+
+/// Wraps styled [Span]s into [Line]s of at most `width` display columns, including the prefix of
+/// the first line, and the prefix of the following lines.
+///
+/// Runs of spaces between words are kept, apart from where the lines wrap, and control characters
+/// are dropped. Words that do not fit move to the next line, and words wider than the lines, e.g.
+/// long URLs, are broken across lines.
+fn wrap(
+    spans: Vec<Span<'static>>,
+    width: usize,
+    first: &[Span<'static>],
+    rest: &[Span<'static>],
+) -> Vec<Line<'static>> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut word: Option<Word> = None;
+    let mut space = Style::default();
+    let mut gap = 0;
+
+    for span in &spans {
+        for c in span.content.chars() {
+            if c.is_whitespace() {
+                words.extend(word.take());
+                space = span.style;
+                gap += if c == '\t' { TAB_WIDTH } else { 1 };
+                continue;
+            }
+            if c.is_control() {
+                continue;
+            }
+
+            let word = word.get_or_insert_with(|| Word {
+                gap: std::mem::take(&mut gap),
+                space,
+                pieces: Vec::new(),
+            });
+            match word.pieces.last_mut() {
+                Some((text, style)) if *style == span.style => text.push(c),
+                _ => word.pieces.push((c.to_string(), span.style)),
+            }
+        }
+    }
+    words.extend(word);
+
+    let prefix_width = |prefix: &[Span]| prefix.iter().map(Span::width).sum::<usize>();
+    let mut lines = Vec::new();
+    let mut line: Vec<Span<'static>> = Vec::new();
+    let mut line_width = 0;
+    let mut available = width.saturating_sub(prefix_width(first)).max(1);
+
+    let mut new_line = |line: &mut Vec<Span<'static>>, line_width: &mut usize| {
+        let prefix = if lines.is_empty() { first } else { rest };
+        lines.push(Line::from(
+            prefix
+                .iter()
+                .cloned()
+                .chain(line.drain(..))
+                .collect::<Vec<_>>(),
+        ));
+        *line_width = 0;
+    };
+
+    for word in words {
+        let w: usize = word.pieces.iter().map(|(text, _)| text.width()).sum();
+
+        if line_width > 0 && line_width + word.gap + w > available {
+            new_line(&mut line, &mut line_width);
+            available = width.saturating_sub(prefix_width(rest)).max(1);
+        }
+
+        if line_width > 0 {
+            line.push(Span::styled(" ".repeat(word.gap), word.space));
+            line_width += word.gap;
+        }
+
+        for (text, style) in word.pieces {
+            for grapheme in text.graphemes(true) {
+                let gw = grapheme.width();
+                if line_width > 0 && line_width + gw > available {
+                    new_line(&mut line, &mut line_width);
+                    available = width.saturating_sub(prefix_width(rest)).max(1);
+                }
+
+                match line.last_mut() {
+                    Some(span) if span.style == style && line_width > 0 => {
+                        span.content.to_mut().push_str(grapheme)
+                    }
+                    _ => line.push(Span::styled(grapheme.to_owned(), style)),
+                }
+                line_width += gw;
+            }
+        }
+    }
+
+    if !line.is_empty() {
+        new_line(&mut line, &mut line_width);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the text of the [Line]s.
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    /// Gets the style of the first [Span] with the text.
+    fn style_of(lines: &[Line], content: &str) -> Style {
+        lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .find(|s| s.content == content)
+            .unwrap_or_else(|| panic!("no span {content:?}"))
+            .style
+    }
+
+    #[test]
+    fn test_render_inline() {
+        let lines = render(
+            "Some **bold**, *italic*, ~~old~~, and `code`, [a link](https://lemmy.ml \"title\"), \
+             <https://lemmy.ml/c/rust>, snake_case_name.",
+            200,
+            false,
+            &Theme::default(),
+        );
+
+        assert_eq!(
+            text(&lines),
+            [
+                "Some bold, italic, old, and code, a link, https://lemmy.ml/c/rust, snake_case_name.",
+                ""
+            ]
+        );
+        assert!(style_of(&lines, "bold")
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(style_of(&lines, "italic")
+            .add_modifier
+            .contains(Modifier::ITALIC));
+        assert!(style_of(&lines, "old")
+            .add_modifier
+            .contains(Modifier::CROSSED_OUT));
+        assert_eq!(style_of(&lines, "code"), Theme::default().code);
+        assert!(style_of(&lines, "a")
+            .add_modifier
+            .contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let markdown = "# Title\n\
+            ## Section ##\n\
+            \n\
+            A paragraph\n\
+            over two lines.\n\
+            > quoted\n\
+            > > nested\n\
+            \n\
+            - one\n\
+            - two\n  continued\n\
+            \x20 - nested\n\
+            \n\
+            3. three\n\
+            ---\n\
+            ```rust\n\
+            fn main() {\n\
+            \n\
+            \tprintln!(\"**not bold**\");\n\
+            }\n\
+            ```\n\
+            After the code.";
+
+        let lines = render(markdown, 40, false, &Theme::default());
+        assert_eq!(
+            text(&lines),
+            [
+                "Title",
+                "",
+                "Section",
+                "",
+                "A paragraph over two lines.",
+                "",
+                "▌ quoted",
+                "▌",
+                "▌ ▌ nested",
+                "",
+                "• one",
+                "• two continued",
+                "  • nested",
+                "3. three",
+                "",
+                "─".repeat(40).as_str(),
+                "",
+                "fn main() {",
+                "",
+                "    println!(\"**not bold**\");",
+                "}",
+                "",
+                "After the code.",
+                "",
+            ]
+        );
+
+        let title = style_of(&lines, "Title").add_modifier;
+        assert!(title.contains(Modifier::BOLD | Modifier::UNDERLINED));
+        assert_eq!(style_of(&lines, "quoted").fg, Theme::default().quote.fg);
+        assert_eq!(style_of(&lines, "}"), Theme::default().code);
+    }
+
+    #[test]
+    fn test_render_wrap() {
+        let lines = render(
+            "- a list item with a `long code span` that wraps, and https://lemmy.ml/a/very/long/url",
+            16,
+            false,
+            &Theme::default(),
+        );
+
+        assert_eq!(
+            text(&lines),
+            [
+                "• a list item",
+                "  with a long",
+                "  code span that",
+                "  wraps, and",
+                "  https://lemmy.",
+                "  ml/a/very/long",
+                "  /url",
+                ""
+            ]
+        );
+        // the space inside the code span keeps its style
+        assert_eq!(lines[1].spans.last().unwrap().style, Theme::default().code);
+    }
+
+    #[test]
+    fn test_render_whitespace() {
+        let theme = Theme::default();
+
+        // code keeps its indentation, and runs of spaces, with tabs expanded to the tab stops
+        let code = "```\nfn main() {\n    let  x = 1;\n\tlet y\t= 2;\u{7}\n}\n```";
+        assert_eq!(
+            text(&render(code, 40, false, &theme)),
+            [
+                "fn main() {",
+                "    let  x = 1;",
+                "    let y   = 2;",
+                "}",
+                ""
+            ]
+        );
+
+        // aligned lines, e.g. a table, keep their own rows, joined to no other line
+        let table = "Results:\nname      score\nferris    10\ncorro\t  7\nin all.";
+        assert_eq!(
+            text(&render(table, 40, false, &theme)),
+            [
+                "Results:",
+                "name      score",
+                "ferris    10",
+                "corro     7",
+                "in all.",
+                ""
+            ]
+        );
+
+        // prose with two spaces after its periods is still one paragraph, with the spaces kept
+        let prose = "First sentence.  Second sentence\nwraps here.  And ends.\nAll  in one.";
+        assert_eq!(
+            text(&render(prose, 80, false, &theme)),
+            [
+                "First sentence.  Second sentence wraps here.  And ends. All  in one.",
+                ""
+            ]
+        );
+
+        // two space runs at the same column align
+        let columns = "a.  one\nb.  two\nthen prose.";
+        assert_eq!(
+            text(&render(columns, 40, false, &theme)),
+            ["a.  one", "b.  two", "then prose.", ""]
+        );
+
+        // words that do not fit move to the next line, and longer words are broken
+        let url = "see https://lemmy.ml/post/123456789 for more";
+        assert_eq!(
+            text(&render(url, 20, false, &theme)),
+            ["see", "https://lemmy.ml/pos", "t/123456789 for more", ""]
+        );
+        assert_eq!(
+            text(&render("a  two  spaced line", 8, false, &theme)),
+            ["a  two", "spaced", "line", ""]
+        );
+    }
+
+    #[test]
+    fn test_render_spoilers() {
+        let markdown = "Before.\n\
+            ::: spoiler Ending\n\
+            They **win**.\n\
+            \n\
+            ::: spoiler\n\
+            Nested.\n\
+            :::\n\
+            :::\n\
+            After.";
+
+        assert!(has_spoiler(markdown));
+        assert_eq!(
+            text(&render(markdown, 40, false, &Theme::default())),
+            ["Before.", "", "▶ spoiler: Ending", "", "After.", ""]
+        );
+        assert_eq!(
+            text(&render(markdown, 40, true, &Theme::default())),
+            [
+                "Before.",
+                "",
+                "▼ spoiler: Ending",
+                "They win.",
+                "",
+                "▼ spoiler",
+                "Nested.",
+                "",
+                "After.",
+                ""
+            ]
+        );
+
+        // unterminated spoilers are plain text
+        let markdown = "::: spoiler Ending\nThey win.\n\nThe end.";
+        assert!(!has_spoiler(markdown));
+        assert_eq!(
+            text(&render(markdown, 40, false, &Theme::default())),
+            ["::: spoiler Ending They win.", "", "The end.", ""]
+        );
+
+        // the inner spoiler is closed, the outer one is not
+        let markdown = "::: spoiler outer\n::: spoiler inner\nhidden\n:::";
+        assert_eq!(
+            text(&render(markdown, 40, false, &Theme::default())),
+            ["::: spoiler outer", "", "▶ spoiler: inner", ""]
+        );
+        assert!(!has_spoiler(":::spoilers\n:::"));
+    }
+
+    #[test]
+    fn test_render_malformed() {
+        let markdown = [
+            "**unclosed bold",
+            "`unclosed code",
+            "[unclosed link](https://lemmy.ml",
+            "[label]",
+            "```\nunterminated code",
+            "> ",
+            "#",
+            "1.",
+            "- ",
+            "\\",
+            "*",
+            "<https://",
+            "![](https://lemmy.ml/image.png)",
+        ];
+
+        let lines: Vec<String> = markdown
+            .iter()
+            .flat_map(|m| text(&render(m, 30, false, &Theme::default())))
+            .filter(|l| !l.is_empty())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "**unclosed bold",
+                "`unclosed code",
+                "[unclosed",
+                "link](https://lemmy.ml",
+                "[label]",
+                "unterminated code",
+                "1.",
+                "\\",
+                "*",
+                "<https://",
+                "[image:",
+                "https://lemmy.ml/image.png]",
+            ]
+        );
+
+        // every line fits, at any width
+        for width in 0..12 {
+            for m in markdown {
+                for line in render(m, width, false, &Theme::default()) {
+                    assert!(line.width() <= width.max(1), "{m:?} at {width}");
+                }
+            }
+        }
+    }
+}
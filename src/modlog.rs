@@ -0,0 +1,402 @@
+//! Types and functions for the moderation log of a community.
+
+use std::time::SystemTime;
+
+use tui::widgets::TableState;
+
+use crate::{
+    client::Client,
+    comments::Comment,
+    community::Community,
+    endpoint::Endpoint,
+    posts::{Creator, Post},
+    utils::parse_timestamp,
+    Result,
+};
+
+/// Represents the removal, or restoration, of a post by a moderator.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModRemovePost {
+    pub id: u64,
+    pub reason: Option<String>,
+    pub removed: bool,
+    #[serde(rename = "when_", alias = "published")]
+    pub when: String,
+}
+
+/// Represents the locking, or unlocking, of a post by a moderator.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModLockPost {
+    pub id: u64,
+    pub locked: bool,
+    #[serde(rename = "when_", alias = "published")]
+    pub when: String,
+}
+
+/// Represents the removal, or restoration, of a comment by a moderator.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModRemoveComment {
+    pub id: u64,
+    pub reason: Option<String>,
+    pub removed: bool,
+    #[serde(rename = "when_", alias = "published")]
+    pub when: String,
+}
+
+/// Represents the ban, or unban, of a person from a community by a moderator.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModBanFromCommunity {
+    pub id: u64,
+    pub reason: Option<String>,
+    pub banned: bool,
+    pub expires: Option<String>,
+    #[serde(rename = "when_", alias = "published")]
+    pub when: String,
+}
+
+/// Represents a [ModRemovePost] entry, with the post it applies to.
+///
+/// The moderator is missing on instances hiding moderator names.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModRemovePostView {
+    pub mod_remove_post: ModRemovePost,
+    pub moderator: Option<Creator>,
+    pub post: Post,
+    pub community: Community,
+}
+
+/// Represents a [ModLockPost] entry, with the post it applies to.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModLockPostView {
+    pub mod_lock_post: ModLockPost,
+    pub moderator: Option<Creator>,
+    pub post: Post,
+    pub community: Community,
+}
+
+/// Represents a [ModRemoveComment] entry, with the comment, and its author.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModRemoveCommentView {
+    pub mod_remove_comment: ModRemoveComment,
+    pub moderator: Option<Creator>,
+    pub comment: Comment,
+    pub commenter: Creator,
+    pub post: Post,
+    pub community: Community,
+}
+
+/// Represents a [ModBanFromCommunity] entry, with the banned person.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ModBanFromCommunityView {
+    pub mod_ban_from_community: ModBanFromCommunity,
+    pub moderator: Option<Creator>,
+    pub community: Community,
+    pub banned_person: Creator,
+}
+
+/// Entry of the moderation log, tagged by the list it is returned in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModlogEntry {
+    /// Entry of `removed_posts`.
+    RemovePost(ModRemovePostView),
+    /// Entry of `locked_posts`.
+    LockPost(ModLockPostView),
+    /// Entry of `removed_comments`, boxed as it is the largest.
+    RemoveComment(Box<ModRemoveCommentView>),
+    /// Entry of `banned_from_community`.
+    BanFromCommunity(ModBanFromCommunityView),
+}
+
+impl ModlogEntry {
+    /// Gets the time of the action, as returned by the API.
+    pub fn when(&self) -> &str {
+        match self {
+            Self::RemovePost(v) => &v.mod_remove_post.when,
+            Self::LockPost(v) => &v.mod_lock_post.when,
+            Self::RemoveComment(v) => &v.mod_remove_comment.when,
+            Self::BanFromCommunity(v) => &v.mod_ban_from_community.when,
+        }
+    }
+
+    /// Gets the moderator who took the action, unless hidden by the instance.
+    pub fn moderator(&self) -> Option<&Creator> {
+        match self {
+            Self::RemovePost(v) => v.moderator.as_ref(),
+            Self::LockPost(v) => v.moderator.as_ref(),
+            Self::RemoveComment(v) => v.moderator.as_ref(),
+            Self::BanFromCommunity(v) => v.moderator.as_ref(),
+        }
+    }
+
+    /// Gets a short description of the action, e.g. `removed post`, or `unbanned`.
+    pub fn action(&self) -> &'static str {
+        match self {
+            Self::RemovePost(v) if v.mod_remove_post.removed => "removed post",
+            Self::RemovePost(_) => "restored post",
+            Self::LockPost(v) if v.mod_lock_post.locked => "locked post",
+            Self::LockPost(_) => "unlocked post",
+            Self::RemoveComment(v) if v.mod_remove_comment.removed => "removed comment",
+            Self::RemoveComment(_) => "restored comment",
+            Self::BanFromCommunity(v) if v.mod_ban_from_community.banned => "banned",
+            Self::BanFromCommunity(_) => "unbanned",
+        }
+    }
+
+    /// Gets the reason given by the moderator, if any.
+    ///
+    /// Locking a post takes no reason.
+    pub fn reason(&self) -> Option<&str> {
+        let reason = match self {
+            Self::RemovePost(v) => v.mod_remove_post.reason.as_deref(),
+            Self::LockPost(_) => None,
+            Self::RemoveComment(v) => v.mod_remove_comment.reason.as_deref(),
+            Self::BanFromCommunity(v) => v.mod_ban_from_community.reason.as_deref(),
+        };
+
+        reason.filter(|r| !r.trim().is_empty())
+    }
+
+    /// Gets the parsed time of the action, if valid.
+    fn time(&self) -> Option<SystemTime> {
+        parse_timestamp(self.when())
+    }
+}
+
+/// Represents a response to the [Modlog](Endpoint::Modlog) endpoint, as a single list of
+/// [ModlogEntry]s, newest first.
+///
+/// Only the removal, lock, and ban lists are deserialized, entry types unknown to `temi` are
+/// skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModlogResponse {
+    pub entries: Vec<ModlogEntry>,
+}
+
+impl<'de> serde::Deserialize<'de> for ModlogResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Default, serde::Deserialize)]
+        #[serde(default)]
+        struct Raw {
+            removed_posts: Vec<ModRemovePostView>,
+            locked_posts: Vec<ModLockPostView>,
+            removed_comments: Vec<ModRemoveCommentView>,
+            banned_from_community: Vec<ModBanFromCommunityView>,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        let mut entries: Vec<ModlogEntry> = raw
+            .removed_posts
+            .into_iter()
+            .map(ModlogEntry::RemovePost)
+            .chain(raw.locked_posts.into_iter().map(ModlogEntry::LockPost))
+            .chain(
+                raw.removed_comments
+                    .into_iter()
+                    .map(|v| ModlogEntry::RemoveComment(Box::new(v))),
+            )
+            .chain(
+                raw.banned_from_community
+                    .into_iter()
+                    .map(ModlogEntry::BanFromCommunity),
+            )
+            .collect();
+        // each list is sorted on its own, entries with invalid times go last
+        entries.sort_by_key(|e| std::cmp::Reverse(e.time()));
+
+        Ok(Self { entries })
+    }
+}
+
+impl Client {
+    /// Gets a page of the moderation log of a [Community] from the
+    /// [Modlog](Endpoint::Modlog) endpoint.
+    pub async fn get_modlog(&self, community_id: u64, page: u64) -> Result<ModlogResponse> {
+        let mut params = vec![
+            ("community_id", community_id.to_string()),
+            ("page", page.to_string()),
+        ];
+        if let Some(limit) = self.posts_query().limit {
+            params.push(("limit", limit.to_string()));
+        }
+        let url = self.url(Endpoint::Modlog, &params);
+
+        self.get_json(url.as_str()).await
+    }
+}
+
+/// Moderation log of a community, shown on the Modlog screen.
+pub struct Modlog {
+    /// Community the log is for.
+    pub community: Community,
+    /// Page of the log.
+    pub page: u64,
+    /// Entries of the page, `None` until downloaded.
+    pub entries: Option<Vec<ModlogEntry>>,
+    /// Selected row of the table.
+    pub state: TableState,
+}
+
+impl Modlog {
+    /// Creates a new [Modlog] for the [Community], on the first page, with nothing downloaded.
+    pub fn new(community: Community) -> Self {
+        Self {
+            community,
+            page: 1,
+            entries: None,
+            state: TableState::default(),
+        }
+    }
+
+    /// Sets the page, starting from 1.
+    pub fn set_page(&mut self, page: u64) {
+        self.page = page.max(1);
+    }
+
+    /// Sets the downloaded page of entries, selecting the first.
+    pub fn set_entries(&mut self, res: ModlogResponse) {
+        self.state = TableState::default();
+        self.state.select((!res.entries.is_empty()).then_some(0));
+        self.entries = Some(res.entries);
+    }
+
+    /// Gets the number of downloaded entries.
+    pub fn len(&self) -> usize {
+        self.entries.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Gets whether there are no downloaded entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Selects the next entry, if any, wrapping around.
+    pub fn next(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        let i = self.state.selected().map_or(0, |i| (i + 1) % self.len());
+        self.state.select(Some(i));
+    }
+
+    /// Selects the previous entry, if any, wrapping around.
+    pub fn previous(&mut self) {
+        let Some(last) = self.len().checked_sub(1) else {
+            return;
+        };
+
+        let i = self
+            .state
+            .selected()
+            .map_or(last, |i| i.checked_sub(1).unwrap_or(last));
+        self.state.select(Some(i));
+    }
+
+    /// Selects the first entry, if any.
+    pub fn first(&mut self) {
+        self.state.select((!self.is_empty()).then_some(0));
+    }
+
+    /// Selects the last entry, if any.
+    pub fn last(&mut self) {
+        self.state.select(self.len().checked_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Response};
+
+    use super::*;
+    use crate::client::mock::serve;
+
+    #[tokio::test]
+    async fn test_get_modlog() {
+        let (addr, _) = serve(|req| {
+            assert_eq!(req.uri().path(), "/api/v3/modlog");
+            assert_eq!(req.uri().query(), Some("community_id=2840&page=2"));
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.19.3/modlog.json"
+            )))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let res = client.get_modlog(2840, 2).await.unwrap();
+
+        // featured posts are not shown, newest first
+        let actions: Vec<_> = res.entries.iter().map(ModlogEntry::action).collect();
+        assert_eq!(
+            actions,
+            ["banned", "removed comment", "removed post", "locked post"]
+        );
+
+        let ModlogEntry::RemoveComment(v) = &res.entries[1] else {
+            panic!("expected a removed comment: {:?}", res.entries[1]);
+        };
+        assert_eq!(v.comment.id(), 4402212);
+        assert_eq!(v.commenter.handle(), "@spammer@spam.example");
+        assert_eq!(res.entries[1].moderator(), None);
+        assert_eq!(res.entries[1].reason(), Some("Rule 2: be civil"));
+
+        assert_eq!(res.entries[0].reason(), None);
+        assert_eq!(
+            res.entries[2].moderator().map(Creator::name),
+            Some("ferris")
+        );
+        assert_eq!(res.entries[3].when(), "2024-02-09T18:30:00.000000Z");
+    }
+
+    #[test]
+    fn test_modlog_unknown_entries() {
+        let res: ModlogResponse = serde_json::from_value(serde_json::json!({
+            "removed_posts": [{
+                "mod_remove_post": {"removed": false, "published": "2024-02-10T09:15:00Z"},
+                "post": {"id": 1, "name": "Restored"},
+            }],
+            "reacted_posts": [{"mod_react_post": {"emoji": 42}}],
+            "banned": "not a list",
+        }))
+        .unwrap();
+
+        assert_eq!(res.entries.len(), 1);
+        assert_eq!(res.entries[0].action(), "restored post");
+        assert_eq!(res.entries[0].when(), "2024-02-10T09:15:00Z");
+
+        let res: ModlogResponse = serde_json::from_str("{}").unwrap();
+        assert!(res.entries.is_empty());
+    }
+
+    #[test]
+    fn test_modlog_selection() {
+        let mut modlog = Modlog::new(Community::new());
+        modlog.next();
+        modlog.previous();
+        assert_eq!(modlog.state.selected(), None);
+        modlog.set_page(0);
+        assert_eq!(modlog.page, 1);
+
+        let entry = ModlogEntry::LockPost(ModLockPostView::default());
+        modlog.set_entries(ModlogResponse {
+            entries: vec![entry; 3],
+        });
+        assert_eq!(modlog.state.selected(), Some(0));
+        modlog.previous();
+        assert_eq!(modlog.state.selected(), Some(2));
+        modlog.next();
+        assert_eq!(modlog.state.selected(), Some(0));
+        modlog.last();
+        assert_eq!(modlog.state.selected(), Some(2));
+        modlog.first();
+        assert_eq!(modlog.state.selected(), Some(0));
+    }
+}
@@ -0,0 +1,89 @@
+//! Offline mode: reading and writing API responses in a local directory.
+//!
+//! Directory layout:
+//!
+//! - `posts.json`: first page of posts
+//! - `posts_<page>.json`: subsequent pages of posts
+//! - `comments_<post_id>.json`: all comments for a post
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    comments::{load_comments, CommentResponses},
+    posts::{load_posts, PostResponses},
+    utils::write_to_file,
+    Result,
+};
+
+/// Gets the path of the posts file for the page.
+pub fn posts_file(dir: &Path, page: u64) -> PathBuf {
+    if page <= 1 {
+        dir.join("posts.json")
+    } else {
+        dir.join(format!("posts_{page}.json"))
+    }
+}
+
+/// Gets the path of the comments file for the post.
+pub fn comments_file(dir: &Path, post_id: u64) -> PathBuf {
+    dir.join(format!("comments_{post_id}.json"))
+}
+
+/// Reads a page of [PostResponses] from the directory.
+pub fn read_posts(dir: &Path, page: u64) -> Result<PostResponses> {
+    load_posts(posts_file(dir, page).to_string_lossy().as_ref())
+}
+
+/// Reads the [CommentResponses] for a post from the directory.
+pub fn read_comments(dir: &Path, post_id: u64) -> Result<CommentResponses> {
+    load_comments(comments_file(dir, post_id).to_string_lossy().as_ref())
+}
+
+/// Writes a page of [PostResponses] to the directory.
+pub fn dump_posts(dir: &Path, page: u64, posts: &PostResponses) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let json = serde_json::to_vec(posts)?;
+    write_to_file(posts_file(dir, page).to_string_lossy().as_ref(), &json)
+}
+
+/// Writes the [CommentResponses] for a post to the directory.
+pub fn dump_comments(dir: &Path, post_id: u64, comments: &CommentResponses) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let json = serde_json::to_vec(comments)?;
+    write_to_file(
+        comments_file(dir, post_id).to_string_lossy().as_ref(),
+        &json,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump_read_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("temi-offline-{}", std::process::id()));
+
+        let posts = PostResponses {
+            posts: Vec::new(),
+            next_page: None,
+        };
+        let comments = CommentResponses::new(Vec::new());
+
+        dump_posts(&dir, 1, &posts).unwrap();
+        dump_posts(&dir, 2, &posts).unwrap();
+        dump_comments(&dir, 42, &comments).unwrap();
+
+        assert!(dir.join("posts.json").exists());
+        assert!(dir.join("posts_2.json").exists());
+
+        assert_eq!(read_posts(&dir, 1).unwrap(), posts);
+        assert_eq!(read_posts(&dir, 2).unwrap(), posts);
+        assert_eq!(read_comments(&dir, 42).unwrap(), comments);
+        assert!(read_posts(&dir, 3).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+//! Showing long text in an external pager, e.g. `less`.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+// the crossterm of the TUI, whose colors convert from [Color]
+use tui::crossterm::style::{self as term, Attribute, ContentStyle, StyledContent};
+use tui::prelude::*;
+
+use crate::Result;
+
+/// Pager used when `$PAGER` is unset, `-R` passes the styling through.
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// Gets the pager command, from `$PAGER`, or the [DEFAULT_PAGER].
+pub fn pager_command() -> String {
+    std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_owned())
+}
+
+/// Converts the styled [Line]s to text with ANSI escape sequences, one line each.
+pub fn to_ansi(lines: &[Line]) -> String {
+    let mut text = String::new();
+
+    for line in lines {
+        for span in &line.spans {
+            let style = content_style(line.style.patch(span.style));
+            text.push_str(&StyledContent::new(style, &span.content).to_string());
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Converts a [Style] to the crossterm [ContentStyle] printing it.
+fn content_style(style: Style) -> ContentStyle {
+    const ATTRIBUTES: [(Modifier, Attribute); 9] = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::SLOW_BLINK, Attribute::SlowBlink),
+        (Modifier::RAPID_BLINK, Attribute::RapidBlink),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::HIDDEN, Attribute::Hidden),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+    ];
+
+    let mut content = ContentStyle {
+        foreground_color: style.fg.map(term::Color::from),
+        background_color: style.bg.map(term::Color::from),
+        ..ContentStyle::default()
+    };
+    for (modifier, attribute) in ATTRIBUTES {
+        if style.add_modifier.contains(modifier) {
+            content.attributes.set(attribute);
+        }
+    }
+
+    content
+}
+
+/// Runs the pager `command`, split on whitespace, with the text on its standard input, and waits
+/// for it to exit.
+///
+/// The pager takes over the terminal, which must be restored first, see
+/// [TerminalGuard::suspend](crate::terminal::TerminalGuard::suspend).
+pub fn run_pager(command: &str, text: &str) -> Result<()> {
+    let args: Vec<&str> = command.split_whitespace().collect();
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty pager command"))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // quitting before reading everything closes the pipe, which is fine
+        match stdin.write_all(text.as_bytes()) {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => {
+                let _ = child.wait();
+                return Err(err.into());
+            }
+            _ => (),
+        }
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("exited with {status}")).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ansi() {
+        let lines = [
+            Line::from(vec![
+                Span::raw("plain "),
+                Span::styled("bold", Style::new().add_modifier(Modifier::BOLD)),
+            ]),
+            Line::from(Span::styled("red", Style::new().fg(Color::Red))),
+        ];
+
+        assert_eq!(
+            to_ansi(&lines),
+            "plain \x1b[1mbold\x1b[0m\n\x1b[38;5;1mred\x1b[39m\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_pager() {
+        run_pager("true", "text\n").unwrap();
+        assert!(run_pager("false", "text\n").is_err());
+        assert!(run_pager("temi-missing-pager", "text\n").is_err());
+        assert!(run_pager(" ", "text\n").is_err());
+    }
+}
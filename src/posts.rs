@@ -1,88 +1,74 @@
 //! Types and functions for posts.
 
-use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
-
+use hashbrown::{HashMap, HashSet};
+use image::DynamicImage;
 use tui::widgets::TableState;
 
-use crate::{counts::Counts, utils::write_to_file, Error, Result};
+use crate::{
+    client::Client,
+    community::{Community, SubscribedType},
+    counts::Counts,
+    endpoint::Endpoint,
+    utils::{normalize_url, url_domain},
+    Error, Result, Source,
+};
 
 mod creator;
+mod filter;
+mod image_cache;
 mod post;
-
-pub use creator::{Creator, Creators};
+mod query;
+
+#[allow(deprecated)]
+pub use creator::block_person;
+pub use creator::{BlockPerson, BlockPersonResponse, Creator, Creators, PersonView};
+pub use filter::{Filters, PostFilter};
+pub use image_cache::{
+    DiskCache, ImageCache, DEFAULT_IMAGE_CACHE_MB, DISK_CACHE_MAX_BYTES, DISK_CACHE_TTL,
+};
 pub use post::{Post, Posts};
-
-static DOWNLOAD_POSTS: AtomicBool = AtomicBool::new(false);
-
-/// Gets whether to download posts.
-pub fn download_posts() -> bool {
-    DOWNLOAD_POSTS.load(Ordering::Relaxed)
-}
-
-/// Sets whether to download posts.
-pub fn set_download_posts(val: bool) {
-    DOWNLOAD_POSTS.store(val, Ordering::SeqCst)
-}
+pub use query::{ListingType, PostsQuery, SortType};
 
 /// Download a response to the [PostList](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::get_posts to share connections between requests")]
 pub async fn dl_posts(url: &str) -> Result<PostResponses> {
-    let https = hyper_tls::HttpsConnector::new();
-    let client = hyper::Client::builder().build::<_, hyper::Body>(https);
-
-    let response = client.get(hyper::Uri::from_str(url)?).await?;
-
-    let body = hyper::body::to_bytes(response.into_body()).await?;
-
-    #[cfg(feature = "debug_endpoints")]
-    write_to_file("posts.json", &body)?;
-
-    serde_json::from_slice::<PostResponses>(&body).map_err(|err| err.into())
+    Client::new("").get_json(url).await
 }
 
-/// Gets whether the URL points to an image file.
+/// Gets whether the URL likely points to an image, from its file extension, or a pict-rs image
+/// path without one.
+///
+/// The actual image type is detected from the downloaded bytes, see [decode_image].
 pub fn is_image(url: &str) -> bool {
-    url.ends_with(".bmp")
-        || url.ends_with(".gif")
-        || url.ends_with(".jpg")
-        || url.ends_with(".jpeg")
-        || url.ends_with(".png")
-        || url.ends_with(".webp")
-}
-
-/// Gets the temporary file name for an image.
-// FIXME: stream the image without creating a temporary file.
-pub fn image_name(url: &str) -> Result<&str> {
-    let uri = url.parse::<http::Uri>()?;
-    let path = uri.path();
+    const EXTENSIONS: [&str; 6] = [".bmp", ".gif", ".jpg", ".jpeg", ".png", ".webp"];
 
-    if path.ends_with(".bmp") {
-        Ok("tmp.bmp")
-    } else if path.ends_with(".gif") {
-        Ok("tmp.gif")
-    } else if path.ends_with(".jpg") || path.ends_with(".jpeg") {
-        Ok("tmp.jpeg")
-    } else if path.ends_with(".png") {
-        Ok("tmp.png")
-    } else if path.ends_with(".webp") {
-        Ok("tmp.webp")
-    } else {
-        Err(Error::Image("unsupported image type".into()))
-    }
+    let path = url_path(url);
+    EXTENSIONS.iter().any(|ext| path.ends_with(ext)) || path.contains("/pictrs/image/")
 }
 
-/// Download a [Post](crate::posts::Post) image.
-pub async fn dl_image(url: &str, file_name: &str) -> Result<()> {
-    let https = hyper_tls::HttpsConnector::new();
-    let client = hyper::Client::builder().build::<_, hyper::Body>(https);
+/// Gets whether the URL points to a video file, from its file extension.
+pub fn is_video(url: &str) -> bool {
+    let path = url_path(url);
+    path.ends_with(".mp4") || path.ends_with(".webm")
+}
 
-    let response = client.get(hyper::Uri::from_str(url)?).await?;
+/// Gets the lowercase URL, without the query string or fragment.
+fn url_path(url: &str) -> String {
+    url.split(['?', '#']).next().unwrap_or(url).to_lowercase()
+}
 
-    let body = hyper::body::to_bytes(response.into_body()).await?;
+/// Download the bytes of a [Post](crate::posts::Post) image.
+#[deprecated(note = "use Client::get_image to share connections between requests")]
+pub async fn dl_image_bytes(url: &str) -> Result<Vec<u8>> {
+    Client::new("").get_image(url, None).await
+}
 
-    write_to_file(file_name, &body)?;
+/// Decodes a downloaded image, detecting its type from the magic bytes.
+pub fn decode_image(bytes: &[u8]) -> Result<DynamicImage> {
+    let format = image::guess_format(bytes)
+        .map_err(|_| Error::Image(Source::msg("unsupported image type")))?;
 
-    Ok(())
+    Ok(image::load_from_memory_with_format(bytes, format)?)
 }
 
 /// Load posts from a file instead of making a call to an endpoint.
@@ -101,24 +87,89 @@ pub fn load_posts(file_name: &str) -> Result<PostResponses> {
     serde_json::from_str::<PostResponses>(res.as_str()).map_err(|err| err.into())
 }
 
+/// Kind of media a [Post] links to, shown as a tag in the post list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum MediaKind {
+    /// Text-only post, without a link.
+    #[default]
+    Text,
+    /// Image link, or an uploaded image with only a thumbnail.
+    Image,
+    /// Video file, or a link with an embedded video.
+    Video,
+    /// Link to a page on the domain, without any `www.` prefix.
+    Link(String),
+}
+
 /// Represents a response from the [Post endpoint](crate::endpoint::Endpoint).
-#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct PostResponse {
     pub post: Post,
     pub creator: Creator,
+    pub community: Community,
     pub counts: Counts,
+    pub subscribed: SubscribedType,
+    /// Vote by the logged-in user: `1`, `-1`, or unset.
+    pub my_vote: Option<i64>,
+    /// Whether the logged-in user saved the post.
+    pub saved: bool,
+    /// Whether the logged-in user read the post.
+    pub read: bool,
+}
+
+impl PostResponse {
+    /// Gets whether the [Post] is NSFW, or in an NSFW [Community].
+    pub fn nsfw(&self) -> bool {
+        self.post.nsfw || self.community.nsfw
+    }
+
+    /// Gets whether the [Post] is pinned, in its community or on the local instance.
+    ///
+    /// Older Lemmy versions only report featured posts in the [Counts].
+    pub fn pinned(&self) -> bool {
+        self.post.featured() || self.counts.featured_community() || self.counts.featured_local()
+    }
+
+    /// Gets the [MediaKind] of the [Post], from its URLs alone.
+    pub fn media_kind(&self) -> MediaKind {
+        let url = self.post.url();
+
+        if !self.post.embed_video_url().is_empty() || is_video(url) {
+            MediaKind::Video
+        } else if is_image(url) || (url.is_empty() && !self.post.thumbnail_url().is_empty()) {
+            MediaKind::Image
+        } else if url.is_empty() {
+            MediaKind::Text
+        } else {
+            let domain = url_domain(url).unwrap_or_default().to_lowercase();
+            let domain = domain.strip_prefix("www.").unwrap_or(&domain);
+
+            MediaKind::Link(domain.to_owned())
+        }
+    }
 }
 
 /// Represents a list of responses to the [Post endpoint](crate::endpoint::Endpoint).
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct PostResponses {
     pub posts: Vec<PostResponse>,
+    /// Cursor for the next page, sent by Lemmy 0.19 and later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page: Option<String>,
 }
 
 /// Represents a table of responses to the [Post endpoint](crate::endpoint::Endpoint).
 pub struct PostResponseTable {
     pub items: Vec<PostResponse>,
     pub state: TableState,
+    /// Number of posts on the page dropped by the [PostFilter].
+    pub filtered: usize,
+    /// Reposts of the same link hidden by [dedupe_links](Self::dedupe_links), keyed by the ID
+    /// of the post kept in their place.
+    pub duplicates: HashMap<u64, Vec<PostResponse>>,
+    /// IDs of the kept posts with their duplicates shown inline.
+    pub expanded: HashSet<u64>,
 }
 
 impl PostResponseTable {
@@ -127,6 +178,9 @@ impl PostResponseTable {
         Self {
             items,
             state: TableState::default(),
+            filtered: 0,
+            duplicates: HashMap::new(),
+            expanded: HashSet::new(),
         }
     }
 
@@ -175,6 +229,16 @@ impl PostResponseTable {
         self.state.select(Some(i));
     }
 
+    /// Updates the [TableState] to select the first item, if any.
+    pub fn first(&mut self) {
+        self.state.select((!self.items.is_empty()).then_some(0));
+    }
+
+    /// Updates the [TableState] to select the last item, if any.
+    pub fn last(&mut self) {
+        self.state.select(self.items.len().checked_sub(1));
+    }
+
     /// Updates the [TableState] to select the previous item.
     pub fn previous(&mut self) {
         let len = self.items.len();
@@ -186,6 +250,190 @@ impl PostResponseTable {
             .unwrap_or(last);
         self.state.select(Some(i));
     }
+
+    /// Removes every [PostResponse] in the given [Community], e.g. after blocking the
+    /// [Community].
+    pub fn remove_community(&mut self, community_id: u64) {
+        self.items.retain(|p| p.community.id != community_id);
+        self.clamp_selection();
+    }
+
+    /// Removes the [PostResponse] for the post, e.g. after unsaving it from the saved posts.
+    pub fn remove_post(&mut self, post_id: u64) {
+        self.items.retain(|p| p.post.id() != post_id);
+        self.clamp_selection();
+    }
+
+    /// Keeps the selection on an item after removing items.
+    fn clamp_selection(&mut self) {
+        if let Some(i) = self.state.selected() {
+            if self.items.is_empty() {
+                self.deselect();
+            } else if i >= self.items.len() {
+                self.state.select(Some(self.items.len() - 1));
+            }
+        }
+    }
+
+    /// Sets the [SubscribedType] for every [PostResponse] in the given [Community].
+    pub fn set_subscribed(&mut self, community_id: u64, subscribed: SubscribedType) {
+        self.items
+            .iter_mut()
+            .filter(|p| p.community.id == community_id)
+            .for_each(|p| p.subscribed = subscribed);
+    }
+
+    /// Replaces the entry for the same post with the [PostResponse], if present.
+    pub fn update(&mut self, post: PostResponse) {
+        if let Some(p) = self
+            .items
+            .iter_mut()
+            .find(|p| p.post.id() == post.post.id())
+        {
+            *p = post;
+        }
+    }
+
+    /// Marks the post as deleted.
+    pub fn set_deleted(&mut self, post_id: u64) {
+        self.items
+            .iter_mut()
+            .filter(|p| p.post.id() == post_id)
+            .for_each(|p| p.post.deleted = true);
+    }
+
+    /// Hides reposts of the same link, keeping the highest scoring post of each group of posts
+    /// with the same normalized URL, see [normalize_url]. Ties keep the first post.
+    ///
+    /// Text posts are never deduplicated. The hidden posts are kept in
+    /// [duplicates](Self::duplicates), and shown again by
+    /// [toggle_duplicates](Self::toggle_duplicates).
+    pub fn dedupe_links(&mut self) {
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, p) in self.items.iter().enumerate() {
+            if let Some(url) = normalize_url(p.post.url()) {
+                groups.entry(url).or_default().push(i);
+            }
+        }
+
+        // index of the kept post, for each duplicate
+        let mut kept_by: HashMap<usize, usize> = HashMap::new();
+        for group in groups.into_values().filter(|g| g.len() > 1) {
+            let kept = group
+                .iter()
+                .copied()
+                .reduce(|kept, i| {
+                    if self.items[i].counts.score() > self.items[kept].counts.score() {
+                        i
+                    } else {
+                        kept
+                    }
+                })
+                .unwrap_or(group[0]);
+            kept_by.extend(group.into_iter().filter(|&i| i != kept).map(|i| (i, kept)));
+        }
+
+        if kept_by.is_empty() {
+            return;
+        }
+
+        let kept_ids: Vec<u64> = self.items.iter().map(|p| p.post.id()).collect();
+        let mut items = Vec::with_capacity(self.items.len() - kept_by.len());
+        for (i, p) in std::mem::take(&mut self.items).into_iter().enumerate() {
+            match kept_by.get(&i) {
+                Some(&kept) => self.duplicates.entry(kept_ids[kept]).or_default().push(p),
+                None => items.push(p),
+            }
+        }
+
+        self.items = items;
+        self.clamp_selection();
+    }
+
+    /// Gets the number of reposts hidden in place of the post.
+    pub fn duplicate_count(&self, post_id: u64) -> usize {
+        self.duplicates.get(&post_id).map_or(0, Vec::len)
+    }
+
+    /// Gets the ID of the post kept in place of the post, if the post is a duplicate.
+    pub fn duplicate_of(&self, post_id: u64) -> Option<u64> {
+        self.duplicates
+            .iter()
+            .find(|(_, posts)| posts.iter().any(|p| p.post.id() == post_id))
+            .map(|(&kept, _)| kept)
+    }
+
+    /// Shows the duplicates of the selected post inline, after it, or hides them again, selecting
+    /// the kept post.
+    ///
+    /// Returns whether the duplicates are shown, `None` if the post has none.
+    pub fn toggle_duplicates(&mut self) -> Option<bool> {
+        let post_id = self.current()?.post.id();
+        // a shown duplicate hides its group
+        let kept = self.duplicate_of(post_id).unwrap_or(post_id);
+        let duplicates = self.duplicates.get(&kept)?;
+        let i = self.items.iter().position(|p| p.post.id() == kept)?;
+
+        let shown = if self.expanded.remove(&kept) {
+            let ids: HashSet<u64> = duplicates.iter().map(|p| p.post.id()).collect();
+            self.items.retain(|p| !ids.contains(&p.post.id()));
+            false
+        } else {
+            self.expanded.insert(kept);
+            self.items.splice(i + 1..i + 1, duplicates.iter().cloned());
+            true
+        };
+        self.state.select(Some(i));
+
+        Some(shown)
+    }
+
+    /// Selects the [PostResponse], replacing the existing entry for the same post, or inserting
+    /// it at the top of the table.
+    pub fn select_or_insert(&mut self, post: PostResponse) {
+        let i = match self
+            .items
+            .iter()
+            .position(|p| p.post.id() == post.post.id())
+        {
+            Some(i) => {
+                self.items[i] = post;
+                i
+            }
+            None => {
+                self.items.insert(0, post);
+                0
+            }
+        };
+
+        self.state.select(Some(i));
+    }
+}
+
+/// Represents a response from the [GetPost](crate::endpoint::Endpoint) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct GetPostResponse {
+    pub post_view: PostResponse,
+    /// Other posts of the same URL, in other communities.
+    ///
+    /// Only the [GetPost](crate::endpoint::Endpoint) endpoint reports crossposts.
+    #[serde(default)]
+    pub cross_posts: Vec<PostResponse>,
+}
+
+impl Client {
+    /// Gets a single [PostResponse] from the [GetPost](crate::endpoint::Endpoint) endpoint.
+    pub async fn get_post(&self, post_id: u64) -> Result<PostResponse> {
+        self.get_post_view(post_id).await.map(|res| res.post_view)
+    }
+
+    /// Gets a single [PostResponse], with its crossposts, from the
+    /// [GetPost](crate::endpoint::Endpoint) endpoint.
+    pub async fn get_post_view(&self, post_id: u64) -> Result<GetPostResponse> {
+        let url = self.url(Endpoint::GetPost, &[("id", post_id.to_string())]);
+
+        self.get_json(url.as_str()).await
+    }
 }
 
 impl From<Vec<PostResponse>> for PostResponseTable {
@@ -211,3 +459,316 @@ impl AsMut<PostResponse> for PostResponse {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{Body, Response};
+
+    use super::*;
+    use crate::client::mock::serve;
+
+    fn roundtrip(json: &str) -> PostResponses {
+        let posts: PostResponses = serde_json::from_str(json).unwrap();
+        let reserialized = serde_json::to_string(&posts).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<PostResponses>(&reserialized).unwrap(),
+            posts
+        );
+
+        posts
+    }
+
+    #[test]
+    fn test_posts_lemmy_0_18() {
+        let posts = roundtrip(include_str!(
+            "../tests/fixtures/lemmy-0.18.5/post_list.json"
+        ));
+
+        assert_eq!(posts.posts.len(), 1);
+
+        let p = &posts.posts[0];
+        assert_eq!(p.post.id(), 4312019);
+        assert_eq!(p.creator.name(), "ferris");
+        assert_eq!(p.community.name, "rust");
+        assert_eq!(p.counts.comments(), 2);
+        assert_eq!(p.subscribed, SubscribedType::NotSubscribed);
+        assert_eq!(p.my_vote, None);
+
+        // featured flags are also reported in the counts
+        let mut p = p.clone();
+        assert!(!p.pinned());
+        p.counts.featured_local = Some(true);
+        assert!(p.pinned());
+    }
+
+    #[test]
+    fn test_posts_lemmy_0_19() {
+        let posts = roundtrip(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/post_list.json"
+        ));
+
+        assert_eq!(posts.posts.len(), 1);
+
+        let p = &posts.posts[0];
+        assert_eq!(p.post.id(), 10822441);
+        assert_eq!(p.creator.name(), "ferris");
+        assert_eq!(p.community.name, "rust");
+        assert_eq!(p.counts.comments(), 1);
+        assert_eq!(p.subscribed, SubscribedType::Subscribed);
+        assert_eq!(p.my_vote, Some(1));
+
+        assert_eq!(p.post.published(), "2024-02-08T14:55:31.407829Z");
+        assert!(!p.post.locked());
+        assert!(!p.pinned());
+        assert_eq!(p.post.updated(), "");
+        assert_eq!(p.post.language_id(), Some(37));
+        assert_eq!(p.post.embed_title(), "Announcing Rust 1.76.0 | Rust Blog");
+        assert_eq!(p.post.embed_video_url(), "");
+    }
+
+    #[test]
+    fn test_posts_logged_in_fields() {
+        let authenticated = roundtrip(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/post_list.json"
+        ));
+        let p = &authenticated.posts[0];
+        assert_eq!(p.my_vote, Some(1));
+        assert_eq!(p.subscribed, SubscribedType::Subscribed);
+        assert!(!p.saved);
+        assert!(!p.read);
+
+        // anonymous responses leave out the vote
+        let anonymous = roundtrip(include_str!(
+            "../tests/fixtures/lemmy-0.19.3/post_list_anonymous.json"
+        ));
+        let p = &anonymous.posts[0];
+        assert_eq!(p.my_vote, None);
+        assert_eq!(p.subscribed, SubscribedType::NotSubscribed);
+        assert!(!p.saved);
+        assert_eq!(p.post, authenticated.posts[0].post);
+
+        // older versions may omit the fields altogether
+        let p: PostResponse = serde_json::from_str(r#"{"post":{"id":1}}"#).unwrap();
+        assert_eq!((p.my_vote, p.saved, p.read), (None, false, false));
+    }
+
+    #[tokio::test]
+    async fn test_get_posts_table() {
+        let (addr, _) = serve(|_| {
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.19.3/post_list.json"
+            )))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let mut table = PostResponseTable::from(client.get_posts(1).await.unwrap());
+
+        table.next();
+
+        let current = table.current().unwrap();
+        assert_eq!(current.post.name(), "Announcing Rust 1.76.0");
+        assert_eq!(current.counts.comments(), 1);
+    }
+
+    #[test]
+    fn test_dedupe_links() {
+        let article = "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html";
+        let post = |id, url: &str, score| PostResponse {
+            post: Post {
+                id,
+                url: Some(url.into()).filter(|u: &String| !u.is_empty()),
+                ..Default::default()
+            },
+            counts: Counts {
+                score,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let ids = |table: &PostResponseTable| -> Vec<u64> {
+            table.items.iter().map(|p| p.post.id()).collect()
+        };
+
+        let mut table = PostResponseTable::new(vec![
+            post(1, article, 5),
+            post(2, "", 50),
+            post(3, &format!("{article}/?utm_source=lemmy"), 9),
+            post(4, "https://example.com/", 1),
+            post(
+                5,
+                &article.replace("blog.rust-lang.org", "Blog.Rust-Lang.org"),
+                9,
+            ),
+            post(6, "", 50),
+        ]);
+        table.dedupe_links();
+
+        // the highest score is kept, the first on ties, and text posts are never deduplicated
+        assert_eq!(ids(&table), [2, 3, 4, 6]);
+        assert_eq!(table.duplicate_count(3), 2);
+        assert_eq!(table.duplicate_count(4), 0);
+        assert_eq!(table.duplicate_of(5), Some(3));
+        assert_eq!(table.duplicate_of(3), None);
+
+        table.first();
+        assert_eq!(table.toggle_duplicates(), None);
+
+        table.next();
+        assert_eq!(table.toggle_duplicates(), Some(true));
+        assert_eq!(ids(&table), [2, 3, 1, 5, 4, 6]);
+        assert_eq!(table.state.selected(), Some(1));
+
+        // a shown duplicate hides its group, selecting the kept post
+        table.next();
+        table.next();
+        assert_eq!(table.toggle_duplicates(), Some(false));
+        assert_eq!(ids(&table), [2, 3, 4, 6]);
+        assert_eq!(table.current().map(|p| p.post.id()), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_get_post_view() {
+        let (addr, _) = serve(|req| {
+            assert_eq!(req.uri().query(), Some("id=10822441"));
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.19.3/post.json"
+            )))
+        });
+
+        let client = Client::new(format!("http://{addr}"));
+        let res = client.get_post_view(10822441).await.unwrap();
+
+        assert_eq!(res.post_view.post.id(), 10822441);
+        assert_eq!(res.cross_posts.len(), 1);
+        let cross_post = &res.cross_posts[0];
+        assert_eq!(cross_post.post.url(), res.post_view.post.url());
+        assert_eq!(cross_post.community.handle(), "!rust@programming.dev");
+        assert_eq!(cross_post.counts.comments(), 12);
+
+        // responses to saving a post have no crossposts
+        let res: GetPostResponse = serde_json::from_value(serde_json::json!({
+            "post_view": res.post_view,
+        }))
+        .unwrap();
+        assert!(res.cross_posts.is_empty());
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn test_dl_posts() {
+        let (addr, _) = serve(|_| {
+            Response::new(Body::from(include_str!(
+                "../tests/fixtures/lemmy-0.18.5/post_list.json"
+            )))
+        });
+
+        let posts = dl_posts(format!("http://{addr}/api/v3/post/list?page=1").as_str())
+            .await
+            .unwrap();
+
+        assert_eq!(posts.posts.len(), 1);
+        assert_eq!(posts.next_page, None);
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn test_dl_image_bytes() {
+        const IMAGE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+        let (addr, _) = serve(|_| Response::new(Body::from(IMAGE)));
+
+        let bytes = dl_image_bytes(format!("http://{addr}/pictrs/image/abc").as_str())
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, IMAGE);
+    }
+
+    #[test]
+    fn test_is_image() {
+        assert!(is_image("https://example.com/cat.png"));
+        assert!(is_image("https://example.com/cat.JPG?width=640"));
+        assert!(is_image("https://lemmy.ml/pictrs/image/0b3f1c2e-9a4d"));
+        assert!(!is_image(
+            "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html"
+        ));
+        assert!(!is_image("https://example.com/page?file=cat.png"));
+        assert!(!is_image(""));
+
+        assert!(is_video("https://example.com/clip.MP4?t=10"));
+        assert!(is_video("https://lemmy.ml/pictrs/image/clip.webm"));
+        assert!(!is_video("https://example.com/clip.gif"));
+    }
+
+    #[test]
+    fn test_media_kind() {
+        let kind = |url: Option<&str>, thumbnail: Option<&str>, video: Option<&str>| {
+            let mut p = PostResponse::default();
+            p.post.url = url.map(Into::into);
+            p.post.thumbnail_url = thumbnail.map(Into::into);
+            p.post.embed_video_url = video.map(Into::into);
+            p.media_kind()
+        };
+
+        assert_eq!(kind(None, None, None), MediaKind::Text);
+        assert_eq!(kind(Some(""), None, None), MediaKind::Text);
+        assert_eq!(
+            kind(Some("https://lemmy.ml/pictrs/image/a.png"), None, None),
+            MediaKind::Image
+        );
+        assert_eq!(
+            kind(None, Some("https://lemmy.ml/pictrs/image/a.png"), None),
+            MediaKind::Image
+        );
+        assert_eq!(
+            kind(Some("https://example.com/clip.webm"), None, None),
+            MediaKind::Video
+        );
+        assert_eq!(
+            kind(
+                Some("https://www.youtube.com/watch?v=x"),
+                Some("https://lemmy.ml/pictrs/image/t.jpg"),
+                Some("https://www.youtube.com/embed/x")
+            ),
+            MediaKind::Video
+        );
+        assert_eq!(
+            kind(
+                Some("https://WWW.Example.com:8443/article"),
+                Some("https://lemmy.ml/pictrs/image/t.jpg"),
+                None
+            ),
+            MediaKind::Link("example.com".into())
+        );
+        assert_eq!(
+            kind(
+                Some("https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html"),
+                None,
+                None
+            ),
+            MediaKind::Link("blog.rust-lang.org".into())
+        );
+    }
+
+    #[test]
+    fn test_decode_image() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(3, 2)
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .unwrap();
+
+        let image = decode_image(png.get_ref()).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+
+        // an HTML page, e.g. a login wall in front of the image
+        let err = decode_image(b"<!DOCTYPE html><html></html>").unwrap_err();
+        assert_eq!(err.to_string(), "image error: unsupported image type");
+
+        // the right magic bytes, but a truncated image
+        assert!(matches!(
+            decode_image(&png.get_ref()[..20]),
+            Err(Error::Image(_))
+        ));
+    }
+}
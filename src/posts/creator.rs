@@ -1,13 +1,20 @@
 use tui::widgets::ListState;
 
+use crate::{client::Client, endpoint::Endpoint, utils::actor_host, Result};
+
 /// Represents a post creator as returned in a posts API response.
+///
+/// Lemmy 0.19 dropped `admin` from person views, and later versions drop `banned`, so missing
+/// fields are defaulted.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Creator {
     pub id: u64,
     pub name: String,
     pub avatar: Option<String>,
     pub banned: bool,
     pub published: String,
+    #[serde(alias = "ap_id")]
     pub actor_id: String,
     pub local: bool,
     pub icon: Option<String>,
@@ -41,6 +48,16 @@ impl Creator {
         self.name.as_str()
     }
 
+    /// Gets the full [Creator] handle, `@name@host`, with the host from the actor ID.
+    ///
+    /// The host is left out if the actor ID has none.
+    pub fn handle(&self) -> String {
+        match actor_host(&self.actor_id) {
+            Some(host) => format!("@{}@{}", self.name, host.to_ascii_lowercase()),
+            None => format!("@{}", self.name),
+        }
+    }
+
     /// Gets the [Creator] published date.
     pub fn published(&self) -> &str {
         self.published.as_str()
@@ -57,6 +74,63 @@ impl Creator {
     }
 }
 
+impl Default for Creator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Represents the request body for the [BlockPerson](Endpoint::BlockPerson) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct BlockPerson<'a> {
+    pub person_id: u64,
+    pub block: bool,
+    pub auth: &'a str,
+}
+
+/// Represents a `person_view` field in a response to a person API request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PersonView {
+    pub person: Creator,
+}
+
+/// Represents a response to the [BlockPerson](Endpoint::BlockPerson) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BlockPersonResponse {
+    pub person_view: PersonView,
+    pub blocked: bool,
+}
+
+/// Block, or unblock, a [Creator] using the [BlockPerson](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::block_person to share connections between requests")]
+pub async fn block_person(
+    instance_url: &str,
+    jwt: &str,
+    person_id: u64,
+    block: bool,
+) -> Result<BlockPersonResponse> {
+    Client::new(instance_url)
+        .with_jwt(jwt)
+        .block_person(person_id, block)
+        .await
+}
+
+impl Client {
+    /// Block, or unblock, a [Creator] using the [BlockPerson](crate::endpoint::Endpoint)
+    /// endpoint.
+    pub async fn block_person(&self, person_id: u64, block: bool) -> Result<BlockPersonResponse> {
+        let auth = self.auth()?;
+        let body = BlockPerson {
+            person_id,
+            block,
+            auth: &auth,
+        };
+
+        self.post_json(self.url(Endpoint::BlockPerson, &[]).as_str(), &body)
+            .await
+    }
+}
+
 /// List of [Creator]s for displaying in the TUI.
 #[derive(Clone, Debug)]
 pub struct Creators {
@@ -145,3 +219,29 @@ impl AsMut<Creator> for Creator {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creator_handle() {
+        let handle = |actor_id: &str| {
+            Creator {
+                name: "admin".into(),
+                actor_id: actor_id.into(),
+                ..Default::default()
+            }
+            .handle()
+        };
+
+        assert_eq!(handle("https://lemmy.ml/u/admin"), "@admin@lemmy.ml");
+        assert_eq!(handle("https://Lemmy.World/u/admin"), "@admin@lemmy.world");
+        assert_eq!(
+            handle("http://localhost:8536/u/admin"),
+            "@admin@localhost:8536"
+        );
+        assert_eq!(handle("sh.itjust.works/u/admin"), "@admin@sh.itjust.works");
+        assert_eq!(handle(""), "@admin");
+    }
+}
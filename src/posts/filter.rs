@@ -0,0 +1,141 @@
+use regex::{Regex, RegexBuilder};
+
+use super::PostResponse;
+use crate::utils::url_domain;
+
+/// Post filters from the `[filters]` config section.
+///
+/// Matching posts are dropped from the post list.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filters {
+    /// Title keywords, matched as case-insensitive substrings, or as case-insensitive regexes
+    /// between slashes, e.g. `/\brust\b/`.
+    pub keywords: Vec<String>,
+    /// Link domains, including their subdomains, e.g. `example.com`.
+    pub domains: Vec<String>,
+    /// Community handles, e.g. `!memes@lemmy.ml`, or `!memes` on any instance.
+    pub communities: Vec<String>,
+    /// Creator handles, e.g. `@spammer@lemmy.ml`, or `@spammer` on any instance.
+    pub creators: Vec<String>,
+}
+
+/// Title keyword of a [PostFilter].
+#[derive(Clone, Debug)]
+enum Keyword {
+    /// Lowercase substring.
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Keyword {
+    fn matches(&self, title: &str) -> bool {
+        match self {
+            Self::Substring(s) => title.to_lowercase().contains(s.as_str()),
+            Self::Regex(re) => re.is_match(title),
+        }
+    }
+}
+
+/// Compiled [Filters], for matching [PostResponse]s.
+#[derive(Clone, Debug, Default)]
+pub struct PostFilter {
+    keywords: Vec<Keyword>,
+    domains: Vec<String>,
+    communities: Vec<String>,
+    creators: Vec<String>,
+}
+
+impl PostFilter {
+    /// Creates a [PostFilter] from the [Filters] config.
+    ///
+    /// Returns an error for an invalid keyword regex.
+    pub fn from_config(filters: &Filters) -> Result<Self, String> {
+        let keywords = filters
+            .keywords
+            .iter()
+            .filter(|k| !k.is_empty())
+            .map(
+                |k| match k.strip_prefix('/').and_then(|k| k.strip_suffix('/')) {
+                    Some(pattern) => RegexBuilder::new(pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .map(Keyword::Regex)
+                        .map_err(|err| format!("invalid filter keyword {k}: {err}")),
+                    None => Ok(Keyword::Substring(k.to_lowercase())),
+                },
+            )
+            .collect::<Result<_, _>>()?;
+
+        // handles are compared without their sigil
+        let handles = |handles: &[String], sigil| {
+            handles
+                .iter()
+                .map(|h| h.trim().trim_start_matches(sigil).to_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect()
+        };
+
+        Ok(Self {
+            keywords,
+            domains: filters
+                .domains
+                .iter()
+                .map(|d| d.trim().trim_start_matches('.').to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            communities: handles(&filters.communities, '!'),
+            creators: handles(&filters.creators, '@'),
+        })
+    }
+
+    /// Gets whether the [PostFilter] has no filters.
+    pub fn is_empty(&self) -> bool {
+        self.keywords.is_empty()
+            && self.domains.is_empty()
+            && self.communities.is_empty()
+            && self.creators.is_empty()
+    }
+
+    /// Gets whether the post matches any of the filters.
+    pub fn matches(&self, post: &PostResponse) -> bool {
+        let title = post.post.name();
+        if self.keywords.iter().any(|k| k.matches(title)) {
+            return true;
+        }
+
+        let domain = url_domain(post.post.url()).map(str::to_lowercase);
+        if let Some(domain) = domain {
+            let in_domain = |d: &String| {
+                domain == *d
+                    || domain
+                        .strip_suffix(d.as_str())
+                        .is_some_and(|sub| sub.ends_with('.'))
+            };
+            if self.domains.iter().any(in_domain) {
+                return true;
+            }
+        }
+
+        let community = post.community.handle().to_lowercase();
+        let creator = post.creator.handle().to_lowercase();
+
+        self.communities
+            .iter()
+            .any(|c| handle_matches(&community[1..], c))
+            || self
+                .creators
+                .iter()
+                .any(|c| handle_matches(&creator[1..], c))
+    }
+}
+
+/// Gets whether the `name@host` handle matches the filter, which matches on any instance if it
+/// has no host.
+fn handle_matches(handle: &str, filter: &str) -> bool {
+    if filter.contains('@') {
+        handle == filter
+    } else {
+        handle.split('@').next() == Some(filter)
+    }
+}
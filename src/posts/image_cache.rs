@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use hashbrown::HashMap;
+use image::DynamicImage;
+
+use crate::Result;
+
+/// Default size of the [ImageCache], in megabytes.
+pub const DEFAULT_IMAGE_CACHE_MB: usize = 64;
+/// Age after which images in the [DiskCache] are downloaded again.
+pub const DISK_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Maximum total size of the [DiskCache], the oldest images are removed beyond it.
+pub const DISK_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Least-recently used cache of decoded images, keyed by URL.
+///
+/// Bounded by the approximate size of the decoded pixels, images larger than the whole cache
+/// are not cached.
+#[derive(Clone, Debug)]
+pub struct ImageCache {
+    capacity: usize,
+    size: usize,
+    entries: HashMap<String, Arc<DynamicImage>>,
+    // least-recently used first
+    order: VecDeque<String>,
+}
+
+impl ImageCache {
+    /// Creates a new [ImageCache] holding up to `capacity` bytes of decoded images.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Gets the capacity, in bytes.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gets the approximate size of the cached images, in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gets the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Gets whether the [ImageCache] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the image for the URL, marking it as most-recently used.
+    pub fn get(&mut self, url: &str) -> Option<Arc<DynamicImage>> {
+        let image = self.entries.get(url).cloned()?;
+        self.touch(url);
+
+        Some(image)
+    }
+
+    /// Inserts the image for the URL, evicting the least-recently used images over capacity.
+    pub fn insert(&mut self, url: &str, image: Arc<DynamicImage>) {
+        let size = image_size(&image);
+        if size > self.capacity {
+            return;
+        }
+
+        match self.entries.insert(url.to_owned(), image) {
+            Some(old) => {
+                self.size -= image_size(&old);
+                self.touch(url);
+            }
+            None => self.order.push_back(url.to_owned()),
+        }
+        self.size += size;
+
+        while self.size > self.capacity {
+            match self.order.pop_front() {
+                Some(url) => {
+                    if let Some(old) = self.entries.remove(&url) {
+                        self.size -= image_size(&old);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn touch(&mut self, url: &str) {
+        if let Some(i) = self.order.iter().position(|u| u == url) {
+            if let Some(url) = self.order.remove(i) {
+                self.order.push_back(url);
+            }
+        }
+    }
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_IMAGE_CACHE_MB * 1024 * 1024)
+    }
+}
+
+/// Gets the approximate size of a decoded image, in bytes.
+fn image_size(image: &DynamicImage) -> usize {
+    image.as_bytes().len()
+}
+
+/// Cache of downloaded image files, keyed by a hash of the URL.
+///
+/// Images older than the TTL are downloaded again, the response headers are not consulted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Creates a new [DiskCache] in the directory, created on the first insert.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: DISK_CACHE_TTL,
+            max_bytes: DISK_CACHE_MAX_BYTES,
+        }
+    }
+
+    /// Builder function that sets the age after which images are downloaded again.
+    pub const fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Builder function that sets the maximum total size of the cached images.
+    pub const fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Gets the default cache directory, `$XDG_CACHE_HOME/temi/images`.
+    ///
+    /// Falls back to `$HOME/.cache` if `XDG_CACHE_HOME` is unset.
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("temi").join("images"))
+    }
+
+    /// Gets the cache directory.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    /// Gets the cached image file for the URL, unless missing, or older than the TTL.
+    pub fn get(&self, url: &str) -> Option<Vec<u8>> {
+        let file = self.file(url);
+        let age = std::fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())?;
+
+        if age > self.ttl {
+            return None;
+        }
+
+        std::fs::read(file).ok()
+    }
+
+    /// Saves the image file for the URL, removing the oldest images over the maximum size.
+    pub fn insert(&self, url: &str, bytes: &[u8]) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        // written in full before replacing the entry, so a concurrent read never sees part of it
+        let file = self.file(url);
+        let tmp = file.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &file)?;
+
+        self.prune()
+    }
+
+    /// Removes expired images, and the oldest images over the maximum size.
+    fn prune(&self) -> Result<()> {
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+
+        // newest first
+        files.sort_by_key(|(modified, ..)| std::cmp::Reverse(*modified));
+
+        let mut total = 0;
+        for (modified, len, path) in files {
+            total += len;
+
+            let expired = modified.elapsed().is_ok_and(|age| age > self.ttl);
+            if expired || total > self.max_bytes {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn file(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}", fnv1a(url.as_bytes())))
+    }
+}
+
+/// Gets the 64-bit FNV-1a hash of the bytes, stable across runs and Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32) -> Arc<DynamicImage> {
+        Arc::new(DynamicImage::new_rgb8(width, height))
+    }
+
+    #[test]
+    fn test_image_cache_eviction() {
+        // room for two 10x10 RGB images
+        let mut cache = ImageCache::new(600);
+
+        cache.insert("a", image(10, 10));
+        cache.insert("b", image(10, 10));
+        assert!(cache.get("a").is_some());
+
+        cache.insert("c", image(10, 10));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size(), 600);
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some() && cache.get("c").is_some());
+
+        // replacing an entry updates the size
+        cache.insert("a", image(5, 5));
+        assert_eq!(cache.size(), 375);
+
+        // larger than the whole cache
+        cache.insert("d", image(20, 20));
+        assert!(cache.get("d").is_none());
+        assert_eq!(cache.len(), 2);
+
+        let mut cache = ImageCache::new(0);
+        cache.insert("a", image(1, 1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_disk_cache() {
+        let dir = std::env::temp_dir().join(format!("temi-images-{}", std::process::id()));
+        let cache = DiskCache::new(&dir).with_max_bytes(10);
+
+        assert_eq!(cache.get("https://lemmy.ml/pictrs/image/a"), None);
+
+        cache
+            .insert("https://lemmy.ml/pictrs/image/a", b"aaaa")
+            .unwrap();
+        cache
+            .insert("https://lemmy.ml/pictrs/image/b", b"bbbb")
+            .unwrap();
+        assert_eq!(
+            cache.get("https://lemmy.ml/pictrs/image/a").as_deref(),
+            Some(&b"aaaa"[..])
+        );
+
+        // the oldest files are removed over the maximum size
+        std::thread::sleep(Duration::from_millis(20));
+        cache
+            .insert("https://lemmy.ml/pictrs/image/c", b"cccc")
+            .unwrap();
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 2);
+        assert!(cache.get("https://lemmy.ml/pictrs/image/c").is_some());
+
+        // expired files are misses
+        let expired = cache.clone().with_ttl(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(expired.get("https://lemmy.ml/pictrs/image/c"), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,7 +1,10 @@
 use tui::widgets::ListState;
 
 /// Represents a post as returned in a posts API response.
+///
+/// Missing fields are defaulted, to tolerate differences between Lemmy versions.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Post {
     pub id: u64,
     pub name: String,
@@ -11,6 +14,15 @@ pub struct Post {
     pub thumbnail_url: Option<String>,
     pub ap_id: String,
     pub body: Option<String>,
+    pub published: String,
+    pub locked: Option<bool>,
+    pub featured_community: Option<bool>,
+    pub featured_local: Option<bool>,
+    pub updated: Option<String>,
+    pub language_id: Option<u64>,
+    pub embed_title: Option<String>,
+    pub embed_description: Option<String>,
+    pub embed_video_url: Option<String>,
     pub sorted: Option<bool>,
 }
 
@@ -26,6 +38,15 @@ impl Post {
             thumbnail_url: None,
             ap_id: String::new(),
             body: None,
+            published: String::new(),
+            locked: None,
+            featured_community: None,
+            featured_local: None,
+            updated: None,
+            language_id: None,
+            embed_title: None,
+            embed_description: None,
+            embed_video_url: None,
             sorted: None,
         }
     }
@@ -60,6 +81,46 @@ impl Post {
         self.ap_id.as_str()
     }
 
+    /// Gets the [Post] published date.
+    pub fn published(&self) -> &str {
+        self.published.as_str()
+    }
+
+    /// Gets whether the [Post] is locked, disallowing new comments.
+    pub fn locked(&self) -> bool {
+        self.locked.unwrap_or(false)
+    }
+
+    /// Gets whether the [Post] is featured in its community, or on the local instance.
+    pub fn featured(&self) -> bool {
+        self.featured_community.unwrap_or(false) || self.featured_local.unwrap_or(false)
+    }
+
+    /// Gets the [Post] updated date, empty if the post was never edited.
+    pub fn updated(&self) -> &str {
+        self.updated.as_deref().unwrap_or("")
+    }
+
+    /// Gets the [Post] language ID, if any.
+    pub const fn language_id(&self) -> Option<u64> {
+        self.language_id
+    }
+
+    /// Gets the [Post] embed title, from the linked page.
+    pub fn embed_title(&self) -> &str {
+        self.embed_title.as_deref().unwrap_or("")
+    }
+
+    /// Gets the [Post] embed description, from the linked page.
+    pub fn embed_description(&self) -> &str {
+        self.embed_description.as_deref().unwrap_or("")
+    }
+
+    /// Gets the [Post] embed video URL, from the linked page.
+    pub fn embed_video_url(&self) -> &str {
+        self.embed_video_url.as_deref().unwrap_or("")
+    }
+
     pub fn sorted(&self) -> bool {
         matches!(self.sorted, Some(true))
     }
@@ -73,6 +134,12 @@ impl Post {
     }
 }
 
+impl Default for Post {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// List of [Post]s for displaying in the TUI.
 #[derive(Clone, Debug)]
 pub struct Posts {
@@ -0,0 +1,219 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// Sort order for the [PostList](crate::endpoint::Endpoint) endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SortType {
+    Active,
+    #[default]
+    Hot,
+    New,
+    Old,
+    TopHour,
+    TopSixHour,
+    TopTwelveHour,
+    TopDay,
+    TopWeek,
+    TopMonth,
+    TopYear,
+    TopAll,
+    MostComments,
+    NewComments,
+}
+
+impl SortType {
+    /// Gets all [SortType] variants.
+    pub const fn all() -> [Self; 14] {
+        [
+            Self::Active,
+            Self::Hot,
+            Self::New,
+            Self::Old,
+            Self::TopHour,
+            Self::TopSixHour,
+            Self::TopTwelveHour,
+            Self::TopDay,
+            Self::TopWeek,
+            Self::TopMonth,
+            Self::TopYear,
+            Self::TopAll,
+            Self::MostComments,
+            Self::NewComments,
+        ]
+    }
+
+    /// Gets the API name of the [SortType].
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "Active",
+            Self::Hot => "Hot",
+            Self::New => "New",
+            Self::Old => "Old",
+            Self::TopHour => "TopHour",
+            Self::TopSixHour => "TopSixHour",
+            Self::TopTwelveHour => "TopTwelveHour",
+            Self::TopDay => "TopDay",
+            Self::TopWeek => "TopWeek",
+            Self::TopMonth => "TopMonth",
+            Self::TopYear => "TopYear",
+            Self::TopAll => "TopAll",
+            Self::MostComments => "MostComments",
+            Self::NewComments => "NewComments",
+        }
+    }
+}
+
+impl fmt::Display for SortType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SortType {
+    type Err = Error;
+
+    /// Parses a [SortType], ignoring case and `_`/`-` separators, e.g. `top_day` or `TopDay`.
+    fn from_str(s: &str) -> Result<Self> {
+        let name = normalize(s);
+
+        Self::all()
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(&name))
+            .ok_or_else(|| Error::Config(format!("invalid sort type: {s}")))
+    }
+}
+
+impl TryFrom<String> for SortType {
+    type Error = Error;
+
+    fn try_from(val: String) -> Result<Self> {
+        val.parse()
+    }
+}
+
+impl From<SortType> for String {
+    fn from(val: SortType) -> Self {
+        val.as_str().into()
+    }
+}
+
+/// Listing type for the [PostList](crate::endpoint::Endpoint) endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ListingType {
+    #[default]
+    All,
+    Local,
+    Subscribed,
+}
+
+impl ListingType {
+    /// Gets all [ListingType] variants.
+    pub const fn all() -> [Self; 3] {
+        [Self::All, Self::Local, Self::Subscribed]
+    }
+
+    /// Gets the API name of the [ListingType].
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::Local => "Local",
+            Self::Subscribed => "Subscribed",
+        }
+    }
+}
+
+impl fmt::Display for ListingType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ListingType {
+    type Err = Error;
+
+    /// Parses a [ListingType], ignoring case.
+    fn from_str(s: &str) -> Result<Self> {
+        let name = normalize(s);
+
+        Self::all()
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(&name))
+            .ok_or_else(|| Error::Config(format!("invalid listing type: {s}")))
+    }
+}
+
+impl TryFrom<String> for ListingType {
+    type Error = Error;
+
+    fn try_from(val: String) -> Result<Self> {
+        val.parse()
+    }
+}
+
+impl From<ListingType> for String {
+    fn from(val: ListingType) -> Self {
+        val.as_str().into()
+    }
+}
+
+/// Query parameters for the [PostList](crate::endpoint::Endpoint) endpoint.
+///
+/// Unset parameters are omitted from the request, and use the instance defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PostsQuery {
+    pub sort: Option<SortType>,
+    pub listing_type: Option<ListingType>,
+    pub community_name: Option<String>,
+    pub limit: Option<u64>,
+}
+
+impl PostsQuery {
+    /// Creates a new [PostsQuery].
+    pub const fn new() -> Self {
+        Self {
+            sort: None,
+            listing_type: None,
+            community_name: None,
+            limit: None,
+        }
+    }
+
+    /// Gets the URL query parameters for the page, e.g. `page=2&sort=New`.
+    pub fn params(&self, page: u64) -> Vec<(&'static str, String)> {
+        self.with_params(("page", page.to_string()))
+    }
+
+    /// Gets the URL query parameters for a page cursor returned by Lemmy 0.19 and later, e.g.
+    /// `page_cursor=Pa123&sort=New`.
+    pub fn cursor_params(&self, cursor: &str) -> Vec<(&'static str, String)> {
+        self.with_params(("page_cursor", cursor.to_owned()))
+    }
+
+    /// Gets the `page` parameter followed by the set parameters.
+    fn with_params(&self, page: (&'static str, String)) -> Vec<(&'static str, String)> {
+        let mut params = vec![page];
+
+        if let Some(sort) = self.sort {
+            params.push(("sort", sort.to_string()));
+        }
+        if let Some(listing_type) = self.listing_type {
+            params.push(("type_", listing_type.to_string()));
+        }
+        if let Some(community_name) = self.community_name.as_ref() {
+            params.push(("community_name", community_name.clone()));
+        }
+        if let Some(limit) = self.limit {
+            params.push(("limit", limit.to_string()));
+        }
+
+        params
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.chars().filter(|c| !matches!(c, '_' | '-')).collect()
+}
@@ -0,0 +1,130 @@
+//! Types and functions for reporting posts and comments to moderators.
+
+use crate::{client::Client, endpoint::Endpoint, Result};
+
+/// Represents the request body for the [CreatePostReport](Endpoint::CreatePostReport) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CreatePostReport<'a> {
+    pub post_id: u64,
+    pub reason: &'a str,
+    pub auth: &'a str,
+}
+
+/// Represents the request body for the [CreateCommentReport](Endpoint::CreateCommentReport)
+/// endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct CreateCommentReport<'a> {
+    pub comment_id: u64,
+    pub reason: &'a str,
+    pub auth: &'a str,
+}
+
+/// Represents a report for a [Post](crate::posts::Post).
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PostReport {
+    pub id: u64,
+    pub creator_id: u64,
+    pub post_id: u64,
+    pub reason: String,
+    pub resolved: bool,
+    pub published: String,
+}
+
+/// Represents a report for a [Comment](crate::comments::Comment).
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CommentReport {
+    pub id: u64,
+    pub creator_id: u64,
+    pub comment_id: u64,
+    pub reason: String,
+    pub resolved: bool,
+    pub published: String,
+}
+
+/// Represents a `post_report_view` field in a response to a report API request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PostReportView {
+    pub post_report: PostReport,
+}
+
+/// Represents a `comment_report_view` field in a response to a report API request.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CommentReportView {
+    pub comment_report: CommentReport,
+}
+
+/// Represents a response to the [CreatePostReport](Endpoint::CreatePostReport) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct PostReportResponse {
+    pub post_report_view: PostReportView,
+}
+
+/// Represents a response to the [CreateCommentReport](Endpoint::CreateCommentReport) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct CommentReportResponse {
+    pub comment_report_view: CommentReportView,
+}
+
+/// Report a [Post](crate::posts::Post) to moderators using the
+/// [CreatePostReport](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::report_post to share connections between requests")]
+pub async fn report_post(
+    instance_url: &str,
+    jwt: &str,
+    post_id: u64,
+    reason: &str,
+) -> Result<PostReportResponse> {
+    Client::new(instance_url)
+        .with_jwt(jwt)
+        .report_post(post_id, reason)
+        .await
+}
+
+/// Report a [Comment](crate::comments::Comment) to moderators using the
+/// [CreateCommentReport](crate::endpoint::Endpoint) endpoint.
+#[deprecated(note = "use Client::report_comment to share connections between requests")]
+pub async fn report_comment(
+    instance_url: &str,
+    jwt: &str,
+    comment_id: u64,
+    reason: &str,
+) -> Result<CommentReportResponse> {
+    Client::new(instance_url)
+        .with_jwt(jwt)
+        .report_comment(comment_id, reason)
+        .await
+}
+
+impl Client {
+    /// Report a [Post](crate::posts::Post) to moderators using the
+    /// [CreatePostReport](crate::endpoint::Endpoint) endpoint.
+    pub async fn report_post(&self, post_id: u64, reason: &str) -> Result<PostReportResponse> {
+        let auth = self.auth()?;
+        let body = CreatePostReport {
+            post_id,
+            reason,
+            auth: &auth,
+        };
+
+        self.post_json(self.url(Endpoint::CreatePostReport, &[]).as_str(), &body)
+            .await
+    }
+
+    /// Report a [Comment](crate::comments::Comment) to moderators using the
+    /// [CreateCommentReport](crate::endpoint::Endpoint) endpoint.
+    pub async fn report_comment(
+        &self,
+        comment_id: u64,
+        reason: &str,
+    ) -> Result<CommentReportResponse> {
+        let auth = self.auth()?;
+        let body = CreateCommentReport {
+            comment_id,
+            reason,
+            auth: &auth,
+        };
+
+        self.post_json(self.url(Endpoint::CreateCommentReport, &[]).as_str(), &body)
+            .await
+    }
+}
@@ -0,0 +1,392 @@
+//! Types and functions for the posts and comments saved by the logged-in user.
+
+use crate::{
+    client::Client,
+    comments::{CommentResponse, CommentResponseTable, CommentResponses, GetCommentResponse},
+    endpoint::Endpoint,
+    posts::{GetPostResponse, PostResponse, PostResponseTable, PostResponses},
+    Result,
+};
+
+/// Represents the request body for the [SavePost](Endpoint::SavePost) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SavePost<'a> {
+    pub post_id: u64,
+    pub save: bool,
+    pub auth: &'a str,
+}
+
+/// Represents the request body for the [SaveComment](Endpoint::SaveComment) endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SaveComment<'a> {
+    pub comment_id: u64,
+    pub save: bool,
+    pub auth: &'a str,
+}
+
+impl Client {
+    /// Save, or unsave, a [Post](crate::posts::Post) using the
+    /// [SavePost](crate::endpoint::Endpoint) endpoint.
+    pub async fn save_post(&self, post_id: u64, save: bool) -> Result<PostResponse> {
+        let auth = self.auth()?;
+        let body = SavePost {
+            post_id,
+            save,
+            auth: &auth,
+        };
+
+        self.put_json::<_, GetPostResponse>(self.url(Endpoint::SavePost, &[]).as_str(), &body)
+            .await
+            .map(|res| res.post_view)
+    }
+
+    /// Save, or unsave, a [Comment](crate::comments::Comment) using the
+    /// [SaveComment](crate::endpoint::Endpoint) endpoint.
+    pub async fn save_comment(&self, comment_id: u64, save: bool) -> Result<CommentResponse> {
+        let auth = self.auth()?;
+        let body = SaveComment {
+            comment_id,
+            save,
+            auth: &auth,
+        };
+
+        self.put_json::<_, GetCommentResponse>(self.url(Endpoint::SaveComment, &[]).as_str(), &body)
+            .await
+            .map(|res| res.comment_view)
+    }
+
+    /// Gets a page of the posts saved by the logged-in user, newest first, from the
+    /// [PostList](Endpoint::PostList) endpoint.
+    ///
+    /// Saved posts are listed from every community, regardless of the configured listing type.
+    pub async fn get_saved_posts(&self, page: u64) -> Result<PostResponses> {
+        let mut params = saved_params(page);
+        if let Some(limit) = self.posts_query().limit {
+            params.push(("limit", limit.to_string()));
+        }
+        let url = self.url(Endpoint::PostList, &params);
+
+        self.get_json(url.as_str()).await
+    }
+
+    /// Gets a page of the comments saved by the logged-in user, newest first, from the
+    /// [CommentList](Endpoint::CommentList) endpoint.
+    pub async fn get_saved_comments(&self, page: u64) -> Result<CommentResponses> {
+        let url = self.url(Endpoint::CommentList, &saved_params(page));
+
+        self.get_json(url.as_str()).await
+    }
+}
+
+/// Gets the query parameters for a page of saved posts, or comments.
+fn saved_params(page: u64) -> Vec<(&'static str, String)> {
+    vec![
+        ("saved_only", "true".into()),
+        ("page", page.to_string()),
+        ("sort", "New".into()),
+        ("type_", "All".into()),
+    ]
+}
+
+/// Tab of the Saved screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SavedTab {
+    #[default]
+    Posts,
+    Comments,
+}
+
+impl SavedTab {
+    /// Gets the other [SavedTab].
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Posts => Self::Comments,
+            Self::Comments => Self::Posts,
+        }
+    }
+
+    /// Gets the title of the [SavedTab].
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Posts => "Posts",
+            Self::Comments => "Comments",
+        }
+    }
+}
+
+/// Saved posts and comments of the logged-in user, shown on the Saved screen.
+///
+/// Each tab is paged separately, and downloaded the first time it is shown.
+pub struct Saved {
+    /// Tab shown on the Saved screen.
+    pub tab: SavedTab,
+    /// Saved posts, `None` until downloaded.
+    pub posts: Option<PostResponseTable>,
+    /// Saved comments, `None` until downloaded.
+    pub comments: Option<CommentResponseTable>,
+    /// Page of saved posts.
+    pub posts_page: u64,
+    /// Page of saved comments.
+    pub comments_page: u64,
+}
+
+impl Saved {
+    /// Creates a new [Saved], on the first page of each tab, with nothing downloaded.
+    pub fn new() -> Self {
+        Self {
+            tab: SavedTab::Posts,
+            posts: None,
+            comments: None,
+            posts_page: 1,
+            comments_page: 1,
+        }
+    }
+
+    /// Gets whether the current tab is downloaded.
+    pub fn loaded(&self) -> bool {
+        match self.tab {
+            SavedTab::Posts => self.posts.is_some(),
+            SavedTab::Comments => self.comments.is_some(),
+        }
+    }
+
+    /// Switches to the other tab.
+    pub fn next_tab(&mut self) {
+        self.tab = self.tab.next();
+    }
+
+    /// Gets the page of the current tab.
+    pub fn page(&self) -> u64 {
+        match self.tab {
+            SavedTab::Posts => self.posts_page,
+            SavedTab::Comments => self.comments_page,
+        }
+    }
+
+    /// Sets the page of the current tab, starting from 1.
+    pub fn set_page(&mut self, page: u64) {
+        match self.tab {
+            SavedTab::Posts => self.posts_page = page.max(1),
+            SavedTab::Comments => self.comments_page = page.max(1),
+        }
+    }
+
+    /// Sets the downloaded page of saved posts.
+    pub fn set_posts(&mut self, posts: PostResponses) {
+        self.posts = Some(posts.into());
+    }
+
+    /// Sets the downloaded page of saved comments.
+    pub fn set_comments(&mut self, comments: CommentResponses) {
+        self.comments = Some(comments.into());
+    }
+
+    /// Gets the number of rows in the current tab.
+    pub fn len(&self) -> usize {
+        match self.tab {
+            SavedTab::Posts => self.posts.as_ref().map_or(0, |p| p.items.len()),
+            SavedTab::Comments => self.comments.as_ref().map_or(0, |c| c.items.len()),
+        }
+    }
+
+    /// Gets whether the current tab has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the selected row of the current tab, if any.
+    pub fn selected(&self) -> Option<usize> {
+        match self.tab {
+            SavedTab::Posts => self.posts.as_ref()?.state.selected(),
+            SavedTab::Comments => self.comments.as_ref()?.state.selected(),
+        }
+    }
+
+    /// Gets the offset of the first row drawn in the current tab.
+    pub fn offset(&self) -> usize {
+        match self.tab {
+            SavedTab::Posts => self.posts.as_ref().map_or(0, |p| p.state.offset()),
+            SavedTab::Comments => self.comments.as_ref().map_or(0, |c| c.state.offset()),
+        }
+    }
+
+    /// Selects the row of the current tab.
+    pub fn select(&mut self, i: Option<usize>) {
+        match self.tab {
+            SavedTab::Posts => self.posts.iter_mut().for_each(|p| p.state.select(i)),
+            SavedTab::Comments => self.comments.iter_mut().for_each(|c| c.state.select(i)),
+        }
+    }
+
+    /// Gets the selected saved post, on the posts tab.
+    pub fn current_post(&self) -> Option<&PostResponse> {
+        match self.tab {
+            SavedTab::Posts => self.posts.as_ref()?.current(),
+            SavedTab::Comments => None,
+        }
+    }
+
+    /// Gets the selected saved comment, on the comments tab.
+    pub fn current_comment(&self) -> Option<&CommentResponse> {
+        match self.tab {
+            SavedTab::Posts => None,
+            SavedTab::Comments => self.comments.as_ref()?.current(),
+        }
+    }
+
+    /// Selects the next row of the current tab, if any.
+    pub fn next(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        match self.tab {
+            SavedTab::Posts => self.posts.iter_mut().for_each(|p| p.next()),
+            SavedTab::Comments => self.comments.iter_mut().for_each(|c| c.next()),
+        }
+    }
+
+    /// Selects the previous row of the current tab, if any.
+    pub fn previous(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+
+        match self.tab {
+            SavedTab::Posts => self.posts.iter_mut().for_each(|p| p.previous()),
+            SavedTab::Comments => self.comments.iter_mut().for_each(|c| c.previous()),
+        }
+    }
+
+    /// Selects the first row of the current tab, if any.
+    pub fn first(&mut self) {
+        self.select((!self.is_empty()).then_some(0));
+    }
+
+    /// Selects the last row of the current tab, if any.
+    pub fn last(&mut self) {
+        self.select(self.len().checked_sub(1));
+    }
+
+    /// Removes the post from the saved posts, e.g. once unsaved.
+    pub fn remove_post(&mut self, post_id: u64) {
+        if let Some(posts) = self.posts.as_mut() {
+            posts.remove_post(post_id);
+        }
+    }
+
+    /// Removes the comment from the saved comments, e.g. once unsaved.
+    pub fn remove_comment(&mut self, comment_id: u64) {
+        if let Some(comments) = self.comments.as_mut() {
+            comments.remove_comment(comment_id);
+        }
+    }
+}
+
+impl Default for Saved {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comments::Comment, posts::Post};
+
+    fn saved() -> Saved {
+        let mut saved = Saved::new();
+        let posts = (1..=3)
+            .map(|id| PostResponse {
+                post: Post {
+                    id,
+                    ..Default::default()
+                },
+                saved: true,
+                ..Default::default()
+            })
+            .collect();
+        saved.set_posts(PostResponses {
+            posts,
+            next_page: None,
+        });
+
+        let comments = (1..=2)
+            .map(|id| CommentResponse {
+                comment: Comment {
+                    id,
+                    path: format!("0.{id}"),
+                    ..Default::default()
+                },
+                saved: true,
+                ..Default::default()
+            })
+            .collect();
+        saved.set_comments(CommentResponses::new(comments));
+
+        saved
+    }
+
+    #[test]
+    fn test_saved_tabs() {
+        let mut saved = Saved::new();
+        assert!(!saved.loaded());
+        saved.next();
+        assert_eq!(saved.selected(), None);
+
+        let mut saved = self::saved();
+        assert!(saved.loaded());
+        assert_eq!(saved.len(), 3);
+
+        saved.last();
+        assert_eq!(saved.current_post().map(|p| p.post.id()), Some(3));
+        assert!(saved.current_comment().is_none());
+        saved.set_page(0);
+        assert_eq!(saved.page(), 1);
+        saved.set_page(2);
+
+        // each tab keeps its own selection, and page
+        saved.next_tab();
+        assert_eq!(saved.tab, SavedTab::Comments);
+        assert_eq!(saved.page(), 1);
+        assert_eq!(saved.selected(), None);
+        saved.next();
+        assert_eq!(saved.current_comment().map(|c| c.comment.id()), Some(1));
+
+        saved.next_tab();
+        assert_eq!(saved.page(), 2);
+        assert_eq!(saved.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_saved_remove() {
+        let mut saved = self::saved();
+
+        saved.last();
+        saved.remove_post(3);
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved.selected(), Some(1));
+
+        saved.next_tab();
+        saved.first();
+        saved.remove_comment(1);
+        saved.remove_comment(2);
+        assert!(saved.is_empty());
+        assert_eq!(saved.selected(), None);
+
+        // nothing left to select
+        saved.next();
+        saved.previous();
+        assert_eq!(saved.selected(), None);
+    }
+
+    #[test]
+    fn test_saved_params() {
+        let url = Client::new("https://lemmy.ml").url(Endpoint::CommentList, &saved_params(2));
+
+        assert_eq!(
+            url,
+            "https://lemmy.ml/api/v3/comment/list?saved_only=true&page=2&sort=New&type_=All"
+        );
+    }
+}
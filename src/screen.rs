@@ -1,16 +1,43 @@
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, SystemTime};
 
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use tui::{
     prelude::*,
     style::Style,
-    widgets::{Block, Borders},
+    widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarState, Wrap},
 };
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app::{App, Command},
+    community::{Community, SubscribedType},
+    config::Config,
+    keymap::KeyAction,
+    posts::{Creator, MediaKind, PostResponse},
+    theme::Theme,
+};
+
+mod diagnostics;
+mod discover;
+mod error_log;
+mod image;
+mod modlog;
 mod post;
 mod posts_list;
+mod saved;
+#[cfg(test)]
+pub(crate) mod snapshot;
 
+pub use self::image::*;
+pub use diagnostics::*;
+pub use discover::*;
+pub use error_log::*;
+pub use modlog::*;
 pub use post::*;
 pub use posts_list::*;
+pub use saved::*;
 
 /// Convenience definition for purple color style.
 pub const PURPLE: Color = Color::Rgb(0x80, 0x00, 0x80);
@@ -20,18 +47,22 @@ pub const GRAY: Color = Color::Rgb(0xbf, 0xba, 0xbe);
 pub const DARK_GRAY: Color = Color::Rgb(0x3f, 0x3a, 0x3e);
 /// Convenience definition for white smoke color style.
 pub const WHITE_SMOKE: Color = Color::Rgb(0xf5, 0xf5, 0xf5);
+/// Convenience definition for sky blue color style.
+pub const SKY_BLUE: Color = Color::Rgb(0x87, 0xce, 0xeb);
+/// Convenience definition for gold color style.
+pub const GOLD: Color = Color::Rgb(0xff, 0xd7, 0x00);
+/// Convenience definition for crimson color style.
+pub const CRIMSON: Color = Color::Rgb(0xdc, 0x14, 0x3c);
 
-static CURRENT_SCREEN: AtomicU16 = AtomicU16::new(0);
-
-/// Gets the currently set [Screen] to display.
-pub fn current_screen() -> Screen {
-    CURRENT_SCREEN.load(Ordering::Relaxed).into()
-}
-
-/// Sets the [Screen] to display.
-pub fn set_current_screen(screen: Screen) {
-    CURRENT_SCREEN.store(screen.into(), Ordering::SeqCst);
-}
+/// Default colors of the comment indent guides, cycled by level.
+pub const INDENT_COLORS: [Color; 6] = [
+    SKY_BLUE,
+    Color::Green,
+    GOLD,
+    Color::Magenta,
+    CRIMSON,
+    Color::Cyan,
+];
 
 /// Representation of the selected screen.
 #[repr(u16)]
@@ -43,6 +74,10 @@ pub enum Screen {
     Image,
     CommentList,
     Comment,
+    ErrorLog,
+    Saved,
+    Modlog,
+    Discover,
 }
 
 impl From<u16> for Screen {
@@ -53,6 +88,10 @@ impl From<u16> for Screen {
             2 => Self::Image,
             3 => Self::CommentList,
             4 => Self::Comment,
+            5 => Self::ErrorLog,
+            6 => Self::Saved,
+            7 => Self::Modlog,
+            8 => Self::Discover,
             _ => Self::PostList,
         }
     }
@@ -76,46 +115,788 @@ impl From<Screen> for u16 {
     }
 }
 
+/// Minimum terminal width for drawing a [Screen].
+pub const MIN_WIDTH: u16 = 20;
+/// Minimum terminal height for drawing a [Screen].
+pub const MIN_HEIGHT: u16 = 8;
+
+/// Renders the current [Screen] of the [App].
+///
+/// Terminals smaller than [MIN_WIDTH] x [MIN_HEIGHT] show a placeholder instead.
+pub fn render_screen(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        let placeholder = Paragraph::new("terminal too small")
+            .style(app.theme.body)
+            .wrap(Wrap { trim: true });
+
+        f.render_widget(placeholder, area);
+        return;
+    }
+
+    match app.screen() {
+        Screen::Post => render_post_screen(f, app),
+        Screen::PostList => render_posts_screen(f, app),
+        Screen::ErrorLog => render_error_log_screen(f, app),
+        Screen::Image => render_image_screen(f, app),
+        Screen::Saved => render_saved_screen(f, app),
+        Screen::Modlog => render_modlog_screen(f, app),
+        Screen::Discover => render_discover_screen(f, app),
+        _ => (),
+    }
+
+    // over every screen, and its overlays
+    draw_diagnostics(f, app);
+}
+
+/// Handles a key press on the current [Screen] of the [App].
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_key(event: KeyEvent, app: &mut App) -> Option<Command> {
+    // Ctrl+c quits from anywhere, without confirmation, and raw mode delivers Ctrl+z as a key,
+    // rather than stopping the process
+    if is_interrupt(&event) {
+        return Some(Command::Quit);
+    } else if is_suspend(&event) {
+        return Some(Command::Suspend);
+    } else if app.diagnostics.visible {
+        return handle_diagnostics_key(app, event);
+    } else if app.keymap.action_for(app.screen(), &event) == Some(KeyAction::ToggleDiagnostics) {
+        app.diagnostics.toggle();
+        return None;
+    }
+
+    match app.screen() {
+        Screen::Post => handle_post_input(event, app),
+        Screen::PostList => handle_posts_input(event, app),
+        Screen::ErrorLog => handle_error_log_input(event, app),
+        Screen::Image => handle_image_input(event, app),
+        Screen::Saved => handle_saved_input(event, app),
+        Screen::Modlog => handle_modlog_input(event, app),
+        Screen::Discover => handle_discover_input(event, app),
+        // screens without input handling can still be backed out of
+        _ if event.code == KeyCode::Esc => app.pop_screen(),
+        _ => None,
+    }
+}
+
+/// Handles a mouse event on the current [Screen] of the [App], for the screen `area`.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_mouse(event: MouseEvent, app: &mut App, area: Rect) -> Option<Command> {
+    // nothing to click on the placeholder
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        return None;
+    } else if app.diagnostics.visible {
+        return handle_diagnostics_mouse(app, event);
+    }
+
+    match app.screen() {
+        Screen::Post => handle_post_mouse(event, app, area),
+        Screen::PostList => handle_posts_mouse(event, app, area),
+        Screen::ErrorLog => handle_error_log_mouse(event, app),
+        Screen::Saved => handle_saved_mouse(event, app, area),
+        Screen::Modlog => handle_modlog_mouse(event, app),
+        Screen::Discover => handle_discover_mouse(event, app),
+        _ => None,
+    }
+}
+
+/// Gets whether the key press is `Ctrl+c`, which always quits, regardless of the
+/// [KeyMap](crate::keymap::KeyMap).
+pub fn is_interrupt(event: &KeyEvent) -> bool {
+    event.code == KeyCode::Char('c') && event.modifiers == KeyModifiers::CONTROL
+}
+
+/// Gets whether the key press is `Ctrl+z`, which always suspends to the shell, regardless of the
+/// [KeyMap](crate::keymap::KeyMap).
+pub fn is_suspend(event: &KeyEvent) -> bool {
+    event.code == KeyCode::Char('z') && event.modifiers == KeyModifiers::CONTROL
+}
+
+/// Gets the handle of a [Creator], without the domain for local users if configured.
+pub fn creator_handle(creator: &Creator, config: &Config) -> String {
+    if config.omit_local_domain && creator.local {
+        format!("@{}", creator.name())
+    } else {
+        creator.handle()
+    }
+}
+
+/// Gets the handle of a [Community], without the domain for local communities if configured.
+pub fn community_handle(community: &Community, config: &Config) -> String {
+    if config.omit_local_domain && community.local {
+        format!("!{}", community.name)
+    } else {
+        community.handle()
+    }
+}
+
+/// Gets the `★` marker, and the `[pinned]` and `[locked]` tag [Span]s of a post, if any.
+pub fn post_tags(p: &PostResponse, theme: &Theme) -> Vec<Span<'static>> {
+    let mut tags = Vec::new();
+
+    if p.saved {
+        tags.push(saved_span(theme));
+    }
+    if p.pinned() {
+        tags.push(Span::styled("[pinned]", theme.pinned));
+    }
+    if p.post.locked() {
+        tags.push(Span::styled("[locked]", theme.locked));
+    }
+
+    tags
+}
+
+/// Gets the `★` marker [Span] of a post, or comment, saved by the logged-in user.
+pub fn saved_span(theme: &Theme) -> Span<'static> {
+    Span::styled("★", theme.saved)
+}
+
+/// Gets the one column [Span] marking the vote of the logged-in user: `▲` for upvotes, `▼` for
+/// downvotes, and a blank otherwise, so scores stay aligned.
+pub fn vote_span(my_vote: Option<i64>, theme: &Theme) -> Span<'static> {
+    match my_vote {
+        Some(vote) if vote > 0 => Span::styled("▲", theme.upvoted),
+        Some(vote) if vote < 0 => Span::styled("▼", theme.downvoted),
+        _ => Span::raw(" "),
+    }
+}
+
+/// Gets the tag [Span] of a subscribed, or pending, community, shown after its handle: `✓`, or
+/// `(pending)`.
+///
+/// Unsubscribed communities, and anonymous responses, have no tag.
+pub fn subscribed_tag(subscribed: SubscribedType, theme: &Theme) -> Option<Span<'static>> {
+    match subscribed {
+        SubscribedType::Subscribed => Some(Span::styled(
+            "✓",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        SubscribedType::Pending => Some(Span::styled("(pending)", theme.pending)),
+        SubscribedType::NotSubscribed => None,
+    }
+}
+
+/// Gets the tag for the [MediaKind] of a post, e.g. `[img]`, or `[link example.com]`.
+pub fn media_tag(kind: &MediaKind, theme: &Theme) -> Span<'static> {
+    let (tag, style) = match kind {
+        MediaKind::Text => ("[text]".to_owned(), theme.link),
+        MediaKind::Image => ("[img]".to_owned(), theme.media),
+        MediaKind::Video => ("[vid]".to_owned(), theme.media),
+        MediaKind::Link(domain) if domain.is_empty() => ("[link]".to_owned(), theme.link),
+        MediaKind::Link(domain) => (format!("[link {domain}]"), theme.link),
+    };
+
+    Span::styled(tag, style)
+}
+
+/// Gets the title shown in place of a hidden NSFW post title, e.g. `[NSFW] !rust@lemmy.ml`.
+pub fn nsfw_title(p: &PostResponse, config: &Config) -> String {
+    format!("[NSFW] {}", community_handle(&p.community, config))
+}
+
 /// Creates a title block
-pub fn title_block(title: &str) -> Block {
+pub fn title_block<'a>(title: &'a str, theme: &Theme) -> Block<'a> {
     Block::default()
         .borders(Borders::ALL)
-        .style(header_style())
+        .style(theme.header)
         .title(Span::styled(
             title,
             Style::default().add_modifier(Modifier::BOLD),
         ))
 }
 
-/// Gets the default style for displaying a [Screen].
-pub fn screen_style() -> Style {
-    Style::default().fg(PURPLE).bg(Color::Black)
+/// Gets a [Rect] centered in `area`, with a percentage of the `area` width, and a fixed height.
+pub fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width.saturating_mul(percent_x) / 100;
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
 }
 
-/// Gets the default style for displaying a header.
-pub fn header_style() -> Style {
-    Style::default().fg(WHITE_SMOKE).bg(Color::Black)
+/// Gets the status bar text, e.g. `lemmy.ml | page 2 | sort Hot | listing All | 20 posts`,
+/// followed by the current message, if any.
+///
+/// The sort and listing type are `default` when left to the instance.
+pub fn status_text(app: &App) -> String {
+    let host = app.account_label();
+    let host = host.as_deref().unwrap_or(app.instance_label());
+    let sort = app.config.sort.map_or("default", |s| s.as_str());
+    let listing = app.config.listing_type.map_or("default", |l| l.as_str());
+
+    let posts = match app.posts.items().len() {
+        1 => "1 post".to_owned(),
+        n => format!("{n} posts"),
+    };
+    let mut text = format!(
+        "{host} | {} | sort {sort} | listing {listing} | {posts}",
+        app.paging_label()
+    );
+
+    if app.live.is_some() {
+        text.push_str(" | live");
+    }
+    push_message(app, &mut text);
+
+    text
 }
 
-/// Gets the default style for displaying a list.
-pub fn list_style() -> Style {
-    Style::default().fg(Color::Green).bg(Color::Black)
+/// Appends the current message, and any [PendingKeys], to the status bar `text`.
+fn push_message(app: &App, text: &mut String) {
+    let message = app.hud_message();
+    if !message.is_empty() {
+        text.push_str(&format!(" | {message}"));
+    }
+    if !app.pending.is_empty() {
+        text.push_str(&format!(" | {}", app.pending.typed()));
+    }
 }
 
-/// Gets the default style for displaying the body of a table.
-pub fn body_style() -> Style {
-    Style::default().fg(Color::Green).bg(Color::Black)
+/// Draws the one-line status bar in `area`, see [status_text].
+pub fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    let text = truncate_width(&status_text(app), area.width as usize);
+
+    f.render_widget(Paragraph::new(text).style(app.theme.header), area);
 }
 
-/// Gets the default style for highlighting.
-pub fn highlight_style() -> Style {
-    Style::default().fg(PURPLE).bg(GRAY)
+/// Draws the [Scrollbar] in `area`, unless the area is empty, e.g. in a very small terminal.
+pub fn draw_scrollbar(f: &mut Frame, scrollbar: Scrollbar, area: Rect, state: &mut ScrollbarState) {
+    if !area.is_empty() {
+        f.render_stateful_widget(scrollbar, area, state);
+    }
+}
+
+/// Draws the yes/no confirmation overlay, if the [App] has an action waiting for confirmation.
+pub fn draw_confirm(f: &mut Frame, app: &App) {
+    if let Some(confirm) = app.confirm.as_ref() {
+        let area = centered_rect(60, 5, f.area());
+
+        let popup = Paragraph::new(vec![
+            Line::from(confirm.prompt.as_str()),
+            Line::from(""),
+            Line::from("(y) yes | (n) no"),
+        ])
+        .style(app.theme.header)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(title_block("Confirm", &app.theme));
+
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+}
+
+/// Handles a key press while the confirmation overlay is displayed.
+///
+/// Returns [Command::Quit] once quitting is confirmed.
+pub fn handle_confirm_key(app: &mut App, code: KeyCode) -> Option<Command> {
+    match code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => return app.accept_confirm(),
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.reject_confirm(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Draws the error banner, if the [App] has an error to display.
+pub fn draw_error(f: &mut Frame, app: &App) {
+    if app.has_error() {
+        let area = centered_rect(80, 5, f.area());
+        let title = format!("Error: {}", app.error_kind());
+
+        let banner = Paragraph::new(vec![
+            Line::from(app.error()),
+            Line::from(""),
+            Line::from("(r) retry | (l) error log | (Esc) dismiss"),
+        ])
+        .style(app.theme.error)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(title_block(title.as_str(), &app.theme));
+
+        f.render_widget(Clear, area);
+        f.render_widget(banner, area);
+    }
+}
+
+/// Handles a key press while the error banner is displayed.
+///
+/// Returns the `retry` [Command] if the user requested to retry the failed request.
+pub fn handle_error_key(app: &mut App, code: KeyCode, retry: Command) -> Option<Command> {
+    match code {
+        KeyCode::Char('r') => {
+            app.clear_error();
+            return Some(retry);
+        }
+        KeyCode::Char('l') => app.open_error_log(),
+        KeyCode::Esc | KeyCode::Enter => app.clear_error(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Single-line text typed by the user, for the text input overlay.
+///
+/// Numeric inputs reject anything but digits, flagging the rejected key to show inline.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    numeric: bool,
+    rejected: bool,
+}
+
+impl TextInput {
+    /// Creates a new empty [TextInput].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new empty [TextInput] accepting only digits.
+    pub fn numeric() -> Self {
+        Self {
+            numeric: true,
+            ..Self::default()
+        }
+    }
+
+    /// Gets the text typed so far.
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Gets whether the input only accepts digits.
+    pub const fn is_numeric(&self) -> bool {
+        self.numeric
+    }
+
+    /// Gets whether the last typed character was rejected.
+    pub const fn rejected(&self) -> bool {
+        self.rejected
+    }
+
+    /// Appends a character, unless rejected by a numeric input.
+    ///
+    /// Returns whether the character was accepted.
+    pub fn push(&mut self, c: char) -> bool {
+        self.rejected = self.numeric && !c.is_ascii_digit();
+        if !self.rejected {
+            self.text.push(c);
+        }
+
+        !self.rejected
+    }
+
+    /// Removes the last character.
+    pub fn pop(&mut self) -> Option<char> {
+        self.rejected = false;
+        self.text.pop()
+    }
+
+    /// Parses the text as a number, `None` if empty, or out of range.
+    pub fn number(&self) -> Option<u64> {
+        self.text.parse().ok()
+    }
+}
+
+/// How long typed [PendingKeys] wait for the rest of the command.
+pub const PENDING_TIMEOUT: Duration = Duration::from_secs(2);
+/// Largest count typed before a [KeyAction].
+pub const MAX_COUNT: usize = 999;
+
+/// Keys typed before a [KeyAction], a count like `5` in `5j`, the first key of `gg`, or the key
+/// of a numbered action waiting for its digit, like `x` in `x2`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PendingKeys {
+    count: Option<usize>,
+    prefix: Option<KeyAction>,
+    typed: String,
+    expires: Option<SystemTime>,
+}
+
+impl PendingKeys {
+    /// Gets whether no keys are pending.
+    pub fn is_empty(&self) -> bool {
+        self.typed.is_empty()
+    }
+
+    /// Gets the keys typed so far, e.g. `5g`.
+    pub fn typed(&self) -> &str {
+        self.typed.as_str()
+    }
+
+    /// Gets the count, one if none was typed.
+    pub fn count(&self) -> usize {
+        self.count.unwrap_or(1)
+    }
+
+    /// Clears the pending keys.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Clears the pending keys once [PENDING_TIMEOUT] has passed since the last key.
+    ///
+    /// Returns whether the keys were cleared.
+    pub fn expire(&mut self, now: SystemTime) -> bool {
+        let expired = self.expires.is_some_and(|e| e <= now);
+        if expired {
+            self.clear();
+        }
+
+        expired
+    }
+
+    /// Appends a digit to the count, up to [MAX_COUNT].
+    fn push_digit(&mut self, digit: usize, now: SystemTime) {
+        let count = self.count.unwrap_or(0).saturating_mul(10) + digit;
+        self.count = Some(count.min(MAX_COUNT));
+        self.push(char::from_digit(digit as u32, 10).unwrap_or('0'), now);
+    }
+
+    /// Holds the first key of a doubled [KeyAction].
+    fn push_prefix(&mut self, action: KeyAction, c: char, now: SystemTime) {
+        self.prefix = Some(action);
+        self.push(c, now);
+    }
+
+    fn push(&mut self, c: char, now: SystemTime) {
+        self.typed.push(c);
+        self.expires = now.checked_add(PENDING_TIMEOUT);
+    }
+}
+
+/// Gets the [KeyAction] bound to the key press on the [Screen], with the count typed before it.
+///
+/// Digits not bound to an action are held as the count, and the character key of a doubled
+/// action waits for the second press, see [KeyAction::doubled]. A numbered action waits for a
+/// digit from 1 to 9, returned as its count, see [KeyAction::numbered]. Esc cancels the pending
+/// keys.
+pub fn pending_action(
+    app: &mut App,
+    screen: Screen,
+    event: &KeyEvent,
+) -> Option<(KeyAction, usize)> {
+    let now = app.now();
+    app.pending.expire(now);
+
+    if event.code == KeyCode::Esc && !app.pending.is_empty() {
+        app.pending.clear();
+        return None;
+    }
+
+    // the digit after a numbered action, any other key cancels it
+    if let Some(action) = app.pending.prefix.filter(|a| a.numbered()) {
+        app.pending.clear();
+
+        return match event.code {
+            KeyCode::Char(c) => c
+                .to_digit(10)
+                .filter(|&d| d > 0)
+                .map(|d| (action, d as usize)),
+            _ => None,
+        };
+    }
+
+    let action = app.keymap.action_for(screen, event);
+    let plain = (event.modifiers - KeyModifiers::SHIFT).is_empty();
+
+    if let (None, KeyCode::Char(c), true) = (action, event.code, plain) {
+        // a count starts from 1, 0 only continues it
+        let digit = c
+            .to_digit(10)
+            .filter(|&d| d > 0 || app.pending.count.is_some());
+
+        if let Some(digit) = digit {
+            app.pending.push_digit(digit as usize, now);
+            return None;
+        }
+    }
+
+    let Some(action) = action else {
+        app.pending.clear();
+        return None;
+    };
+
+    if let KeyCode::Char(c) = event.code {
+        if action.numbered() {
+            app.pending.clear();
+            app.pending.push_prefix(action, c, now);
+            return None;
+        }
+        if action.doubled() && app.pending.prefix != Some(action) {
+            app.pending.push_prefix(action, c, now);
+            return None;
+        }
+    }
+
+    let count = app.pending.count();
+    app.pending.clear();
+
+    Some((action, count))
+}
+
+/// Draws the single-line text input overlay, if the [App] is waiting for text from the user.
+///
+/// Rejected characters are flagged after the text, e.g. letters in a page number.
+pub fn draw_input(f: &mut Frame, app: &App) {
+    if let Some(input) = app.input.as_ref() {
+        let area = centered_rect(60, 3, f.area());
+
+        let mut line = vec![Span::raw(format!("{}_", input.text()))];
+        if input.text.rejected() {
+            line.push(Span::styled(" numbers only", app.theme.error));
+        }
+
+        let popup = Paragraph::new(Line::from(line))
+            .style(app.theme.header)
+            .block(title_block(input.prompt(), &app.theme));
+
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+}
+
+/// Handles a key press while the text input overlay is displayed.
+///
+/// Returns a [Command] for the main loop to perform, if any, e.g. after entering a page number.
+pub fn handle_input_key(app: &mut App, code: KeyCode) -> Option<Command> {
+    match code {
+        KeyCode::Enter => return app.submit_input(),
+        KeyCode::Esc => app.cancel_input(),
+        KeyCode::Backspace => {
+            if let Some(input) = app.input.as_mut() {
+                input.text.pop();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some(input) = app.input.as_mut() {
+                input.text.push(c);
+            }
+        }
+        _ => (),
+    }
+
+    None
+}
+
+/// Draws the numbered link list overlay, if the [App] has links to pick from.
+pub fn draw_links(f: &mut Frame, app: &App) {
+    if let Some(picker) = app.links.as_ref() {
+        // the links, a spacer, the hints, and the borders
+        let height = picker.links.len().saturating_add(4).min(u16::MAX as usize) as u16;
+        let area = centered_rect(80, height, f.area());
+        let width = area.width.saturating_sub(2) as usize;
+
+        let number_width = picker.links.len().to_string().len();
+        let mut lines: Vec<Line> = picker
+            .links
+            .iter()
+            .enumerate()
+            .map(|(i, link)| {
+                let number = format!("{:>number_width$}. ", i + 1);
+                let link = truncate_width(link, width.saturating_sub(number.len()));
+
+                Line::from(vec![
+                    Span::styled(number, Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(link),
+                ])
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::from(format!(
+            "{}_ | (number) open | (Alt+number) copy | (Esc) close",
+            picker.number
+        )));
+
+        let popup = Paragraph::new(lines)
+            .style(app.theme.header)
+            .block(title_block("Links", &app.theme));
+
+        f.render_widget(Clear, area);
+        f.render_widget(popup, area);
+    }
+}
+
+/// Handles a key press while the link list overlay is displayed.
+///
+/// The picked link is opened in the browser, or copied to the clipboard with Alt held.
+pub fn handle_links_key(app: &mut App, event: KeyEvent) {
+    let Some(picker) = app.links.as_mut() else {
+        return;
+    };
+
+    let link = match event.code {
+        KeyCode::Char(c) if c.is_ascii_digit() => picker.push_digit(c).map(str::to_owned),
+        KeyCode::Enter => picker.selected().map(str::to_owned),
+        KeyCode::Backspace => {
+            picker.number.pop();
+            None
+        }
+        KeyCode::Esc => {
+            app.close_links();
+            None
+        }
+        _ => None,
+    };
+
+    if let Some(link) = link {
+        app.close_links();
+
+        if event.modifiers.contains(KeyModifiers::ALT) {
+            app.set_clipboard(link);
+        } else {
+            app.set_open_url(link);
+        }
+    }
+}
+
+/// Draws the instance list overlay, if the [App] has an [InstancePicker](crate::app::InstancePicker)
+/// displayed.
+pub fn draw_instances(f: &mut Frame, app: &App) {
+    if let Some(picker) = app.instance_picker.as_ref() {
+        let items = app
+            .config
+            .instances
+            .iter()
+            .map(|instance| {
+                let mut text = format!("{}  {}", instance.label(), instance.url);
+                let accounts = instance.accounts();
+                if !accounts.is_empty() {
+                    text.push_str(&format!(" ({})", accounts.join(", ")));
+                }
+                text
+            })
+            .collect();
+
+        draw_picker(f, app, "Instances", items, picker.selected);
+    }
+}
+
+/// Handles a key press while the instance list overlay is displayed.
+///
+/// Returns the [Command] from [App::switch_instance] once an instance is picked.
+pub fn handle_instances_key(app: &mut App, code: KeyCode) -> Option<Command> {
+    let len = app.config.instances.len();
+    let picker = app.instance_picker.as_mut()?;
+
+    match picker_key(code, &mut picker.selected, len)? {
+        Some(index) => app.switch_instance(index),
+        None => {
+            app.close_instance_picker();
+            None
+        }
+    }
+}
+
+/// Draws the account list overlay, if the [App] has an [AccountPicker](crate::app::AccountPicker)
+/// displayed.
+pub fn draw_accounts(f: &mut Frame, app: &App) {
+    if let Some(picker) = app.account_picker.as_ref() {
+        let host = app.instance_label();
+        let items = app
+            .config
+            .accounts()
+            .iter()
+            .map(|account| format!("{account}@{host}"))
+            .collect();
+
+        draw_picker(f, app, "Accounts", items, picker.selected);
+    }
+}
+
+/// Handles a key press while the account list overlay is displayed.
+///
+/// Returns the [Command] from [App::switch_account] once an account is picked.
+pub fn handle_accounts_key(app: &mut App, code: KeyCode) -> Option<Command> {
+    let len = app.config.accounts().len();
+    let picker = app.account_picker.as_mut()?;
+
+    match picker_key(code, &mut picker.selected, len)? {
+        Some(index) => app.switch_account(index),
+        None => {
+            app.close_account_picker();
+            None
+        }
+    }
+}
+
+/// Draws a numbered list overlay of `items` to switch to, highlighting the `selected` item.
+fn draw_picker(f: &mut Frame, app: &App, title: &str, items: Vec<String>, selected: usize) {
+    // the items, a spacer, the hints, and the borders
+    let height = items.len().saturating_add(4).min(u16::MAX as usize) as u16;
+    let area = centered_rect(80, height, f.area());
+    let width = area.width.saturating_sub(2) as usize;
+
+    let number_width = items.len().to_string().len();
+    let mut lines: Vec<Line> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let number = format!("{:>number_width$}. ", i + 1);
+            let text = truncate_width(item, width.saturating_sub(number.len()));
+
+            let line = Line::from(vec![
+                Span::styled(number, Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(text),
+            ]);
+            if i == selected {
+                line.style(app.theme.highlight)
+            } else {
+                line
+            }
+        })
+        .collect();
+    lines.push(Line::default());
+    lines.push(Line::from(
+        "(j/k) select | (Enter/number) switch | (Esc) close",
+    ));
+
+    let popup = Paragraph::new(lines)
+        .style(app.theme.header)
+        .block(title_block(title, &app.theme));
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Moves the `selected` item of a list overlay with `len` items for the key.
+///
+/// Returns `Some(Some(index))` once an item is picked, with Enter or its number, and
+/// `Some(None)` to close the overlay.
+fn picker_key(code: KeyCode, selected: &mut usize, len: usize) -> Option<Option<usize>> {
+    match code {
+        KeyCode::Down | KeyCode::Char('j') => {
+            *selected = (*selected + 1).min(len.saturating_sub(1));
+            None
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            *selected = selected.saturating_sub(1);
+            None
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            let index = c as usize - '1' as usize;
+            (index < len).then_some(Some(index))
+        }
+        KeyCode::Enter => Some(Some(*selected)),
+        KeyCode::Esc => Some(None),
+        _ => None,
+    }
 }
 
 /// Split text into cell width, useful for table layouts that have
 /// text that needs to span multiple cells.
 ///
-/// Currently, only works for evenly spaced cells.
+/// Currently, only works for evenly spaced cells. `width` is in display columns, see
+/// [split_width].
 ///
 /// Returns the total height of the row.
 pub fn split_cells(text: &str, width: usize, out: &mut [String]) -> usize {
@@ -125,11 +906,11 @@ pub fn split_cells(text: &str, width: usize, out: &mut [String]) -> usize {
 
     let stripped: String = text.chars().filter(|&c| c != '\r' && c != '\n').collect();
 
-    for c in stripped.as_bytes().chunks(width) {
+    for c in split_width(stripped.as_str(), width) {
         if height != 1 {
-            out[cell_idx] += format!("\n{}", std::str::from_utf8(c).unwrap_or("")).as_str();
+            out[cell_idx] += format!("\n{c}").as_str();
         } else {
-            out[cell_idx] += std::str::from_utf8(c).unwrap_or("");
+            out[cell_idx] += c;
         }
 
         cell_idx = (cell_idx + 1) % num_cells;
@@ -142,11 +923,307 @@ pub fn split_cells(text: &str, width: usize, out: &mut [String]) -> usize {
     height
 }
 
+/// Splits text into pieces of at most `width` display columns, without splitting grapheme
+/// clusters, e.g. an emoji sequence, or a letter with combining accents.
+///
+/// A grapheme wider than `width`, e.g. a CJK character with a `width` of 1, is a piece of its own.
+pub fn split_width(text: &str, width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut piece_width = 0;
+
+    for (i, grapheme) in text.grapheme_indices(true) {
+        let w = grapheme.width();
+
+        if i > start && piece_width + w > width {
+            pieces.push(&text[start..i]);
+            start = i;
+            piece_width = 0;
+        }
+
+        piece_width += w;
+    }
+
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+
+    pieces
+}
+
+/// Truncates text to at most `width` display columns, ending truncated text with `…`.
+///
+/// Grapheme clusters are not split.
+pub fn truncate_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_owned();
+    }
+
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+
+    // leave room for the ellipsis
+    for grapheme in text.graphemes(true) {
+        let w = grapheme.width();
+        if truncated_width + w + 1 > width {
+            break;
+        }
+
+        truncated.push_str(grapheme);
+        truncated_width += w;
+    }
+
+    if width > 0 {
+        truncated.push('…');
+    }
+
+    truncated
+}
+
+/// Gets the number of rows the [Line]s take up when wrapped to `width` display columns.
+///
+/// Every line takes up at least one row, including empty lines. Lines wrapped by word, e.g. in a
+/// [Paragraph], are exact when already wrapped to `width`, e.g. with [split_width].
+pub fn measure_lines(lines: &[Line], width: u16) -> usize {
+    if width == 0 {
+        return 0;
+    }
+
+    lines
+        .iter()
+        .map(|line| line.width().div_ceil(width as usize).max(1))
+        .sum()
+}
+
 /// Gets the wrapped height of provided text width.
 pub fn wrapped_height(text_width: usize, screen_width: usize) -> usize {
-    if screen_width == 0 {
-        0
-    } else {
-        (text_width / screen_width).saturating_add(text_width % screen_width) + 1
+    text_width
+        .checked_div(screen_width)
+        .map(|h| h.saturating_add(text_width % screen_width) + 1)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{Action, Confirm},
+        screen::snapshot::{buffer_text, fixture_app, render},
+        Error,
+    };
+
+    #[test]
+    fn test_render_small_sizes() {
+        let mut app = fixture_app();
+        app.set_error(&Error::Api("couldnt_find_post".into()));
+        app.set_confirm(Confirm::new(
+            "Block community rust?",
+            Action::BlockCommunity {
+                community_id: 1,
+                block: true,
+            },
+        ));
+
+        for screen in [Screen::PostList, Screen::Post, Screen::ErrorLog] {
+            app.replace_screen(screen);
+
+            for width in [0, 1, 2, 3, 4, 5, MIN_WIDTH - 1, MIN_WIDTH] {
+                for height in [0, 1, 2, 3, 4, 5, MIN_HEIGHT - 1, MIN_HEIGHT] {
+                    render(width, height, &mut app, render_screen);
+                    render(width, height, &mut app, render_posts_screen);
+                    render(width, height, &mut app, render_post_screen);
+                    render(width, height, &mut app, render_error_log_screen);
+                }
+            }
+        }
+
+        let buffer = render(MIN_WIDTH, MIN_HEIGHT - 1, &mut app, render_screen);
+        assert!(buffer_text(&buffer).contains("terminal too small"));
+    }
+
+    #[test]
+    fn test_confirm_quit() {
+        let mut app = fixture_app();
+        let q = KeyEvent::from(KeyCode::Char('q'));
+        assert_eq!(handle_key(q, &mut app), Some(Command::Quit));
+
+        app.config.confirm_quit = true;
+        for screen in [Screen::PostList, Screen::Post, Screen::ErrorLog] {
+            app.replace_screen(screen);
+            assert_eq!(handle_key(q, &mut app), None);
+            assert!(app.confirming());
+            let text = buffer_text(&render(80, 24, &mut app, render_screen));
+            assert!(text.contains("Quit temi?"), "{text}");
+
+            handle_key(KeyEvent::from(KeyCode::Char('n')), &mut app);
+            assert!(!app.confirming());
+            handle_key(q, &mut app);
+            assert_eq!(
+                handle_key(KeyEvent::from(KeyCode::Char('y')), &mut app),
+                Some(Command::Quit)
+            );
+        }
+
+        // Ctrl+c skips the confirmation
+        handle_key(q, &mut app);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(handle_key(ctrl_c, &mut app), Some(Command::Quit));
+    }
+
+    #[test]
+    fn test_suspend() {
+        let mut app = fixture_app();
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+
+        // from any screen, even with the error banner up
+        app.set_error(&Error::Api("couldnt_find_post".into()));
+        for screen in [Screen::PostList, Screen::Post, Screen::ErrorLog] {
+            app.replace_screen(screen);
+            assert_eq!(handle_key(ctrl_z, &mut app), Some(Command::Suspend));
+        }
+        assert!(app.has_error());
+    }
+
+    #[test]
+    fn test_status_bar() {
+        let mut app = fixture_app();
+        assert_eq!(
+            status_text(&app),
+            "voyager.lemmy.ml | page 1 | sort default | listing default | 1 post"
+        );
+
+        app.config = Config::from_toml("sort = \"top_day\"\nlisting_type = \"all\"").unwrap();
+        app.set_message("copied");
+        assert_eq!(
+            status_text(&app),
+            "voyager.lemmy.ml | page 1 | sort TopDay | listing All | 1 post | copied"
+        );
+
+        // on both main screens
+        for screen in [Screen::PostList, Screen::Post] {
+            app.screen = screen;
+            let text = buffer_text(&render(120, 30, &mut app, render_screen));
+            assert!(text.contains("| listing All | 1 post | copied"), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_text_input() {
+        let mut input = TextInput::numeric();
+        assert!(input.push('1'));
+        assert!(!input.push('x'));
+        assert!(input.rejected());
+        assert!(input.push('5'));
+        assert!(!input.rejected());
+        assert_eq!(input.text(), "15");
+        assert_eq!(input.number(), Some(15));
+
+        assert_eq!(input.pop(), Some('5'));
+        assert_eq!(input.pop(), Some('1'));
+        assert_eq!(input.pop(), None);
+        assert_eq!(input.number(), None);
+
+        let mut input = TextInput::new();
+        assert!(input.push('x'));
+        assert_eq!(input.number(), None);
+    }
+
+    #[test]
+    fn test_truncate_width() {
+        assert_eq!(truncate_width("short", 10), "short");
+        assert_eq!(truncate_width("exactly", 7), "exactly");
+        assert_eq!(truncate_width("truncated", 6), "trunc…");
+        assert_eq!(truncate_width("日本語です", 6), "日本…");
+        assert_eq!(truncate_width("日本語です", 5), "日本…");
+        assert_eq!(truncate_width("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(truncate_width("text", 1), "…");
+        assert_eq!(truncate_width("text", 0), "");
+    }
+
+    #[test]
+    fn test_split_width() {
+        assert_eq!(split_width("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(split_width("", 3), Vec::<&str>::new());
+
+        // combining accents stay with their letter
+        assert_eq!(
+            split_width("e\u{301}e\u{301}e\u{301}", 2),
+            ["e\u{301}e\u{301}", "e\u{301}"]
+        );
+
+        // CJK characters are two columns wide
+        assert_eq!(split_width("日本語です", 4), ["日本", "語で", "す"]);
+        assert_eq!(split_width("日本", 1), ["日", "本"]);
+
+        // emoji sequences are never split
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let text = format!("{family}{family}ok");
+        assert_eq!(split_width(&text, 2), [family, family, "ok"]);
+
+        for text in [
+            "plain ascii text",
+            "café crème",
+            "中文字符测试",
+            "🦀🦀🦀 rust",
+        ] {
+            for width in 0..8 {
+                for piece in split_width(text, width) {
+                    assert!(
+                        piece.width() <= width.max(2),
+                        "{piece:?} wider than {width}"
+                    );
+                }
+                assert_eq!(split_width(text, width).concat(), text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_cells() {
+        let mut out = vec![String::new(); 2];
+        assert_eq!(split_cells("abcdef\n", 2, &mut out), 2);
+        assert_eq!(out, ["ab\nef", "cd"]);
+
+        let mut out = vec![String::new(); 2];
+        assert_eq!(split_cells("日本語é\u{301}", 2, &mut out), 3);
+        assert_eq!(out, ["日\n語", "本\né\u{301}"]);
+    }
+
+    #[test]
+    fn test_measure_lines() {
+        let lines = [
+            Line::from("short"),
+            Line::from(""),
+            Line::from("x".repeat(25)),
+            Line::from(vec![
+                Span::raw("_|_|"),
+                Span::raw(" "),
+                Span::raw("y".repeat(6)),
+            ]),
+            Line::from("日本語日本語"),
+        ];
+
+        assert_eq!(measure_lines(&lines, 10), 1 + 1 + 3 + 2 + 2);
+        assert_eq!(measure_lines(&lines, 80), 5);
+        assert_eq!(measure_lines(&lines[..0], 10), 0);
+        assert_eq!(measure_lines(&lines, 0), 0);
+
+        // matches the rows drawn by a wrapped Paragraph for pre-wrapped lines
+        let text = "word ".repeat(20);
+        let lines = split_width(&text, 12)
+            .into_iter()
+            .map(Line::from)
+            .collect::<Vec<_>>();
+        let paragraph = Paragraph::new(lines.clone()).wrap(Wrap { trim: false });
+        let mut app = fixture_app();
+        let buffer = render(12, 20, &mut app, |f, _| {
+            f.render_widget(paragraph, f.area())
+        });
+        let drawn = buffer_text(&buffer)
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .count();
+
+        assert_eq!(measure_lines(&lines, 12), drawn);
     }
 }
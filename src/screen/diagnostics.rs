@@ -0,0 +1,253 @@
+//! Facilities for drawing the diagnostics overlay.
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use tui::{prelude::*, widgets::*};
+
+use crate::{
+    app::{App, Command},
+    config::Config,
+    diagnostics::{RequestRecord, REQUEST_LOG_LEN},
+    keymap::KeyAction,
+    utils::format_bytes,
+};
+
+use super::{centered_rect, draw_scrollbar, title_block, truncate_width};
+
+/// Gets the line of a [RequestRecord]: status, method, duration, size, and URL.
+///
+/// Requests without a response show `---` for the status.
+pub fn request_line(record: &RequestRecord, width: usize) -> Line<'static> {
+    let status = record
+        .status
+        .map_or("---".to_owned(), |status| status.to_string());
+    let text = format!(
+        "{status} {:<6} {:>6} ms {:>9}  {}",
+        record.method,
+        record.duration.as_millis(),
+        format_bytes(record.bytes),
+        record.url
+    );
+
+    let style = match record.status {
+        Some(200..=299) => Style::default(),
+        _ => Style::default().add_modifier(Modifier::BOLD),
+    };
+
+    Line::styled(truncate_width(&text, width), style)
+}
+
+/// Gets the active [Config] values worth checking on a slow instance, as `name = value` pairs.
+pub fn config_values(config: &Config) -> Vec<(&'static str, String)> {
+    let or_default = |value: Option<&str>| value.unwrap_or("default").to_owned();
+
+    vec![
+        ("instance_url", config.instance_url.clone()),
+        ("account", or_default(config.account.as_deref())),
+        ("sort", or_default(config.sort.map(|s| s.as_str()))),
+        (
+            "listing_type",
+            or_default(config.listing_type.map(|l| l.as_str())),
+        ),
+        ("community", or_default(config.community.as_deref())),
+        (
+            "page_limit",
+            or_default(config.page_limit.map(|l| l.to_string()).as_deref()),
+        ),
+        ("timeout", format!("{}s", config.timeout)),
+        ("comment_limit", config.comment_limit.to_string()),
+        ("comment_fetch_cap", config.comment_fetch_cap.to_string()),
+        ("comment_cache_size", config.comment_cache_size.to_string()),
+        ("image_cache_mb", config.image_cache_mb.to_string()),
+        ("image_disk_cache", config.image_disk_cache.to_string()),
+        ("live_interval", format!("{}s", config.live_interval)),
+        (
+            "accept_invalid_certs",
+            config.accept_invalid_certs.to_string(),
+        ),
+    ]
+}
+
+/// Gets the lines of the diagnostics overlay, `width` columns wide: recent requests, cache
+/// sizes, the frame render time, and the active config values.
+pub fn diagnostics_lines(app: &App, width: usize) -> Vec<Line<'static>> {
+    let heading = |text: &str| Line::styled(text.to_owned(), Modifier::BOLD);
+    let requests = app.diagnostics.requests.recent();
+
+    let mut lines = vec![heading(&format!(
+        "Requests, last {REQUEST_LOG_LEN}, newest first"
+    ))];
+    if requests.is_empty() {
+        lines.push(Line::from("no requests"));
+    }
+    lines.extend(requests.iter().map(|r| request_line(r, width)));
+
+    let prefetched = app
+        .prefetched
+        .as_ref()
+        .map_or("none".to_owned(), |(page, _)| format!("page {page}"));
+    lines.extend([
+        Line::default(),
+        heading("Caches"),
+        Line::from(format!(
+            "posts: {} on page {}, prefetched: {prefetched}",
+            app.posts.items().len(),
+            app.page()
+        )),
+        Line::from(format!("comment tables: {}", app.comments.len())),
+        Line::from(format!(
+            "images: {}, {} of {}",
+            app.images.len(),
+            format_bytes(app.images.size() as u64),
+            format_bytes(app.images.capacity() as u64)
+        )),
+        Line::default(),
+        heading("Rendering"),
+        Line::from(format!(
+            "frame: {:.1} ms",
+            app.diagnostics.frame_time.as_secs_f64() * 1000.0
+        )),
+        Line::default(),
+        heading("Config"),
+    ]);
+    lines.extend(
+        config_values(&app.config)
+            .into_iter()
+            .map(|(name, value)| Line::from(truncate_width(&format!("{name} = {value}"), width))),
+    );
+
+    lines
+}
+
+/// Draws the diagnostics overlay, if displayed, scrolling when taller than the popup.
+pub fn draw_diagnostics(f: &mut Frame, app: &mut App) {
+    if !app.diagnostics.visible {
+        return;
+    }
+
+    let area = centered_rect(90, f.area().height.saturating_sub(4), f.area());
+    let inner = Block::bordered().inner(area);
+    let lines = diagnostics_lines(app, inner.width.saturating_sub(1) as usize);
+
+    let scroll = &mut app.diagnostics.scroll;
+    scroll.set_content_length(lines.len());
+    scroll.set_viewport_length(inner.height as usize);
+
+    let popup = Paragraph::new(lines)
+        .style(app.theme.header)
+        .scroll((scroll.offset(), 0))
+        .block(title_block("Diagnostics", &app.theme));
+
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+    draw_scrollbar(
+        f,
+        Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight),
+        area,
+        &mut scroll.state,
+    );
+}
+
+/// Handles a key press while the diagnostics overlay is displayed, the toggle key or `Esc` hides
+/// it, and the arrow and page keys scroll it.
+pub fn handle_diagnostics_key(app: &mut App, event: KeyEvent) -> Option<Command> {
+    let toggle = app.keymap.action_for(app.screen(), &event) == Some(KeyAction::ToggleDiagnostics);
+    let scroll = &mut app.diagnostics.scroll;
+
+    match event.code {
+        _ if toggle => app.diagnostics.toggle(),
+        KeyCode::Esc => app.diagnostics.toggle(),
+        KeyCode::Down | KeyCode::Char('j') => scroll.next(),
+        KeyCode::Up | KeyCode::Char('k') => scroll.prev(),
+        KeyCode::PageDown => scroll.page_down(),
+        KeyCode::PageUp => scroll.page_up(),
+        KeyCode::Home => scroll.first(),
+        KeyCode::End => scroll.last(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Handles a mouse event while the diagnostics overlay is displayed, scrolling it.
+pub fn handle_diagnostics_mouse(app: &mut App, event: MouseEvent) -> Option<Command> {
+    match event.kind {
+        MouseEventKind::ScrollDown => app.diagnostics.scroll.next(),
+        MouseEventKind::ScrollUp => app.diagnostics.scroll.prev(),
+        _ => (),
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+    use crate::screen::{
+        handle_key, render_screen,
+        snapshot::{buffer_text, fixture_app, render},
+        Screen,
+    };
+
+    #[test]
+    fn test_diagnostics_overlay() {
+        let mut app = fixture_app();
+        let f12 = KeyEvent::from(KeyCode::F(12));
+        app.diagnostics.frame_time = Duration::from_micros(2500);
+        app.diagnostics.requests.push(RequestRecord {
+            method: "GET".into(),
+            url: "https://lemmy.ml/api/v3/post/list?page=1".into(),
+            status: Some(200),
+            duration: Duration::from_millis(420),
+            bytes: 2048,
+        });
+        app.diagnostics.requests.push(RequestRecord {
+            method: "GET".into(),
+            url: "https://lemmy.ml/api/v3/comment/list?post_id=1".into(),
+            status: None,
+            duration: Duration::from_secs(15),
+            bytes: 0,
+        });
+
+        // hidden by default, and toggled from any screen
+        let text = buffer_text(&render(120, 50, &mut app, render_screen));
+        assert!(!text.contains("Diagnostics"), "{text}");
+        app.replace_screen(Screen::Post);
+        assert_eq!(handle_key(f12, &mut app), None);
+        assert!(app.diagnostics.visible);
+
+        let text = buffer_text(&render(120, 50, &mut app, render_screen));
+        assert!(text.contains("Diagnostics"), "{text}");
+        assert!(
+            text.contains("--- GET     15000 ms       0 B  https://lemmy.ml/api/v3/comment/list"),
+            "{text}"
+        );
+        assert!(
+            text.contains("200 GET       420 ms   2.0 KiB  https://lemmy.ml/api/v3/post/list"),
+            "{text}"
+        );
+        assert!(
+            text.contains("posts: 1 on page 1, prefetched: none"),
+            "{text}"
+        );
+        assert!(text.contains("frame: 2.5 ms"), "{text}");
+        assert!(
+            text.contains("instance_url = https://voyager.lemmy.ml"),
+            "{text}"
+        );
+
+        // scrolls in a small terminal, taking the keys meant for the screen
+        render(80, 16, &mut app, render_screen);
+        handle_key(KeyEvent::from(KeyCode::End), &mut app);
+        assert!(app.diagnostics.scroll.position() > 0);
+        let text = buffer_text(&render(80, 16, &mut app, render_screen));
+        assert!(text.contains("accept_invalid_certs = false"), "{text}");
+
+        handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), &mut app);
+        assert!(!app.diagnostics.visible);
+        assert_eq!(app.screen(), Screen::Post);
+    }
+}
@@ -0,0 +1,349 @@
+//! Facilities for drawing the Discover screen.
+
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crossterm::event;
+use tui::{layout::Constraint, prelude::*, widgets::*};
+
+use crate::{
+    app::{Action, App, Command},
+    community::CommunityView,
+    keymap::KeyAction,
+    posts::ListingType,
+    utils::format_count,
+};
+
+use super::{
+    community_handle, draw_confirm, draw_error, handle_confirm_key, handle_error_key, is_interrupt,
+    pending_action, push_message, subscribed_tag, title_block, truncate_width, Screen,
+};
+
+/// Listing types shown as tabs, in order.
+const TABS: [ListingType; 2] = [ListingType::All, ListingType::Local];
+
+/// Widths of the community, subscribers, posts per week, and active users columns, the title
+/// takes the rest.
+const COLUMN_WIDTHS: [u16; 4] = [36, 12, 10, 10];
+
+/// Gets the layout of the Discover screen `area`, inside the title block: tabs, table, HUD, and
+/// status bar.
+pub fn discover_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(Block::bordered().inner(area))
+}
+
+/// Formats posts per week, with a decimal below 10, e.g. `2.5`, or `140`.
+pub fn format_posts_per_week(posts: f64) -> String {
+    if posts < 10.0 {
+        format!("{posts:.1}")
+    } else {
+        format_count(posts.round() as u64)
+    }
+}
+
+/// Gets the table row of a community: its handle and subscription, subscribers, posts per week,
+/// users active over the last week, and title.
+pub fn community_row(c: &CommunityView, app: &App, now: SystemTime) -> Row<'static> {
+    let mut community = vec![Span::raw(community_handle(&c.community, &app.config))];
+    if let Some(tag) = subscribed_tag(c.subscribed, &app.theme) {
+        community.extend([Span::raw(" "), tag]);
+    }
+
+    Row::new([
+        Cell::from(Line::from(community)),
+        Cell::from(format_count(c.counts.subscribers)),
+        Cell::from(format_posts_per_week(c.counts.posts_per_week(now))),
+        Cell::from(format_count(c.counts.users_active_week)),
+        Cell::from(c.community.title.clone()),
+    ])
+    .style(app.theme.body)
+}
+
+/// Gets the status bar text of the Discover screen, e.g. `lemmy.ml | top communities this month
+/// | page 2 | 20 communities`, followed by the current message, if any.
+pub fn discover_status_text(app: &App) -> String {
+    let host = app.account_label();
+    let host = host.as_deref().unwrap_or(app.instance_label());
+
+    let mut text = format!(
+        "{host} | top communities this month | page {}",
+        app.discover.page
+    );
+    match app.discover.communities.as_ref().map(Vec::len) {
+        Some(1) => text.push_str(" | 1 community"),
+        Some(n) => text.push_str(&format!(" | {n} communities")),
+        None => (),
+    }
+    push_message(app, &mut text);
+
+    text
+}
+
+/// Renders the Discover screen, without handling input.
+pub fn render_discover_screen(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+    let chunks = discover_layout(size);
+    let now = app.now();
+
+    let tabs = Tabs::new(TABS.map(|t| t.as_str()))
+        .select(
+            TABS.iter()
+                .position(|&t| t == app.discover.listing_type)
+                .unwrap_or(0),
+        )
+        .style(app.theme.header)
+        .highlight_style(app.theme.highlight);
+
+    let rows: Option<Vec<Row>> = app.discover.communities.as_ref().map(|communities| {
+        communities
+            .iter()
+            .map(|c| community_row(c, app, now))
+            .collect()
+    });
+
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::Back], "back"),
+        (&[KeyAction::OpenPost], "open"),
+        (&[KeyAction::Subscribe], "subscribe"),
+        (&[KeyAction::NextTab], "all/local"),
+        (&[KeyAction::PrevPage], "prev page"),
+        (&[KeyAction::NextPage], "next page"),
+        (&[KeyAction::Refresh], "refresh"),
+    ]))
+    .style(app.theme.header);
+    let status = truncate_width(&discover_status_text(app), chunks[3].width as usize);
+
+    f.render_widget(title_block("Discover", &app.theme), size);
+    f.render_widget(tabs, chunks[0]);
+
+    let placeholder = match rows {
+        None => Some("loading communities"),
+        Some(rows) if rows.is_empty() => Some("no communities"),
+        Some(rows) => {
+            let [community, subscribers, posts, active] = COLUMN_WIDTHS.map(Constraint::Length);
+            let header = Row::new(["community", "subscribers", "posts/wk", "active/wk", "title"])
+                .style(app.theme.header.add_modifier(Modifier::BOLD));
+            // the table scrolls to keep the selected row in view
+            let table = Table::new(
+                rows,
+                [community, subscribers, posts, active, Constraint::Fill(1)],
+            )
+            .header(header)
+            .style(app.theme.header)
+            .highlight_style(app.theme.highlight);
+
+            f.render_stateful_widget(table, chunks[1], &mut app.discover.state);
+            None
+        }
+    };
+    if let Some(text) = placeholder {
+        f.render_widget(Paragraph::new(text).style(app.theme.body), chunks[1]);
+    }
+
+    f.render_widget(hud, chunks[2]);
+    f.render_widget(Paragraph::new(status).style(app.theme.header), chunks[3]);
+
+    draw_confirm(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a mouse event on the Discover screen, scrolling moves the selection.
+pub fn handle_discover_mouse(event: event::MouseEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() || app.confirming() {
+        return None;
+    }
+
+    match event.kind {
+        event::MouseEventKind::ScrollDown => app.discover.next(),
+        event::MouseEventKind::ScrollUp => app.discover.previous(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Handles a key press on the Discover screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_discover_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::DownloadCommunities);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    } else if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
+
+    let (action, count) = pending_action(app, Screen::Discover, &event)?;
+
+    match action {
+        KeyAction::Back => return app.pop_screen(),
+        KeyAction::SelectNext => (0..count).for_each(|_| app.discover.next()),
+        KeyAction::SelectPrev => (0..count).for_each(|_| app.discover.previous()),
+        KeyAction::FirstPost => app.discover.first(),
+        KeyAction::LastPost => app.discover.last(),
+        KeyAction::OpenPost => {
+            if let Some(c) = app.discover.current().map(|c| c.community.clone()) {
+                return app.open_community(&c);
+            }
+        }
+        KeyAction::Subscribe => {
+            if let Some(c) = app.discover.current() {
+                app.set_action(Action::FollowCommunity {
+                    community_id: c.community.id,
+                    follow: !c.subscribed.is_subscribed(),
+                });
+            }
+        }
+        KeyAction::NextTab => {
+            app.discover.toggle_listing_type();
+            return Some(Command::DownloadCommunities);
+        }
+        KeyAction::NextPage => {
+            let page = app.discover.page.saturating_add(count as u64);
+            app.discover.set_page(page);
+            return Some(Command::DownloadCommunities);
+        }
+        KeyAction::PrevPage if app.discover.page > 1 => {
+            let page = app.discover.page.saturating_sub(count as u64);
+            app.discover.set_page(page);
+            return Some(Command::DownloadCommunities);
+        }
+        KeyAction::Refresh => return Some(Command::DownloadCommunities),
+        KeyAction::Quit => return app.quit(),
+        _ => (),
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::{
+        community::{ListCommunitiesResponse, SubscribedType},
+        screen::{
+            handle_posts_input,
+            snapshot::{buffer_text, fixture_app, render},
+        },
+    };
+
+    /// Gets the fixture [App] on the Discover screen, with the fixture communities downloaded.
+    fn discover_app() -> App {
+        let mut app = fixture_app();
+        assert_eq!(
+            handle_posts_input(KeyEvent::from(KeyCode::Char('C')), &mut app),
+            Some(Command::DownloadCommunities)
+        );
+        assert_eq!(app.screen(), Screen::Discover);
+
+        let res: ListCommunitiesResponse = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lemmy-0.19.3/community_list.json"
+        ))
+        .unwrap();
+        app.discover.set_communities(res);
+
+        app
+    }
+
+    #[test]
+    fn test_format_posts_per_week() {
+        assert_eq!(format_posts_per_week(0.0), "0.0");
+        assert_eq!(format_posts_per_week(2.54), "2.5");
+        assert_eq!(format_posts_per_week(9.99), "10.0");
+        assert_eq!(format_posts_per_week(139.6), "140");
+        assert_eq!(format_posts_per_week(1234.0), "1,234");
+    }
+
+    #[test]
+    fn test_render_discover_screen() {
+        let mut app = fixture_app();
+        app.open_discover();
+        let text = buffer_text(&render(120, 24, &mut app, render_discover_screen));
+        assert!(text.contains("loading communities"), "{text}");
+
+        let mut app = discover_app();
+        let text = buffer_text(&render(120, 24, &mut app, render_discover_screen));
+        assert!(text.contains("All │ Local"), "{text}");
+        assert!(text.contains("!rust@lemmy.ml ✓"), "{text}");
+        assert!(text.contains("48,213"), "{text}");
+        assert!(text.contains("!linux@programming.dev"), "{text}");
+        assert!(text.contains("Rust Programming"), "{text}");
+        assert!(
+            discover_status_text(&app)
+                .ends_with("| top communities this month | page 1 | 2 communities"),
+            "{}",
+            discover_status_text(&app)
+        );
+    }
+
+    #[test]
+    fn test_handle_discover_input() {
+        let mut app = discover_app();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        // subscribing to the selected community
+        handle_discover_input(KeyEvent::from(KeyCode::Down), &mut app);
+        assert_eq!(handle_discover_input(key('S'), &mut app), None);
+        assert_eq!(
+            app.take_action(),
+            Some(Action::FollowCommunity {
+                community_id: 7710,
+                follow: true
+            })
+        );
+        app.discover
+            .set_subscribed(7710, SubscribedType::Subscribed);
+        handle_discover_input(key('S'), &mut app);
+        assert_eq!(
+            app.take_action(),
+            Some(Action::FollowCommunity {
+                community_id: 7710,
+                follow: false
+            })
+        );
+
+        assert_eq!(
+            handle_discover_input(key('n'), &mut app),
+            Some(Command::DownloadCommunities)
+        );
+        assert_eq!(app.discover.page, 2);
+
+        // switching to local communities starts over
+        assert_eq!(
+            handle_discover_input(KeyEvent::from(KeyCode::Tab), &mut app),
+            Some(Command::DownloadCommunities)
+        );
+        assert_eq!(app.discover.listing_type, ListingType::Local);
+        assert_eq!(app.discover.page, 1);
+    }
+
+    #[test]
+    fn test_open_community() {
+        let mut app = discover_app();
+        handle_discover_input(KeyEvent::from(KeyCode::End), &mut app);
+
+        assert_eq!(
+            handle_discover_input(KeyEvent::from(KeyCode::Enter), &mut app),
+            Some(Command::DownloadPosts)
+        );
+        assert_eq!(app.screen(), Screen::PostList);
+        assert!(app.nav.is_empty());
+        assert_eq!(app.page(), 1);
+        assert_eq!(
+            app.config.community.as_deref(),
+            Some("linux@programming.dev")
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Facilities for drawing the ErrorLog screen.
+
+use crossterm::event;
+use tui::{prelude::*, widgets::*};
+
+use crate::{
+    app::{App, Command, Scroll},
+    keymap::KeyAction,
+};
+
+use super::{draw_confirm, draw_scrollbar, handle_confirm_key, is_interrupt, title_block, Screen};
+
+/// Renders the screen to show the in-memory log of recent errors, newest first.
+pub fn render_error_log_screen(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(size);
+
+    let mut lines: Vec<Line> = Vec::with_capacity(app.error_log.len() * 3);
+
+    if app.error_log.is_empty() {
+        lines.push(Line::from("No errors"));
+    }
+
+    for entry in app.error_log.iter().rev() {
+        lines.extend_from_slice(&[
+            Line::from(vec![
+                Span::styled(format!("[{}] ", entry.kind()), app.theme.error),
+                Span::styled(httpdate::fmt_http_date(entry.time()), app.theme.header),
+            ]),
+            Line::from(entry.message().to_owned()),
+            Line::from(""),
+        ]);
+    }
+
+    app.error_log_scroll.set_content_length(lines.len());
+    app.error_log_scroll
+        .set_viewport_length(chunks[0].height.saturating_sub(2) as usize);
+
+    let log = Paragraph::new(lines)
+        .style(app.theme.body)
+        .block(title_block("Error log", &app.theme))
+        .wrap(Wrap { trim: false })
+        .scroll((app.error_log_scroll.offset(), 0));
+
+    f.render_widget(log, chunks[0]);
+
+    let scrollbar = Scrollbar::default()
+        .orientation(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("▲"))
+        .end_symbol(Some("▼"));
+
+    draw_scrollbar(
+        f,
+        scrollbar,
+        chunks[0].inner(Scroll::margin()),
+        &mut app.error_log_scroll.state,
+    );
+
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::Back], "back"),
+        (&[KeyAction::ScrollUp, KeyAction::ScrollDown], "scroll"),
+        (&[KeyAction::ClearLog], "clear"),
+    ]))
+    .style(app.theme.body);
+
+    f.render_widget(hud, chunks[1]);
+    draw_confirm(f, app);
+}
+
+/// Handles a mouse event on the ErrorLog screen, scrolling the log.
+pub fn handle_error_log_mouse(event: event::MouseEvent, app: &mut App) -> Option<Command> {
+    if app.confirming() {
+        return None;
+    }
+
+    match event.kind {
+        event::MouseEventKind::ScrollDown => app.error_log_scroll.next(),
+        event::MouseEventKind::ScrollUp => app.error_log_scroll.prev(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Handles a key press on the ErrorLog screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_error_log_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if is_interrupt(&event) {
+        return Some(Command::Quit);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    }
+
+    match app.keymap.action_for(Screen::ErrorLog, &event)? {
+        KeyAction::Back => return app.close_error_log(),
+        KeyAction::ScrollUp => app.error_log_scroll.prev(),
+        KeyAction::ScrollDown => app.error_log_scroll.next(),
+        KeyAction::ClearLog => {
+            app.error_log.clear();
+            app.error_log_scroll.first();
+        }
+        KeyAction::Quit => return app.quit(),
+        _ => (),
+    }
+
+    None
+}
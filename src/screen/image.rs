@@ -0,0 +1,826 @@
+//! Facilities for drawing the Image screen.
+//!
+//! Images are drawn with the graphics protocol of the terminal, if any, otherwise as a mosaic of
+//! halfblock characters, two pixels per cell.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use crossterm::event;
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageOutputFormat, RgbImage};
+use tui::{prelude::*, widgets::*};
+
+use crate::{
+    app::{App, Command},
+    keymap::KeyAction,
+    utils, Result,
+};
+
+use super::{
+    draw_confirm, draw_error, handle_confirm_key, handle_error_key, is_interrupt, title_block,
+    Screen,
+};
+
+/// Maximum zoom factor of an [ImageView].
+pub const MAX_ZOOM: f32 = 8.0;
+/// Zoom factor change of a zoom in, or out.
+const ZOOM_STEP: f32 = 1.25;
+/// Pan distance, as a fraction of the visible part of the image.
+const PAN_STEP: f32 = 0.1;
+/// Size of a terminal cell in pixels, for terminals that do not report it.
+pub const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// Represents the graphics protocol used to draw images.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Graphics {
+    /// Kitty graphics protocol, also supported by Ghostty.
+    Kitty,
+    /// iTerm2 inline images, also supported by WezTerm.
+    Iterm,
+    /// Sixel graphics, e.g. in foot and mlterm.
+    Sixel,
+    /// Unicode halfblocks with true colors, for every other terminal.
+    #[default]
+    Halfblocks,
+}
+
+impl Graphics {
+    /// Detects the [Graphics] protocol of the terminal from the environment.
+    ///
+    /// Uses [Graphics::Halfblocks] inside tmux, which does not pass graphics through by default.
+    pub fn detect() -> Self {
+        Self::from_env(|var| std::env::var(var).ok())
+    }
+
+    fn from_env<F: Fn(&str) -> Option<String>>(var: F) -> Self {
+        if var("TMUX").is_some() {
+            return Self::Halfblocks;
+        }
+
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+
+        if var("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || program == "ghostty" {
+            Self::Kitty
+        } else if matches!(program.as_str(), "iTerm.app" | "WezTerm")
+            || var("LC_TERMINAL").as_deref() == Some("iTerm2")
+        {
+            Self::Iterm
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Self::Sixel
+        } else {
+            Self::Halfblocks
+        }
+    }
+
+    /// Gets the sequence removing the images drawn with the [Graphics] protocol, once the screen
+    /// is left.
+    ///
+    /// Only Kitty draws images on a separate layer, other images are overwritten by the text.
+    pub const fn clear_sequence(self) -> &'static str {
+        match self {
+            Self::Kitty => "\x1b_Ga=d,q=2\x1b\\",
+            _ => "",
+        }
+    }
+}
+
+/// Represents the image on the Image screen, and its zoom and pan.
+#[derive(Clone, Debug)]
+pub struct ImageView {
+    url: String,
+    /// Decoded image, `None` while downloading.
+    image: Option<Arc<DynamicImage>>,
+    failed: bool,
+    /// Zoom factor, `1.0` fits the whole image.
+    zoom: f32,
+    /// Center of the visible part, as fractions of the image width and height.
+    center: (f32, f32),
+    /// Last rendered view, to avoid scaling the image every frame.
+    rendered: Option<Rendered>,
+    /// Terminal graphics sequence for the main loop to write after the frame.
+    output: Option<String>,
+}
+
+/// Visible part of an [ImageView], scaled to the screen.
+#[derive(Clone, Debug)]
+struct Rendered {
+    key: ViewKey,
+    /// Area of the image, in cells.
+    area: Rect,
+    /// Halfblock mosaic, empty for terminal graphics.
+    lines: Vec<Line<'static>>,
+}
+
+/// Parameters of a [Rendered] view, it is rendered again if any of them change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ViewKey {
+    area: Rect,
+    zoom: f32,
+    center: (f32, f32),
+    graphics: Graphics,
+    cell_size: (u16, u16),
+}
+
+impl ImageView {
+    /// Creates a new [ImageView] for the image at the URL, waiting for the download.
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: url.into(),
+            image: None,
+            failed: false,
+            zoom: 1.0,
+            center: (0.5, 0.5),
+            rendered: None,
+            output: None,
+        }
+    }
+
+    /// Gets the URL of the image.
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// Gets the decoded image, if downloaded.
+    pub fn image(&self) -> Option<&DynamicImage> {
+        self.image.as_deref()
+    }
+
+    /// Sets the decoded image, e.g. shared with the [ImageCache](crate::posts::ImageCache).
+    pub fn set_image<I: Into<Arc<DynamicImage>>>(&mut self, image: I) {
+        self.image = Some(image.into());
+        self.failed = false;
+        self.rendered = None;
+    }
+
+    /// Gets whether downloading, or decoding, the image failed.
+    pub const fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Sets whether downloading, or decoding, the image failed.
+    pub fn set_failed(&mut self, failed: bool) {
+        self.failed = failed;
+    }
+
+    /// Gets the zoom factor, `1.0` fits the whole image on the screen.
+    pub const fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Zooms in, up to [MAX_ZOOM].
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom * ZOOM_STEP).min(MAX_ZOOM);
+        self.clamp_center();
+    }
+
+    /// Zooms out, down to fitting the whole image.
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom / ZOOM_STEP).max(1.0);
+        self.clamp_center();
+    }
+
+    /// Moves the visible part of a zoomed image by `dx` and `dy` steps, negative to the left,
+    /// and up.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.center.0 += dx * PAN_STEP / self.zoom;
+        self.center.1 += dy * PAN_STEP / self.zoom;
+        self.clamp_center();
+    }
+
+    /// Keeps the visible part inside the image.
+    fn clamp_center(&mut self) {
+        let half = 0.5 / self.zoom;
+
+        self.center = (
+            self.center.0.clamp(half, 1.0 - half),
+            self.center.1.clamp(half, 1.0 - half),
+        );
+    }
+
+    /// Gets the visible part of the image, as `(x, y, width, height)` in pixels.
+    pub fn crop(&self) -> Option<(u32, u32, u32, u32)> {
+        let (width, height) = self.image.as_ref()?.dimensions();
+
+        let axis = |size: u32, center: f32| {
+            let visible = ((size as f32 / self.zoom).round() as u32).clamp(1, size.max(1));
+            let start = (center * size as f32 - visible as f32 / 2.0).round();
+
+            (start.clamp(0.0, (size - visible) as f32) as u32, visible)
+        };
+
+        let (x, w) = axis(width, self.center.0);
+        let (y, h) = axis(height, self.center.1);
+
+        Some((x, y, w, h))
+    }
+
+    /// Forces the image to be drawn again, e.g. after the terminal was cleared.
+    pub fn redraw(&mut self) {
+        self.rendered = None;
+    }
+
+    /// Takes the terminal graphics sequence to write after the frame, if any.
+    pub fn take_output(&mut self) -> Option<String> {
+        self.output.take()
+    }
+
+    /// Scales the visible part of the image to fit `area`, unless it is already scaled for it.
+    ///
+    /// For terminal graphics, also sets the sequence drawing it.
+    fn render(&mut self, area: Rect, graphics: Graphics, cell_size: (u16, u16)) -> Result<()> {
+        let key = ViewKey {
+            area,
+            zoom: self.zoom,
+            center: self.center,
+            graphics,
+            cell_size,
+        };
+
+        if self.rendered.as_ref().is_some_and(|r| r.key == key) {
+            return Ok(());
+        }
+
+        let (Some(image), Some((x, y, w, h))) = (self.image.as_ref(), self.crop()) else {
+            return Ok(());
+        };
+
+        // halfblocks are two square pixels per cell
+        let cell = match graphics {
+            Graphics::Halfblocks => (1, 2),
+            _ => (cell_size.0.max(1), cell_size.1.max(1)),
+        };
+        let (fit, width, height) = fit((w, h), area, cell);
+        if fit.is_empty() {
+            return Ok(());
+        }
+
+        let scaled = image
+            .crop_imm(x, y, w, h)
+            .resize_exact(width, height, FilterType::Triangle);
+
+        let (lines, output) = match graphics {
+            Graphics::Halfblocks => (halfblocks(&scaled.to_rgb8()), None),
+            Graphics::Kitty => (Vec::new(), Some(kitty(&scaled, fit)?)),
+            Graphics::Iterm => (Vec::new(), Some(iterm(&scaled, fit)?)),
+            Graphics::Sixel => (
+                Vec::new(),
+                Some(format!("{}{}", cursor_to(fit), sixel(&scaled.to_rgb8()))),
+            ),
+        };
+
+        // erase the previous image, e.g. before zooming out
+        self.output = output.map(|out| format!("{}{out}", erase(area)));
+        self.rendered = Some(Rendered {
+            key,
+            area: fit,
+            lines,
+        });
+
+        Ok(())
+    }
+}
+
+/// Renders the screen to view the image of a post.
+pub fn render_image_screen(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+        .split(f.area());
+
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::Back], "back"),
+        (&[KeyAction::ZoomIn, KeyAction::ZoomOut], "zoom"),
+        (
+            &[
+                KeyAction::PanLeft,
+                KeyAction::PanRight,
+                KeyAction::PanUp,
+                KeyAction::PanDown,
+            ],
+            "pan",
+        ),
+    ]))
+    .style(app.theme.body);
+
+    f.render_widget(hud, chunks[1]);
+
+    let (graphics, cell_size) = (app.graphics, app.cell_size);
+    // bytes received and expected, with no total before the response headers
+    let received = app
+        .image_task
+        .as_ref()
+        .map(|t| (t.progress().bytes(), t.progress().bytes_total()));
+    let Some(view) = app.image.as_mut() else {
+        return;
+    };
+
+    let title = format!("Image {:.0}% | {}", view.zoom() * 100.0, view.url());
+    let block = title_block(title.as_str(), &app.theme);
+    let area = block.inner(chunks[0]);
+
+    f.render_widget(block.style(app.theme.body), chunks[0]);
+
+    let status = if view.failed() {
+        Some("error loading image".to_owned())
+    } else if view.image().is_none() {
+        Some(match received {
+            Some((bytes, 0)) if bytes > 0 => {
+                format!("loading image... {}", utils::format_bytes(bytes))
+            }
+            Some((bytes, total)) if total > 0 => format!(
+                "loading image... {} / {}",
+                utils::format_bytes(bytes),
+                utils::format_bytes(total)
+            ),
+            _ => "loading image...".to_owned(),
+        })
+    } else {
+        None
+    };
+
+    if let Some(status) = status {
+        let y = area.y + area.height / 2;
+        let text = Paragraph::new(status).alignment(Alignment::Center);
+
+        f.render_widget(text, Rect::new(area.x, y, area.width, 1.min(area.height)));
+
+        if let Some((bytes, total)) = received.filter(|&(_, total)| total > 0) {
+            let width = area.width.min(40);
+            let gauge_area =
+                Rect::new(area.x + (area.width - width) / 2, y + 1, width, 1).intersection(area);
+            let gauge = Gauge::default()
+                .gauge_style(app.theme.body)
+                .ratio((bytes as f64 / total as f64).clamp(0.0, 1.0));
+
+            f.render_widget(gauge, gauge_area);
+        }
+    } else if let Err(err) = view.render(area, graphics, cell_size) {
+        view.set_failed(true);
+        app.set_error(&err);
+    } else if let Some(rendered) = view.rendered.as_ref() {
+        if graphics == Graphics::Halfblocks {
+            f.render_widget(Paragraph::new(rendered.lines.clone()), rendered.area);
+        } else {
+            // the terminal draws the image over the skipped cells after the frame
+            let buf = f.buffer_mut();
+            for y in area.top()..area.bottom() {
+                for x in area.left()..area.right() {
+                    buf[(x, y)].set_skip(true);
+                }
+            }
+        }
+    }
+
+    draw_confirm(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a key press on the Image screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_image_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
+
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::Refresh);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    }
+
+    let action = app.keymap.action_for(Screen::Image, &event)?;
+
+    if let Some(view) = app.image.as_mut() {
+        match action {
+            KeyAction::ZoomIn => view.zoom_in(),
+            KeyAction::ZoomOut => view.zoom_out(),
+            KeyAction::PanLeft => view.pan(-1.0, 0.0),
+            KeyAction::PanRight => view.pan(1.0, 0.0),
+            KeyAction::PanUp => view.pan(0.0, -1.0),
+            KeyAction::PanDown => view.pan(0.0, 1.0),
+            _ => (),
+        }
+    }
+
+    match action {
+        KeyAction::Back => app.close_image(),
+        KeyAction::Quit => app.quit(),
+        _ => None,
+    }
+}
+
+/// Fits an image of `size` pixels in `area`, keeping its aspect ratio, for cells of `cell`
+/// pixels.
+///
+/// Returns the centered area of the image, in cells, and its scaled size, in pixels.
+fn fit(size: (u32, u32), area: Rect, cell: (u16, u16)) -> (Rect, u32, u32) {
+    let max_width = u32::from(area.width) * u32::from(cell.0);
+    let max_height = u32::from(area.height) * u32::from(cell.1);
+
+    let scale = (max_width as f32 / size.0 as f32).min(max_height as f32 / size.1 as f32);
+    let width = ((size.0 as f32 * scale).round() as u32).clamp(1, max_width.max(1));
+    let height = ((size.1 as f32 * scale).round() as u32).clamp(1, max_height.max(1));
+
+    let cols = (width.div_ceil(u32::from(cell.0)) as u16).min(area.width);
+    let rows = (height.div_ceil(u32::from(cell.1)) as u16).min(area.height);
+
+    let fit = Rect {
+        x: area.x + (area.width - cols) / 2,
+        y: area.y + (area.height - rows) / 2,
+        width: cols,
+        height: rows,
+    };
+
+    (fit, width, height)
+}
+
+/// Gets the halfblock mosaic of the image, the upper pixel of each cell in the foreground color.
+fn halfblocks(image: &RgbImage) -> Vec<Line<'static>> {
+    let color = |x, y| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        Color::Rgb(r, g, b)
+    };
+
+    (0..image.height())
+        .step_by(2)
+        .map(|y| {
+            let spans: Vec<Span> = (0..image.width())
+                .map(|x| {
+                    let bottom = if y + 1 < image.height() {
+                        color(x, y + 1)
+                    } else {
+                        Color::Black
+                    };
+
+                    Span::styled("▀", Style::default().fg(color(x, y)).bg(bottom))
+                })
+                .collect();
+
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Gets the sequence moving the cursor to the top left cell of the area.
+fn cursor_to(area: Rect) -> String {
+    format!("\x1b[{};{}H", area.y + 1, area.x + 1)
+}
+
+/// Gets the sequence erasing every cell of the area, including images.
+fn erase(area: Rect) -> String {
+    (area.top()..area.bottom())
+        .map(|y| format!("\x1b[{};{}H\x1b[{}X", y + 1, area.x + 1, area.width))
+        .collect()
+}
+
+/// Encodes the image as a PNG file.
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png)?;
+
+    Ok(png.into_inner())
+}
+
+/// Gets the Kitty graphics sequence drawing the image scaled to the area, replacing any
+/// previous image.
+fn kitty(image: &DynamicImage, area: Rect) -> Result<String> {
+    const CHUNK: usize = 4096;
+
+    let data = utils::base64(&png(image)?);
+
+    let mut out = format!("{}{}", Graphics::Kitty.clear_sequence(), cursor_to(area));
+    for start in (0..data.len()).step_by(CHUNK) {
+        let end = (start + CHUNK).min(data.len());
+        let more = u8::from(end < data.len());
+
+        if start == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=100,q=2,c={},r={},m={more};",
+                area.width, area.height
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};");
+        }
+
+        out.push_str(&data[start..end]);
+        out.push_str("\x1b\\");
+    }
+
+    Ok(out)
+}
+
+/// Gets the iTerm2 inline image sequence drawing the image scaled to the area.
+fn iterm(image: &DynamicImage, area: Rect) -> Result<String> {
+    let png = png(image)?;
+
+    Ok(format!(
+        "{}\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        cursor_to(area),
+        png.len(),
+        area.width,
+        area.height,
+        utils::base64(&png)
+    ))
+}
+
+/// Gets the sixel sequence drawing the image, with a 6x6x6 color cube palette.
+fn sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+
+    let mut out = format!("\x1bP0;1q\"1;1;{width};{height}");
+    for i in 0..216 {
+        let _ = write!(
+            out,
+            "#{i};2;{};{};{}",
+            i / 36 * 20,
+            i / 6 % 6 * 20,
+            i % 6 * 20
+        );
+    }
+
+    let index = |x, y| {
+        let level = |c: u8| (usize::from(c) * 5 + 127) / 255;
+        let [r, g, b] = image.get_pixel(x, y).0;
+
+        level(r) * 36 + level(g) * 6 + level(b)
+    };
+
+    // each band of 6 rows is drawn once for every color in it
+    for band in (0..height).step_by(6) {
+        let mut colors: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+
+        for dy in 0..(height - band).min(6) {
+            for x in 0..width {
+                colors
+                    .entry(index(x, band + dy))
+                    .or_insert_with(|| vec![0; width as usize])[x as usize] |= 1 << dy;
+            }
+        }
+
+        for (i, (color, sixels)) in colors.iter().enumerate() {
+            if i > 0 {
+                // back to the start of the band
+                out.push('$');
+            }
+            let _ = write!(out, "#{color}");
+
+            let mut x = 0;
+            while x < sixels.len() {
+                let run = sixels[x..].iter().take_while(|&&s| s == sixels[x]).count();
+                let c = char::from(63 + sixels[x]);
+
+                if run > 3 {
+                    let _ = write!(out, "!{run}{c}");
+                } else {
+                    out.extend(std::iter::repeat_n(c, run));
+                }
+                x += run;
+            }
+        }
+
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+    use crate::screen::snapshot::{fixture_app, render};
+
+    /// Gets an image with the left half red, and the right half blue.
+    fn split_image(width: u32, height: u32) -> DynamicImage {
+        let image = RgbImage::from_fn(width, height, |x, _| {
+            if x < width / 2 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+
+        DynamicImage::ImageRgb8(image)
+    }
+
+    #[test]
+    fn test_graphics_from_env() {
+        let detect = |vars: &[(&str, &str)]| {
+            Graphics::from_env(|var| {
+                vars.iter()
+                    .find(|(k, _)| *k == var)
+                    .map(|(_, v)| v.to_string())
+            })
+        };
+
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), Graphics::Kitty);
+        assert_eq!(detect(&[("KITTY_WINDOW_ID", "1")]), Graphics::Kitty);
+        assert_eq!(detect(&[("TERM_PROGRAM", "iTerm.app")]), Graphics::Iterm);
+        assert_eq!(detect(&[("TERM", "foot")]), Graphics::Sixel);
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), Graphics::Halfblocks);
+        assert_eq!(detect(&[]), Graphics::Halfblocks);
+
+        // tmux needs passthrough for graphics
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")]),
+            Graphics::Halfblocks
+        );
+    }
+
+    #[test]
+    fn test_zoom_pan() {
+        let mut view = ImageView::new("https://lemmy.ml/pictrs/image/cat.png");
+        assert_eq!(view.crop(), None);
+
+        view.set_image(split_image(100, 50));
+        assert_eq!(view.crop(), Some((0, 0, 100, 50)));
+
+        // nothing to pan without zooming
+        view.pan(1.0, 1.0);
+        assert_eq!(view.crop(), Some((0, 0, 100, 50)));
+
+        view.zoom_in();
+        view.zoom_in();
+        assert_eq!(view.zoom(), 1.5625);
+        assert_eq!(view.crop(), Some((18, 9, 64, 32)));
+
+        view.pan(-1.0, 0.0);
+        assert_eq!(view.crop(), Some((12, 9, 64, 32)));
+
+        // the visible part stays inside the image
+        (0..20).for_each(|_| view.pan(1.0, 1.0));
+        assert_eq!(view.crop(), Some((36, 18, 64, 32)));
+
+        (0..20).for_each(|_| view.zoom_in());
+        assert_eq!(view.zoom(), MAX_ZOOM);
+
+        (0..20).for_each(|_| view.zoom_out());
+        assert_eq!(view.zoom(), 1.0);
+        assert_eq!(view.crop(), Some((0, 0, 100, 50)));
+    }
+
+    #[test]
+    fn test_fit() {
+        // halfblock cells are two pixels tall
+        assert_eq!(
+            fit((100, 50), Rect::new(1, 1, 40, 20), (1, 2)),
+            (Rect::new(1, 6, 40, 10), 40, 20)
+        );
+        assert_eq!(
+            fit((50, 100), Rect::new(0, 0, 40, 20), (1, 2)),
+            (Rect::new(10, 0, 20, 20), 20, 40)
+        );
+        assert_eq!(
+            fit((100, 100), Rect::new(0, 0, 10, 10), (8, 16)),
+            (Rect::new(0, 2, 10, 5), 80, 80)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_progress() {
+        let mut app = fixture_app();
+        let url = "https://lemmy.ml/pictrs/image/cat.png";
+        app.open_image(url);
+
+        let client = Box::new(crate::api::fake::FakeClient::new(vec![], vec![], 10));
+        let task = crate::task::ImageTask::spawn(client, url.into(), None, None);
+        task.progress().add_bytes(1024);
+        app.image_task = Some(task);
+        let buffer = render(40, 12, &mut app, render_image_screen);
+        let text = crate::screen::snapshot::buffer_text(&buffer);
+        assert!(text.contains("loading image... 1.0 KiB"), "{text}");
+        assert_eq!(
+            text.matches('%').count(),
+            1,
+            "only the zoom, no gauge without a length"
+        );
+
+        let progress = app.image_task.as_ref().unwrap().progress();
+        progress.add_bytes_total(2048);
+        let buffer = render(40, 12, &mut app, render_image_screen);
+        let text = crate::screen::snapshot::buffer_text(&buffer);
+        assert!(
+            text.contains("loading image... 1.0 KiB / 2.0 KiB"),
+            "{text}"
+        );
+        assert!(text.contains("50%"), "{text}");
+
+        app.cancel_image_task();
+    }
+
+    #[test]
+    fn test_render_halfblocks() {
+        let mut app = fixture_app();
+        app.open_image("https://lemmy.ml/pictrs/image/cat.png");
+
+        let buffer = render(40, 12, &mut app, render_image_screen);
+        let text = crate::screen::snapshot::buffer_text(&buffer);
+        assert!(text.contains("loading image..."));
+
+        app.image.as_mut().unwrap().set_image(split_image(8, 4));
+        let buffer = render(40, 12, &mut app, render_image_screen);
+
+        // 38x9 cells inside the border, the 2:1 image is 36x9 cells centered
+        let left = &buffer[(11, 5)];
+        assert_eq!(left.symbol(), "▀");
+        assert_eq!(
+            (left.fg, left.bg),
+            (Color::Rgb(255, 0, 0), Color::Rgb(255, 0, 0))
+        );
+
+        let right = &buffer[(28, 5)];
+        assert_eq!(
+            (right.fg, right.bg),
+            (Color::Rgb(0, 0, 255), Color::Rgb(0, 0, 255))
+        );
+
+        assert_eq!(buffer[(1, 5)].symbol(), " ");
+        assert_eq!(app.image.as_mut().unwrap().take_output(), None);
+    }
+
+    #[test]
+    fn test_render_graphics() {
+        let mut app = fixture_app();
+        app.graphics = Graphics::Kitty;
+        app.open_image("https://lemmy.ml/pictrs/image/cat.png");
+        app.image.as_mut().unwrap().set_image(split_image(8, 4));
+
+        // the image cells are skipped, and left blank for the terminal to draw over
+        let buffer = render(40, 12, &mut app, render_image_screen);
+        assert_eq!(buffer[(20, 5)].symbol(), " ");
+
+        let output = app.image.as_mut().unwrap().take_output().unwrap();
+        assert!(output.starts_with("\x1b[2;2H\x1b[38X"));
+        assert!(output.contains("\x1b_Ga=d,q=2\x1b\\\x1b[2;3H\x1b_Ga=T,f=100,q=2,c=36,r=9,m=0;"));
+
+        // the terminal keeps the image until the view changes
+        render(40, 12, &mut app, render_image_screen);
+        assert_eq!(app.image.as_mut().unwrap().take_output(), None);
+
+        handle_image_input(KeyEvent::from(KeyCode::Char('+')), &mut app);
+        render(40, 12, &mut app, render_image_screen);
+        assert!(app.image.as_mut().unwrap().take_output().is_some());
+    }
+
+    #[test]
+    fn test_sixel() {
+        let image = RgbImage::from_fn(5, 7, |x, _| {
+            if x == 0 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+
+        let out = sixel(&image);
+        assert!(out.starts_with("\x1bP0;1q\"1;1;5;7#0;2;0;0;0#1;2;0;0;20"));
+        assert!(out.contains("#215;2;100;100;100"));
+
+        // red is 180 and blue is 5 in the palette, the last row is a band of its own
+        assert!(out.ends_with("#5?!4~$#180~!4?-#5?!4@$#180@!4?-\x1b\\"));
+    }
+
+    #[test]
+    fn test_open_cached_image() {
+        let mut app = fixture_app();
+        app.images.insert(
+            "https://lemmy.ml/pictrs/image/cat.png",
+            split_image(8, 4).into(),
+        );
+
+        app.open_image("https://lemmy.ml/pictrs/image/cat.png");
+        assert!(app.image.as_ref().unwrap().image().is_some());
+
+        app.close_image();
+        app.open_image("https://lemmy.ml/pictrs/image/dog.png");
+        assert!(app.image.as_ref().unwrap().image().is_none());
+    }
+
+    #[test]
+    fn test_handle_image_input() {
+        let mut app = fixture_app();
+        app.push_screen(Screen::Post);
+        app.open_image("https://lemmy.ml/pictrs/image/cat.png");
+        assert_eq!(app.screen(), Screen::Image);
+
+        handle_image_input(KeyEvent::from(KeyCode::Char('+')), &mut app);
+        handle_image_input(KeyEvent::from(KeyCode::Char('=')), &mut app);
+        assert_eq!(app.image.as_ref().unwrap().zoom(), 1.5625);
+
+        handle_image_input(KeyEvent::from(KeyCode::Char('-')), &mut app);
+        assert_eq!(app.image.as_ref().unwrap().zoom(), 1.25);
+
+        handle_image_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!(app.screen(), Screen::Post);
+        assert!(app.image.is_none());
+    }
+}
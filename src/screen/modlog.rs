@@ -0,0 +1,331 @@
+//! Facilities for drawing the Modlog screen.
+
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crossterm::event;
+use tui::{layout::Constraint, prelude::*, widgets::*};
+
+use crate::{
+    app::{App, Command},
+    config::Config,
+    keymap::KeyAction,
+    modlog::ModlogEntry,
+    utils::format_timestamp,
+};
+
+use super::{
+    community_handle, creator_handle, draw_confirm, draw_error, handle_confirm_key,
+    handle_error_key, is_interrupt, pending_action, push_message, title_block, truncate_width,
+    Screen,
+};
+
+/// Widths of the time, moderator, and action columns, the target and reason share the rest.
+const COLUMN_WIDTHS: [u16; 3] = [14, 20, 16];
+
+/// Gets the layout of the Modlog screen `area`, inside the title block: table, HUD, and status
+/// bar.
+pub fn modlog_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(Block::bordered().inner(area))
+}
+
+/// Gets what a [ModlogEntry] applies to: the post title, the comment author and its content
+/// flattened to one line, or the banned person.
+pub fn modlog_target(entry: &ModlogEntry, config: &Config) -> String {
+    match entry {
+        ModlogEntry::RemovePost(v) => v.post.name().to_owned(),
+        ModlogEntry::LockPost(v) => v.post.name().to_owned(),
+        ModlogEntry::RemoveComment(v) => {
+            let content = v.comment.content().split_whitespace().collect::<Vec<_>>();
+            format!(
+                "{}: {}",
+                creator_handle(&v.commenter, config),
+                content.join(" ")
+            )
+        }
+        ModlogEntry::BanFromCommunity(v) => creator_handle(&v.banned_person, config),
+    }
+}
+
+/// Gets the table row of a [ModlogEntry]: time, moderator, action, target, and reason.
+///
+/// Moderators hidden by the instance are shown as `(hidden)`.
+pub fn modlog_row(entry: &ModlogEntry, app: &App, now: SystemTime) -> Row<'static> {
+    let moderator = entry
+        .moderator()
+        .map_or("(hidden)".to_owned(), |m| creator_handle(m, &app.config));
+
+    Row::new([
+        format_timestamp(entry.when(), app.config.timestamps, now),
+        moderator,
+        entry.action().to_owned(),
+        modlog_target(entry, &app.config),
+        entry.reason().unwrap_or_default().to_owned(),
+    ])
+    .style(app.theme.body)
+}
+
+/// Gets the status bar text of the Modlog screen, e.g. `lemmy.ml | modlog !rust@lemmy.ml | page
+/// 2 | 20 entries`, followed by the current message, if any.
+pub fn modlog_status_text(app: &App) -> String {
+    let host = app.account_label();
+    let host = host.as_deref().unwrap_or(app.instance_label());
+
+    let mut text = host.to_owned();
+    if let Some(modlog) = app.modlog.as_ref() {
+        let community = community_handle(&modlog.community, &app.config);
+        text.push_str(&format!(" | modlog {community} | page {}", modlog.page));
+
+        match modlog.entries.as_ref().map(Vec::len) {
+            Some(1) => text.push_str(" | 1 entry"),
+            Some(n) => text.push_str(&format!(" | {n} entries")),
+            None => (),
+        }
+    }
+    push_message(app, &mut text);
+
+    text
+}
+
+/// Renders the Modlog screen, without handling input.
+pub fn render_modlog_screen(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+    let chunks = modlog_layout(size);
+    let now = app.now();
+
+    let rows: Option<Vec<Row>> = app.modlog.as_ref().and_then(|modlog| {
+        let entries = modlog.entries.as_ref()?;
+
+        Some(entries.iter().map(|e| modlog_row(e, app, now)).collect())
+    });
+
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::Back], "back"),
+        (&[KeyAction::PrevPage], "prev page"),
+        (&[KeyAction::NextPage], "next page"),
+        (&[KeyAction::Refresh], "refresh"),
+    ]))
+    .style(app.theme.header);
+    let status = truncate_width(&modlog_status_text(app), chunks[2].width as usize);
+
+    f.render_widget(title_block("Modlog", &app.theme), size);
+
+    let placeholder = match rows {
+        None => Some("loading modlog"),
+        Some(rows) if rows.is_empty() => Some("no moderator actions"),
+        Some(rows) => {
+            let [time, moderator, action] = COLUMN_WIDTHS.map(Constraint::Length);
+            let header = Row::new(["time", "moderator", "action", "target", "reason"])
+                .style(app.theme.header.add_modifier(Modifier::BOLD));
+            // the table scrolls to keep the selected row in view
+            let table = Table::new(
+                rows,
+                [
+                    time,
+                    moderator,
+                    action,
+                    Constraint::Fill(2),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(header)
+            .style(app.theme.header)
+            .highlight_style(app.theme.highlight);
+
+            if let Some(modlog) = app.modlog.as_mut() {
+                f.render_stateful_widget(table, chunks[0], &mut modlog.state);
+            }
+            None
+        }
+    };
+    if let Some(text) = placeholder {
+        f.render_widget(Paragraph::new(text).style(app.theme.body), chunks[0]);
+    }
+
+    f.render_widget(hud, chunks[1]);
+    f.render_widget(Paragraph::new(status).style(app.theme.header), chunks[2]);
+
+    draw_confirm(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a mouse event on the Modlog screen, scrolling moves the selection.
+pub fn handle_modlog_mouse(event: event::MouseEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() || app.confirming() {
+        return None;
+    }
+
+    if let Some(modlog) = app.modlog.as_mut() {
+        match event.kind {
+            event::MouseEventKind::ScrollDown => modlog.next(),
+            event::MouseEventKind::ScrollUp => modlog.previous(),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/// Handles a key press on the Modlog screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_modlog_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::DownloadModlog);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    } else if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
+
+    let (action, count) = pending_action(app, Screen::Modlog, &event)?;
+    match action {
+        KeyAction::Back => return app.pop_screen(),
+        KeyAction::Quit => return app.quit(),
+        _ => (),
+    }
+    let modlog = app.modlog.as_mut()?;
+
+    match action {
+        KeyAction::SelectNext => (0..count).for_each(|_| modlog.next()),
+        KeyAction::SelectPrev => (0..count).for_each(|_| modlog.previous()),
+        KeyAction::FirstPost => modlog.first(),
+        KeyAction::LastPost => modlog.last(),
+        KeyAction::NextPage => {
+            modlog.set_page(modlog.page.saturating_add(count as u64));
+            return Some(Command::DownloadModlog);
+        }
+        KeyAction::PrevPage if modlog.page > 1 => {
+            modlog.set_page(modlog.page.saturating_sub(count as u64));
+            return Some(Command::DownloadModlog);
+        }
+        KeyAction::Refresh => return Some(Command::DownloadModlog),
+        _ => (),
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::{
+        modlog::ModlogResponse,
+        screen::{
+            handle_post_input, handle_posts_input,
+            snapshot::{buffer_text, fixture_app, render},
+        },
+    };
+
+    /// Gets the fixture [App] on the Modlog screen of the fixture community, with the fixture
+    /// modlog downloaded.
+    fn modlog_app() -> App {
+        let mut app = fixture_app();
+        let community = app.posts.current().unwrap().community.clone();
+        assert_eq!(app.open_modlog(community), Some(Command::DownloadModlog));
+        assert_eq!(app.screen(), Screen::Modlog);
+
+        let res: ModlogResponse = serde_json::from_str(include_str!(
+            "../../tests/fixtures/lemmy-0.19.3/modlog.json"
+        ))
+        .unwrap();
+        app.modlog.as_mut().unwrap().set_entries(res);
+
+        app
+    }
+
+    #[test]
+    fn test_render_modlog_screen() {
+        let mut app = fixture_app();
+        let community = app.posts.current().unwrap().community.clone();
+        app.open_modlog(community);
+        let text = buffer_text(&render(120, 24, &mut app, render_modlog_screen));
+        assert!(text.contains("loading modlog"), "{text}");
+
+        let mut app = modlog_app();
+        let text = buffer_text(&render(120, 24, &mut app, render_modlog_screen));
+        assert!(text.contains("moderator"), "{text}");
+        assert!(text.contains("@ferris"), "{text}");
+        assert!(text.contains("removed comment"), "{text}");
+        assert!(text.contains("(hidden)"), "{text}");
+        assert!(
+            text.contains("@spammer@spam.example: This release is"),
+            "{text}"
+        );
+        assert!(text.contains("Rule 2: be civil"), "{text}");
+        assert!(text.contains("Cheap watches"), "{text}");
+        assert!(
+            modlog_status_text(&app).ends_with("| modlog !rust@lemmy.ml | page 1 | 4 entries"),
+            "{}",
+            modlog_status_text(&app)
+        );
+
+        app.modlog
+            .as_mut()
+            .unwrap()
+            .set_entries(ModlogResponse::default());
+        let text = buffer_text(&render(120, 24, &mut app, render_modlog_screen));
+        assert!(text.contains("no moderator actions"), "{text}");
+    }
+
+    #[test]
+    fn test_handle_modlog_input() {
+        let mut app = modlog_app();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        handle_modlog_input(KeyEvent::from(KeyCode::Down), &mut app);
+        assert_eq!(app.modlog.as_ref().unwrap().state.selected(), Some(1));
+
+        assert_eq!(
+            handle_modlog_input(key('n'), &mut app),
+            Some(Command::DownloadModlog)
+        );
+        assert_eq!(app.modlog.as_ref().unwrap().page, 2);
+        assert_eq!(
+            handle_modlog_input(key('p'), &mut app),
+            Some(Command::DownloadModlog)
+        );
+        assert_eq!(handle_modlog_input(key('p'), &mut app), None);
+        assert_eq!(app.modlog.as_ref().unwrap().page, 1);
+
+        handle_modlog_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!(app.screen(), Screen::PostList);
+    }
+
+    #[test]
+    fn test_show_modlog() {
+        let mut app = fixture_app();
+        let key = KeyEvent::from(KeyCode::Char('M'));
+
+        // the post list needs a community filter
+        assert_eq!(handle_posts_input(key, &mut app), None);
+        assert_eq!(app.screen(), Screen::PostList);
+
+        app.config.community = Some("rust".into());
+        assert_eq!(
+            handle_posts_input(key, &mut app),
+            Some(Command::DownloadModlog)
+        );
+        assert_eq!(app.modlog.as_ref().unwrap().community.name, "rust");
+
+        // the Post screen uses the community of the post
+        app.pop_screen();
+        app.config.community = None;
+        app.push_screen(Screen::Post);
+        assert_eq!(
+            handle_post_input(key, &mut app),
+            Some(Command::DownloadModlog)
+        );
+        assert_eq!(app.screen(), Screen::Modlog);
+    }
+}
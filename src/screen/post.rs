@@ -1,242 +1,2658 @@
 //! Facilities for drawing the Post screen.
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
-use std::time;
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::SystemTime;
 
 use crossterm::event;
 use tui::{prelude::*, widgets::*};
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
-    app::{App, Scroll, TemiTerminal},
-    Result,
+    app::{Action, App, Command, Confirm, InputKind, RefreshTarget, Scroll, Spoilers},
+    comments::{CommentLines, CommentLinesKey, CommentResponse, CommentResponseTable},
+    community::{Community, SubscribedType},
+    config::Config,
+    export,
+    keymap::KeyAction,
+    markdown,
+    posts::{is_image, is_video},
+    theme::Theme,
+    utils::{extract_links, format_bytes, format_count, format_timestamp},
 };
 
-use super::{body_style, set_current_screen, title_block, wrapped_height, Screen};
+use super::{
+    community_handle, creator_handle, draw_confirm, draw_error, draw_input, draw_links,
+    draw_scrollbar, draw_status_bar, handle_confirm_key, handle_error_key, handle_input_key,
+    handle_links_key, is_interrupt, measure_lines, nsfw_title, pending_action, post_tags,
+    saved_span, split_width, title_block, vote_span, Screen,
+};
 
-/// Draw the screen to show an individual [Post](crate::posts::Post).
-pub fn draw_post_screen(
-    terminal: &mut TemiTerminal,
-    app: &mut App,
-    stop: Arc<AtomicBool>,
-) -> Result<()> {
-    terminal.draw(|f| {
-        match app.posts.current_mut() {
-            Some(p) => {
-                let size = f.area();
-
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(2)
-                    .constraints([
-                                 Constraint::Percentage(30),
-                                 Constraint::Percentage(60),
-                                 Constraint::Percentage(5),
-                                 Constraint::Percentage(5),
-                                 Constraint::Min(1),
-                    ]
-                    .as_ref(),
-                    )
-                    .split(size);
-
-                let creator = p.creator.name();
-                let comments = p.counts.comments();
-                let published = p.creator.published();
-
-                let info = format!("creator: {creator}, published: {published}, comments: {comments}");
-
-                let url = p.post.url();
-                let title = p.post.name().chars().filter(|c| !c.is_control()).collect::<String>();
-                let body = p.post.body().chars().filter(|c| !c.is_control()).collect::<String>();
-
-                let post_lens = [title.len(), info.len(), published.len(), body.len()];
-
-                let mut lines = vec![
-                    Line::from(title),
-                    Line::from(""),
-                ];
-
-                body.split("\n\n").map(|b| Line::from(b)).for_each(|b| {
-                    lines.push(b);
-                    lines.push(Line::from(""));
-                });
+/// Most crossposts listed under the post body, one for each digit typed after the
+/// [OpenCrossPost](KeyAction::OpenCrossPost) key.
+const MAX_CROSS_POSTS: usize = 9;
 
-                lines.extend_from_slice(&[
-                    Line::from(""),
-                    Line::from(""),
-                    Line::from(info),
-                    Line::from(url),
-                ]);
-
-                let posts_height: usize = wrapped_height(post_lens.iter().sum(), size.width as usize);
-                app.post_scroll.set_content_length(posts_height as u16);
-
-                let post_text = Paragraph::new(lines)
-                    .style(body_style())
-                    .block(title_block("Post"))
-                    .wrap(Wrap { trim: false })
-                    .scroll((app.post_scroll.position(), 0));
-
-                f.render_widget(post_text, chunks[0]);
-
-                let orientation = ScrollbarOrientation::VerticalRight;
-                let post_scrollbar = Scrollbar::default()
-                    .orientation(orientation.clone())
-                    .begin_symbol(Some("▲"))
-                    .end_symbol(Some("▼"));
-
-                f.render_stateful_widget(
-                    post_scrollbar,
-                    chunks[0].inner(Scroll::margin()),
-                    &mut app.post_scroll.state,
-                );
+/// Gets the layout of the Post screen `area`: post, comments, spacer, HUD, and status bar.
+///
+/// Shared by drawing and mouse handling, so mouse events map to the chunks as drawn.
+pub fn post_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(60),
+                Constraint::Percentage(5),
+                Constraint::Length(1),
+                Constraint::Min(1),
+            ]
+            .as_ref(),
+        )
+        .split(area)
+}
 
-                // multiple `Line`s per-comment for spacing/formatting
-                let cap = app.comments[&p.post.id()].items.len() * 5;
-                let mut comments: Vec<Line> = Vec::with_capacity(cap);
+/// Renders the Post screen for the selected [Post](crate::posts::Post), without handling input.
+///
+/// Only the overlays are drawn when no post is selected.
+pub fn render_post_screen(f: &mut Frame, app: &mut App) {
+    let now = app.now();
+    let blurred = app.posts.current().is_some_and(|p| app.nsfw_blurred(p));
+    let reveal_hint = app
+        .keymap
+        .hint(&[KeyAction::RevealNsfw], "reveal")
+        .unwrap_or_default();
+    let cross_posts = app
+        .posts
+        .current()
+        .map(|p| cross_post_lines(app, p.post.id()))
+        .unwrap_or_default();
 
-                let mut comment_height = 0;
-                if let Some(c) = app.comments.get_mut(&p.post.id()) {
-                    // sort comments chronologically, grouping by parent-child relation
-                    if !p.post.sorted() {
-                        c.sort_comments();
-                        p.post.set_sorted(true);
-                    }
+    if let Some(p) = app.posts.current_mut() {
+        let size = f.area();
+        let chunks = post_layout(size);
 
-                    for cr in c.items.iter() {
-                        let ct = cr.comment.content();
-                        let a = cr.creator.name();
-                        let n = cr.counts.child_count();
-
-                        // add child comment indicators by level
-                        // all comments have a root level (0), and at least one parent (1)
-                        // so, the first child is level 2
-                        let levels = cr.comment.path.split('.').count().saturating_sub(2);
-                        let tabs = "_|".repeat(levels);
-
-                        let info = format!("[ author: {a}, child comments: {n} ]");
-
-                        let height = ct.len() + a.len() + (tabs.len() * 2) + info.len();
-                        let width = size.width as usize;
-                        comment_height += wrapped_height(height, width) + 2;
-
-                        ct.split("\n\n").for_each(|c| {
-                            filter_line(String::from(c).as_str(), width)
-                                .map(|line| Line::from(vec![
-                                    Span::raw(tabs.clone()),
-                                    Span::raw(" "),
-                                    Span::raw(line),
-                                ]))
-                                .for_each(|line| comments.push(line));
-
-                            comments.push(Line::from(tabs.clone()));
-                            comment_height = comment_height.saturating_add(2);
-                        });
-
-                        comments.extend_from_slice(&[
-                            Line::from(vec![Span::raw(tabs.clone()), Span::raw(" "), Span::raw(info)]),
-                            Line::from(""),
-                            Line::from(""),
-                        ]);
-                    }
+        let creator = creator_handle(&p.creator, &app.config);
+        let comments = p.counts.comments();
+        let published = format_timestamp(p.post.published(), app.config.timestamps, now);
+
+        let info = format!("creator: {creator}, published: {published}, comments: {comments}");
+        let community = Line::from(vec![
+            Span::raw(format!(
+                "community: {}, ",
+                community_handle(&p.community, &app.config)
+            )),
+            subscribed_span(&p.community, p.subscribed, &app.theme),
+        ]);
+
+        // inside the block borders
+        let post_width = chunks[0].width.saturating_sub(2);
+
+        // NSFW content is hidden until revealed
+        let nsfw_body = format!("[NSFW content hidden] {reveal_hint}");
+        let (title, body, url) = if blurred {
+            (nsfw_title(p, &app.config), nsfw_body.as_str(), "")
+        } else if p.post.deleted {
+            (p.post.name().to_owned(), "[deleted]", p.post.url())
+        } else {
+            (p.post.name().to_owned(), p.post.body(), p.post.url())
+        };
+
+        let mut lines: Vec<Line> = filter_line(&title, post_width as usize)
+            .map(Line::from)
+            .collect();
+        lines.push(Line::from(""));
+
+        lines.extend(markdown::render(
+            body,
+            post_width as usize,
+            app.spoilers.post,
+            &app.theme,
+        ));
+
+        if !cross_posts.is_empty() {
+            lines.push(Line::from(""));
+            lines.extend(cross_posts);
+        }
+
+        lines.extend_from_slice(&[
+            Line::from(""),
+            Line::from(""),
+            Line::from(info),
+            community,
+            Line::from(url),
+        ]);
+
+        // preview of the linked page
+        let embed_title = if blurred { "" } else { p.post.embed_title() };
+        if !embed_title.is_empty() {
+            let style = Style::default().add_modifier(Modifier::BOLD);
+            lines.extend(
+                filter_line(embed_title, post_width as usize)
+                    .map(|line| Line::from(Span::styled(line, style))),
+            );
+        }
+        let embed_description = if blurred {
+            ""
+        } else {
+            p.post.embed_description()
+        };
+        if !embed_description.is_empty() {
+            let style = Style::default().add_modifier(Modifier::ITALIC);
+            lines.extend(
+                filter_line(embed_description, post_width as usize)
+                    .map(|line| Line::from(Span::styled(line, style))),
+            );
+        }
+
+        app.post_scroll
+            .set_content_length(measure_lines(&lines, post_width));
+        app.post_scroll
+            .set_viewport_length(chunks[0].height.saturating_sub(2) as usize);
+
+        let post_text = Paragraph::new(lines)
+            .style(app.theme.body)
+            .block(
+                post_tags(p, &app.theme)
+                    .into_iter()
+                    .fold(title_block("Post", &app.theme), |block, tag| {
+                        block.title(tag)
+                    }),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((app.post_scroll.offset(), 0));
+
+        f.render_widget(post_text, chunks[0]);
+
+        let orientation = ScrollbarOrientation::VerticalRight;
+        let post_scrollbar = Scrollbar::default()
+            .orientation(orientation.clone())
+            .begin_symbol(Some("▲"))
+            .end_symbol(Some("▼"));
+
+        draw_scrollbar(
+            f,
+            post_scrollbar,
+            chunks[0].inner(Scroll::margin()),
+            &mut app.post_scroll.state,
+        );
+
+        // inside the block borders
+        let comment_width = chunks[1].width.saturating_sub(2);
+        let comment_height = chunks[1].height.saturating_sub(2) as usize;
+        let loading = app
+            .comments_task
+            .as_ref()
+            .filter(|t| t.post_id() == p.post.id());
+        // streamed pages are shown as they arrive, with the rest still loading
+        let streaming = loading.is_some_and(|t| t.streams()) && app.comments.contains(p.post.id());
+
+        // lines above, and below, the comments
+        let mut header: Vec<Line> = Vec::new();
+        let mut footer: Vec<Line> = Vec::new();
+        if let Some(task) = loading.filter(|_| !streaming) {
+            let progress = task.progress();
+            header.extend_from_slice(&[
+                Line::from(Span::styled(
+                    format!(
+                        "Loading comments… (page {}/{}, {})",
+                        progress.done(),
+                        progress.total(),
+                        format_bytes(progress.bytes())
+                    ),
+                    Style::default().add_modifier(Modifier::ITALIC),
+                )),
+                Line::from(""),
+            ]);
+        }
+        let header_offsets = row_offsets(&header, comment_width);
+        let header_rows = header_offsets[header.len()];
+
+        let mut revealed: Vec<u64> = app.spoilers.comments.iter().copied().collect();
+        revealed.sort_unstable();
+        let key = CommentLinesKey {
+            width: comment_width,
+            // comments published, or edited, since the last visit, or added in live mode
+            since: app.visits.since(p.post.id()),
+            loading_replies: app.replies_task.as_ref().map(|t| t.comment_id()),
+            revealed,
+            minute: now
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() / 60),
+        };
+
+        let mut focus_row = None;
+        let mut new_count = 0;
+        let mut selected_lines = None;
+
+        if let Some(c) = app.comments.get_mut(p.post.id()) {
+            // group replies under their parents, e.g. after loading, or changing the sort
+            if c.sort != Some(app.comment_sort) {
+                c.sort_comments(app.comment_sort);
+            }
+
+            // rendered again only when the comments, or the pane width, change
+            let lines = match c.lines.take() {
+                Some(lines) if lines.key == key => lines,
+                _ => comment_lines(c, key, p.creator.id, &app.config, &app.theme, now),
+            };
+            let lines = c.lines.insert(lines);
+            new_count = lines.new_count;
+
+            let focused = app
+                .focus_comment
+                .and_then(|id| c.items.iter().position(|c| c.comment.id() == id))
+                .filter(|&i| !lines.comments[i].is_empty());
+            if let Some(i) = focused {
+                focus_row = Some(header_rows + lines.offsets[lines.comments[i].start]);
+                c.state.select(Some(i));
+            }
+            selected_lines = c
+                .state
+                .selected()
+                .and_then(|i| lines.comments.get(i))
+                .cloned();
+
+            c.rows = lines
+                .comments
+                .iter()
+                .map(|l| header_rows + lines.offsets[l.start]..header_rows + lines.offsets[l.end])
+                .collect();
+
+            if streaming {
+                footer.push(Line::from(Span::styled(
+                    "loading more…",
+                    Style::default().add_modifier(Modifier::ITALIC),
+                )));
+            }
+
+            // comments beyond the fetch cap are loaded by hand
+            let total = p.counts.comments();
+            if let Some(fetched) = c
+                .fetched
+                .filter(|_| loading.is_none() && c.remaining(total) > 0)
+            {
+                let load = app
+                    .keymap
+                    .keys(KeyAction::LoadMoreComments)
+                    .first()
+                    .map_or(String::new(), |k| format!(" — press {k} to load more"));
+
+                footer.push(Line::from(Span::styled(
+                    format!(
+                        "showing first {} of {} comments{load}",
+                        format_count(fetched),
+                        format_count(total)
+                    ),
+                    Style::default().add_modifier(Modifier::ITALIC),
+                )));
+            }
+
+            // focus only applies to the first load of the comments, once the comment arrives
+            if focus_row.is_some() || !streaming {
+                app.focus_comment = None;
+            }
+        }
+
+        let empty = CommentLines::default();
+        let lines = app
+            .comments
+            .get(p.post.id())
+            .and_then(|c| c.lines.as_ref())
+            .unwrap_or(&empty);
+        let comment_rows = lines.offsets.last().copied().unwrap_or(0);
+        let footer_offsets = row_offsets(&footer, comment_width);
+
+        app.comment_scroll
+            .set_content_length(header_rows + comment_rows + footer_offsets[footer.len()]);
+        app.comment_scroll.set_viewport_length(comment_height);
+
+        if let Some(row) = focus_row {
+            app.comment_scroll.set_position(row);
+        } else if let Some((id, skip)) = app.comment_anchor.take() {
+            // keep the same comment at the top after merged comments move the rows
+            let rows = app.comments.get(p.post.id()).and_then(|c| {
+                c.rows
+                    .get(c.items.iter().position(|c| c.comment.id() == id)?)
+            });
+            if let Some(rows) = rows {
+                app.comment_scroll.set_position(rows.start + skip);
+            }
+        }
+
+        // only the lines in view are drawn, the first scrolled by its rows above the pane, keeping
+        // the paragraph scroll within a line however long the thread
+        let top = app.comment_scroll.position();
+        let mut comments: Vec<Line> = Vec::new();
+        let mut scroll = None;
+        for (part, offsets, start, highlighted) in [
+            (header.as_slice(), header_offsets.as_slice(), 0, None),
+            (
+                lines.lines.as_slice(),
+                lines.offsets.as_slice(),
+                header_rows,
+                selected_lines,
+            ),
+            (
+                footer.as_slice(),
+                footer_offsets.as_slice(),
+                header_rows + comment_rows,
+                None,
+            ),
+        ] {
+            let visible = visible_lines(offsets, top.saturating_sub(start), comment_height);
+            if visible.is_empty() {
+                continue;
+            }
+
+            scroll.get_or_insert(top.saturating_sub(start + offsets[visible.start]));
+            let highlighted = highlighted.unwrap_or_default();
+            comments.extend(visible.map(|i| {
+                let mut line = part[i].clone();
+                if highlighted.contains(&i) {
+                    line.style = app.theme.highlight;
                 }
+                line
+            }));
+        }
+
+        let mut comment_title = format!("Comments | {}", app.comment_sort);
+        if new_count > 0 {
+            comment_title.push_str(&format!(" | {new_count} new"));
+        }
+        if streaming {
+            let loaded = app.comments.get(p.post.id()).map_or(0, |c| c.items.len());
+            comment_title.push_str(&format!(" | loaded {loaded}/{}", p.counts.comments()));
+        }
+        let comment_block = Paragraph::new(comments)
+            .style(app.theme.body)
+            .block(title_block(comment_title.as_str(), &app.theme))
+            .wrap(Wrap { trim: false })
+            .scroll((
+                scroll.map_or(0, |rows| u16::try_from(rows).unwrap_or(u16::MAX)),
+                0,
+            ));
+
+        f.render_widget(comment_block, chunks[1]);
+
+        let comment_scrollbar = Scrollbar::default()
+            .orientation(orientation.clone())
+            .begin_symbol(Some("▲"))
+            .end_symbol(Some("▼"));
+
+        draw_scrollbar(
+            f,
+            comment_scrollbar,
+            chunks[1].inner(Scroll::margin()),
+            &mut app.comment_scroll.state,
+        );
+
+        let hud = Block::default()
+            .title(app.keymap.hud(&[
+                (&[KeyAction::Quit], "quit"),
+                (&[KeyAction::OpenComments], "select"),
+                (
+                    &[KeyAction::ScrollPostUp, KeyAction::ScrollPostDown],
+                    "scroll post",
+                ),
+                (
+                    &[KeyAction::NextComment, KeyAction::PrevComment],
+                    "select comment",
+                ),
+                (&[KeyAction::ToggleReplies], "collapse"),
+                (&[KeyAction::SortComments], "sort"),
+                (&[KeyAction::NextPost], "next"),
+                (&[KeyAction::PrevPost], "previous"),
+                (&[KeyAction::Refresh], "refresh"),
+                (&[KeyAction::ToggleLive], "live"),
+                (&[KeyAction::Subscribe], "subscribe"),
+                (&[KeyAction::BlockUser], "block user"),
+                (&[KeyAction::Report], "report"),
+                (&[KeyAction::GoTo], "go to comment"),
+                (&[KeyAction::OpenLink], "open"),
+                (&[KeyAction::Yank], "copy"),
+            ]))
+            .title_alignment(Alignment::Right);
 
-                app.comment_scroll.set_content_length(comment_height as u16);
+        f.render_widget(hud, chunks[3]);
+        draw_status_bar(f, app, chunks[4]);
+    }
+
+    draw_confirm(f, app);
+    draw_input(f, app);
+    draw_links(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a mouse event on the Post screen, for the screen `area`.
+///
+/// Scrolling over the post scrolls the post, scrolling anywhere else scrolls the comments.
+pub fn handle_post_mouse(event: event::MouseEvent, app: &mut App, area: Rect) -> Option<Command> {
+    if app.has_error() || app.confirming() || app.inputting() || app.picking_link() {
+        return None;
+    }
+
+    let over_post = post_layout(area)[0].contains(Position::new(event.column, event.row));
+
+    match event.kind {
+        event::MouseEventKind::ScrollDown if over_post => app.post_scroll.next(),
+        event::MouseEventKind::ScrollUp if over_post => app.post_scroll.prev(),
+        event::MouseEventKind::ScrollDown => app.comment_scroll.next(),
+        event::MouseEventKind::ScrollUp => app.comment_scroll.prev(),
+        _ => (),
+    }
 
-                let comment_block = Paragraph::new(comments)
-                    .style(body_style())
-                    .block(title_block("Comments"))
-                    .wrap(Wrap { trim: false })
-                    .scroll((app.comment_scroll.position(), 0));
+    None
+}
+
+/// Handles a key press on the Post screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_post_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::Refresh);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    } else if app.inputting() {
+        return handle_input_key(app, event.code);
+    } else if app.picking_link() {
+        handle_links_key(app, event);
+        return None;
+    } else if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
 
-                f.render_widget(comment_block, chunks[1]);
+    let (action, count) = pending_action(app, Screen::Post, &event)?;
 
-                let comment_scrollbar = Scrollbar::default()
-                    .orientation(orientation.clone())
-                    .begin_symbol(Some("▲"))
-                    .end_symbol(Some("▼"));
+    // spoilers take Enter and Space from the comment actions
+    if matches!(action, KeyAction::OpenComments | KeyAction::ToggleReplies) && toggle_spoilers(app)
+    {
+        return None;
+    }
 
-                f.render_stateful_widget(
-                    comment_scrollbar,
-                    chunks[1].inner(Scroll::margin()),
-                    &mut app.comment_scroll.state,
+    match action {
+        // the first Back stops a download, e.g. a hung one, the next leaves the post
+        KeyAction::Back if app.stop_fetch() => (),
+        KeyAction::Back => {
+            app.cancel_comments_task();
+            app.cancel_replies_task();
+            app.spoilers = Spoilers::default();
+            return app.pop_screen();
+        }
+        KeyAction::OpenComments => app.push_screen(Screen::CommentList),
+        KeyAction::ScrollPostUp => (0..count).for_each(|_| app.post_scroll.prev()),
+        KeyAction::ScrollPostDown => (0..count).for_each(|_| app.post_scroll.next()),
+        KeyAction::ScrollCommentsUp => (0..count).for_each(|_| app.comment_scroll.prev()),
+        KeyAction::ScrollCommentsDown => (0..count).for_each(|_| app.comment_scroll.next()),
+        KeyAction::NextComment => {
+            for _ in 0..count {
+                select_comment(app, CommentResponseTable::next, CommentResponseTable::next);
+            }
+        }
+        KeyAction::PrevComment => {
+            for _ in 0..count {
+                select_comment(
+                    app,
+                    CommentResponseTable::previous,
+                    CommentResponseTable::previous,
                 );
+            }
+        }
+        KeyAction::ToggleReplies => {
+            let selected = app
+                .posts
+                .current()
+                .map(|p| p.post.id())
+                .and_then(|id| app.comments_get_mut(id))
+                .and_then(|c| {
+                    let id = c.current()?.comment.id();
+                    Some((c, id))
+                });
 
-                let hud = Block::default()
-                    .title("| (q) quit | (Enter) select | (▲, ▼) scroll post | (j, k) scroll comment | (n) next | (p) previous |")
-                    .title_alignment(Alignment::Right);
+            // the next draw recomputes the comment rows, and scroll content length
+            match selected {
+                Some((c, id)) => c.toggle_collapsed(id),
+                None => app.set_message("no comment selected"),
+            }
+        }
+        KeyAction::LoadReplies => {
+            let missing = app
+                .posts
+                .current()
+                .and_then(|p| app.comments_get(p.post.id()))
+                .and_then(|c| {
+                    let i = c.state.selected()?;
+                    Some((c.items[i].comment.id(), c.missing_replies(i)))
+                });
 
-                f.render_widget(hud, chunks[4]);
+            match missing {
+                Some((id, n)) if n > 0 => app.set_load_replies(id),
+                Some(_) => app.set_message("no more replies to load"),
+                None => app.set_message("no comment selected"),
             }
-            _ => set_current_screen(Screen::PostList),
         }
-    })?;
+        KeyAction::LoadMoreComments => {
+            let post = app
+                .posts
+                .current()
+                .map(|p| (p.post.id(), p.counts.comments()));
+            let remaining = post.and_then(|(id, total)| {
+                Some(app.comments_get(id)?.remaining(total))
+                    .filter(|_| app.comments_loading(id).is_none())
+            });
 
-    if event::poll(time::Duration::from_millis(200))? {
-        if let event::Event::Key(event) = event::read()? {
-            match event.code {
-                event::KeyCode::Esc => set_current_screen(Screen::PostList),
-                event::KeyCode::Enter => set_current_screen(Screen::CommentList),
-                event::KeyCode::Up => app.post_scroll.prev(),
-                event::KeyCode::Down => app.post_scroll.next(),
-                event::KeyCode::Char('k') => app.comment_scroll.prev(),
-                event::KeyCode::Char('j') => app.comment_scroll.next(),
-                event::KeyCode::Char('n') => {
-                    app.post_scroll.first();
-                    app.comment_scroll.first();
+            match remaining {
+                Some(n) if n > 0 => app.set_load_more_comments(),
+                Some(_) => app.set_message("every comment is loaded"),
+                None => app.set_message("comments are loading"),
+            }
+        }
+        KeyAction::SortComments => {
+            // the draw re-sorts the comments
+            app.comment_sort = app.comment_sort.next();
+            app.comment_scroll.first();
+        }
+        KeyAction::CollapseAll | KeyAction::ExpandAll => {
+            let comments = app
+                .posts
+                .current()
+                .map(|p| p.post.id())
+                .and_then(|id| app.comments_get_mut(id));
 
-                    app.posts.next()
+            if let Some(c) = comments {
+                if action == KeyAction::CollapseAll {
+                    c.collapse_roots();
+                } else {
+                    c.expand_all();
                 }
-                event::KeyCode::Char('p') => {
+
+                // every row moves, so scroll to the selected comment once it is drawn
+                let selected = c.current().map(|c| c.comment.id());
+                app.comment_scroll.first();
+                app.focus_comment = selected;
+            }
+        }
+        KeyAction::PagePostUp => (0..count).for_each(|_| app.post_scroll.page_up()),
+        KeyAction::PagePostDown => (0..count).for_each(|_| app.post_scroll.page_down()),
+        KeyAction::PostTop => app.post_scroll.first(),
+        KeyAction::PostBottom => app.post_scroll.last(),
+        KeyAction::PageCommentsUp => (0..count).for_each(|_| app.comment_scroll.page_up()),
+        KeyAction::PageCommentsDown => (0..count).for_each(|_| app.comment_scroll.page_down()),
+        KeyAction::CommentsTop => {
+            app.comment_scroll.first();
+            select_comment(app, CommentResponseTable::first, CommentResponseTable::next);
+        }
+        KeyAction::CommentsBottom => {
+            app.comment_scroll.last();
+            select_comment(
+                app,
+                CommentResponseTable::last,
+                CommentResponseTable::previous,
+            );
+        }
+        KeyAction::NextNew => select_new_comment(app, true),
+        KeyAction::PrevNew => select_new_comment(app, false),
+        KeyAction::NextPost => {
+            app.post_scroll.first();
+            app.comment_scroll.first();
+            app.spoilers = Spoilers::default();
+
+            app.posts.next()
+        }
+        KeyAction::PrevPost => {
+            app.post_scroll.first();
+            app.comment_scroll.first();
+            app.spoilers = Spoilers::default();
+
+            app.posts.previous()
+        }
+        KeyAction::OpenImage => {
+            if app.posts.current().is_some_and(|p| app.nsfw_blurred(p)) {
+                app.set_message("NSFW image hidden, reveal the post first");
+            } else if let Some(p) = app.posts.current() {
+                let url = p.post.url().to_owned();
+                let video = match p.post.embed_video_url() {
+                    "" => Some(url.clone()).filter(|u| is_video(u)),
+                    embed => Some(embed.to_owned()),
+                };
+
+                let player = app.config.video_player().map(str::to_owned);
+                let viewer = app.config.image_viewer().map(str::to_owned);
+
+                if let (Some(video), Some(player)) = (video, player) {
+                    app.set_viewer(player, video);
+                } else if !is_image(&url) {
+                    app.set_message("not an image link");
+                } else if let Some(viewer) = viewer {
+                    app.set_viewer(viewer, url);
+                } else if app.is_offline() {
+                    app.set_message("offline mode: images unavailable");
+                } else {
+                    app.open_image(url);
+                }
+            }
+        }
+        KeyAction::OpenLink => {
+            if app.posts.current().is_some_and(|p| app.nsfw_blurred(p)) {
+                app.set_message("NSFW link hidden, reveal the post first");
+            } else if let Some(p) = app.posts.current() {
+                let url = match p.post.url() {
+                    "" => p.post.ap_id(),
+                    url => url,
+                };
+
+                app.set_open_url(url.to_owned());
+            }
+        }
+        KeyAction::OpenApId | KeyAction::YankApId => {
+            if let Some(p) = app.posts.current() {
+                // the selected comment, if any, otherwise the post
+                let ap_id = app
+                    .comments_get(p.post.id())
+                    .and_then(|c| c.current())
+                    .map_or(p.post.ap_id(), |c| c.comment.ap_id())
+                    .to_owned();
+
+                if action == KeyAction::OpenApId {
+                    app.set_open_url(ap_id);
+                } else {
+                    app.set_clipboard(ap_id);
+                }
+            }
+        }
+        KeyAction::Yank => {
+            if let Some(p) = app.posts.current() {
+                let comment = app
+                    .comments_get(p.post.id())
+                    .and_then(|c| c.current())
+                    .map(|c| c.comment.content().to_owned());
+
+                match comment {
+                    Some(content) => app.set_clipboard(content),
+                    None if app.nsfw_blurred(p) => {
+                        app.set_message("NSFW link hidden, reveal the post first")
+                    }
+                    None => {
+                        let url = match p.post.url() {
+                            "" => p.post.ap_id(),
+                            url => url,
+                        };
+                        app.set_clipboard(url.to_owned());
+                    }
+                }
+            }
+        }
+        KeyAction::Pager => {
+            if let Some(p) = app.posts.current() {
+                let comment = app
+                    .comments_get(p.post.id())
+                    .and_then(|c| c.current())
+                    .map(|c| {
+                        let reveal = app.spoilers.comments.contains(&c.comment.id());
+                        (c.comment.content().to_owned(), reveal)
+                    });
+
+                match comment {
+                    Some((content, reveal)) => app.set_pager(content, reveal),
+                    None if app.nsfw_blurred(p) => {
+                        app.set_message("NSFW content hidden, reveal the post first")
+                    }
+                    None => {
+                        let body = if p.post.deleted {
+                            "[deleted]"
+                        } else {
+                            p.post.body()
+                        };
+                        let text = format!("# {}\n\n{body}", p.post.name().trim());
+                        app.set_pager(text, app.spoilers.post);
+                    }
+                }
+            }
+        }
+        KeyAction::ShowLinks => {
+            if let Some(p) = app.posts.current() {
+                let mut text = String::new();
+                if !app.nsfw_blurred(p) {
+                    text.push_str(p.post.body());
+                }
+                if let Some(c) = app.comments_get(p.post.id()).and_then(|c| c.current()) {
+                    text.push('\n');
+                    text.push_str(c.comment.content());
+                }
+
+                let links = extract_links(&text);
+                if links.is_empty() {
+                    app.set_message("no links found");
+                } else {
+                    app.set_links(links);
+                }
+            }
+        }
+        KeyAction::Refresh => {
+            let post_id = app.posts.current().map(|p| p.post.id());
+            if post_id.is_some_and(|id| app.debounce_refresh(RefreshTarget::Post(id))) {
+                return Some(Command::Refresh);
+            }
+        }
+        KeyAction::ToggleLive => app.toggle_live(),
+        KeyAction::ExportPost => {
+            if let Some(p) = app.posts.current() {
+                let kind = InputKind::ExportPost {
+                    post_id: p.post.id(),
+                };
+                let path = export::default_path(p);
+
+                app.set_input_text(kind, &path);
+            }
+        }
+        KeyAction::Report => {
+            if let Some(p) = app.posts.current() {
+                // report the selected comment, if any, otherwise report the post
+                let kind = app
+                    .comments_get(p.post.id())
+                    .and_then(|c| c.current())
+                    .map(|c| InputKind::ReportComment {
+                        comment_id: c.comment.id(),
+                    })
+                    .unwrap_or(InputKind::ReportPost {
+                        post_id: p.post.id(),
+                    });
+
+                app.set_input(kind);
+            }
+        }
+        KeyAction::ToggleSave => {
+            if let Some(p) = app.posts.current() {
+                // save the selected comment, if any, otherwise save the post
+                let action = app
+                    .comments_get(p.post.id())
+                    .and_then(|c| c.current())
+                    .map(|c| Action::SaveComment {
+                        comment_id: c.comment.id(),
+                        save: !c.saved,
+                    })
+                    .unwrap_or(Action::SavePost {
+                        post_id: p.post.id(),
+                        save: !p.saved,
+                    });
+
+                app.set_action(action);
+            }
+        }
+        KeyAction::ShowModlog => {
+            if let Some(community) = app.posts.current().map(|p| p.community.clone()) {
+                return app.open_modlog(community);
+            }
+        }
+        KeyAction::GoTo => {
+            let has_comments = app
+                .posts
+                .current()
+                .and_then(|p| app.comments_get(p.post.id()))
+                .is_some_and(|c| !c.items.is_empty());
+
+            if has_comments {
+                app.set_input(InputKind::GoToComment);
+            } else {
+                app.set_message("no comments");
+            }
+        }
+        KeyAction::BlockUser => {
+            let creator = app
+                .posts
+                .current()
+                .and_then(|p| app.comments_get(p.post.id()))
+                .and_then(|c| c.current())
+                .map(|c| (c.creator.id, c.creator.name().to_owned()));
+
+            match creator {
+                Some((person_id, name)) => app.set_confirm(Confirm::new(
+                    format!("Block user {name}?"),
+                    Action::BlockPerson {
+                        person_id,
+                        block: true,
+                    },
+                )),
+                None => app.set_message("no comment selected"),
+            }
+        }
+        KeyAction::RevealNsfw => {
+            if let Some(p) = app.posts.current().filter(|p| app.nsfw_blurred(p)) {
+                let post_id = p.post.id();
+                app.nsfw_revealed.insert(post_id);
+            }
+        }
+        // the count is the digit typed after the key
+        KeyAction::OpenCrossPost => {
+            let cross_post = app
+                .posts
+                .current()
+                .and_then(|p| app.cross_posts(p.post.id()))
+                .and_then(|c| c.get(count.checked_sub(1)?))
+                .cloned();
+
+            match cross_post {
+                Some(post) => {
                     app.post_scroll.first();
                     app.comment_scroll.first();
+                    app.spoilers = Spoilers::default();
 
-                    app.posts.previous()
+                    app.posts.select_or_insert(post);
                 }
-                event::KeyCode::Char('i') => set_current_screen(Screen::Image),
-                event::KeyCode::Char('c') => {
-                    if event.modifiers == event::KeyModifiers::CONTROL {
-                        stop.store(true, Ordering::SeqCst);
-                    }
+                None => app.set_message(format!("no crosspost {count}")),
+            }
+        }
+        KeyAction::Subscribe => {
+            if let Some(p) = app.posts.current() {
+                let community_id = p.community.id;
+                let follow = !p.subscribed.is_subscribed();
+
+                if community_id == 0 {
+                    app.set_message("post has no community to subscribe to");
+                } else {
+                    app.set_action(Action::FollowCommunity {
+                        community_id,
+                        follow,
+                    });
                 }
-                event::KeyCode::Char('q') => stop.store(true, Ordering::SeqCst),
-                _ => (),
             }
         }
+        KeyAction::Quit => return app.quit(),
+        _ => (),
     }
 
-    Ok(())
+    None
 }
 
-fn filter_line<'l>(raw: &'l str, width: usize) -> impl Iterator<Item = String> + 'l {
-    let mut words = raw.split(" ").peekable();
+/// Reveals, or hides, the spoilers of the selected comment, or of the post body if no comment is
+/// selected.
+///
+/// Returns `false` if there are no spoilers to toggle.
+fn toggle_spoilers(app: &mut App) -> bool {
+    let Some(p) = app.posts.current() else {
+        return false;
+    };
+
+    let comment = app
+        .comments_get(p.post.id())
+        .and_then(|c| c.current())
+        .map(|c| (c.comment.id(), markdown::has_spoiler(c.comment.content())));
+
+    match comment {
+        Some((id, true)) => {
+            if !app.spoilers.comments.remove(&id) {
+                app.spoilers.comments.insert(id);
+            }
+            true
+        }
+        Some((_, false)) => false,
+        None if markdown::has_spoiler(p.post.body()) => {
+            app.spoilers.post = !app.spoilers.post;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Selects the next, or previous, comment published since the last visit to the current post, or
+/// added in live mode, wrapping around, then scrolls the comment pane to keep it in view.
+///
+/// Replies hidden by a collapsed comment are skipped.
+fn select_new_comment(app: &mut App, forward: bool) {
+    let Some(post_id) = app.posts.current().map(|p| p.post.id()) else {
+        return;
+    };
+    let since = app.visits.since(post_id);
+
+    let selected = app.comments_get_mut(post_id).and_then(|c| {
+        let hidden = c.hidden();
+        let new: Vec<usize> = (0..c.items.len())
+            .filter(|&i| !hidden[i] && c.is_new(i, since))
+            .collect();
+
+        let current = c.state.selected();
+        let i = if forward {
+            new.iter()
+                .find(|&&i| current.is_none_or(|s| i > s))
+                .or(new.first())
+        } else {
+            new.iter()
+                .rev()
+                .find(|&&i| current.is_none_or(|s| i < s))
+                .or(new.last())
+        };
+
+        let i = *i?;
+        c.state.select(Some(i));
+        Some(c.rows.get(i).cloned())
+    });
+
+    match selected {
+        Some(Some(rows)) => app.comment_scroll.show(rows),
+        Some(None) => (),
+        None => app.set_message("no new comments"),
+    }
+}
 
-    std::iter::from_fn(move || {
-        if words.peek().is_some() {
-            let mut line_len = 0;
-            let mut line = String::new();
+/// Moves the comment selection of the current post with `select`, moving on with `skip` past hidden
+/// replies, then scrolls the comment pane to keep the selected comment in view.
+fn select_comment(
+    app: &mut App,
+    select: fn(&mut CommentResponseTable),
+    skip: fn(&mut CommentResponseTable),
+) {
+    let Some(post_id) = app.posts.current().map(|p| p.post.id()) else {
+        return;
+    };
 
-            while let Some(w) = words.next() {
-                let wf: String = w
-                    .chars()
-                    .filter(|c| !c.is_whitespace() && !c.is_control())
-                    .collect();
-                let count = wf.chars().count();
+    let rows = match app.comments_get_mut(post_id) {
+        Some(c) if !c.items.is_empty() => {
+            let hidden = c.hidden();
 
-                if line_len + count >= width {
-                    line = format!("{line} {wf}");
+            // skip replies hidden by a collapsed comment
+            select(c);
+            for _ in 0..c.items.len() {
+                if !c.state.selected().is_some_and(|i| hidden[i]) {
                     break;
+                }
+                skip(c);
+            }
+
+            // every comment is hidden, e.g. all from bots
+            if c.state.selected().is_some_and(|i| hidden[i]) {
+                c.state.select(None);
+            }
+
+            c.state.selected().and_then(|i| c.rows.get(i)).cloned()
+        }
+        _ => None,
+    };
+
+    if let Some(rows) = rows {
+        app.comment_scroll.show(rows);
+    }
+}
+
+/// Gets the lines listing the crossposts of the post, e.g. `(x1) also posted in !rust@lemmy.ml
+/// (12 comments)`, up to [MAX_CROSS_POSTS].
+///
+/// Posts without crossposts, or not downloaded yet, have no lines.
+fn cross_post_lines(app: &App, post_id: u64) -> Vec<Line<'static>> {
+    let cross_posts = app.cross_posts(post_id).unwrap_or_default();
+    // only character keys take the digit after them
+    let key = app
+        .keymap
+        .keys(KeyAction::OpenCrossPost)
+        .iter()
+        .find(|k| matches!(k.code, event::KeyCode::Char(_)))
+        .map(ToString::to_string);
+
+    let mut lines: Vec<Line> = cross_posts
+        .iter()
+        .take(MAX_CROSS_POSTS)
+        .enumerate()
+        .map(|(i, p)| {
+            let comments = match p.counts.comments() {
+                1 => "1 comment".to_owned(),
+                n => format!("{n} comments"),
+            };
+            let mut spans = Vec::new();
+            if let Some(key) = key.as_deref() {
+                spans.push(Span::styled(format!("({key}{}) ", i + 1), app.theme.link));
+            }
+            spans.push(Span::raw(format!(
+                "also posted in {} ({comments})",
+                community_handle(&p.community, &app.config)
+            )));
+
+            Line::from(spans)
+        })
+        .collect();
+
+    if cross_posts.len() > MAX_CROSS_POSTS {
+        let more = cross_posts.len() - MAX_CROSS_POSTS;
+        lines.push(Line::from(format!("… and {more} more")));
+    }
+
+    lines
+}
+
+/// Renders the lines of every comment in the table, for drawing until the comments, or the
+/// [CommentLinesKey], change. Replies to collapsed comments take no lines.
+///
+/// Each comment has its markdown body, indented with guides by level, and an info line, followed
+/// by markers for hidden, and missing, replies. New comments are bold.
+fn comment_lines(
+    c: &CommentResponseTable,
+    key: CommentLinesKey,
+    op_id: u64,
+    config: &Config,
+    theme: &Theme,
+    now: SystemTime,
+) -> CommentLines {
+    let width = key.width as usize;
+    let hidden = c.hidden();
+
+    // multiple `Line`s per-comment for spacing/formatting
+    let mut lines: Vec<Line<'static>> = Vec::with_capacity(c.items.len() * 5);
+    let mut comments = Vec::with_capacity(c.items.len());
+    let mut new_count = 0;
+
+    for (i, cr) in c.items.iter().enumerate() {
+        let start = lines.len();
+        let new = c.is_new(i, key.since);
+        if new {
+            new_count += 1;
+        }
+
+        // replies to collapsed comments take no rows
+        if hidden[i] {
+            comments.push(start..start);
+            continue;
+        }
+
+        // add indent guides by level after a margin, marking malformed paths
+        let tabs = match cr.comment.path_level() {
+            Some(_) => [Span::raw(" ")]
+                .into_iter()
+                .chain(indent_guides(
+                    cr.level(),
+                    &config.indent_guide,
+                    &theme.indent,
+                    width / 2,
+                ))
+                .collect(),
+            None => vec![Span::raw("[?] ")],
+        };
+        let indented = |spans: Vec<Span<'static>>| {
+            Line::from(tabs.iter().cloned().chain(spans).collect::<Vec<_>>())
+        };
+
+        // blocked creators' comments are collapsed to a single-line stub
+        if cr.creator_blocked {
+            lines.push(indented(vec![Span::raw("[blocked user]")]));
+        } else {
+            let mut info = comment_info(cr, op_id, config, theme, now);
+            if new {
+                info.insert(0, Span::styled("[NEW] ", theme.new));
+            }
+
+            // wrap inside the indent prefix, so wrapped lines keep their guides
+            let indent: usize = tabs.iter().map(Span::width).sum();
+            let reveal = key.revealed.binary_search(&cr.comment.id()).is_ok();
+            for line in markdown::render(
+                cr.comment.content(),
+                width.saturating_sub(indent),
+                reveal,
+                theme,
+            ) {
+                if !line.spans.is_empty() {
+                    lines.push(indented(line.spans));
+                } else if tabs.iter().all(|s| s.content.trim().is_empty()) {
+                    // whitespace-only lines wrap to an extra row
+                    lines.push(Line::default());
                 } else {
-                    line_len += count;
-                    line = format!("{line} {wf}");
+                    lines.push(indented(Vec::new()));
                 }
             }
 
-            Some(line)
+            lines.push(indented(info));
+        }
+
+        let replies = c.reply_count(i);
+        if replies > 0 && c.collapsed.contains(&cr.comment.id()) {
+            let summary = match replies {
+                1 => "[+] 1 reply hidden".to_owned(),
+                n => format!("[+] {n} replies hidden"),
+            };
+
+            lines.push(indented(vec![Span::styled(
+                summary,
+                Style::default().add_modifier(Modifier::ITALIC),
+            )]));
+        }
+
+        // replies below the depth returned with the post, loaded on demand
+        let missing = c.missing_replies(i);
+        if missing > 0 && !c.collapsed.contains(&cr.comment.id()) {
+            let replies = match missing {
+                1 => "1 more reply".to_owned(),
+                n => format!("{n} more replies"),
+            };
+            let marker = if key.loading_replies == Some(cr.comment.id()) {
+                format!("[…] loading {replies}")
+            } else {
+                format!("[+] load {replies}")
+            };
+
+            lines.push(indented(vec![Span::styled(
+                marker,
+                Style::default().add_modifier(Modifier::ITALIC),
+            )]));
+        }
+
+        if new {
+            lines[start..]
+                .iter_mut()
+                .for_each(|line| line.style = line.style.add_modifier(Modifier::BOLD));
+        }
+
+        comments.push(start..lines.len());
+
+        if cr.creator_blocked {
+            lines.push(Line::from(""));
         } else {
+            lines.extend_from_slice(&[Line::from(""), Line::from("")]);
+        }
+    }
+
+    let offsets = row_offsets(&lines, key.width);
+    CommentLines {
+        key,
+        lines,
+        comments,
+        offsets,
+        new_count,
+    }
+}
+
+/// Gets the range of lines, starting at the row `offsets` from [row_offsets], with rows in view
+/// of the `height` rows from `top`.
+fn visible_lines(offsets: &[usize], top: usize, height: usize) -> Range<usize> {
+    let Some(len) = offsets.len().checked_sub(1) else {
+        return 0..0;
+    };
+    // the first line ending below the top, up to the first starting below the bottom
+    let start = offsets[1..].partition_point(|&end| end <= top);
+    let end = offsets[..len].partition_point(|&row| row < top.saturating_add(height));
+
+    start..end.max(start)
+}
+
+/// Gets the row each of the `lines` starts at when wrapped to `width`, followed by the total
+/// number of rows.
+fn row_offsets(lines: &[Line], width: u16) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut row = 0;
+
+    offsets.push(row);
+    for line in lines {
+        row += measure_lines(std::slice::from_ref(line), width);
+        offsets.push(row);
+    }
+
+    offsets
+}
+
+/// Gets the info line [Span]s for a comment: author, score, age, and number of replies.
+///
+/// The author is tagged as the original poster (`op_id`), moderator, admin, or bot. Negative
+/// scores, and scores of at least the configured high score, are styled by the [Theme]. The
+/// score is marked with the vote of the logged-in user, and `★` if saved.
+fn comment_info(
+    cr: &CommentResponse,
+    op_id: u64,
+    config: &Config,
+    theme: &Theme,
+    now: SystemTime,
+) -> Vec<Span<'static>> {
+    let counts = &cr.counts;
+    let score = counts.score();
+
+    let mut score_text = score.to_string();
+    if counts.downvotes() > 0 {
+        score_text.push_str(&format!(" (+{}/-{})", counts.upvotes(), counts.downvotes()));
+    }
+
+    let score_style = if score < 0 {
+        theme.score_negative
+    } else if score >= config.high_score {
+        theme.score_high
+    } else {
+        Style::default()
+    };
+
+    let published = cr.comment.published();
+    let mut age = if published.is_empty() {
+        String::new()
+    } else {
+        format!(", {}", format_timestamp(published, config.timestamps, now))
+    };
+    if cr.comment.edited() {
+        age.push_str(" (edited)");
+    }
+
+    let creator = &cr.creator;
+    let author_style = if creator.id == op_id {
+        theme.op
+    } else {
+        Style::default()
+    };
+
+    let mut spans = vec![
+        Span::raw("[ author: "),
+        Span::styled(creator_handle(creator, config), author_style),
+    ];
+
+    let tags = [
+        (creator.id == op_id, "OP", theme.op),
+        (cr.comment.distinguished(), "mod", theme.moderator),
+        (creator.admin == Some(true), "admin", theme.admin),
+        (creator.bot_account, "bot", theme.bot),
+    ];
+    for (_, tag, style) in tags.into_iter().filter(|(is_tagged, ..)| *is_tagged) {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(format!("[{tag}]"), style));
+    }
+
+    spans.extend([
+        Span::raw(", score: "),
+        Span::styled(score_text, score_style),
+    ]);
+    if cr.my_vote.is_some_and(|vote| vote != 0) {
+        spans.extend([Span::raw(" "), vote_span(cr.my_vote, theme)]);
+    }
+    if cr.saved {
+        spans.extend([Span::raw(" "), saved_span(theme)]);
+    }
+    spans.push(Span::raw(format!(
+        "{age}, child comments: {} ]",
+        counts.child_count()
+    )));
+    if cr.orphaned {
+        spans.extend([Span::raw(" "), Span::styled("⚠ orphaned", theme.error)]);
+    }
+
+    spans
+}
+
+/// Gets a styled [Span] for the [SubscribedType] of a [Community].
+///
+/// `Pending` subscriptions to communities on remote instances are displayed distinctly, since
+/// they require approval from the remote instance.
+fn subscribed_span(
+    community: &Community,
+    subscribed: SubscribedType,
+    theme: &Theme,
+) -> Span<'static> {
+    match subscribed {
+        SubscribedType::Subscribed => {
+            Span::styled("Subscribed", Style::default().add_modifier(Modifier::BOLD))
+        }
+        SubscribedType::NotSubscribed => Span::raw("NotSubscribed"),
+        SubscribedType::Pending if !community.local => {
+            Span::styled("Pending (remote)", theme.pending)
+        }
+        SubscribedType::Pending => {
+            Span::styled("Pending", Style::default().add_modifier(Modifier::ITALIC))
+        }
+    }
+}
+
+/// Gets the indent guide [Span]s for a comment at `level`, one `guide` per level, colored from
+/// the `palette` by level.
+///
+/// Guides of deeply nested comments are capped to `max_width` display columns, leaving room for
+/// the comment text.
+fn indent_guides(
+    level: usize,
+    guide: &str,
+    palette: &[Color],
+    max_width: usize,
+) -> Vec<Span<'static>> {
+    let levels = level.min(max_width / guide.width().max(1));
+
+    (0..levels)
+        .map(|l| {
+            let style = palette
+                .get(l % palette.len().max(1))
+                .map_or_else(Style::default, |&color| Style::default().fg(color));
+            Span::styled(guide.to_owned(), style)
+        })
+        .collect()
+}
+
+/// Wraps a paragraph into lines of at most `width` display columns.
+///
+/// Runs of spaces between words are kept, apart from where the lines wrap, with tabs expanded and
+/// control characters dropped, see [markdown::expand_tabs]. Words that do not fit move to the next
+/// line, and words wider than `width`, e.g. long URLs, are broken across lines.
+fn filter_line(raw: &str, width: usize) -> impl Iterator<Item = String> {
+    let width = width.max(1);
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    // spaces before the next word
+    let mut gap = 0;
+
+    for (i, word) in markdown::expand_tabs(raw).split(' ').enumerate() {
+        if i > 0 {
+            gap += 1;
+        }
+
+        for piece in split_width(word, width) {
+            let w = piece.width();
+
+            if line_width > 0 && line_width + gap + w > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+
+            if line_width > 0 {
+                line.extend(std::iter::repeat_n(' ', gap));
+                line_width += gap;
+            }
+
+            line.push_str(piece);
+            line_width += w;
+            gap = 0;
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+
+    use super::*;
+    use crate::api::fake::FakeClient;
+    use crate::comments::CommentSort;
+    use crate::config::NsfwMode;
+    use crate::screen::snapshot::{assert_snapshot, buffer_text, fixture_app, render};
+    use crate::screen::INDENT_COLORS;
+    use crate::session::Visits;
+    use crate::task::CommentsTask;
+    use crate::utils::parse_timestamp;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_render_post_screen() {
+        for (width, height) in [(80, 24), (120, 40)] {
+            let mut app = fixture_app();
+            let buffer = render(width, height, &mut app, render_post_screen);
+
+            assert_snapshot(&format!("post_{width}x{height}"), &buffer);
+        }
+    }
+
+    #[test]
+    fn test_nsfw_blur() {
+        let mut app = fixture_app();
+        app.config.nsfw = NsfwMode::Blur;
+        app.posts.current_mut().unwrap().post.nsfw = true;
+
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│[NSFW] !rust@lemmy.ml "));
+        assert!(text.contains("│[NSFW content hidden] (v) reveal "));
+        assert!(!text.contains("Announcing Rust"));
+        assert!(!text.contains("https://blog.rust-lang.org"));
+
+        let text = buffer_text(&render(
+            120,
+            40,
+            &mut app,
+            crate::screen::render_posts_screen,
+        ));
+        assert!(text.contains(" 1 c [link blog.rust-lang.org] [NSFW] !rust@lemmy.ml "));
+
+        // images stay hidden until the post is revealed
+        handle_post_input(KeyEvent::from(KeyCode::Char('i')), &mut app);
+        assert_eq!(app.screen(), Screen::PostList);
+        assert_eq!(
+            app.hud_message(),
+            "NSFW image hidden, reveal the post first"
+        );
+        handle_post_input(KeyEvent::from(KeyCode::Char('o')), &mut app);
+        assert_eq!(app.take_open_url(), None);
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('v')), &mut app);
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│Announcing Rust 1.76.0 "));
+        assert!(text.contains("https://blog.rust-lang.org"));
+
+        // revealed, but not an image
+        handle_post_input(KeyEvent::from(KeyCode::Char('i')), &mut app);
+        assert_eq!(app.screen(), Screen::PostList);
+        assert_eq!(app.hud_message(), "not an image link");
+
+        app.posts.current_mut().unwrap().post.url =
+            Some("https://lemmy.ml/pictrs/image/cat.png".into());
+        handle_post_input(KeyEvent::from(KeyCode::Char('i')), &mut app);
+        assert_eq!(app.screen(), Screen::Image);
+    }
+
+    #[test]
+    fn test_open_viewer() {
+        let mut app = fixture_app();
+        app.config.image_viewer = Some("swayimg %u".into());
+        app.config.video_player = Some("mpv %u".into());
+        let open = |app: &mut App| {
+            handle_post_input(KeyEvent::from(KeyCode::Char('i')), app);
+            app.take_viewer()
+        };
+
+        app.posts.current_mut().unwrap().post.url =
+            Some("https://lemmy.ml/pictrs/image/cat.png".into());
+        assert_eq!(
+            open(&mut app),
+            Some((
+                "swayimg %u".into(),
+                "https://lemmy.ml/pictrs/image/cat.png".into()
+            ))
+        );
+        assert_eq!(app.screen(), Screen::PostList);
+
+        app.posts.current_mut().unwrap().post.url = Some("https://example.com/clip.webm".into());
+        assert_eq!(
+            open(&mut app),
+            Some(("mpv %u".into(), "https://example.com/clip.webm".into()))
+        );
+
+        // the embedded video, instead of the link
+        let post = &mut app.posts.current_mut().unwrap().post;
+        post.url = Some("https://youtube.com/watch?v=1".into());
+        post.embed_video_url = Some("https://youtube.com/embed/1".into());
+        assert_eq!(
+            open(&mut app),
+            Some(("mpv %u".into(), "https://youtube.com/embed/1".into()))
+        );
+
+        // without a viewer, images open on the Image screen
+        app.config.image_viewer = None;
+        app.posts.current_mut().unwrap().post.url =
+            Some("https://lemmy.ml/pictrs/image/cat.png".into());
+        app.posts.current_mut().unwrap().post.embed_video_url = None;
+        assert_eq!(open(&mut app), None);
+        assert_eq!(app.screen(), Screen::Image);
+    }
+
+    #[test]
+    fn test_open_link() {
+        let mut app = fixture_app();
+        let open = |app: &mut App, key| {
+            handle_post_input(KeyEvent::from(KeyCode::Char(key)), app);
+            app.take_open_url()
+        };
+
+        assert_eq!(
+            open(&mut app, 'o').as_deref(),
+            Some("https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html")
+        );
+        assert_eq!(
+            open(&mut app, 'O').as_deref(),
+            Some("https://lemmy.ml/post/10822441")
+        );
+
+        // the selected comment, instead of the post
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        assert_eq!(
+            open(&mut app, 'O').as_deref(),
+            Some("https://lemmy.ml/comment/8100321")
+        );
+
+        // text posts fall back to the post itself
+        app.posts.current_mut().unwrap().post.url = None;
+        assert_eq!(
+            open(&mut app, 'o').as_deref(),
+            Some("https://lemmy.ml/post/10822441")
+        );
+    }
+
+    #[test]
+    fn test_show_links() {
+        let mut app = fixture_app();
+        let key = |app: &mut App, code| handle_post_input(KeyEvent::from(code), app);
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('f')), &mut app);
+        assert!(!app.picking_link());
+        assert_eq!(app.hud_message(), "no links found");
+
+        app.posts.current_mut().unwrap().post.body = Some(
+            "Read [the notes](https://blog.rust-lang.org/notes) or https://lemmy.ml/post/1.".into(),
+        );
+        key(&mut app, KeyCode::Char('f'));
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│1. https://blog.rust-lang.org/notes "));
+        assert!(text.contains("│2. https://lemmy.ml/post/1 "));
+
+        // other keys go to the overlay
+        key(&mut app, KeyCode::Char('j'));
+        assert!(app.comments_get(10822441).unwrap().current().is_none());
+
+        key(&mut app, KeyCode::Char('2'));
+        assert!(!app.picking_link());
+        assert_eq!(
+            app.take_open_url().as_deref(),
+            Some("https://lemmy.ml/post/1")
+        );
+
+        key(&mut app, KeyCode::Char('f'));
+        handle_post_input(
+            KeyEvent::new(KeyCode::Char('1'), event::KeyModifiers::ALT),
+            &mut app,
+        );
+        assert_eq!(app.take_open_url(), None);
+        assert_eq!(
+            app.take_clipboard().as_deref(),
+            Some("https://blog.rust-lang.org/notes")
+        );
+
+        key(&mut app, KeyCode::Char('f'));
+        key(&mut app, KeyCode::Esc);
+        assert!(!app.picking_link());
+
+        // comment links follow the post links
+        key(&mut app, KeyCode::Char('j'));
+        app.comments_get_mut(10822441).unwrap().items[0]
+            .comment
+            .content = "https://lemmy.ml/post/1 and https://crates.io".into();
+        key(&mut app, KeyCode::Char('f'));
+        assert_eq!(
+            app.links.as_ref().unwrap().links,
+            [
+                "https://blog.rust-lang.org/notes",
+                "https://lemmy.ml/post/1",
+                "https://crates.io"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yank() {
+        let mut app = fixture_app();
+        let yank = |app: &mut App, key| {
+            handle_post_input(KeyEvent::from(KeyCode::Char(key)), app);
+            app.take_clipboard()
+        };
+
+        assert_eq!(
+            yank(&mut app, 'y').as_deref(),
+            Some("https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html")
+        );
+        assert_eq!(
+            yank(&mut app, 'Y').as_deref(),
+            Some("https://lemmy.ml/post/10822441")
+        );
+        assert_eq!(app.take_open_url(), None);
+
+        // the selected comment text, instead of the post link
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        assert_eq!(
+            yank(&mut app, 'y').as_deref(),
+            Some("`Option::inspect` is going to clean up so much debugging code.")
+        );
+        assert_eq!(
+            yank(&mut app, 'Y').as_deref(),
+            Some("https://lemmy.ml/comment/8100321")
+        );
+    }
+
+    #[test]
+    fn test_pager() {
+        let mut app = fixture_app();
+        let key = KeyEvent::from(KeyCode::Char('|'));
+
+        handle_post_input(key, &mut app);
+        let (text, reveal) = app.take_pager().unwrap();
+        assert!(text.starts_with("# Announcing Rust 1.76.0\n\n"), "{text}");
+        assert!(!reveal);
+
+        // the selected comment, instead of the post
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        handle_post_input(key, &mut app);
+        assert_eq!(
+            app.take_pager(),
+            Some((
+                "`Option::inspect` is going to clean up so much debugging code.".into(),
+                false
+            ))
+        );
+    }
+
+    #[test]
+    fn test_render_post_tags() {
+        let mut app = fixture_app();
+        let p = app.posts.current_mut().unwrap();
+        p.post.locked = Some(true);
+        p.post.featured_community = Some(true);
+        p.post.body = None;
+        p.saved = true;
+
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("┌Post─★─[pinned]─[locked]─"));
+        assert!(text.contains("│Announcing Rust 1.76.0 | Rust Blog "));
+
+        app.post_scroll.last();
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│Empowering everyone to build reliable and efficient software."));
+
+        let text = buffer_text(&render(
+            120,
+            40,
+            &mut app,
+            crate::screen::render_posts_screen,
+        ));
+        assert!(
+            text.contains("★ [pinned] [locked] [link blog.rust-lang.org] Announcing Rust 1.76.0")
+        );
+    }
+
+    #[test]
+    fn test_cross_posts() {
+        let mut app = fixture_app();
+        app.push_screen(Screen::Post);
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        let post = app.posts.current().unwrap().clone();
+        let post_id = post.post.id();
+
+        // not downloaded yet
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        assert!(!text.contains("also posted in"));
+
+        let cross_posts: Vec<_> = (1..=10)
+            .map(|i| {
+                let mut p = post.clone();
+                p.post.id = post_id + i;
+                p.community.name = format!("rust{i}");
+                p.counts.comments = Some(i);
+                p
+            })
+            .collect();
+        app.set_cross_posts(post_id, cross_posts);
+
+        let text = buffer_text(&render(80, 100, &mut app, render_post_screen));
+        assert!(text.contains("│(x1) also posted in !rust1@lemmy.ml (1 comment)"));
+        assert!(text.contains("│(x9) also posted in !rust9@lemmy.ml (9 comments)"));
+        assert!(!text.contains("rust10"));
+        assert!(text.contains("│… and 1 more"));
+
+        // the digit after the key picks the crosspost
+        handle_post_input(key('x'), &mut app);
+        assert_eq!(app.pending.typed(), "x");
+        handle_post_input(key('2'), &mut app);
+        assert!(app.pending.is_empty());
+        assert_eq!(app.posts.current().unwrap().post.id(), post_id + 2);
+        assert_eq!(app.screen(), Screen::Post);
+
+        // this crosspost has no crossposts downloaded yet
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        assert!(!text.contains("also posted in"));
+        handle_post_input(key('x'), &mut app);
+        handle_post_input(key('1'), &mut app);
+        assert_eq!(app.hud_message(), "no crosspost 1");
+
+        // other keys cancel the key, without acting
+        app.posts.select_or_insert(post);
+        handle_post_input(key('x'), &mut app);
+        handle_post_input(key('j'), &mut app);
+        handle_post_input(key('0'), &mut app);
+        assert!(app.pending.is_empty());
+        assert_eq!(app.posts.current().unwrap().post.id(), post_id);
+    }
+
+    #[test]
+    fn test_handle_post_input() {
+        let mut app = fixture_app();
+
+        // no comment selected, so the post is reported
+        handle_post_input(KeyEvent::from(KeyCode::Char('!')), &mut app);
+        assert_eq!(
+            app.input.as_ref().map(|i| i.prompt()),
+            Some("Report post, reason")
+        );
+
+        let command = handle_post_input(KeyEvent::from(KeyCode::Char('q')), &mut app);
+        assert_eq!(command, None, "input overlay takes the key");
+
+        handle_post_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert!(app.input.is_none());
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('X')), &mut app);
+        assert_eq!(app.hud_message(), "no comment selected");
+
+        let command = handle_post_input(KeyEvent::from(KeyCode::Char('R')), &mut app);
+        assert_eq!(command, Some(Command::Refresh));
+
+        let command = handle_post_input(KeyEvent::from(KeyCode::Char('q')), &mut app);
+        assert_eq!(command, Some(Command::Quit));
+    }
+
+    #[test]
+    fn test_export_post() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        // the path is prompted, with the default filled in
+        handle_post_input(KeyEvent::from(KeyCode::Char('E')), &mut app);
+        let input = app.input.as_ref().unwrap();
+        assert_eq!(input.prompt(), "Export post to");
+        assert_eq!(
+            input.text(),
+            "~/Downloads/10822441-announcing-rust-1-76-0.md"
+        );
+
+        handle_post_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(
+            app.take_action(),
+            Some(Action::ExportPost {
+                post_id,
+                path: "~/Downloads/10822441-announcing-rust-1-76-0.md".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_handle_post_mouse() {
+        let mut app = fixture_app();
+        let area = Rect::new(0, 0, 80, 24);
+        let scroll = |row| MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 10,
+            row,
+            modifiers: event::KeyModifiers::NONE,
+        };
+
+        app.post_scroll.set_content_length(10);
+        app.comment_scroll.set_content_length(10);
+
+        let chunks = post_layout(area);
+
+        handle_post_mouse(scroll(chunks[0].y), &mut app, area);
+        assert_eq!(app.post_scroll.position(), 1);
+        assert_eq!(app.comment_scroll.position(), 0);
+
+        handle_post_mouse(scroll(chunks[1].y), &mut app, area);
+        assert_eq!(app.post_scroll.position(), 1);
+        assert_eq!(app.comment_scroll.position(), 1);
+    }
+
+    #[test]
+    fn test_select_comment() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let table = app.comments_get_mut(post_id).unwrap();
+        let comment = table.items[0].clone();
+        table.items = (1..=6)
+            .map(|id| {
+                let mut c = comment.clone();
+                c.comment.id = id;
+                c.comment.path = format!("0.{id}");
+                c.comment.content = format!("comment {id}\n\nsecond paragraph");
+                c
+            })
+            .collect();
+
+        let j = KeyEvent::from(KeyCode::Char('j'));
+        let current = |app: &App| {
+            app.comments_get(post_id)
+                .unwrap()
+                .current()
+                .map(|c| c.comment.id)
+        };
+
+        render(80, 24, &mut app, render_post_screen);
+        assert_eq!(app.comments_get(post_id).unwrap().rows.len(), 6);
+        assert_eq!(current(&app), None);
+
+        for id in 1..=6 {
+            handle_post_input(j, &mut app);
+            assert_eq!(current(&app), Some(id));
+
+            // the selected comment is scrolled into view
+            let rows = app.comments_get(post_id).unwrap().rows[id as usize - 1].clone();
+            let scroll = &app.comment_scroll;
+            assert!(rows.start >= scroll.position());
+            assert!(rows.end <= scroll.position() + scroll.viewport_length());
+
+            let buffer = render(80, 24, &mut app, render_post_screen);
+            let text = buffer_text(&buffer);
+            let (y, _) = text
+                .lines()
+                .enumerate()
+                .find(|(_, l)| l.contains(&format!("comment {id}")))
+                .unwrap();
+            assert_eq!(buffer[(5, y as u16)].bg, app.theme.highlight.bg.unwrap());
+        }
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('k')), &mut app);
+        assert_eq!(current(&app), Some(5));
+
+        // counts, and the first and last comments
+        handle_post_input(KeyEvent::from(KeyCode::Char('3')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('k')), &mut app);
+        assert_eq!(current(&app), Some(2));
+        handle_post_input(KeyEvent::from(KeyCode::Char('G')), &mut app);
+        assert_eq!(current(&app), Some(6));
+        handle_post_input(KeyEvent::from(KeyCode::Char('g')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('g')), &mut app);
+        assert_eq!(current(&app), Some(1));
+        assert_eq!(app.comment_scroll.position(), 0);
+        handle_post_input(KeyEvent::from(KeyCode::Char('4')), &mut app);
+        handle_post_input(j, &mut app);
+        assert_eq!(current(&app), Some(5));
+
+        // line scrolling leaves the selection
+        let position = app.comment_scroll.position();
+        handle_post_input(
+            KeyEvent::new(KeyCode::Up, event::KeyModifiers::SHIFT),
+            &mut app,
+        );
+        assert_eq!(app.comment_scroll.position(), position - 1);
+        assert_eq!(current(&app), Some(5));
+
+        // the selected comment is reported
+        handle_post_input(KeyEvent::from(KeyCode::Char('!')), &mut app);
+        assert_eq!(
+            app.input.as_ref().map(|i| i.prompt()),
+            Some("Report comment, reason")
+        );
+    }
+
+    #[test]
+    fn test_comment_lines_cache() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let table = app.comments_get_mut(post_id).unwrap();
+        let comment = table.items[0].clone();
+        table.items = (1..=30)
+            .map(|id| {
+                let mut c = comment.clone();
+                c.comment.id = id;
+                c.comment.path = format!("0.{id}");
+                c.comment.content = format!("comment {id}");
+                c
+            })
+            .collect();
+        let cached = |app: &App| {
+            app.comments_get(post_id)
+                .unwrap()
+                .lines
+                .as_ref()
+                .map(|l| (l.key.width, l.lines.as_ptr()))
+        };
+
+        // rendered once, and reused while nothing changes
+        let text = buffer_text(&render(80, 24, &mut app, render_post_screen));
+        assert!(text.contains("comment 1 "), "{text}");
+        let lines = cached(&app).unwrap();
+        assert_eq!(lines.0, 74);
+        render(80, 24, &mut app, render_post_screen);
+        assert_eq!(cached(&app), Some(lines));
+
+        // only the lines in view are drawn, scrolled part way into the first one
+        app.comment_scroll
+            .set_position(app.comments_get(post_id).unwrap().rows[19].start + 1);
+        let text = buffer_text(&render(80, 24, &mut app, render_post_screen));
+        assert!(!text.contains("comment 19 "), "{text}");
+        assert!(!text.contains("comment 20 "), "{text}");
+        assert!(text.contains("[ author: @crab@lemmy.ml"), "{text}");
+        assert!(text.contains("comment 21 "), "{text}");
+        assert_eq!(cached(&app), Some(lines));
+
+        // changes render the lines again
+        let c = app.comments_get_mut(post_id).unwrap();
+        c.toggle_collapsed(1);
+        assert_eq!(cached(&app), None);
+        render(100, 24, &mut app, render_post_screen);
+        assert_eq!(cached(&app).unwrap().0, 94);
+
+        assert_eq!(visible_lines(&[0, 1, 3, 4], 2, 1), 1..2);
+        assert_eq!(visible_lines(&[0, 1, 3, 4], 0, 10), 0..3);
+        assert_eq!(visible_lines(&[0, 1, 3, 4], 4, 10), 3..3);
+        assert_eq!(visible_lines(&[], 0, 10), 0..0);
+    }
+
+    #[test]
+    fn test_long_thread_scroll() {
+        // a thread of 100k lines, every third wrapping onto a second row
+        let long = "word ".repeat(10);
+        let lines: Vec<Line> = (0..100_000)
+            .map(|i| Line::from(if i % 3 == 0 { long.as_str() } else { "reply" }))
+            .collect();
+        let offsets = row_offsets(&lines, 40);
+        let rows = offsets[lines.len()];
+        assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(rows, 133_334);
+
+        let height = 20;
+        let mut scroll = Scroll::create(0, rows, height);
+        let mut last = None;
+        loop {
+            let top = scroll.position();
+            let visible = visible_lines(&offsets, top, height);
+            assert!(!visible.is_empty());
+
+            // the paragraph scroll stays within the first line, however far down the thread
+            let skip = top - offsets[visible.start];
+            assert!(skip < offsets[visible.start + 1] - offsets[visible.start]);
+
+            if let Some((last_top, last_start)) = last {
+                assert!(top > last_top, "{top} after {last_top}");
+                assert!(visible.start >= last_start);
+            }
+            last = Some((top, visible.start));
+
+            if top + height == rows {
+                assert_eq!(visible.end, lines.len());
+                break;
+            }
+            scroll.page_down();
+        }
+        assert_eq!(scroll.position(), rows - height);
+        assert!(scroll.position() > u16::MAX as usize);
+        assert_eq!(scroll.offset(), u16::MAX);
+    }
+
+    #[test]
+    fn test_collapse_replies() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let table = app.comments_get_mut(post_id).unwrap();
+        table.items = ["0.1", "0.1.2", "0.1.2.3", "0.4"]
+            .into_iter()
+            .map(|path| {
+                let mut c = CommentResponse::test_at(path);
+                let id = c.comment.id;
+                c.comment.content = format!("comment {id}");
+                c
+            })
+            .collect();
+
+        let space = KeyEvent::from(KeyCode::Char(' '));
+        let draw = |app: &mut App| buffer_text(&render(80, 40, app, render_post_screen));
+        let current = |app: &App| {
+            app.comments_get(post_id)
+                .unwrap()
+                .current()
+                .map(|c| c.comment.id)
+        };
+
+        draw(&mut app);
+        handle_post_input(space, &mut app);
+        assert_eq!(app.hud_message(), "no comment selected");
+
+        let expanded = app.comment_scroll.content_length();
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        handle_post_input(space, &mut app);
+
+        let text = draw(&mut app);
+        assert!(text.contains("[+] 2 replies hidden"));
+        assert!(!text.contains("comment 2"));
+        assert!(!text.contains("comment 3"));
+        assert!(app.comment_scroll.content_length() < expanded);
+
+        // hidden replies are skipped
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        assert_eq!(current(&app), Some(4));
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('Z')), &mut app);
+        let text = draw(&mut app);
+        assert!(!text.contains("hidden"));
+        assert_eq!(app.comment_scroll.content_length(), expanded);
+
+        // scrolled to the selected comment
+        assert!(text.contains("comment 4"));
+        assert!(!text.contains("comment 3"));
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('k')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('z')), &mut app);
+        assert_eq!(
+            current(&app),
+            Some(1),
+            "selection moves to the root comment"
+        );
+        assert!(draw(&mut app).contains("[+] 2 replies hidden"));
+    }
+
+    #[test]
+    fn test_hide_bots() {
+        let mut app = fixture_app();
+        app.config.hide_bots = true;
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let items = ["0.1", "0.1.2", "0.1.2.3", "0.4"]
+            .into_iter()
+            .map(|path| {
+                let mut c = CommentResponse::test_at(path);
+                let id = c.comment.id;
+                c.comment.content = format!("comment {id}");
+                c.creator.bot_account = id == 2;
+                c
+            })
+            .collect();
+        app.comments_insert(post_id, CommentResponseTable::new(items));
+
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        assert!(text.contains("comment 1"));
+        assert!(!text.contains("comment 2"), "bot comment is hidden");
+        assert!(!text.contains("comment 3"), "replies to the bot are hidden");
+        assert!(text.contains("comment 4"));
+
+        // selection skips the hidden comments
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        let current = app.comments_get(post_id).unwrap().current();
+        assert_eq!(current.map(|c| c.comment.id), Some(4));
+    }
+
+    #[test]
+    fn test_go_to_comment() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        let items = ["0.1", "0.1.2", "0.1.2.3", "0.4", "0.5"]
+            .into_iter()
+            .map(CommentResponse::test_at)
+            .collect();
+        app.comments_insert(post_id, CommentResponseTable::new(items));
+        render(80, 24, &mut app, render_post_screen);
+
+        // replies are not counted
+        handle_post_input(key(':'), &mut app);
+        assert_eq!(
+            app.input.as_ref().map(|i| i.prompt()),
+            Some("Go to comment")
+        );
+        handle_post_input(key('3'), &mut app);
+        assert_eq!(
+            handle_post_input(KeyEvent::from(KeyCode::Enter), &mut app),
             None
+        );
+        let current = app.comments_get(post_id).unwrap().current();
+        assert_eq!(current.map(|c| c.comment.id), Some(5));
+
+        handle_post_input(key(':'), &mut app);
+        handle_post_input(key('4'), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.hud_message(), "no such comment");
+        let current = app.comments_get(post_id).unwrap().current();
+        assert_eq!(current.map(|c| c.comment.id), Some(5));
+
+        app.comments_insert(post_id, CommentResponseTable::new(Vec::new()));
+        handle_post_input(key(':'), &mut app);
+        assert!(app.input.is_none());
+        assert_eq!(app.hud_message(), "no comments");
+    }
+
+    #[test]
+    fn test_new_comments() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        let current = |app: &App| {
+            app.comments_get(post_id)
+                .unwrap()
+                .current()
+                .map(|c| c.comment.id)
+        };
+
+        let comment = app.comments_get(post_id).unwrap().items[0].clone();
+        let items = [
+            ("0.1", "2024-01-01T00:00:00Z", None),
+            ("0.2", "2024-03-01T00:00:00Z", None),
+            ("0.3", "2024-01-01T00:00:00Z", Some("2024-03-01T00:00:00Z")),
+            ("0.4", "2024-01-01T00:00:00Z", None),
+        ]
+        .into_iter()
+        .map(|(path, published, updated)| {
+            let mut c = comment.clone();
+            c.comment.id = path[2..].parse().unwrap();
+            c.comment.path = path.into();
+            c.comment.content = format!("comment {}", c.comment.id);
+            c.comment.published = published.into();
+            c.comment.updated = updated.map(Into::into);
+            c
+        })
+        .collect();
+        app.comments_insert(post_id, CommentResponseTable::new(items));
+
+        // first visit, nothing is new
+        app.visits.visit(post_id, app.now());
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        assert!(!text.contains("[NEW]"));
+        handle_post_input(key(']'), &mut app);
+        assert_eq!(app.hud_message(), "no new comments");
+
+        // last visited in February
+        let february = parse_timestamp("2024-02-01T00:00:00Z").unwrap();
+        let secs = february.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        app.visits = Visits::new([(post_id, secs)].into());
+        app.visits.visit(post_id, app.now());
+
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        let title = format!("Comments | {} | 2 new", app.comment_sort);
+        assert!(text.contains(&title), "{text}");
+        assert_eq!(text.matches("[NEW] [ author:").count(), 2);
+
+        handle_post_input(key(']'), &mut app);
+        assert_eq!(current(&app), Some(2));
+        handle_post_input(key(']'), &mut app);
+        assert_eq!(current(&app), Some(3));
+        handle_post_input(key(']'), &mut app);
+        assert_eq!(current(&app), Some(2), "wraps around");
+        handle_post_input(key('['), &mut app);
+        assert_eq!(current(&app), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_streaming_comments() {
+        let mut app = fixture_app();
+        let post = app.posts.current().unwrap().clone();
+        let post_id = post.post.id();
+        let comments = app.comments.remove(post_id).unwrap().items;
+
+        let client = FakeClient::new(vec![post.clone()], comments.clone(), 10);
+        let task = CommentsTask::spawn(
+            Box::new(client),
+            post_id,
+            0..comments.len() as u64,
+            1,
+            true,
+            None,
+        );
+        while !task.is_finished() {
+            tokio::task::yield_now().await;
         }
-    })
+
+        // the first page is streamed before the rest
+        let mut pages = task.take_pages();
+        assert_eq!(pages.len(), comments.len());
+        assert_eq!(pages[0].comments()[0].comment.id, comments[0].comment.id);
+        assert!(task.take_pages().is_empty());
+
+        app.comments_insert(post_id, pages.swap_remove(0).into());
+        app.comments_task = Some(task);
+
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        let loaded = format!("loaded 1/{}", post.counts.comments());
+        assert!(text.contains(&loaded), "{text}");
+        assert!(text.contains("loading more…"));
+        assert!(!text.contains("Loading comments…"));
+
+        // the rest of a cancelled download is fetched again on the next visit
+        app.cancel_comments_task();
+        assert!(app.comments_get(post_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stop_fetch() {
+        let mut app = fixture_app();
+        let post = app.posts.current().unwrap().clone();
+        let post_id = post.post.id();
+        let comments = app.comments.remove(post_id).unwrap().items;
+        app.push_screen(Screen::Post);
+
+        let client = FakeClient::new(vec![post], comments.clone(), 10);
+        let task = CommentsTask::spawn(
+            Box::new(client),
+            post_id,
+            0..comments.len() as u64,
+            1,
+            true,
+            None,
+        );
+        while !task.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        app.comments_task = Some(task);
+
+        // the downloaded pages are kept, and Esc stays on the post
+        handle_post_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert!(app.comments_task.is_none());
+        assert_eq!(app.screen(), Screen::Post);
+        assert_eq!(app.message(), "fetch cancelled");
+        assert_eq!(
+            app.comments_get(post_id).unwrap().items.len(),
+            comments.len()
+        );
+
+        // with nothing downloading, Esc leaves the post
+        handle_post_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_ne!(app.screen(), Screen::Post);
+        assert_eq!(
+            app.comments_get(post_id).unwrap().items.len(),
+            comments.len()
+        );
+    }
+
+    #[test]
+    fn test_load_more_comments() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let key = |app: &mut App| handle_post_input(KeyEvent::from(KeyCode::Char('m')), app);
+
+        // every comment was downloaded
+        key(&mut app);
+        assert!(!app.take_load_more_comments());
+        assert_eq!(app.message(), "every comment is loaded");
+
+        app.posts.current_mut().unwrap().counts.comments = Some(4231);
+        let c = app.comments_get_mut(post_id).unwrap();
+        c.set_fetched(500, 4231);
+        assert_eq!(c.remaining(4231), 3731);
+
+        let text = buffer_text(&render(100, 40, &mut app, render_post_screen));
+        assert!(
+            text.contains("showing first 500 of 4,231 comments — press m to load more"),
+            "{text}"
+        );
+
+        key(&mut app);
+        assert!(app.take_load_more_comments());
+
+        // the last batch loads the rest
+        let c = app.comments_get_mut(post_id).unwrap();
+        c.set_fetched(4500, 4231);
+        assert_eq!(c.fetched, None);
+        let text = buffer_text(&render(100, 40, &mut app, render_post_screen));
+        assert!(!text.contains("showing first"));
+    }
+
+    #[test]
+    fn test_load_replies() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        // the chain ends at 0.1.2, with two replies below it not returned with the post
+        let items = [("0.1", 3), ("0.1.2", 2), ("0.4", 0)]
+            .into_iter()
+            .map(|(path, child_count)| {
+                let mut c = CommentResponse::test_at(path);
+                c.counts.child_count = Some(child_count);
+                c
+            })
+            .collect();
+        app.comments_insert(post_id, CommentResponseTable::new(items));
+
+        let text = buffer_text(&render(80, 40, &mut app, render_post_screen));
+        assert_eq!(text.matches("[+] load 2 more replies").count(), 1, "{text}");
+
+        app.comments_get_mut(post_id).unwrap().state.select(Some(2));
+        handle_post_input(key('+'), &mut app);
+        assert_eq!(app.hud_message(), "no more replies to load");
+        assert_eq!(app.take_load_replies(), None);
+
+        app.comments_get_mut(post_id).unwrap().state.select(Some(1));
+        handle_post_input(key('+'), &mut app);
+        assert_eq!(app.take_load_replies(), Some(2));
+    }
+
+    #[test]
+    fn test_comment_info() {
+        let app = fixture_app();
+        let mut cr = app
+            .comments_get(app.posts.current().unwrap().post.id())
+            .unwrap()
+            .items[0]
+            .clone();
+        let text = |spans: &[Span]| spans.iter().map(|s| s.content.as_ref()).collect::<String>();
+
+        cr.counts.score = 5;
+        cr.counts.upvotes = 6;
+        cr.counts.downvotes = 1;
+        cr.comment.updated = Some("2024-02-09T00:00:00Z".into());
+
+        // the fixture comment is upvoted by the logged-in user
+        let info = comment_info(&cr, 0, &app.config, &app.theme, app.now());
+        assert_eq!(
+            text(&info),
+            "[ author: @crab@lemmy.ml, score: 5 (+6/-1) ▲, 1d ago (edited), child comments: 0 ]"
+        );
+        assert_eq!(info[3].style, Style::default());
+        assert_eq!(info[5].style, app.theme.upvoted);
+
+        cr.my_vote = Some(-1);
+        cr.saved = true;
+        let info = comment_info(&cr, 0, &app.config, &app.theme, app.now());
+        assert!(text(&info).contains("score: 5 (+6/-1) ▼ ★, 1d ago"));
+        assert_eq!(info[5].style, app.theme.downvoted);
+        assert_eq!(info[7].style, app.theme.saved);
+
+        cr.my_vote = None;
+        cr.saved = false;
+        let info = comment_info(&cr, 0, &app.config, &app.theme, app.now());
+        assert!(text(&info).contains("score: 5 (+6/-1), 1d ago"));
+
+        cr.counts.score = -3;
+        cr.counts.downvotes = 0;
+        let info = comment_info(&cr, 0, &app.config, &app.theme, app.now());
+        assert_eq!(info[3].content, "-3");
+        assert_eq!(info[3].style.fg, Some(Color::Red));
+
+        let mut config =
+            Config::from_toml("high_score = 10\n[colors]\nscore_high = \"cyan\"").unwrap();
+        cr.counts.score = 10;
+        let info = comment_info(&cr, 0, &config, &config.theme().unwrap(), app.now());
+        assert_eq!(info[3].style.fg, Some(Color::Cyan));
+
+        // unknown publish times are left out
+        config.high_score = 11;
+        cr.comment.published.clear();
+        cr.comment.updated = None;
+        let info = comment_info(&cr, 0, &config, &config.theme().unwrap(), app.now());
+        assert_eq!(
+            text(&info),
+            "[ author: @crab@lemmy.ml, score: 10, child comments: 0 ]"
+        );
+        assert_eq!(info[3].style, Style::default());
+
+        // tags follow the author
+        cr.comment.distinguished = true;
+        cr.creator.admin = Some(true);
+        cr.creator.bot_account = true;
+        let info = comment_info(&cr, cr.creator.id, &app.config, &app.theme, app.now());
+        assert_eq!(
+            text(&info),
+            "[ author: @crab@lemmy.ml [OP] [mod] [admin] [bot], score: 10, child comments: 0 ]"
+        );
+        assert_eq!(info[1].style, app.theme.op);
+        assert_eq!(info[3].style, info[1].style);
+        assert_eq!(info[5].style, app.theme.moderator);
+        assert_eq!(info[7].style, app.theme.admin);
+        assert!(info[9].style.add_modifier.contains(Modifier::DIM));
+
+        // comments with malformed paths are marked
+        cr.orphaned = true;
+        let info = comment_info(&cr, 0, &app.config, &app.theme, app.now());
+        assert!(text(&info).ends_with("child comments: 0 ] ⚠ orphaned"));
+        assert_eq!(info.last().unwrap().style, app.theme.error);
+
+        // local users may be shown without the domain
+        config.omit_local_domain = true;
+        cr.creator.local = true;
+        let info = comment_info(&cr, 0, &config, &config.theme().unwrap(), app.now());
+        assert_eq!(info[1].content, "@crab");
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        app.posts.current_mut().unwrap().post.body = Some("## Notes\n\n- **one**\n- two".into());
+        app.comments_get_mut(post_id).unwrap().items[0]
+            .comment
+            .content = "> quoted\n\n```\nlet a = 1;\n\nlet b = 2;\n```".into();
+
+        let buffer = render(120, 40, &mut app, render_post_screen);
+        let text = buffer_text(&buffer);
+        for line in [
+            "│Notes ",
+            "│• one ",
+            "│• two ",
+            "│ ▌ quoted ",
+            "│ let a = 1; ",
+        ] {
+            assert!(text.contains(line), "{line}");
+        }
+
+        // blank lines inside code blocks are kept
+        let lines: Vec<&str> = text.lines().collect();
+        let a = lines.iter().position(|l| l.contains("let a = 1;")).unwrap();
+        assert!(lines[a + 2].contains("│ let b = 2; "));
+
+        let row = lines.iter().position(|l| l.contains("│• one ")).unwrap() as u16;
+        let x = lines[row as usize][..lines[row as usize].find("one").unwrap()]
+            .chars()
+            .count() as u16;
+        assert!(buffer[(x, row)].modifier.contains(Modifier::BOLD));
+
+        // the quote, the code block with its blank line, the spacers, and the info line
+        let table = app.comments_get(post_id).unwrap();
+        assert_eq!(table.rows[0].len(), 7);
+    }
+
+    #[test]
+    fn test_spoilers() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        app.posts.current_mut().unwrap().post.body = Some("::: spoiler plot\nhidden\n:::".into());
+        app.comments_get_mut(post_id).unwrap().items[0]
+            .comment
+            .content = "::: spoiler ending\nsecret\n:::".into();
+
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│▶ spoiler: plot "));
+        assert!(text.contains("│ ▶ spoiler: ending "));
+        assert!(!text.contains("hidden") && !text.contains("secret"));
+
+        // the post body, without a selected comment
+        let screen = app.screen();
+        handle_post_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert!(app.spoilers.post);
+        assert_eq!(app.screen(), screen);
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('j')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char(' ')), &mut app);
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│▼ spoiler: plot "));
+        assert!(text.contains("hidden") && text.contains("secret"));
+
+        handle_post_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        let text = buffer_text(&render(120, 40, &mut app, render_post_screen));
+        assert!(text.contains("│ ▶ spoiler: ending ") && !text.contains("secret"));
+
+        // leaving the post hides them again
+        handle_post_input(KeyEvent::from(KeyCode::Char(' ')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('n')), &mut app);
+        assert_eq!(app.spoilers, Spoilers::default());
+    }
+
+    #[test]
+    fn test_comment_levels() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let table = app.comments_get_mut(post_id).unwrap();
+        let comment = table.items[0].clone();
+        table.items = [(1, "0.1"), (2, "0.1.2"), (3, "0.1.2.3"), (4, "0.1.x.4")]
+            .map(|(id, path)| {
+                let mut c = comment.clone();
+                c.comment.id = id;
+                c.comment.path = path.into();
+                c.comment.content = format!("comment {id}");
+                c.finalize();
+                c
+            })
+            .to_vec();
+        table.items[2]
+            .comment
+            .content
+            .push_str(&" wrapped".repeat(20));
+
+        let buffer = render(80, 40, &mut app, render_post_screen);
+        let text = buffer_text(&buffer);
+
+        assert!(text.contains("│ comment 1"));
+        assert!(text.contains("│ │ comment 2"));
+        assert!(text.contains("│ │ │ comment 3"));
+        assert!(text.contains("│[?] comment 4"), "malformed path is marked");
+
+        // wrapped lines keep their guides
+        let wrapped: Vec<&str> = text.lines().filter(|l| l.contains("wrapped")).collect();
+        assert!(wrapped.len() > 1);
+        assert!(wrapped.iter().all(|l| l.contains("│ │ │ ")));
+
+        // each level has its own color
+        let (x, y) = text
+            .lines()
+            .enumerate()
+            .find_map(|(y, l)| {
+                l.find("│ │ │ comment 3")
+                    .map(|x| (l[..x].chars().count(), y))
+            })
+            .unwrap();
+        let colors: Vec<_> = [x + 2, x + 4]
+            .map(|x| buffer[(x as u16, y as u16)].fg)
+            .to_vec();
+        assert_eq!(colors, app.theme.indent[..2]);
+    }
+
+    #[test]
+    fn test_indent_guides() {
+        let text = |spans: Vec<Span>| spans.iter().map(|s| s.content.as_ref()).collect::<String>();
+
+        let guides = indent_guides(3, "| ", &[Color::Red, Color::Blue], 40);
+        assert_eq!(text(guides.clone()), "| | | ");
+        let colors: Vec<_> = guides.iter().map(|s| s.style.fg).collect();
+        assert_eq!(
+            colors,
+            [Some(Color::Red), Some(Color::Blue), Some(Color::Red)]
+        );
+
+        // deep levels are capped to the maximum width
+        assert_eq!(text(indent_guides(30, "│ ", &INDENT_COLORS, 9)), "│ │ │ │ ");
+        assert_eq!(indent_guides(2, "|", &[], 10)[0].style, Style::default());
+    }
+
+    #[test]
+    fn test_sort_comments() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+
+        let table = app.comments_get_mut(post_id).unwrap();
+        let comment = table.items[0].clone();
+        table.items = (1..=3)
+            .map(|id| {
+                let mut c = comment.clone();
+                c.comment.id = id;
+                c.comment.path = format!("0.{id}");
+                c.comment.content = format!("comment {id}");
+                c.counts.score = [2, 7, 1][id as usize - 1];
+                c
+            })
+            .collect();
+
+        let draw = |app: &mut App| buffer_text(&render(80, 40, app, render_post_screen));
+        let order = |text: &str| {
+            let mut ids: Vec<_> = (1..=3)
+                .map(|id| (text.find(&format!("comment {id}")).unwrap(), id))
+                .collect();
+            ids.sort();
+            ids.into_iter().map(|(_, id)| id).collect::<Vec<_>>()
+        };
+
+        let text = draw(&mut app);
+        assert!(text.contains("Comments | Old"));
+        assert_eq!(order(&text), [1, 2, 3]);
+
+        app.comment_scroll.set_position(2);
+        handle_post_input(KeyEvent::from(KeyCode::Char('s')), &mut app);
+        assert_eq!(app.comment_scroll.position(), 0);
+
+        let text = draw(&mut app);
+        assert!(text.contains("Comments | Controversial"));
+
+        handle_post_input(KeyEvent::from(KeyCode::Char('s')), &mut app);
+        handle_post_input(KeyEvent::from(KeyCode::Char('s')), &mut app);
+        let text = draw(&mut app);
+        assert!(text.contains("Comments | Top"));
+        assert_eq!(order(&text), [2, 1, 3]);
+
+        // the sort is kept for other posts
+        assert_eq!(app.comment_sort, CommentSort::Top);
+    }
+
+    #[test]
+    fn test_handle_post_input_paging() {
+        let mut app = fixture_app();
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        let shift = event::KeyModifiers::SHIFT;
+        let ctrl = event::KeyModifiers::CONTROL;
+
+        app.post_scroll = Scroll::create(0, 30, 8);
+        app.comment_scroll = Scroll::create(0, 50, 12);
+
+        handle_post_input(key(KeyCode::PageDown, event::KeyModifiers::NONE), &mut app);
+        assert_eq!(app.comment_scroll.position(), 12);
+        handle_post_input(key(KeyCode::Char('d'), ctrl), &mut app);
+        assert_eq!(app.comment_scroll.position(), 24);
+        handle_post_input(key(KeyCode::Char('u'), ctrl), &mut app);
+        assert_eq!(app.comment_scroll.position(), 12);
+
+        // counts multiply the page keys
+        handle_post_input(KeyEvent::from(KeyCode::Char('2')), &mut app);
+        handle_post_input(key(KeyCode::PageDown, event::KeyModifiers::NONE), &mut app);
+        assert_eq!(app.comment_scroll.position(), 36);
+        handle_post_input(KeyEvent::from(KeyCode::Char('3')), &mut app);
+        handle_post_input(key(KeyCode::Char('u'), ctrl), &mut app);
+        assert_eq!(app.comment_scroll.position(), 0);
+
+        handle_post_input(key(KeyCode::Char('G'), shift), &mut app);
+        assert_eq!(app.comment_scroll.position(), 38);
+        handle_post_input(KeyEvent::from(KeyCode::Char('g')), &mut app);
+        assert_eq!(app.comment_scroll.position(), 38, "waits for the second g");
+        handle_post_input(KeyEvent::from(KeyCode::Char('g')), &mut app);
+        assert_eq!(app.comment_scroll.position(), 0);
+        assert_eq!(app.post_scroll.position(), 0, "post pane is unaffected");
+
+        handle_post_input(key(KeyCode::PageDown, shift), &mut app);
+        assert_eq!(app.post_scroll.position(), 8);
+        handle_post_input(key(KeyCode::End, shift), &mut app);
+        assert_eq!(app.post_scroll.position(), 22);
+        handle_post_input(key(KeyCode::PageUp, shift), &mut app);
+        assert_eq!(app.post_scroll.position(), 14);
+        handle_post_input(KeyEvent::from(KeyCode::Char('5')), &mut app);
+        handle_post_input(key(KeyCode::Up, event::KeyModifiers::NONE), &mut app);
+        assert_eq!(app.post_scroll.position(), 9);
+        handle_post_input(key(KeyCode::Home, shift), &mut app);
+        assert_eq!(app.post_scroll.position(), 0);
+        assert_eq!(app.comment_scroll.position(), 0);
+
+        // the draw sets the viewport from the pane height
+        render(80, 24, &mut app, render_post_screen);
+        let chunks = post_layout(Rect::new(0, 0, 80, 24));
+        assert_eq!(
+            app.comment_scroll.viewport_length(),
+            chunks[1].height as usize - 2
+        );
+    }
+
+    #[test]
+    fn test_filter_line() {
+        let lines = |raw, width| filter_line(raw, width).collect::<Vec<_>>();
+
+        assert_eq!(lines("the quick brown fox", 10), ["the quick", "brown fox"]);
+        // runs of spaces are kept, and tabs expanded, apart from where the lines wrap
+        assert_eq!(lines("  spaced\tout\u{7}  ", 20), ["spaced    out"]);
+        assert_eq!(lines("a  b\nc", 20), ["a  b c"]);
+        assert_eq!(lines("aligned    columns", 10), ["aligned", "columns"]);
+        assert!(lines("", 10).is_empty());
+
+        // long words are broken, instead of overflowing or disappearing
+        let url = format!("https://example.com/{}", "a".repeat(300));
+        let raw = format!("see {url} here");
+        let wrapped = lines(&raw, 40);
+        assert_eq!(wrapped.concat().replace(' ', ""), format!("see{url}here"));
+
+        assert_eq!(lines("日本語の文章です", 6), ["日本語", "の文章", "です"]);
+        assert_eq!(
+            lines("cafe\u{301} 🦀🦀 ok", 5),
+            ["cafe\u{301}", "🦀🦀", "ok"]
+        );
+
+        for raw in [
+            "plain ascii text",
+            "e\u{301}e\u{301} combining",
+            "中文字符测试",
+            "🦀 🦀🦀🦀",
+        ] {
+            for width in 1..12 {
+                for line in filter_line(raw, width) {
+                    assert!(line.width() <= width.max(2), "{line:?} wider than {width}");
+                }
+            }
+        }
+    }
 }
@@ -1,98 +1,816 @@
 //! Facilities for drawing the PostsList screen.
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
-use std::time;
+use std::rc::Rc;
+use std::time::SystemTime;
 
 use crossterm::event;
 use tui::{layout::Constraint, prelude::*, widgets::*};
 
 use crate::{
-    app::{App, TemiTerminal},
-    posts::*,
-    Result,
+    app::{Action, App, Command, Confirm, InputKind, RefreshTarget},
+    counts::format_score,
+    keymap::KeyAction,
+    posts::{MediaKind, PostResponse, PostResponseTable},
+    theme::Theme,
+    utils::format_timestamp,
 };
 
-use super::{body_style, highlight_style, set_current_screen, title_block, Screen};
-
-/// Draw the screen to show a list of [Posts](crate::posts::Posts).
-pub fn draw_posts_screen(
-    terminal: &mut TemiTerminal,
-    app: &mut App,
-    stop: Arc<AtomicBool>,
-) -> Result<()> {
-    terminal.draw(|f| {
-        let size = f.area();
-
-        let frame_height = size.height as usize;
-
-        let mut rows: Vec<Row> = app.posts.items.iter().map(|p| {
-            let title = p.post.name.as_str();
-            let author = p.creator.name();
-            let date = p.creator.published();
-
-            Row::new(vec![
-                Cell::from(
-                    Text::from(
-                        vec![
-                        Line::from(title),
-                        Line::from(format!("    [ author: {author} | published: {date} ]")),
-                        Line::from("-".repeat(size.width as usize)),
-                        ]
-                    )
-                )
-            ])
-            .style(body_style())
-            .height(3)
-        })
+use super::{
+    community_handle, creator_handle, draw_accounts, draw_confirm, draw_error, draw_input,
+    draw_instances, draw_status_bar, handle_accounts_key, handle_confirm_key, handle_error_key,
+    handle_input_key, handle_instances_key, is_interrupt, media_tag, nsfw_title, pending_action,
+    post_tags, subscribed_tag, title_block, truncate_width, vote_span, Screen,
+};
+
+/// Height of a post row in the table.
+pub const ROW_HEIGHT: u16 = 3;
+/// Width of the score column, fitting e.g. `-999.9k`.
+pub const SCORE_WIDTH: usize = 7;
+/// Width of the comment count column, fitting e.g. `999.9k c`.
+const COMMENTS_WIDTH: usize = 8;
+/// Narrowest table that shows the comment count column.
+const MIN_COMMENTS_WIDTH: u16 = 40;
+/// Width kept for the title before the link domain is dropped from the media tag.
+const MIN_TITLE_WIDTH: usize = 20;
+
+/// Gets the layout of the PostsList screen `area`, inside the title block: post table, HUD, and
+/// status bar.
+///
+/// Shared by drawing and mouse handling, so clicks map to the rows as drawn.
+pub fn posts_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(Block::bordered().inner(area))
+}
+
+/// Gets the table row of a post, [ROW_HEIGHT] lines for a table `width` columns wide: the
+/// score, comment count, tags, and title, then the author, community, and date.
+///
+/// Posts kept in place of reposts of the same link in the `table` are annotated, e.g.
+/// `(+4 duplicates)`. Shared with the Saved screen.
+pub fn post_row(
+    p: &PostResponse,
+    table: &PostResponseTable,
+    app: &App,
+    width: u16,
+    now: SystemTime,
+) -> Row<'static> {
+    // narrow terminals drop the comment count column
+    let show_comments = width >= MIN_COMMENTS_WIDTH;
+    // the info line is aligned with the titles
+    let indent = if show_comments {
+        " ".repeat(SCORE_WIDTH + COMMENTS_WIDTH + 2)
+    } else {
+        " ".repeat(SCORE_WIDTH + 1)
+    };
+
+    let author = creator_handle(&p.creator, &app.config);
+    let community = community_handle(&p.community, &app.config);
+    let date = format_timestamp(p.post.published(), app.config.timestamps, now);
+
+    let mut title_line = vec![
+        score_span(p.counts.score(), app),
+        vote_span(p.my_vote, &app.theme),
+    ];
+    if show_comments {
+        let comments = format!("{} c", format_score(p.counts.comments() as i64));
+        title_line.push(Span::raw(format!("{comments:>COMMENTS_WIDTH$} ")));
+    }
+    for tag in post_tags(p, &app.theme) {
+        title_line.extend([tag, Span::raw(" ")]);
+    }
+    if let Some(tag) = duplicates_tag(p, table, &app.theme) {
+        title_line.extend([tag, Span::raw(" ")]);
+    }
+
+    // link domains are dropped before squeezing the title
+    let used: usize = title_line.iter().map(Span::width).sum();
+    let kind = p.media_kind();
+    let mut tag = media_tag(&kind, &app.theme);
+    if matches!(kind, MediaKind::Link(_))
+        && used + tag.width() + 1 + MIN_TITLE_WIDTH > width as usize
+    {
+        tag = media_tag(&MediaKind::Link(String::new()), &app.theme);
+    }
+    title_line.extend([tag, Span::raw(" ")]);
+
+    // the title takes the rest of the row
+    let used: usize = title_line.iter().map(Span::width).sum();
+    let name = if app.nsfw_blurred(p) {
+        nsfw_title(p, &app.config)
+    } else {
+        p.post.name().to_owned()
+    };
+    let title = truncate_width(&name, (width as usize).saturating_sub(used));
+    title_line.push(Span::raw(title));
+
+    let mut info_line = vec![Span::raw(format!(
+        "{indent}[ author: {author} | community: {community}"
+    ))];
+    if let Some(tag) = subscribed_tag(p.subscribed, &app.theme) {
+        info_line.extend([Span::raw(" "), tag]);
+    }
+    info_line.push(Span::raw(format!(" | published: {date} ]")));
+
+    Row::new(vec![Cell::from(Text::from(vec![
+        Line::from(title_line),
+        Line::from(info_line),
+        Line::from("-".repeat(width as usize)),
+    ]))])
+    .style(app.theme.body)
+    .height(ROW_HEIGHT)
+}
+
+/// Gets the tag [Span] of a post kept in place of reposts of the same link, e.g.
+/// `(+4 duplicates)` while hidden, or `(4 duplicates)` while shown, and `(duplicate)` for the
+/// shown reposts.
+fn duplicates_tag(
+    p: &PostResponse,
+    table: &PostResponseTable,
+    theme: &Theme,
+) -> Option<Span<'static>> {
+    let post_id = p.post.id();
+    let tag = match table.duplicate_count(post_id) {
+        0 if table.duplicate_of(post_id).is_some() => "(duplicate)".to_owned(),
+        0 => return None,
+        n if table.expanded.contains(&post_id) => format!("({})", duplicates_label(n)),
+        n => format!("(+{})", duplicates_label(n)),
+    };
+
+    Some(Span::styled(tag, theme.link))
+}
+
+/// Gets the number of duplicates, e.g. `1 duplicate`, or `4 duplicates`.
+fn duplicates_label(count: usize) -> String {
+    match count {
+        1 => "1 duplicate".to_owned(),
+        n => format!("{n} duplicates"),
+    }
+}
+
+/// Gets the score column [Span] of a row, styled for negative scores.
+///
+/// The column is followed by the [vote_span] of the logged-in user.
+pub fn score_span(score: i64, app: &App) -> Span<'static> {
+    let style = if score < 0 {
+        app.theme.score_negative
+    } else {
+        Style::default()
+    };
+
+    Span::styled(format!("{:>SCORE_WIDTH$}", format_score(score)), style)
+}
+
+/// Renders the PostsList screen, without handling input.
+pub fn render_posts_screen(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+    let chunks = posts_layout(size);
+    let now = app.now();
+    let width = chunks[0].width;
+
+    let rows: Vec<Row> = app
+        .posts
+        .items
+        .iter()
+        .map(|p| post_row(p, &app.posts, app, width, now))
         .collect();
 
-        let total_height = rows.len() * 3;
-        // add blank rows to push the info row(s) to the bottom
-        for _ in total_height..(frame_height - 4) {
-            rows.push(Row::new([""]));
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::OpenPost], "select"),
+        (&[KeyAction::PrevPage], "prev page"),
+        (&[KeyAction::SelectPrev], "prev post"),
+        (&[KeyAction::SelectNext], "next post"),
+        (&[KeyAction::NextPage], "next page"),
+        (&[KeyAction::BlockCommunity], "block community"),
+        (&[KeyAction::ToggleNsfw], "nsfw"),
+        (&[KeyAction::ToggleFilters], "filters"),
+        (&[KeyAction::GoTo], "go to page"),
+        (&[KeyAction::Refresh], "refresh"),
+    ]))
+    .style(app.theme.header);
+
+    let mut title = "Posts".to_owned();
+    match app.posts.filtered {
+        0 => (),
+        1 => title.push_str(" | 1 post filtered"),
+        n => title.push_str(&format!(" | {n} posts filtered")),
+    }
+
+    // the table scrolls to keep the selected post in view
+    let table = Table::new(rows, [Constraint::Percentage(100)])
+        .style(app.theme.header)
+        .highlight_style(app.theme.highlight)
+        .column_spacing(0);
+
+    f.render_widget(title_block(title.as_str(), &app.theme), size);
+    f.render_stateful_widget(table, chunks[0], &mut app.posts.state);
+    f.render_widget(hud, chunks[1]);
+    draw_status_bar(f, app, chunks[2]);
+
+    draw_instances(f, app);
+    draw_accounts(f, app);
+    draw_confirm(f, app);
+    draw_input(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a mouse event on the PostsList screen, for the screen `area`.
+///
+/// Scrolling moves the selection, clicking a post selects it, and clicking the selected post
+/// opens it.
+pub fn handle_posts_mouse(event: event::MouseEvent, app: &mut App, area: Rect) -> Option<Command> {
+    if app.has_error()
+        || app.confirming()
+        || app.inputting()
+        || app.picking_instance()
+        || app.picking_account()
+    {
+        return None;
+    }
+
+    match event.kind {
+        event::MouseEventKind::ScrollDown => app.posts.next(),
+        event::MouseEventKind::ScrollUp => app.posts.previous(),
+        event::MouseEventKind::Down(event::MouseButton::Left) => {
+            let rows = posts_layout(area)[0];
+            if !rows.contains(Position::new(event.column, event.row)) {
+                return None;
+            }
+
+            let i = app.posts.state.offset() + ((event.row - rows.y) / ROW_HEIGHT) as usize;
+            if i >= app.posts.items.len() {
+                return None;
+            }
+
+            if app.posts.state.selected() == Some(i) {
+                app.push_screen(Screen::Post);
+            } else {
+                app.posts.state.select(Some(i));
+            }
         }
+        _ => (),
+    }
 
-        rows.push(Row::new(["| (q) quit | (Enter) select | (◄, p) prev page | (▲)  prev post | (▼)  next post | next page (n, ►) |"]));
-
-        let num_rows = rows.len();
-        let table = Table::new(rows, vec![Constraint::Length(size.width); num_rows])
-            .style(body_style())
-            .highlight_style(highlight_style())
-            .column_spacing(0)
-            .widths(&[Constraint::Percentage(100)])
-            .block(title_block("Posts"));
-
-        f.render_stateful_widget(table, size, &mut app.posts.state);
-    })?;
-
-    if event::poll(time::Duration::from_millis(200))? {
-        if let event::Event::Key(event) = event::read()? {
-            match event.code {
-                event::KeyCode::Esc => app.posts.deselect(),
-                event::KeyCode::Down => app.posts.next(),
-                event::KeyCode::Up => app.posts.previous(),
-                event::KeyCode::Enter => set_current_screen(Screen::Post),
-                event::KeyCode::Char('c') => {
-                    if event.modifiers == event::KeyModifiers::CONTROL {
-                        stop.store(true, Ordering::SeqCst);
-                    }
-                }
-                event::KeyCode::Char('n') | event::KeyCode::Right => {
-                    app.next_page();
-                    set_download_posts(true);
-                }
-                event::KeyCode::Char('p') | event::KeyCode::Left => {
-                    app.previous_page();
-                    set_download_posts(true);
+    None
+}
+
+/// Handles a key press on the PostsList screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_posts_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::DownloadPosts);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    } else if app.inputting() {
+        return handle_input_key(app, event.code);
+    } else if app.picking_instance() {
+        return handle_instances_key(app, event.code);
+    } else if app.picking_account() {
+        return handle_accounts_key(app, event.code);
+    } else if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
+
+    let (action, count) = pending_action(app, Screen::PostList, &event)?;
+
+    match action {
+        KeyAction::Back => app.posts.deselect(),
+        KeyAction::SelectNext => (0..count).for_each(|_| app.posts.next()),
+        KeyAction::SelectPrev => (0..count).for_each(|_| app.posts.previous()),
+        KeyAction::FirstPost => app.posts.first(),
+        KeyAction::LastPost => app.posts.last(),
+        KeyAction::OpenPost if app.posts.current().is_some() => app.push_screen(Screen::Post),
+        KeyAction::NextPage => {
+            app.set_page(app.page().saturating_add(count as u64));
+            return Some(Command::DownloadPosts);
+        }
+        KeyAction::PrevPage => {
+            app.set_page(app.page().saturating_sub(count as u64));
+            return Some(Command::DownloadPosts);
+        }
+        KeyAction::SwitchInstance => app.open_instance_picker(),
+        KeyAction::SwitchAccount => app.open_account_picker(),
+        KeyAction::ShowSaved => return app.open_saved(),
+        KeyAction::ShowDiscover => return app.open_discover(),
+        KeyAction::ShowModlog => {
+            // the community ID comes from a listed post, the filter only has its name
+            let community = app
+                .posts
+                .current()
+                .or_else(|| app.posts.items().first())
+                .map(|p| p.community.clone());
+
+            match community {
+                Some(community) if app.config.community.is_some() => {
+                    return app.open_modlog(community)
                 }
-                event::KeyCode::Char('q') => stop.store(true, Ordering::SeqCst),
-                _ => (),
+                Some(_) => app.set_message("modlog needs a community: filter with --community"),
+                None => app.set_message("no posts in the community"),
             }
         }
+        KeyAction::Logout => app.set_confirm(Confirm::new(
+            format!("log out of {}?", app.instance_label()),
+            Action::Logout,
+        )),
+        KeyAction::ToggleNsfw => {
+            app.config.nsfw = app.config.nsfw.next();
+            app.set_message(format!("NSFW posts: {}", app.config.nsfw));
+
+            // hidden posts were filtered out of the page
+            return Some(Command::DownloadPosts);
+        }
+        KeyAction::ToggleFilters => {
+            app.filters_enabled = !app.filters_enabled;
+            let state = if app.filters_enabled { "on" } else { "off" };
+            app.set_message(format!("Filters: {state}"));
+
+            return Some(Command::DownloadPosts);
+        }
+        KeyAction::ToggleDuplicates => match app.posts.toggle_duplicates() {
+            Some(shown) => {
+                // the kept post is selected after toggling
+                let count = app
+                    .posts
+                    .current()
+                    .map_or(0, |p| app.posts.duplicate_count(p.post.id()));
+                let verb = if shown { "showing" } else { "hiding" };
+                app.set_message(format!("{verb} {}", duplicates_label(count)));
+            }
+            None => app.set_message("no duplicates of this post"),
+        },
+        KeyAction::GoTo => app.set_input(InputKind::GoToPage),
+        KeyAction::Refresh if app.debounce_refresh(RefreshTarget::Page(app.page())) => {
+            return Some(Command::DownloadPosts);
+        }
+        KeyAction::BlockCommunity => {
+            if let Some(p) = app.posts.current() {
+                let community_id = p.community.id;
+                let prompt = format!("Block community {}?", p.community.name);
+
+                app.set_confirm(Confirm::new(
+                    prompt,
+                    Action::BlockCommunity {
+                        community_id,
+                        block: true,
+                    },
+                ));
+            }
+        }
+        KeyAction::Quit => return app.quit(),
+        _ => (),
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+
+    use super::*;
+    use crate::{
+        config::{Config, InstanceConfig, NsfwMode},
+        posts::PostResponses,
+        screen::{
+            snapshot::{assert_snapshot, buffer_text, fixture_app, render},
+            status_text, PENDING_TIMEOUT,
+        },
+        theme::Theme,
+    };
+
+    #[test]
+    fn test_render_posts_screen() {
+        for (width, height) in [(80, 24), (120, 40)] {
+            let mut app = fixture_app();
+            let buffer = render(width, height, &mut app, render_posts_screen);
+
+            assert_snapshot(&format!("posts_list_{width}x{height}"), &buffer);
+        }
+    }
+
+    #[test]
+    fn test_render_posts_mono() {
+        let mut app = fixture_app();
+        app.theme = Theme::mono();
+        app.posts.items[0].counts.score = -1;
+
+        let buffer = render(80, 24, &mut app, render_posts_screen);
+        assert!(buffer
+            .content()
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+
+        // the selected post stands out by its modifiers alone
+        assert!(buffer[(2, 1)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_render_posts_columns() {
+        let mut app = fixture_app();
+        let post = app.posts.items[0].clone();
+
+        app.posts.items = [
+            (1234, 5, "Short", None),
+            (-3, 12_000, "A much longer post title", Some(-1)),
+        ]
+        .map(|(score, comments, name, my_vote)| {
+            let mut p = post.clone();
+            p.counts.score = score;
+            p.counts.comments = Some(comments);
+            p.post.name = name.into();
+            p.my_vote = my_vote;
+            p
+        })
+        .to_vec();
+
+        let buffer = render(42, 12, &mut app, render_posts_screen);
+        let text = buffer_text(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+
+        // columns stay aligned with votes, and long titles are truncated
+        assert_eq!(lines[1], "│   1.2k      5 c [link] Short           │");
+        assert_eq!(lines[4], "│     -3▼ 12.0k c [link] A much longer p…│");
+        assert_eq!(buffer[(6, 4)].fg, Color::Red);
+        assert_eq!(buffer[(8, 4)].fg, app.theme.downvoted.fg.unwrap());
+
+        // narrow terminals drop the comment count
+        let text = buffer_text(&render(30, 12, &mut app, render_posts_screen));
+        assert!(text.contains("│   1.2k [link] Short"));
+        assert!(text.contains("│     -3▼[link] A much longe…│"));
+
+        // wide rows show the link domain
+        let text = buffer_text(&render(80, 12, &mut app, render_posts_screen));
+        assert!(text.contains("5 c [link blog.rust-lang.org] Short"));
+    }
+
+    #[test]
+    fn test_handle_posts_input() {
+        let mut app = fixture_app();
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('X')), &mut app);
+        assert_eq!(command, None);
+        assert_eq!(
+            app.confirm.as_ref().map(|c| c.prompt.as_str()),
+            Some("Block community rust?")
+        );
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('n')), &mut app);
+        assert!(app.confirm.is_none());
+        assert_eq!(command, None, "confirm overlay takes the key");
+
+        handle_posts_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!(app.posts.state.selected(), None);
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Right), &mut app);
+        assert_eq!(command, Some(Command::DownloadPosts));
+        assert_eq!(app.page(), 2);
+
+        // nothing selected, nothing to open
+        handle_posts_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.screen(), Screen::PostList);
+
+        handle_posts_input(KeyEvent::from(KeyCode::Down), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.screen(), Screen::Post);
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('q')), &mut app);
+        assert_eq!(command, Some(Command::Quit));
+    }
+
+    #[test]
+    fn test_handle_posts_input_go_to_page() {
+        let mut app = fixture_app();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        handle_posts_input(key(':'), &mut app);
+        assert_eq!(app.input.as_ref().map(|i| i.prompt()), Some("Go to page"));
+
+        for c in ['1', 'x', '5'] {
+            assert_eq!(handle_posts_input(key(c), &mut app), None);
+        }
+        let text = buffer_text(&render(80, 24, &mut app, render_posts_screen));
+        assert!(text.contains("15_"), "{text}");
+        assert!(!text.contains("numbers only"));
+
+        handle_posts_input(key('y'), &mut app);
+        let text = buffer_text(&render(80, 24, &mut app, render_posts_screen));
+        assert!(text.contains("15_ numbers only"), "{text}");
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(command, Some(Command::DownloadPosts));
+        assert_eq!(app.page(), 15);
+        assert!(app.input.is_none());
+
+        // page 0 does not exist
+        handle_posts_input(key(':'), &mut app);
+        handle_posts_input(key('0'), &mut app);
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(command, None);
+        assert_eq!(app.hud_message(), "invalid page number");
+        assert_eq!(app.page(), 15);
+
+        handle_posts_input(key(':'), &mut app);
+        handle_posts_input(key('3'), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Backspace), &mut app);
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(command, None);
+        assert_eq!(app.hud_message(), "cancelled");
+
+        handle_posts_input(key(':'), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert!(app.input.is_none());
+        assert_eq!(app.page(), 15);
+    }
+
+    #[test]
+    fn test_handle_posts_input_count() {
+        let mut app = fixture_app();
+        let post = app.posts.items[0].clone();
+        app.posts.items = vec![post; 12];
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+
+        handle_posts_input(key('1'), &mut app);
+        handle_posts_input(key('0'), &mut app);
+        assert_eq!(app.pending.typed(), "10");
+        assert!(
+            status_text(&app).ends_with(" | 10"),
+            "{}",
+            status_text(&app)
+        );
+
+        handle_posts_input(KeyEvent::from(KeyCode::Down), &mut app);
+        assert_eq!(app.posts.state.selected(), Some(10));
+        assert!(app.pending.is_empty());
+
+        handle_posts_input(key('3'), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Up), &mut app);
+        assert_eq!(app.posts.state.selected(), Some(7));
+
+        handle_posts_input(key('G'), &mut app);
+        assert_eq!(app.posts.state.selected(), Some(11));
+        handle_posts_input(key('g'), &mut app);
+        assert_eq!(app.pending.typed(), "g");
+        handle_posts_input(key('g'), &mut app);
+        assert_eq!(app.posts.state.selected(), Some(0));
+
+        // Esc cancels the count, not the selection
+        handle_posts_input(key('4'), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert!(app.pending.is_empty());
+        assert_eq!(app.posts.state.selected(), Some(0));
+
+        // the count times out
+        handle_posts_input(key('4'), &mut app);
+        app.now = app.now.map(|now| now + PENDING_TIMEOUT);
+        handle_posts_input(KeyEvent::from(KeyCode::Down), &mut app);
+        assert_eq!(app.posts.state.selected(), Some(1));
+
+        let command = handle_posts_input(key('3'), &mut app);
+        assert_eq!(command, None);
+        let command = handle_posts_input(key('n'), &mut app);
+        assert_eq!(command, Some(Command::DownloadPosts));
+        assert_eq!(app.page(), 4);
+        handle_posts_input(key('5'), &mut app);
+        handle_posts_input(key('p'), &mut app);
+        assert_eq!(app.page(), 1);
+    }
+
+    #[test]
+    fn test_handle_posts_input_refresh() {
+        let mut app = fixture_app();
+        let refresh = KeyEvent::from(KeyCode::Char('R'));
+
+        assert_eq!(
+            handle_posts_input(refresh, &mut app),
+            Some(Command::DownloadPosts)
+        );
+        assert_eq!(handle_posts_input(refresh, &mut app), None);
+        assert!(status_text(&app).ends_with("refreshed recently, try again in 5s"));
+
+        // each page is debounced on its own
+        handle_posts_input(KeyEvent::from(KeyCode::Right), &mut app);
+        assert_eq!(
+            handle_posts_input(refresh, &mut app),
+            Some(Command::DownloadPosts)
+        );
     }
 
-    Ok(())
+    #[test]
+    fn test_handle_posts_input_keymap() {
+        let mut app = fixture_app();
+        app.config = Config::from_toml("[keys]\nnext_page = \"t\"\nquit = \"ctrl+q\"").unwrap();
+        app.keymap = app.config.keymap().unwrap();
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('n')), &mut app);
+        assert_eq!(command, None);
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('t')), &mut app);
+        assert_eq!(command, Some(Command::DownloadPosts));
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('q')), &mut app);
+        assert_eq!(command, None);
+
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), event::KeyModifiers::CONTROL);
+        assert_eq!(handle_posts_input(ctrl_q, &mut app), Some(Command::Quit));
+
+        // always quits
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), event::KeyModifiers::CONTROL);
+        assert_eq!(handle_posts_input(ctrl_c, &mut app), Some(Command::Quit));
+
+        let buffer = render(120, 40, &mut app, render_posts_screen);
+        assert!(buffer_text(&buffer).contains("| (Ctrl+q) quit |"));
+    }
+
+    #[test]
+    fn test_toggle_nsfw() {
+        let mut app = fixture_app();
+        assert_eq!(app.config.nsfw, NsfwMode::Hide);
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('N')), &mut app);
+        assert_eq!(
+            command,
+            Some(Command::DownloadPosts),
+            "page is filtered again"
+        );
+        assert_eq!(app.config.nsfw, NsfwMode::Blur);
+        assert_eq!(app.hud_message(), "NSFW posts: blur");
+
+        handle_posts_input(KeyEvent::from(KeyCode::Char('N')), &mut app);
+        handle_posts_input(KeyEvent::from(KeyCode::Char('N')), &mut app);
+        assert_eq!(app.config.nsfw, NsfwMode::Hide);
+    }
+
+    #[test]
+    fn test_toggle_filters() {
+        let mut app = fixture_app();
+        app.posts.filtered = 3;
+
+        let buffer = render(80, 10, &mut app, render_posts_screen);
+        assert!(buffer_text(&buffer)
+            .lines()
+            .next()
+            .unwrap()
+            .contains("Posts | 3 posts filtered"));
+
+        let command = handle_posts_input(KeyEvent::from(KeyCode::Char('F')), &mut app);
+        assert_eq!(command, Some(Command::DownloadPosts));
+        assert!(!app.filters_enabled);
+        assert_eq!(app.hud_message(), "Filters: off");
+
+        handle_posts_input(KeyEvent::from(KeyCode::Char('F')), &mut app);
+        assert!(app.filters_enabled);
+    }
+
+    #[test]
+    fn test_dedupe_links() {
+        let mut app = fixture_app();
+        app.config.dedupe_links = true;
+        let post = app.posts.items[0].clone();
+        let posts = (0..3)
+            .map(|i| {
+                let mut p = post.clone();
+                p.post.id += i;
+                p.counts.score -= i as i64;
+                p
+            })
+            .collect();
+        app.set_posts(PostResponses {
+            posts,
+            next_page: None,
+        });
+        app.posts.first();
+
+        let text = buffer_text(&render(120, 24, &mut app, render_posts_screen));
+        assert!(
+            text.contains("│    112▲     1 c (+2 duplicates) [link blog.rust-lang.org] Announcing")
+        );
+        assert_eq!(text.matches("Announcing Rust").count(), 1);
+
+        let key = KeyEvent::from(KeyCode::Char('D'));
+        handle_posts_input(key, &mut app);
+        assert_eq!(app.hud_message(), "showing 2 duplicates");
+        let text = buffer_text(&render(120, 24, &mut app, render_posts_screen));
+        assert!(text.contains("1 c (2 duplicates) [link"));
+        assert!(text.contains("│    111▲     1 c (duplicate) [link"));
+        assert_eq!(text.matches("Announcing Rust").count(), 3);
+
+        app.posts.last();
+        handle_posts_input(key, &mut app);
+        assert_eq!(app.hud_message(), "hiding 2 duplicates");
+        assert_eq!(app.posts.items.len(), 1);
+
+        // without dedupe, nothing is hidden
+        app.config.dedupe_links = false;
+        let posts = app.posts.items.clone();
+        app.set_posts(PostResponses {
+            posts,
+            next_page: None,
+        });
+        app.posts.first();
+        handle_posts_input(key, &mut app);
+        assert_eq!(app.hud_message(), "no duplicates of this post");
+    }
+
+    #[test]
+    fn test_handle_posts_mouse() {
+        let mut app = fixture_app();
+        let area = Rect::new(0, 0, 80, 24);
+        let click = |column, row| MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: event::KeyModifiers::NONE,
+        };
+
+        app.posts.deselect();
+
+        // the title block border is not a row
+        handle_posts_mouse(click(10, 0), &mut app, area);
+        assert_eq!(app.posts.state.selected(), None);
+
+        // below the only post
+        handle_posts_mouse(click(10, 4), &mut app, area);
+        assert_eq!(app.posts.state.selected(), None);
+
+        handle_posts_mouse(click(10, 2), &mut app, area);
+        assert_eq!(app.posts.state.selected(), Some(0));
+        assert_eq!(app.screen(), Screen::PostList);
+
+        handle_posts_mouse(click(10, 1), &mut app, area);
+        assert_eq!(app.screen(), Screen::Post);
+    }
+
+    #[test]
+    fn test_render_posts_screen_overflow() {
+        let mut app = fixture_app();
+        let post = app.posts.items[0].clone();
+
+        app.posts.items = (1..=20)
+            .map(|i| {
+                let mut p = post.clone();
+                p.post.name = format!("Post number {i}");
+                p
+            })
+            .collect();
+
+        for _ in 0..19 {
+            app.posts.next();
+        }
+
+        let text = buffer_text(&render(80, 12, &mut app, render_posts_screen));
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert!(text.contains("Post number 20"), "selected post is in view");
+        assert!(!text.contains("Post number 1 "));
+        assert!(
+            lines[lines.len() - 3].contains("| (q) quit |"),
+            "HUD is pinned"
+        );
+        assert!(
+            lines[lines.len() - 2].contains("│voyager.lemmy.ml | page 1 |"),
+            "status bar is pinned"
+        );
+    }
+
+    #[test]
+    fn test_instance_picker() {
+        let mut app = fixture_app();
+        app.config.instances = ["https://lemmy.ml", "https://programming.dev"]
+            .into_iter()
+            .map(|url| InstanceConfig {
+                url: url.into(),
+                ..Default::default()
+            })
+            .collect();
+
+        let key = |code| KeyEvent::from(code);
+        assert_eq!(handle_posts_input(key(KeyCode::Char('@')), &mut app), None);
+        assert!(app.picking_instance());
+
+        let buffer = render(80, 24, &mut app, render_posts_screen);
+        let text = buffer_text(&buffer);
+        assert!(text.contains("Instances"));
+        assert!(text.contains("2. programming.dev  https://programming.dev"));
+
+        // keys go to the overlay
+        assert_eq!(handle_posts_input(key(KeyCode::Char('j')), &mut app), None);
+        assert_eq!(app.instance_picker.unwrap().selected, 1);
+        assert_eq!(
+            handle_posts_input(key(KeyCode::Enter), &mut app),
+            Some(Command::SwitchInstance)
+        );
+        assert!(!app.picking_instance());
+        assert!(status_text(&app).starts_with("programming.dev |"));
+
+        handle_posts_input(key(KeyCode::Char('@')), &mut app);
+        handle_posts_input(key(KeyCode::Esc), &mut app);
+        assert!(!app.picking_instance());
+    }
 }
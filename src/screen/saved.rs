@@ -0,0 +1,438 @@
+//! Facilities for drawing the Saved screen.
+
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crossterm::event;
+use tui::{layout::Constraint, prelude::*, widgets::*};
+
+use crate::{
+    app::{Action, App, Command},
+    comments::CommentResponse,
+    keymap::KeyAction,
+    saved::SavedTab,
+    utils::format_timestamp,
+};
+
+use super::{
+    community_handle, creator_handle, draw_confirm, draw_error, handle_confirm_key,
+    handle_error_key, is_interrupt, pending_action, post_row, push_message, saved_span, score_span,
+    title_block, truncate_width, vote_span, Screen, ROW_HEIGHT, SCORE_WIDTH,
+};
+
+/// Widest post title in the info line of a saved comment, leaving room for the author and date.
+const MAX_POST_WIDTH: usize = 24;
+
+/// Gets the layout of the Saved screen `area`, inside the title block: tabs, table, HUD, and
+/// status bar.
+///
+/// Shared by drawing and mouse handling, so clicks map to the rows as drawn.
+pub fn saved_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(Block::bordered().inner(area))
+}
+
+/// Gets the table row of a saved comment, [ROW_HEIGHT] lines for a table `width` columns wide:
+/// the score and the comment flattened to one line, then the post, author, community, and date.
+pub fn saved_comment_row(
+    c: &CommentResponse,
+    app: &App,
+    width: u16,
+    now: SystemTime,
+) -> Row<'static> {
+    let indent = " ".repeat(SCORE_WIDTH + 1);
+    let author = creator_handle(&c.creator, &app.config);
+    let community = community_handle(&c.community, &app.config);
+    let date = format_timestamp(c.comment.published(), app.config.timestamps, now);
+
+    let mut line = vec![
+        score_span(c.counts.score(), app),
+        vote_span(c.my_vote, &app.theme),
+    ];
+    if c.saved {
+        line.extend([saved_span(&app.theme), Span::raw(" ")]);
+    }
+    let used: usize = line.iter().map(Span::width).sum();
+    let content = c
+        .comment
+        .content()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let content = truncate_width(&content, (width as usize).saturating_sub(used));
+    line.push(Span::raw(content));
+    let post = truncate_width(c.post.name(), MAX_POST_WIDTH);
+    let info = format!(
+        "{indent}[ post: {post} | author: {author} | community: {community} | published: {date} ]"
+    );
+
+    Row::new(vec![Cell::from(Text::from(vec![
+        Line::from(line),
+        Line::from(truncate_width(&info, width as usize)),
+        Line::from("-".repeat(width as usize)),
+    ]))])
+    .style(app.theme.body)
+    .height(ROW_HEIGHT)
+}
+
+/// Gets the status bar text of the Saved screen, e.g. `lemmy.ml | saved posts | page 2 | 20
+/// posts`, followed by the current message, if any.
+pub fn saved_status_text(app: &App) -> String {
+    let host = app.account_label();
+    let host = host.as_deref().unwrap_or(app.instance_label());
+    let tab = app.saved.tab.title().to_lowercase();
+
+    let mut text = format!("{host} | saved {tab} | page {}", app.saved.page());
+    if app.saved.loaded() {
+        // `1 post`, `2 posts`
+        match app.saved.len() {
+            1 => text.push_str(&format!(" | 1 {}", tab.trim_end_matches('s'))),
+            n => text.push_str(&format!(" | {n} {tab}")),
+        }
+    }
+    push_message(app, &mut text);
+
+    text
+}
+
+/// Renders the Saved screen, without handling input.
+///
+/// Without a JWT, the table is replaced by a login hint.
+pub fn render_saved_screen(f: &mut Frame, app: &mut App) {
+    let size = f.area();
+    let chunks = saved_layout(size);
+    let now = app.now();
+    let width = chunks[1].width;
+
+    let tabs = Tabs::new([SavedTab::Posts, SavedTab::Comments].map(SavedTab::title))
+        .select(app.saved.tab as usize)
+        .style(app.theme.header)
+        .highlight_style(app.theme.highlight);
+
+    let rows: Option<Vec<Row>> = match app.saved.tab {
+        SavedTab::Posts => app.saved.posts.as_ref().map(|posts| {
+            posts
+                .items
+                .iter()
+                .map(|p| post_row(p, posts, app, width, now))
+                .collect()
+        }),
+        SavedTab::Comments => app.saved.comments.as_ref().map(|comments| {
+            comments
+                .items
+                .iter()
+                .map(|c| saved_comment_row(c, app, width, now))
+                .collect()
+        }),
+    };
+
+    let hud = Paragraph::new(app.keymap.hud(&[
+        (&[KeyAction::Quit], "quit"),
+        (&[KeyAction::Back], "back"),
+        (&[KeyAction::OpenPost], "open"),
+        (&[KeyAction::NextTab], "posts/comments"),
+        (&[KeyAction::PrevPage], "prev page"),
+        (&[KeyAction::NextPage], "next page"),
+        (&[KeyAction::ToggleSave], "unsave"),
+        (&[KeyAction::Refresh], "refresh"),
+    ]))
+    .style(app.theme.header);
+    let status = truncate_width(&saved_status_text(app), chunks[3].width as usize);
+
+    f.render_widget(title_block("Saved", &app.theme), size);
+    f.render_widget(tabs, chunks[0]);
+
+    let tab = app.saved.tab.title().to_lowercase();
+    let placeholder = match rows {
+        _ if app.jwt().is_none() => Some("login required: set LEMMY_JWT".to_owned()),
+        None => Some(format!("loading saved {tab}")),
+        Some(rows) if rows.is_empty() => Some(format!("no saved {tab}")),
+        Some(rows) => {
+            // the table scrolls to keep the selected row in view
+            let table = Table::new(rows, [Constraint::Percentage(100)])
+                .style(app.theme.header)
+                .highlight_style(app.theme.highlight)
+                .column_spacing(0);
+            let state = match app.saved.tab {
+                SavedTab::Posts => app.saved.posts.as_mut().map(|p| &mut p.state),
+                SavedTab::Comments => app.saved.comments.as_mut().map(|c| &mut c.state),
+            };
+
+            if let Some(state) = state {
+                f.render_stateful_widget(table, chunks[1], state);
+            }
+            None
+        }
+    };
+    if let Some(text) = placeholder {
+        f.render_widget(Paragraph::new(text).style(app.theme.body), chunks[1]);
+    }
+
+    f.render_widget(hud, chunks[2]);
+    f.render_widget(Paragraph::new(status).style(app.theme.header), chunks[3]);
+
+    draw_confirm(f, app);
+    draw_error(f, app);
+}
+
+/// Handles a mouse event on the Saved screen, for the screen `area`.
+///
+/// Scrolling moves the selection, clicking a row selects it, and clicking the selected row
+/// opens it.
+pub fn handle_saved_mouse(event: event::MouseEvent, app: &mut App, area: Rect) -> Option<Command> {
+    if app.has_error() || app.confirming() {
+        return None;
+    }
+
+    match event.kind {
+        event::MouseEventKind::ScrollDown => app.saved.next(),
+        event::MouseEventKind::ScrollUp => app.saved.previous(),
+        event::MouseEventKind::Down(event::MouseButton::Left) => {
+            let rows = saved_layout(area)[1];
+            if !rows.contains(Position::new(event.column, event.row)) {
+                return None;
+            }
+
+            let i = app.saved.offset() + ((event.row - rows.y) / ROW_HEIGHT) as usize;
+            if i >= app.saved.len() {
+                return None;
+            }
+
+            if app.saved.selected() == Some(i) {
+                open_selected(app);
+            } else {
+                app.saved.select(Some(i));
+            }
+        }
+        _ => (),
+    }
+
+    None
+}
+
+/// Handles a key press on the Saved screen.
+///
+/// Returns a [Command] for the main loop to perform, if any.
+pub fn handle_saved_input(event: event::KeyEvent, app: &mut App) -> Option<Command> {
+    if app.has_error() {
+        return handle_error_key(app, event.code, Command::DownloadSaved);
+    } else if app.confirming() {
+        return handle_confirm_key(app, event.code);
+    } else if is_interrupt(&event) {
+        return Some(Command::Quit);
+    }
+
+    let (action, count) = pending_action(app, Screen::Saved, &event)?;
+
+    match action {
+        KeyAction::Back => return app.pop_screen(),
+        KeyAction::SelectNext => (0..count).for_each(|_| app.saved.next()),
+        KeyAction::SelectPrev => (0..count).for_each(|_| app.saved.previous()),
+        KeyAction::FirstPost => app.saved.first(),
+        KeyAction::LastPost => app.saved.last(),
+        KeyAction::OpenPost => open_selected(app),
+        KeyAction::NextPage => {
+            app.saved
+                .set_page(app.saved.page().saturating_add(count as u64));
+            return download(app);
+        }
+        KeyAction::PrevPage if app.saved.page() > 1 => {
+            app.saved
+                .set_page(app.saved.page().saturating_sub(count as u64));
+            return download(app);
+        }
+        KeyAction::NextTab => {
+            app.saved.next_tab();
+            if !app.saved.loaded() {
+                return download(app);
+            }
+        }
+        KeyAction::ToggleSave => unsave_selected(app),
+        KeyAction::Refresh => return download(app),
+        KeyAction::Quit => return app.quit(),
+        _ => (),
+    }
+
+    None
+}
+
+/// Gets the [Command] to download the current tab, unless logged out.
+fn download(app: &mut App) -> Option<Command> {
+    if app.jwt().is_none() {
+        app.set_message("login required: set LEMMY_JWT");
+        return None;
+    }
+
+    Some(Command::DownloadSaved)
+}
+
+/// Opens the selected saved post, or the post of the selected saved comment, scrolled to the
+/// comment.
+fn open_selected(app: &mut App) {
+    if let Some(post) = app.saved.current_post().cloned() {
+        app.posts.select_or_insert(post);
+        app.push_screen(Screen::Post);
+    } else if let Some(c) = app.saved.current_comment() {
+        let (post_id, comment_id) = (c.post.id(), c.comment.id());
+
+        // the post is downloaded by the main loop, the comment from the post is not enough
+        app.focus_comment = Some(comment_id);
+        app.set_open_post(post_id);
+    }
+}
+
+/// Unsaves the selected saved post, or comment, removing its row right away.
+fn unsave_selected(app: &mut App) {
+    if let Some(post_id) = app.saved.current_post().map(|p| p.post.id()) {
+        app.saved.remove_post(post_id);
+        app.set_action(Action::SavePost {
+            post_id,
+            save: false,
+        });
+    } else if let Some(comment_id) = app.saved.current_comment().map(|c| c.comment.id()) {
+        app.saved.remove_comment(comment_id);
+        app.set_action(Action::SaveComment {
+            comment_id,
+            save: false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::{
+        comments::CommentResponses,
+        posts::PostResponses,
+        screen::snapshot::{buffer_text, fixture_app, render},
+    };
+
+    /// Gets the fixture [App] on the Saved screen, with every fixture post and comment saved.
+    fn saved_app() -> App {
+        let mut app = fixture_app();
+        app.set_jwt(Some("token".into()));
+        assert_eq!(app.open_saved(), Some(Command::DownloadSaved));
+
+        let post = app.posts.current().unwrap().clone();
+        let post_id = post.post.id();
+        let posts = (0..3)
+            .map(|i| {
+                let mut p = post.clone();
+                p.post.id += i;
+                p
+            })
+            .collect();
+        let comments = app.comments_get(post_id).unwrap().items.clone();
+        app.saved.set_posts(PostResponses {
+            posts,
+            next_page: None,
+        });
+        app.saved.set_comments(CommentResponses::new(comments));
+
+        app
+    }
+
+    #[test]
+    fn test_render_saved_screen() {
+        let mut app = fixture_app();
+        assert_eq!(app.open_saved(), None);
+        assert_eq!(app.screen(), Screen::Saved);
+
+        let text = buffer_text(&render(80, 24, &mut app, render_saved_screen));
+        assert!(text.contains("login required: set LEMMY_JWT"), "{text}");
+
+        let mut app = saved_app();
+        let title = app.posts.items[0].post.name().to_owned();
+        let text = buffer_text(&render(80, 24, &mut app, render_saved_screen));
+        assert!(text.contains("Posts │ Comments"), "{text}");
+        assert!(text.contains(&title[..20]), "{text}");
+        assert!(
+            saved_status_text(&app).ends_with(&format!(
+                "| saved posts | page 1 | {} posts",
+                app.saved.len()
+            )),
+            "{}",
+            saved_status_text(&app)
+        );
+
+        handle_saved_input(KeyEvent::from(KeyCode::Tab), &mut app);
+        let c = app.saved.comments.as_ref().unwrap().items[0].clone();
+        let text = buffer_text(&render(80, 24, &mut app, render_saved_screen));
+        let content = c.comment.content().split_whitespace().next().unwrap();
+        assert!(text.contains(content), "{text}");
+        assert!(
+            text.contains(&format!("post: {}", &c.post.name()[..10])),
+            "{text}"
+        );
+
+        app.saved.comments = Some(CommentResponses::new(Vec::new()).into());
+        let text = buffer_text(&render(80, 24, &mut app, render_saved_screen));
+        assert!(text.contains("no saved comments"), "{text}");
+    }
+
+    #[test]
+    fn test_handle_saved_input() {
+        let mut app = saved_app();
+        let key = |c| KeyEvent::from(KeyCode::Char(c));
+        let len = app.saved.len();
+
+        // unsaving removes the row right away
+        handle_saved_input(KeyEvent::from(KeyCode::Down), &mut app);
+        let post_id = app.saved.current_post().unwrap().post.id();
+        assert_eq!(handle_saved_input(key('b'), &mut app), None);
+        assert_eq!(app.saved.len(), len - 1);
+        assert_eq!(
+            app.take_action(),
+            Some(Action::SavePost {
+                post_id,
+                save: false
+            })
+        );
+
+        // each tab is paged separately
+        assert_eq!(
+            handle_saved_input(key('n'), &mut app),
+            Some(Command::DownloadSaved)
+        );
+        assert_eq!(app.saved.page(), 2);
+        assert_eq!(
+            handle_saved_input(key('p'), &mut app),
+            Some(Command::DownloadSaved)
+        );
+        assert_eq!(handle_saved_input(key('p'), &mut app), None);
+        assert_eq!(app.saved.page(), 1);
+
+        // comments open their post, scrolled to the comment
+        assert_eq!(
+            handle_saved_input(KeyEvent::from(KeyCode::Tab), &mut app),
+            None
+        );
+        handle_saved_input(KeyEvent::from(KeyCode::Down), &mut app);
+        let c = app.saved.current_comment().unwrap().clone();
+        handle_saved_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.take_open_post(), Some(c.post.id()));
+        assert_eq!(app.focus_comment, Some(c.comment.id()));
+        assert_eq!(app.screen(), Screen::Saved);
+
+        // posts open right away
+        handle_saved_input(KeyEvent::from(KeyCode::Tab), &mut app);
+        let post_id = app.saved.current_post().unwrap().post.id();
+        handle_saved_input(KeyEvent::from(KeyCode::Enter), &mut app);
+        assert_eq!(app.screen(), Screen::Post);
+        assert_eq!(app.posts.current().map(|p| p.post.id()), Some(post_id));
+
+        app.pop_screen();
+        assert_eq!(app.screen(), Screen::Saved);
+        handle_saved_input(KeyEvent::from(KeyCode::Esc), &mut app);
+        assert_eq!(app.screen(), Screen::PostList);
+    }
+}
@@ -0,0 +1,91 @@
+//! Golden snapshot assertions for rendered screens.
+//!
+//! Snapshots are stored as plain text under `tests/snapshots`. Run the tests with
+//! `UPDATE_SNAPSHOTS=1` to write new snapshots after an intended rendering change.
+
+use std::path::PathBuf;
+
+use tui::{backend::TestBackend, buffer::Buffer, Frame, Terminal};
+
+use crate::{
+    app::App,
+    comments::CommentResponses,
+    config::Config,
+    posts::{PostResponseTable, PostResponses},
+};
+
+/// Environment variable to overwrite snapshots with the rendered output.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Gets the text content of a [Buffer], one line per row, with trailing whitespace trimmed.
+pub fn buffer_text(buffer: &Buffer) -> String {
+    let width = buffer.area.width as usize;
+
+    buffer
+        .content
+        .chunks(width.max(1))
+        .map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+/// Asserts the [Buffer] text matches the `name` snapshot.
+pub fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{name}.txt"));
+    let actual = buffer_text(buffer);
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path.as_path(), actual.as_str()).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path.as_path()).unwrap_or_else(|err| {
+        panic!(
+            "missing snapshot {}: {err}, run with {UPDATE_SNAPSHOTS_ENV}=1 to create it",
+            path.display()
+        )
+    });
+
+    assert_eq!(actual, expected, "snapshot mismatch: {}", path.display());
+}
+
+/// Creates an [App] with the Lemmy 0.19 fixture posts and comments, with the first post
+/// selected.
+pub fn fixture_app() -> App {
+    let posts: PostResponses = serde_json::from_str(include_str!(
+        "../../tests/fixtures/lemmy-0.19.3/post_list.json"
+    ))
+    .unwrap();
+    let comments: CommentResponses = serde_json::from_str(include_str!(
+        "../../tests/fixtures/lemmy-0.19.3/comment_list.json"
+    ))
+    .unwrap();
+
+    let mut app = App::new(Config::new(), PostResponseTable::new(Vec::new()));
+    // relative timestamps do not change over time
+    app.now = crate::utils::parse_timestamp("2024-02-09T18:00:00Z");
+    app.set_posts(posts);
+    app.posts.next();
+
+    let post_id = app.posts.current().unwrap().post.id();
+    app.comments_insert(post_id, comments.into());
+
+    app
+}
+
+/// Renders a screen into a [TestBackend] of the given size.
+pub fn render<F>(width: u16, height: u16, app: &mut App, render: F) -> Buffer
+where
+    F: FnOnce(&mut Frame, &mut App),
+{
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| render(f, app)).unwrap();
+
+    terminal.backend().buffer().clone()
+}
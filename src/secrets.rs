@@ -0,0 +1,342 @@
+//! Credentials, e.g. the JWT of an account, stored in the OS keyring.
+//!
+//! Systems without a keyring service fall back to a file readable only by the user, see
+//! [SecretStore::path].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{client::InstanceUrl, config::Config, utils::actor_host, Error, Result, Source};
+
+/// Service name of the entries in the OS keyring.
+const SERVICE: &str = "temi";
+
+/// Kind of secret stored for an [Account].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Secret {
+    /// JWT sent to the instance, see [Client::set_jwt](crate::client::Client::set_jwt).
+    Jwt,
+    /// Password of the account, to log in again once the JWT expires.
+    Password,
+}
+
+impl Secret {
+    /// All the [Secret] kinds, e.g. to delete them all on logout.
+    pub const ALL: [Self; 2] = [Self::Jwt, Self::Password];
+
+    /// Gets the name of the [Secret], used in its key.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Jwt => "jwt",
+            Self::Password => "password",
+        }
+    }
+}
+
+/// Account on an instance that secrets are stored for.
+///
+/// Instances without a configured account name have a single, unnamed, account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    /// Instance the account is on.
+    pub instance: InstanceUrl,
+    /// Name of the account, if configured.
+    pub username: Option<String>,
+}
+
+impl Account {
+    /// Creates a new [Account].
+    pub fn new<S: Into<InstanceUrl>>(instance: S, username: Option<String>) -> Self {
+        Self {
+            instance: instance.into(),
+            username,
+        }
+    }
+
+    /// Creates the [Account] for the configured instance, named by the
+    /// [active account](Config::active_account), if any.
+    pub fn from_config(config: &Config) -> Self {
+        let username = config.active_account().map(str::to_owned);
+
+        Self::new(config.instance_url.as_str(), username)
+    }
+
+    /// Gets the key of the [Secret] for the [Account], e.g. `ferris@https://lemmy.ml#jwt`.
+    pub fn key(&self, secret: Secret) -> String {
+        match self.username.as_deref() {
+            Some(username) => format!("{username}@{}#{}", self.instance, secret.as_str()),
+            None => format!("{}#{}", self.instance, secret.as_str()),
+        }
+    }
+}
+
+/// Displays the account as `ferris@lemmy.ml`, or the host for unnamed accounts.
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = self.instance.as_str();
+        let host = actor_host(url).unwrap_or(url);
+
+        match self.username.as_deref() {
+            Some(username) => write!(f, "{username}@{host}"),
+            None => f.write_str(host),
+        }
+    }
+}
+
+/// Where a [Secret] was stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Storage {
+    /// The OS keyring.
+    Keyring,
+    /// The fallback file, for systems without a keyring service.
+    File(PathBuf),
+}
+
+/// Stores [Secret]s in the OS keyring, or in a file readable only by the user.
+///
+/// On Linux, the kernel keyring keeps secrets until the user logs out of the system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecretStore {
+    /// Whether to try the OS keyring before the file.
+    keyring: bool,
+    /// Fallback file, see [SecretStore::path].
+    file: Option<PathBuf>,
+}
+
+impl SecretStore {
+    /// Creates a new [SecretStore], using the OS keyring, and the default fallback file.
+    pub fn new() -> Self {
+        Self {
+            keyring: true,
+            file: Self::path(),
+        }
+    }
+
+    /// Creates a [SecretStore] using only the file, e.g. in tests.
+    pub fn with_file<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            keyring: false,
+            file: Some(path.into()),
+        }
+    }
+
+    /// Gets the default fallback file path, `$XDG_STATE_HOME/temi/secrets.json`.
+    ///
+    /// Falls back to `$HOME/.local/state` if `XDG_STATE_HOME` is unset.
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+            })
+            .map(|dir| dir.join("temi").join("secrets.json"))
+    }
+
+    /// Loads the [Secret] of the [Account], if stored.
+    ///
+    /// Secrets stored in the file while the keyring was unavailable are found too.
+    pub fn load(&self, account: &Account, secret: Secret) -> Result<Option<String>> {
+        let key = account.key(secret);
+
+        if self.keyring {
+            match keyring_entry(&key).and_then(|entry| entry.get_password()) {
+                Ok(value) => return Ok(Some(value)),
+                Err(keyring::Error::NoEntry) => (),
+                Err(err) if is_unavailable(&err) => (),
+                Err(err) => return Err(Error::Secret(Source::new(err))),
+            }
+        }
+
+        match self.file.as_deref() {
+            Some(path) => Ok(read_file(path)?.remove(&key)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores the [Secret] of the [Account], in the keyring if available.
+    ///
+    /// Returns the [Storage] the secret was stored in, e.g. to warn about the fallback file.
+    pub fn store(&self, account: &Account, secret: Secret, value: &str) -> Result<Storage> {
+        let key = account.key(secret);
+
+        if self.keyring {
+            match keyring_entry(&key).and_then(|entry| entry.set_password(value)) {
+                Ok(()) => return Ok(Storage::Keyring),
+                Err(err) if is_unavailable(&err) => (),
+                Err(err) => return Err(Error::Secret(Source::new(err))),
+            }
+        }
+
+        let path = self.file.as_deref().ok_or(Error::Config(
+            "no keyring, and no file to store secrets in".into(),
+        ))?;
+        let mut secrets = read_file(path)?;
+        secrets.insert(key, value.to_owned());
+        write_file(path, &secrets)?;
+
+        Ok(Storage::File(path.to_owned()))
+    }
+
+    /// Deletes the [Secret] of the [Account] from the keyring, and the file.
+    ///
+    /// Returns whether a secret was deleted.
+    pub fn delete(&self, account: &Account, secret: Secret) -> Result<bool> {
+        let key = account.key(secret);
+        let mut deleted = false;
+
+        if self.keyring {
+            match keyring_entry(&key).and_then(|entry| entry.delete_credential()) {
+                Ok(()) => deleted = true,
+                Err(keyring::Error::NoEntry) => (),
+                Err(err) if is_unavailable(&err) => (),
+                Err(err) => return Err(Error::Secret(Source::new(err))),
+            }
+        }
+
+        if let Some(path) = self.file.as_deref() {
+            let mut secrets = read_file(path)?;
+            if secrets.remove(&key).is_some() {
+                write_file(path, &secrets)?;
+                deleted = true;
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Deletes every [Secret] of the [Account], e.g. on logout.
+    ///
+    /// Returns whether any secret was deleted.
+    pub fn delete_all(&self, account: &Account) -> Result<bool> {
+        let mut deleted = false;
+        for secret in Secret::ALL {
+            deleted |= self.delete(account, secret)?;
+        }
+
+        Ok(deleted)
+    }
+}
+
+impl Default for SecretStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn keyring_entry(key: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, key)
+}
+
+/// Gets whether the keyring error means there is no usable keyring service.
+fn is_unavailable(err: &keyring::Error) -> bool {
+    matches!(
+        err,
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_)
+    )
+}
+
+/// Reads the secrets file, a missing file has no secrets.
+fn read_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the secrets file, readable and writable only by the user on Unix.
+fn write_file(path: &Path, secrets: &BTreeMap<String, String>) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    // written in full before replacing the file, so an interrupted write keeps the old secrets
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    options
+        .open(&tmp)?
+        .write_all(&serde_json::to_vec_pretty(secrets)?)?;
+    std::fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account() {
+        let mut config = Config::new();
+        config.instance_url = "https://lemmy.ml/".into();
+        config.instances = vec![crate::config::InstanceConfig {
+            url: "https://lemmy.ml".into(),
+            account: Some("ferris".into()),
+            ..Default::default()
+        }];
+
+        let account = Account::from_config(&config);
+        assert_eq!(account.key(Secret::Jwt), "ferris@https://lemmy.ml#jwt");
+        assert_eq!(account.to_string(), "ferris@lemmy.ml");
+
+        let account = Account::new("https://programming.dev", None);
+        assert_eq!(
+            account.key(Secret::Password),
+            "https://programming.dev#password"
+        );
+        assert_eq!(account.to_string(), "programming.dev");
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("temi-secrets-{}", std::process::id()));
+        let path = dir.join("secrets.json");
+        let store = SecretStore::with_file(&path);
+        let ferris = Account::new("https://lemmy.ml", Some("ferris".into()));
+        let other = Account::new("https://lemmy.ml", None);
+
+        assert_eq!(store.load(&ferris, Secret::Jwt).unwrap(), None);
+        assert_eq!(
+            store.store(&ferris, Secret::Jwt, "token").unwrap(),
+            Storage::File(path.clone())
+        );
+        store.store(&other, Secret::Jwt, "other").unwrap();
+        assert_eq!(
+            store.load(&ferris, Secret::Jwt).unwrap().as_deref(),
+            Some("token")
+        );
+        assert_eq!(store.load(&ferris, Secret::Password).unwrap(), None);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(store.delete(&ferris, Secret::Jwt).unwrap());
+        assert!(!store.delete(&ferris, Secret::Jwt).unwrap());
+        assert_eq!(store.load(&ferris, Secret::Jwt).unwrap(), None);
+
+        store.store(&ferris, Secret::Password, "hunter2").unwrap();
+        assert!(store.delete_all(&ferris).unwrap());
+        assert!(!store.delete_all(&ferris).unwrap());
+        assert_eq!(
+            store.load(&other, Secret::Jwt).unwrap().as_deref(),
+            Some("other")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
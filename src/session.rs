@@ -0,0 +1,283 @@
+//! Session state saved on a clean exit to `$XDG_STATE_HOME/temi/session.json`, and restored on
+//! the next run, see [RestoreSession].
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hashbrown::HashMap;
+
+use crate::{
+    app::App,
+    client::InstanceUrl,
+    config::Config,
+    posts::{ListingType, SortType},
+    Result,
+};
+
+/// Maximum number of posts to remember the last visit to, the oldest visits are dropped.
+pub const MAX_VISITS: usize = 500;
+
+/// When to restore the previous [Session] on startup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreSession {
+    /// Restore without asking.
+    Always,
+    /// Ask before restoring, skipped when stdin is not a terminal.
+    #[default]
+    Ask,
+    /// Never restore the post list position.
+    Never,
+}
+
+/// Represents the post list position when the application last exited.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// Lemmy instance URL the posts were listed from.
+    pub instance_url: String,
+    /// Page number, starting from 1.
+    pub page: u64,
+    /// Post sort order, if set.
+    pub sort: Option<SortType>,
+    /// Post listing type, if set.
+    pub listing_type: Option<ListingType>,
+    /// Selected post, if any.
+    pub post_id: Option<u64>,
+    /// Index of the first post scrolled into view.
+    pub offset: usize,
+    /// Time of the last visit to each post, in seconds since the Unix epoch.
+    pub visits: BTreeMap<u64, u64>,
+}
+
+impl Session {
+    /// Creates a [Session] from the current state of the [App].
+    pub fn from_app(app: &App) -> Self {
+        Self {
+            instance_url: app.config.instance_url.clone(),
+            page: app.page(),
+            sort: app.config.sort,
+            listing_type: app.config.listing_type,
+            post_id: app.posts.current().map(|p| p.post.id()),
+            offset: app.posts.state.offset(),
+            visits: app.visits.recent(),
+        }
+    }
+
+    /// Gets the default session file path, `$XDG_STATE_HOME/temi/session.json`.
+    ///
+    /// Falls back to `$HOME/.local/state` if `XDG_STATE_HOME` is unset.
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("XDG_STATE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+            })
+            .map(|dir| dir.join("temi").join("session.json"))
+    }
+
+    /// Loads the [Session] from a file.
+    ///
+    /// A missing file results in `None`.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the [Session] to a file, creating the parent directory if needed.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // written in full before replacing the file, so an interrupted write keeps the old session
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Gets whether the [Session] is for the configured instance, ignoring trailing slashes.
+    pub fn matches(&self, config: &Config) -> bool {
+        InstanceUrl::from(self.instance_url.as_str())
+            == InstanceUrl::from(config.instance_url.as_str())
+    }
+
+    /// Gets a short description for the restore prompt, e.g. `page 3, sort TopDay`.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("page {}", self.page.max(1));
+        if let Some(sort) = self.sort {
+            summary.push_str(&format!(", sort {}", sort.as_str()));
+        }
+        if let Some(listing) = self.listing_type {
+            summary.push_str(&format!(", listing {}", listing.as_str()));
+        }
+
+        summary
+    }
+
+    /// Applies the sort and listing type to the [Config], before creating the [App].
+    pub fn apply(&self, config: &mut Config) {
+        if self.sort.is_some() {
+            config.sort = self.sort;
+        }
+        if self.listing_type.is_some() {
+            config.listing_type = self.listing_type;
+        }
+    }
+
+    /// Selects the post, and restores the scroll offset, once the page is loaded.
+    ///
+    /// Nothing is selected if the post is no longer on the page.
+    pub fn select(&self, app: &mut App) {
+        let i = self
+            .post_id
+            .and_then(|id| app.posts.items.iter().position(|p| p.post.id() == id));
+        let last = app.posts.items.len().saturating_sub(1);
+
+        app.posts.state.select(i);
+        *app.posts.state.offset_mut() = self.offset.min(last);
+    }
+}
+
+/// Last visit to each post, for marking the comments published since.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Visits {
+    // seconds since the Unix epoch, by post ID
+    last: BTreeMap<u64, u64>,
+    // visit before the first one this run, by post ID
+    since: HashMap<u64, Option<SystemTime>>,
+}
+
+impl Visits {
+    /// Creates [Visits] from the saved [Session::visits].
+    pub fn new(last: BTreeMap<u64, u64>) -> Self {
+        Self {
+            last,
+            since: HashMap::new(),
+        }
+    }
+
+    /// Records a visit to the post.
+    ///
+    /// The previous visit is kept for the rest of the run, so comments stay new while reading.
+    pub fn visit(&mut self, post_id: u64, now: SystemTime) {
+        let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let previous = self
+            .last
+            .insert(post_id, secs)
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+        self.since.entry(post_id).or_insert(previous);
+    }
+
+    /// Gets the time of the last visit to the post before this run, if any.
+    pub fn since(&self, post_id: u64) -> Option<SystemTime> {
+        self.since.get(&post_id).copied().flatten()
+    }
+
+    /// Gets the most recent visits, up to [MAX_VISITS].
+    pub fn recent(&self) -> BTreeMap<u64, u64> {
+        let mut visits: Vec<(u64, u64)> = self.last.iter().map(|(&id, &t)| (id, t)).collect();
+        visits.sort_by_key(|&(_, t)| std::cmp::Reverse(t));
+
+        visits.into_iter().take(MAX_VISITS).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::snapshot::fixture_app;
+
+    #[test]
+    fn test_session_save_load() {
+        let dir = std::env::temp_dir().join(format!("temi-session-{}", std::process::id()));
+        let path = dir.join("state").join("session.json");
+
+        assert_eq!(Session::load(&path).unwrap(), None);
+
+        let mut app = fixture_app();
+        app.set_page(3);
+        app.config.sort = Some(SortType::TopDay);
+        let session = Session::from_app(&app);
+        assert_eq!(session.post_id, app.posts.current().map(|p| p.post.id()));
+
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), Some(session.clone()));
+        assert_eq!(session.summary(), "page 3, sort TopDay");
+
+        // a file in place of the directory fails without panicking
+        assert!(session.save(&path.join("session.json")).is_err());
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Session::load(&path).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_restore() {
+        let mut app = fixture_app();
+        let post_id = app.posts.current().unwrap().post.id();
+        let mut session = Session {
+            instance_url: format!("{}/", app.config.instance_url),
+            page: 2,
+            sort: Some(SortType::New),
+            listing_type: None,
+            post_id: Some(post_id),
+            offset: 5,
+            visits: BTreeMap::new(),
+        };
+
+        assert!(session.matches(&app.config));
+        assert!(
+            !session.matches(&Config::from_toml("instance_url = \"https://lemmy.ml\"").unwrap())
+        );
+
+        let mut config = Config::from_toml("listing_type = \"local\"").unwrap();
+        session.apply(&mut config);
+        assert_eq!(config.sort, Some(SortType::New));
+        assert_eq!(config.listing_type, Some(ListingType::Local));
+
+        app.posts.deselect();
+        session.select(&mut app);
+        assert_eq!(app.posts.current().map(|p| p.post.id()), Some(post_id));
+        assert_eq!(app.posts.state.offset(), 0, "clamped to the page");
+
+        // the post is no longer on the page
+        session.post_id = Some(post_id + 1);
+        session.select(&mut app);
+        assert_eq!(app.posts.current(), None);
+    }
+
+    #[test]
+    fn test_visits() {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        let mut visits = Visits::new(BTreeMap::from([(1, 100)]));
+
+        visits.visit(1, at(200));
+        visits.visit(2, at(300));
+        assert_eq!(visits.since(1), Some(at(100)));
+        assert_eq!(visits.since(2), None, "first visit");
+
+        // later visits this run keep the earlier time
+        visits.visit(1, at(400));
+        assert_eq!(visits.since(1), Some(at(100)));
+        assert_eq!(visits.recent(), BTreeMap::from([(1, 400), (2, 300)]));
+
+        let mut visits = Visits::default();
+        for id in 0..MAX_VISITS as u64 + 10 {
+            visits.visit(id, at(id));
+        }
+        let recent = visits.recent();
+        assert_eq!(recent.len(), MAX_VISITS);
+        assert_eq!(recent.keys().next(), Some(&10), "oldest visits are dropped");
+    }
+}
@@ -0,0 +1,59 @@
+//! Types and functions for the Lemmy site, i.e. instance, information.
+
+use crate::{client::Client, endpoint::Endpoint, Result};
+
+/// Represents a response to the [GetSite](Endpoint::GetSite) endpoint.
+///
+/// Only the fields used by `temi` are deserialized.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GetSiteResponse {
+    /// Lemmy version running on the instance, e.g. `0.19.3`.
+    pub version: String,
+}
+
+impl GetSiteResponse {
+    /// Gets whether the instance supports cursor pagination for the
+    /// [PostList](Endpoint::PostList) endpoint.
+    pub fn supports_cursor_paging(&self) -> bool {
+        supports_cursor_paging(self.version.as_str())
+    }
+}
+
+/// Gets whether a Lemmy version supports cursor pagination, i.e. is `0.19` or later.
+///
+/// Unparseable versions are assumed to use numeric pages.
+pub fn supports_cursor_paging(version: &str) -> bool {
+    let mut parts = version
+        .trim_start_matches('v')
+        .split(['.', '-'])
+        .map(|p| p.parse::<u64>());
+
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => (major, minor) >= (0, 19),
+        _ => false,
+    }
+}
+
+impl Client {
+    /// Gets the site information from the [GetSite](Endpoint::GetSite) endpoint.
+    pub async fn get_site(&self) -> Result<GetSiteResponse> {
+        self.get_json(self.url(Endpoint::GetSite, &[]).as_str())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports_cursor_paging() {
+        assert!(!supports_cursor_paging("0.18.5"));
+        assert!(supports_cursor_paging("0.19.0-rc.1"));
+        assert!(supports_cursor_paging("0.19.3"));
+        assert!(supports_cursor_paging("v1.0.0"));
+        assert!(!supports_cursor_paging("unknown"));
+        assert!(!supports_cursor_paging(""));
+    }
+}
@@ -0,0 +1,447 @@
+//! Types for running and tracking background tasks.
+
+use std::ops::Range;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use image::DynamicImage;
+use tokio::task::JoinHandle;
+
+use crate::{
+    api::ApiClient,
+    comments::{dl_all_comments_with_progress, CommentResponses, REPLIES_MAX_DEPTH},
+    event::{Event, EventSender},
+    posts::{decode_image, DiskCache, GetPostResponse, PostResponses},
+    Error, Result, Source,
+};
+
+/// Tracks the progress of a multi-step background task, e.g. a multi-page download, and the
+/// bytes received.
+#[derive(Debug, Default)]
+pub struct Progress {
+    done: AtomicU64,
+    total: AtomicU64,
+    bytes: AtomicU64,
+    bytes_total: AtomicU64,
+}
+
+impl Progress {
+    /// Creates a new [Progress].
+    pub const fn new() -> Self {
+        Self {
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            bytes_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets the number of response body bytes received.
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of response body bytes expected, from the `Content-Length` headers, zero
+    /// if unknown.
+    pub fn bytes_total(&self) -> u64 {
+        self.bytes_total.load(Ordering::Relaxed)
+    }
+
+    /// Adds to the number of response body bytes received.
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+
+    /// Adds to the number of response body bytes expected.
+    pub fn add_bytes_total(&self, bytes: u64) {
+        self.bytes_total.fetch_add(bytes, Ordering::SeqCst);
+    }
+
+    /// Gets the number of completed steps.
+    pub fn done(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Gets the total number of steps.
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Sets the total number of steps.
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::SeqCst);
+    }
+
+    /// Increments the number of completed steps.
+    pub fn inc(&self) {
+        self.done.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Background task downloading every page of comments for a [Post](crate::posts::Post).
+#[derive(Debug)]
+pub struct CommentsTask {
+    post_id: u64,
+    comments: Range<u64>,
+    progress: Arc<Progress>,
+    // pages downloaded so far, when streaming
+    pages: Option<Arc<Mutex<Vec<CommentResponses>>>>,
+    handle: JoinHandle<Result<CommentResponses>>,
+}
+
+impl CommentsTask {
+    /// Spawns a new [CommentsTask] on the `tokio` runtime, downloading the pages covering the
+    /// `comments` range, see [dl_all_comments_with_progress].
+    ///
+    /// With `stream`, each page is kept for [take_pages](Self::take_pages) as it arrives, so the
+    /// comments can be shown before every page is downloaded. Sends [Event::FetchComplete] to
+    /// `notify`, if any, for each streamed page, and when finished.
+    pub fn spawn(
+        client: Box<dyn ApiClient>,
+        post_id: u64,
+        comments: Range<u64>,
+        limit: u64,
+        stream: bool,
+        notify: Option<EventSender>,
+    ) -> Self {
+        let progress = Arc::new(Progress::new());
+        let task_progress = Arc::clone(&progress);
+        let pages = stream.then(|| Arc::new(Mutex::new(Vec::new())));
+        let task_pages = pages.clone();
+        let task_comments = comments.clone();
+
+        let handle = tokio::spawn(async move {
+            let page_notify = notify.clone();
+            let on_page = move |page: &CommentResponses| {
+                if let Some(pages) = task_pages.as_ref() {
+                    if let Ok(mut pages) = pages.lock() {
+                        pages.push(page.clone());
+                    }
+                    fetch_complete(page_notify.clone());
+                }
+            };
+
+            let res = dl_all_comments_with_progress(
+                client.as_ref(),
+                post_id,
+                task_comments,
+                limit,
+                &task_progress,
+                &on_page,
+            )
+            .await;
+
+            fetch_complete(notify);
+
+            res
+        });
+
+        Self {
+            post_id,
+            comments,
+            progress,
+            pages,
+            handle,
+        }
+    }
+
+    /// Gets whether the [CommentsTask] is streaming pages, see [take_pages](Self::take_pages).
+    pub const fn streams(&self) -> bool {
+        self.pages.is_some()
+    }
+
+    /// Takes the pages downloaded since the last call, when streaming.
+    pub fn take_pages(&self) -> Vec<CommentResponses> {
+        self.pages
+            .as_ref()
+            .and_then(|pages| pages.lock().ok())
+            .map(|mut pages| std::mem::take(&mut *pages))
+            .unwrap_or_default()
+    }
+
+    /// Gets the [Post](crate::posts::Post) ID for the [CommentsTask].
+    pub const fn post_id(&self) -> u64 {
+        self.post_id
+    }
+
+    /// Gets the range of comments downloading, e.g. `500..1000` for the second batch of 500.
+    pub fn comments(&self) -> Range<u64> {
+        self.comments.clone()
+    }
+
+    /// Gets the download [Progress], in pages.
+    pub fn progress(&self) -> &Progress {
+        self.progress.as_ref()
+    }
+
+    /// Gets whether the [CommentsTask] has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Cancels the [CommentsTask].
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the [CommentsTask] to finish, and returns the downloaded comments.
+    pub async fn join(self) -> Result<CommentResponses> {
+        self.handle
+            .await
+            .map_err(|err| Error::Task(Source::new(err)))?
+    }
+}
+
+/// Background task loading the replies to a comment, beyond the depth returned with the
+/// [Post](crate::posts::Post).
+#[derive(Debug)]
+pub struct RepliesTask {
+    post_id: u64,
+    comment_id: u64,
+    handle: JoinHandle<Result<CommentResponses>>,
+}
+
+impl RepliesTask {
+    /// Spawns a new [RepliesTask] on the `tokio` runtime, loading up to [REPLIES_MAX_DEPTH]
+    /// levels of replies.
+    ///
+    /// Sends [Event::FetchComplete] to `notify`, if any, when finished.
+    pub fn spawn(
+        client: Box<dyn ApiClient>,
+        post_id: u64,
+        comment_id: u64,
+        notify: Option<EventSender>,
+    ) -> Self {
+        let handle = tokio::spawn(async move {
+            let res = client.list_replies(comment_id, REPLIES_MAX_DEPTH).await;
+
+            fetch_complete(notify);
+
+            res
+        });
+
+        Self {
+            post_id,
+            comment_id,
+            handle,
+        }
+    }
+
+    /// Gets the [Post](crate::posts::Post) ID for the [RepliesTask].
+    pub const fn post_id(&self) -> u64 {
+        self.post_id
+    }
+
+    /// Gets the ID of the comment whose replies are loading.
+    pub const fn comment_id(&self) -> u64 {
+        self.comment_id
+    }
+
+    /// Gets whether the [RepliesTask] has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Cancels the [RepliesTask].
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the [RepliesTask] to finish, and returns the loaded replies.
+    pub async fn join(self) -> Result<CommentResponses> {
+        self.handle
+            .await
+            .map_err(|err| Error::Task(Source::new(err)))?
+    }
+}
+
+/// Background task downloading a [Post](crate::posts::Post) with its crossposts, once opened.
+#[derive(Debug)]
+pub struct CrossPostsTask {
+    post_id: u64,
+    handle: JoinHandle<Result<GetPostResponse>>,
+}
+
+impl CrossPostsTask {
+    /// Spawns a new [CrossPostsTask] on the `tokio` runtime.
+    ///
+    /// Sends [Event::FetchComplete] to `notify`, if any, when finished.
+    pub fn spawn(client: Box<dyn ApiClient>, post_id: u64, notify: Option<EventSender>) -> Self {
+        let handle = tokio::spawn(async move {
+            let res = client.get_post_view(post_id).await;
+
+            fetch_complete(notify);
+
+            res
+        });
+
+        Self { post_id, handle }
+    }
+
+    /// Gets the [Post](crate::posts::Post) ID for the [CrossPostsTask].
+    pub const fn post_id(&self) -> u64 {
+        self.post_id
+    }
+
+    /// Gets whether the [CrossPostsTask] has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Cancels the [CrossPostsTask].
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the [CrossPostsTask] to finish, and returns the post with its crossposts.
+    pub async fn join(self) -> Result<GetPostResponse> {
+        self.handle
+            .await
+            .map_err(|err| Error::Task(Source::new(err)))?
+    }
+}
+
+/// Background task downloading a page of [PostResponses].
+#[derive(Debug)]
+pub struct PostsTask {
+    page: u64,
+    handle: JoinHandle<Result<PostResponses>>,
+}
+
+impl PostsTask {
+    /// Spawns a new [PostsTask] on the `tokio` runtime.
+    ///
+    /// Uses the page `cursor`, if any, otherwise the page number. Sends [Event::FetchComplete]
+    /// to `notify`, if any, when finished.
+    pub fn spawn(
+        client: Box<dyn ApiClient>,
+        page: u64,
+        cursor: Option<String>,
+        notify: Option<EventSender>,
+    ) -> Self {
+        let handle = tokio::spawn(async move {
+            let res = match cursor {
+                Some(cursor) => client.list_posts_cursor(&cursor).await,
+                None => client.list_posts(page).await,
+            };
+
+            fetch_complete(notify);
+
+            res
+        });
+
+        Self { page, handle }
+    }
+
+    /// Gets the [PostList](crate::endpoint::Endpoint) page for the [PostsTask].
+    pub const fn page(&self) -> u64 {
+        self.page
+    }
+
+    /// Gets whether the [PostsTask] has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Cancels the [PostsTask].
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the [PostsTask] to finish, and returns the downloaded posts.
+    pub async fn join(self) -> Result<PostResponses> {
+        self.handle
+            .await
+            .map_err(|err| Error::Task(Source::new(err)))?
+    }
+}
+
+/// Background task downloading, and decoding, a [Post](crate::posts::Post) image.
+#[derive(Debug)]
+pub struct ImageTask {
+    progress: Arc<Progress>,
+    handle: JoinHandle<Result<DynamicImage>>,
+}
+
+impl ImageTask {
+    /// Spawns a new [ImageTask] on the `tokio` runtime.
+    ///
+    /// Reads the image from the [DiskCache], if any, and saves downloaded images to it. Sends
+    /// [Event::FetchComplete] to `notify`, if any, when finished.
+    pub fn spawn(
+        client: Box<dyn ApiClient>,
+        url: String,
+        disk_cache: Option<DiskCache>,
+        notify: Option<EventSender>,
+    ) -> Self {
+        let progress = Arc::new(Progress::new());
+        let task_progress = Arc::clone(&progress);
+
+        let handle = tokio::spawn(async move {
+            let res = load_image(client.as_ref(), &url, disk_cache.as_ref(), &task_progress).await;
+
+            fetch_complete(notify);
+
+            res
+        });
+
+        Self { progress, handle }
+    }
+
+    /// Gets the download [Progress], in bytes.
+    pub fn progress(&self) -> &Progress {
+        self.progress.as_ref()
+    }
+
+    /// Gets whether the [ImageTask] has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Cancels the [ImageTask].
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Waits for the [ImageTask] to finish, and returns the decoded image.
+    pub async fn join(self) -> Result<DynamicImage> {
+        self.handle
+            .await
+            .map_err(|err| Error::Task(Source::new(err)))?
+    }
+}
+
+/// Gets the image from the [DiskCache], if cached and valid, otherwise downloads it.
+async fn load_image(
+    client: &dyn ApiClient,
+    url: &str,
+    disk_cache: Option<&DiskCache>,
+    progress: &Progress,
+) -> Result<DynamicImage> {
+    let cached = disk_cache
+        .and_then(|cache| cache.get(url))
+        .and_then(|bytes| decode_image(&bytes).ok());
+    if let Some(image) = cached {
+        return Ok(image);
+    }
+
+    let body = client.get_image(url, Some(progress)).await?;
+    let image = decode_image(&body)?;
+
+    // the image is shown even if caching fails, e.g. on a read-only file system
+    if let Some(cache) = disk_cache {
+        let _ = cache.insert(url, &body);
+    }
+
+    Ok(image)
+}
+
+/// Notifies the main loop that a background download finished.
+fn fetch_complete(notify: Option<EventSender>) {
+    if let Some(tx) = notify {
+        // main loop exited, nothing to notify
+        let _ = tx.send(Event::FetchComplete);
+    }
+}
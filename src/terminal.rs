@@ -0,0 +1,180 @@
+//! Terminal setup and teardown.
+//!
+//! The terminal is restored on normal exit, early error returns, and panics, so the shell is never
+//! left in raw mode on the alternate screen.
+
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute, terminal,
+};
+use tui::{backend::CrosstermBackend, Terminal};
+
+use crate::{app::TemiTerminal, Result};
+
+/// Restores the terminal: disables raw mode and mouse capture, leaves the alternate screen, and
+/// shows the cursor.
+pub fn restore() -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        terminal::LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    )
+}
+
+/// Installs a panic hook that restores the terminal before running the previous hook.
+///
+/// Without restoring the terminal first, the panic message is printed to the alternate screen,
+/// and immediately lost.
+pub fn install_panic_hook() {
+    install_restore_hook(|| {
+        let _ = restore();
+    });
+}
+
+/// Installs a panic hook that runs `restore` before running the previous hook.
+fn install_restore_hook<F>(restore: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+    }));
+}
+
+/// Enables raw mode, and mouse capture if `mouse_capture` is set, and enters the alternate
+/// screen, restoring the terminal on failure.
+fn enter(mouse_capture: bool) -> Result<()> {
+    terminal::enable_raw_mode()?;
+
+    let mut stdout = io::stdout();
+    let res = if mouse_capture {
+        execute!(stdout, terminal::EnterAlternateScreen, EnableMouseCapture)
+    } else {
+        execute!(stdout, terminal::EnterAlternateScreen)
+    };
+
+    if let Err(err) = res {
+        let _ = restore();
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+/// RAII guard for the [TemiTerminal], restores the terminal when dropped.
+pub struct TerminalGuard {
+    terminal: TemiTerminal,
+    mouse_capture: bool,
+}
+
+impl TerminalGuard {
+    /// Enables raw mode, and mouse capture if `mouse_capture` is set, and enters the alternate
+    /// screen.
+    ///
+    /// Without mouse capture, the terminal's native text selection keeps working.
+    pub fn new(mouse_capture: bool) -> Result<Self> {
+        enter(mouse_capture)?;
+
+        match Terminal::new(CrosstermBackend::new(io::stdout())) {
+            Ok(terminal) => Ok(Self {
+                terminal,
+                mouse_capture,
+            }),
+            Err(err) => {
+                let _ = restore();
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Restores the terminal for another program to take over, e.g. a pager, until
+    /// [TerminalGuard::resume].
+    pub fn suspend(&mut self) -> Result<()> {
+        restore()?;
+        Ok(())
+    }
+
+    /// Suspends to the shell, as Ctrl+z does outside raw mode: restores the terminal, and stops
+    /// the process group with `SIGTSTP`.
+    ///
+    /// Returns once continued, e.g. by `fg`, with the terminal left for [TerminalGuard::resume]
+    /// to set up again.
+    #[cfg(unix)]
+    pub fn stop(&mut self) -> Result<()> {
+        self.suspend()?;
+
+        // SAFETY: kill has no memory safety requirements, 0 is the process group of the caller
+        if unsafe { libc::kill(0, libc::SIGTSTP) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(())
+    }
+
+    /// Sets the terminal up again after [TerminalGuard::suspend], clearing it so the next draw is
+    /// a full redraw.
+    pub fn resume(&mut self) -> Result<()> {
+        enter(self.mouse_capture)?;
+
+        // the size may have changed meanwhile
+        self.terminal.autoresize()?;
+        self.terminal.clear()?;
+        Ok(())
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = TemiTerminal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_panic_hook() {
+        static RESTORED: AtomicBool = AtomicBool::new(false);
+        static RESTORED_FIRST: AtomicBool = AtomicBool::new(false);
+
+        // the previous hook, printing the panic message, runs once the terminal is restored
+        std::panic::set_hook(Box::new(|_| {
+            RESTORED_FIRST.store(RESTORED.load(Ordering::SeqCst), Ordering::SeqCst);
+        }));
+        install_restore_hook(|| RESTORED.store(true, Ordering::SeqCst));
+
+        let res = std::panic::catch_unwind(|| panic!("panic requested by the test"));
+        // back to the default hook
+        let _ = std::panic::take_hook();
+
+        assert!(res.is_err());
+        assert!(RESTORED.load(Ordering::SeqCst));
+        assert!(RESTORED_FIRST.load(Ordering::SeqCst));
+    }
+}
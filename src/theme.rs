@@ -0,0 +1,391 @@
+//! Colors and styles of the screens, loaded from the `[theme]` config table.
+//!
+//! A theme starts from a built-in preset, with any styles overridden by name, e.g.:
+//!
+//! ```toml
+//! [theme]
+//! name = "light"
+//! op = "#1e5aa0"
+//! highlight = { fg = "white", bg = "purple" }
+//! indent = ["blue", "green"]
+//! ```
+//!
+//! Colors are ratatui color names, or hex `#rrggbb` values.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use tui::style::{Color, Modifier, Style};
+
+use crate::screen::{CRIMSON, DARK_GRAY, GOLD, GRAY, INDENT_COLORS, PURPLE, SKY_BLUE, WHITE_SMOKE};
+
+/// Built-in [Theme] presets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    /// Light text on a black background.
+    #[default]
+    Dark,
+    /// Dark text on the terminal background, for light terminals.
+    Light,
+    /// No colors, only modifiers, used when `NO_COLOR` is set.
+    Mono,
+}
+
+/// Style override in the `[theme]` table.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum ThemeColor {
+    /// Foreground color, keeping the background of the preset.
+    Fg(String),
+    /// Foreground and background colors, either defaults to the preset.
+    Style {
+        fg: Option<String>,
+        bg: Option<String>,
+    },
+    /// Palette, only for the comment indent guides.
+    Palette(Vec<String>),
+}
+
+/// The `[theme]` config table: a preset, and style overrides by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// Preset the overrides apply to.
+    pub name: ThemePreset,
+    /// Style overrides, e.g. `op = "cyan"`.
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, ThemeColor>,
+}
+
+/// Colors and styles of the screens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Style of a whole screen.
+    pub screen: Style,
+    /// Style of headers, title blocks, and overlays.
+    pub header: Style,
+    /// Style of lists.
+    pub list: Style,
+    /// Style of the body of tables and text.
+    pub body: Style,
+    /// Style of the error banner, and error log kinds.
+    pub error: Style,
+    /// Style of the selected post or comment.
+    pub highlight: Style,
+    /// Style of inline code and code blocks.
+    pub code: Style,
+    /// Style of blockquotes.
+    pub quote: Style,
+    /// Style of the `[pinned]` post tag.
+    pub pinned: Style,
+    /// Style of the `[locked]` post tag.
+    pub locked: Style,
+    /// Style of the `[img]` and `[vid]` post tags.
+    pub media: Style,
+    /// Style of the `[text]` and `[link]` post tags.
+    pub link: Style,
+    /// Style of the original poster, and the `[OP]` comment tag.
+    pub op: Style,
+    /// Style of the `[mod]` comment tag.
+    pub moderator: Style,
+    /// Style of the `[admin]` comment tag.
+    pub admin: Style,
+    /// Style of the `[bot]` comment tag.
+    pub bot: Style,
+    /// Style of the `[NEW]` tag of comments since the last visit.
+    pub new: Style,
+    /// Style of negative scores.
+    pub score_negative: Style,
+    /// Style of comment scores of at least the high score.
+    pub score_high: Style,
+    /// Style of pending subscriptions to remote communities.
+    pub pending: Style,
+    /// Style of the `▲` marker of posts and comments upvoted by the logged-in user.
+    pub upvoted: Style,
+    /// Style of the `▼` marker of posts and comments downvoted by the logged-in user.
+    pub downvoted: Style,
+    /// Style of the `★` marker of posts and comments saved by the logged-in user.
+    pub saved: Style,
+    /// Colors of the comment indent guides, cycled by level, uncolored if empty.
+    pub indent: Vec<Color>,
+}
+
+impl Theme {
+    /// Creates the [Theme] for a [ThemePreset].
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Self::dark(),
+            ThemePreset::Light => Self::light(),
+            ThemePreset::Mono => Self::mono(),
+        }
+    }
+
+    /// Creates the default dark [Theme].
+    pub fn dark() -> Self {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+
+        Self {
+            screen: Style::default().fg(PURPLE).bg(Color::Black),
+            header: Style::default().fg(WHITE_SMOKE).bg(Color::Black),
+            list: Style::default().fg(Color::Green).bg(Color::Black),
+            body: Style::default().fg(Color::Green).bg(Color::Black),
+            error: Style::default().fg(WHITE_SMOKE).bg(Color::Red),
+            highlight: Style::default().fg(PURPLE).bg(GRAY),
+            code: Style::default().bg(DARK_GRAY),
+            quote: Style::default().fg(SKY_BLUE),
+            pinned: bold.fg(GOLD),
+            locked: bold.fg(CRIMSON),
+            media: Style::default().fg(SKY_BLUE),
+            link: Style::default().fg(GRAY),
+            op: Style::default().fg(SKY_BLUE),
+            moderator: bold.fg(GOLD),
+            admin: bold.fg(CRIMSON),
+            bot: Style::default().fg(GRAY).add_modifier(Modifier::DIM),
+            new: bold.fg(Color::LightCyan),
+            score_negative: Style::default().fg(Color::Red),
+            score_high: bold.fg(Color::LightGreen),
+            pending: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::ITALIC),
+            upvoted: bold.fg(Color::Rgb(0xff, 0x8b, 0x60)),
+            downvoted: bold.fg(Color::Rgb(0x94, 0x94, 0xff)),
+            saved: Style::default().fg(GOLD),
+            indent: INDENT_COLORS.to_vec(),
+        }
+    }
+
+    /// Creates the light [Theme], drawn on the terminal background.
+    pub fn light() -> Self {
+        const BLUE: Color = Color::Rgb(0x1e, 0x5a, 0xa0);
+        const GREEN: Color = Color::Rgb(0x00, 0x64, 0x00);
+        const GOLDENROD: Color = Color::Rgb(0xb8, 0x86, 0x0b);
+        const SLATE: Color = Color::Rgb(0x5a, 0x5a, 0x5a);
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+
+        Self {
+            screen: Style::default().fg(PURPLE).bg(Color::Reset),
+            header: Style::default().fg(Color::Black).bg(Color::Reset),
+            list: Style::default().fg(GREEN).bg(Color::Reset),
+            body: Style::default().fg(GREEN).bg(Color::Reset),
+            error: Style::default().fg(Color::White).bg(CRIMSON),
+            highlight: Style::default().fg(Color::White).bg(PURPLE),
+            code: Style::default().bg(Color::Rgb(0xe4, 0xe4, 0xe4)),
+            quote: Style::default().fg(BLUE),
+            pinned: bold.fg(GOLDENROD),
+            locked: bold.fg(CRIMSON),
+            media: Style::default().fg(BLUE),
+            link: Style::default().fg(SLATE),
+            op: Style::default().fg(BLUE),
+            moderator: bold.fg(GOLDENROD),
+            admin: bold.fg(CRIMSON),
+            bot: Style::default().fg(SLATE).add_modifier(Modifier::DIM),
+            new: bold.fg(Color::Rgb(0x00, 0x80, 0x80)),
+            score_negative: Style::default().fg(Color::Red),
+            score_high: bold.fg(GREEN),
+            pending: Style::default()
+                .fg(GOLDENROD)
+                .add_modifier(Modifier::ITALIC),
+            upvoted: bold.fg(Color::Rgb(0xd9, 0x48, 0x00)),
+            downvoted: bold.fg(BLUE),
+            saved: Style::default().fg(GOLDENROD),
+            indent: vec![
+                BLUE,
+                GREEN,
+                GOLDENROD,
+                Color::Rgb(0x8b, 0x00, 0x8b),
+                CRIMSON,
+                Color::Rgb(0x00, 0x80, 0x80),
+            ],
+        }
+    }
+
+    /// Creates the monochrome [Theme], distinguishing styles by modifiers only.
+    pub fn mono() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let dim = plain.add_modifier(Modifier::DIM);
+        let italic = plain.add_modifier(Modifier::ITALIC);
+
+        Self {
+            screen: plain,
+            header: plain,
+            list: plain,
+            body: plain,
+            error: bold.add_modifier(Modifier::REVERSED),
+            highlight: plain.add_modifier(Modifier::REVERSED),
+            code: dim,
+            quote: italic,
+            pinned: bold,
+            locked: bold,
+            media: dim,
+            link: dim,
+            op: plain.add_modifier(Modifier::UNDERLINED),
+            moderator: bold,
+            admin: bold,
+            bot: dim,
+            new: bold,
+            score_negative: dim,
+            score_high: bold,
+            pending: italic,
+            upvoted: bold,
+            downvoted: dim,
+            saved: bold,
+            indent: Vec::new(),
+        }
+    }
+
+    /// Creates the [Theme] for the `[theme]` config table.
+    pub fn from_config(config: &ThemeConfig) -> Result<Self, String> {
+        let mut theme = Self::preset(config.name);
+
+        for (name, color) in config.colors.iter() {
+            theme.apply(name, color)?;
+        }
+
+        Ok(theme)
+    }
+
+    /// Overrides the style, or indent palette, with the `name`.
+    pub fn apply(&mut self, name: &str, color: &ThemeColor) -> Result<(), String> {
+        let parse = |color: &str| {
+            Color::from_str(color).map_err(|_| format!("invalid color for {name}: {color}"))
+        };
+
+        if name == "indent" {
+            return match color {
+                ThemeColor::Palette(colors) => {
+                    self.indent = colors.iter().map(|c| parse(c)).collect::<Result<_, _>>()?;
+                    Ok(())
+                }
+                _ => Err("theme indent must be a list of colors".into()),
+            };
+        }
+
+        let style = self
+            .style_mut(name)
+            .ok_or_else(|| format!("unknown theme style: {name}"))?;
+
+        match color {
+            ThemeColor::Fg(fg) => *style = style.fg(parse(fg)?),
+            ThemeColor::Style { fg, bg } => {
+                if let Some(fg) = fg {
+                    *style = style.fg(parse(fg)?);
+                }
+                if let Some(bg) = bg {
+                    *style = style.bg(parse(bg)?);
+                }
+            }
+            ThemeColor::Palette(_) => return Err(format!("theme {name} must be a single color")),
+        }
+
+        Ok(())
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        let style = match name {
+            "screen" => &mut self.screen,
+            "header" => &mut self.header,
+            "list" => &mut self.list,
+            "body" => &mut self.body,
+            "error" => &mut self.error,
+            "highlight" => &mut self.highlight,
+            "code" => &mut self.code,
+            "quote" => &mut self.quote,
+            "pinned" => &mut self.pinned,
+            "locked" => &mut self.locked,
+            "media" => &mut self.media,
+            "link" => &mut self.link,
+            "op" => &mut self.op,
+            "moderator" => &mut self.moderator,
+            "admin" => &mut self.admin,
+            "bot" => &mut self.bot,
+            "new" => &mut self.new,
+            "score_negative" => &mut self.score_negative,
+            "score_high" => &mut self.score_high,
+            "pending" => &mut self.pending,
+            "upvoted" => &mut self.upvoted,
+            "downvoted" => &mut self.downvoted,
+            "saved" => &mut self.saved,
+            _ => return None,
+        };
+
+        Some(style)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_config() {
+        let config: ThemeConfig = toml::from_str(
+            r##"
+name = "light"
+op = "#ff0000"
+highlight = { bg = "blue" }
+indent = ["red", "green"]
+"##,
+        )
+        .unwrap();
+        assert_eq!(config.name, ThemePreset::Light);
+
+        let theme = Theme::from_config(&config).unwrap();
+        let light = Theme::light();
+        assert_eq!(theme.op.fg, Some(Color::Rgb(0xff, 0, 0)));
+        assert_eq!(theme.highlight.fg, light.highlight.fg);
+        assert_eq!(theme.highlight.bg, Some(Color::Blue));
+        assert_eq!(theme.indent, [Color::Red, Color::Green]);
+        assert_eq!(theme.body, light.body);
+
+        assert_eq!(
+            Theme::from_config(&ThemeConfig::default()),
+            Ok(Theme::dark())
+        );
+
+        let invalid = |toml: &str| {
+            let config: ThemeConfig = toml::from_str(toml).unwrap();
+            Theme::from_config(&config).unwrap_err()
+        };
+        assert_eq!(invalid("op = \"nope\""), "invalid color for op: nope");
+        assert_eq!(invalid("sidebar = \"red\""), "unknown theme style: sidebar");
+        assert_eq!(
+            invalid("indent = \"red\""),
+            "theme indent must be a list of colors"
+        );
+        assert_eq!(invalid("op = [\"red\"]"), "theme op must be a single color");
+        assert!(toml::from_str::<ThemeConfig>("name = \"solarized\"").is_err());
+    }
+
+    #[test]
+    fn test_mono_theme() {
+        let theme = Theme::mono();
+        let styles = [
+            theme.screen,
+            theme.header,
+            theme.body,
+            theme.error,
+            theme.highlight,
+            theme.code,
+            theme.quote,
+            theme.op,
+            theme.score_negative,
+            theme.pending,
+            theme.upvoted,
+            theme.downvoted,
+        ];
+
+        // modifiers only
+        assert!(styles.iter().all(|s| s.fg.is_none() && s.bg.is_none()));
+        assert!(theme.indent.is_empty());
+        assert_ne!(theme.highlight, theme.body);
+    }
+}
@@ -1,7 +1,11 @@
 //! Library utilities.
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use crate::Result;
 
+pub mod debug_writer;
+
 /// Convenience function for writing bytes to a file.
 ///
 /// Mostly helpful for debugging API endpoint responses.
@@ -13,3 +17,750 @@ pub fn write_to_file(file_name: &str, bytes: &[u8]) -> Result<()> {
 
     Ok(())
 }
+
+/// Opens a web URL in the system browser, with `xdg-open`, `open`, or the Windows URL handler
+/// depending on the platform.
+///
+/// The opener runs detached, with its output discarded so it cannot draw over the TUI.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    use std::process::Command;
+
+    check_web_url(url)?;
+
+    let args = browser_command(std::env::consts::OS, url);
+    let mut cmd = Command::new(&args[0]);
+    cmd.args(&args[1..]);
+    spawn_detached(cmd)
+}
+
+/// Gets the program, and its arguments, opening the URL in the system browser on the `os`, as in
+/// [std::env::consts::OS].
+///
+/// The URL is always a single argument, never read by a shell, e.g. `cmd.exe` would run the
+/// commands after an `&` in the URL.
+fn browser_command(os: &str, url: &str) -> Vec<String> {
+    let command: &[&str] = match os {
+        "macos" => &["open"],
+        "windows" => &["rundll32", "url.dll,FileProtocolHandler"],
+        _ => &["xdg-open"],
+    };
+
+    command
+        .iter()
+        .map(|&arg| arg.to_owned())
+        .chain([url.to_owned()])
+        .collect()
+}
+
+/// Splits a viewer command line, e.g. `mpv --fs %u`, into the program and its arguments, with
+/// `%u` replaced by the URL.
+///
+/// The URL is appended if the command has no `%u`. Arguments are split on whitespace, without
+/// shell quoting, so the URL is always passed as is.
+pub fn viewer_command(command: &str, url: &str) -> Vec<String> {
+    let mut args: Vec<String> = command
+        .split_whitespace()
+        .map(|arg| arg.replace("%u", url))
+        .collect();
+
+    if !command.contains("%u") {
+        args.push(url.to_owned());
+    }
+
+    args
+}
+
+/// Runs a configured viewer command for a web URL, see [viewer_command].
+///
+/// The viewer runs detached, in its own process group, with its output discarded so it cannot
+/// draw over the TUI.
+pub fn spawn_viewer(command: &str, url: &str) -> Result<()> {
+    use std::io::{Error, ErrorKind};
+    use std::process::Command;
+
+    check_web_url(url)?;
+
+    let args = viewer_command(command, url);
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty viewer command"))?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    // keeps playing after temi exits, and out of reach of terminal signals
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+    spawn_detached(cmd)
+}
+
+/// Returns an error unless the URL is an `http` or `https` URL.
+fn check_web_url(url: &str) -> Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    // links come from untrusted content, keep openers away from local files and apps
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::InvalidInput, format!("not a web URL: {url}")).into())
+    }
+}
+
+/// Spawns the command without a terminal, and reaps it once it exits, without blocking the UI.
+fn spawn_detached(mut cmd: std::process::Command) -> Result<()> {
+    use std::process::Stdio;
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Extracts the web links from markdown text, in order, without duplicates.
+///
+/// Finds markdown links, `<...>` autolinks, and bare URLs. Trailing punctuation, and unbalanced
+/// closing parentheses, e.g. from a markdown link or a sentence, are not part of the URL.
+///
+/// Extract from the raw text, since wrapping can split a URL across lines.
+pub fn extract_links(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = find_url_start(rest) {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || "<>\"'`[]".contains(c))
+            .unwrap_or(candidate.len());
+        rest = &candidate[end..];
+
+        let mut url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', '*']);
+        while url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+            url = url[..url.len() - 1].trim_end_matches(['.', ',', ';', ':', '!', '?', '*']);
+        }
+
+        let has_host = url
+            .split_once("://")
+            .is_some_and(|(_, host)| !host.is_empty());
+        if has_host && !links.iter().any(|l| l == url) {
+            links.push(url.to_owned());
+        }
+    }
+
+    links
+}
+
+/// Finds the start of the next `http://` or `https://` URL.
+fn find_url_start(text: &str) -> Option<usize> {
+    match (text.find("http://"), text.find("https://")) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Maximum number of bytes copied to the clipboard, terminals drop larger OSC 52 sequences.
+pub const CLIPBOARD_MAX_BYTES: usize = 75_000;
+
+/// Copies text to the clipboard with the OSC 52 escape sequence, which works over SSH.
+///
+/// Outside SSH sessions, the text is also piped to `wl-copy`, `xclip`, or `pbcopy` if available,
+/// for terminals that ignore OSC 52. Text longer than [CLIPBOARD_MAX_BYTES] is truncated.
+///
+/// Returns whether the text was truncated.
+pub fn copy_to_clipboard(text: &str) -> Result<bool> {
+    use std::io::Write;
+
+    let mut len = text.len().min(CLIPBOARD_MAX_BYTES);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    let truncated = len < text.len();
+    let text = &text[..len];
+
+    // the sequence prints nothing, so it can go straight to the terminal between frames
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(osc52(text, std::env::var_os("TMUX").is_some()).as_bytes())?;
+    stdout.flush()?;
+
+    if std::env::var_os("SSH_CONNECTION").is_none() {
+        copy_with_tool(text)?;
+    }
+
+    Ok(truncated)
+}
+
+/// Gets the OSC 52 sequence setting the clipboard to the text, wrapped for tmux to pass through.
+fn osc52(text: &str, tmux: bool) -> String {
+    let osc = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+
+    if tmux {
+        format!("\x1bPtmux;\x1b{osc}\x1b\\")
+    } else {
+        osc
+    }
+}
+
+/// Encodes the bytes as padded base64.
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Pipes the text to the first local clipboard tool found, if any.
+fn copy_with_tool(text: &str) -> Result<()> {
+    use std::io::{ErrorKind, Write};
+    use std::process::{Command, Stdio};
+
+    let tools: [(&str, &[&str], bool); 3] = [
+        (
+            "wl-copy",
+            &[],
+            std::env::var_os("WAYLAND_DISPLAY").is_some(),
+        ),
+        (
+            "xclip",
+            &["-selection", "clipboard"],
+            std::env::var_os("DISPLAY").is_some(),
+        ),
+        ("pbcopy", &[], cfg!(target_os = "macos")),
+    ];
+
+    for (tool, args, _) in tools.iter().filter(|(_, _, available)| *available) {
+        let spawned = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        match spawned {
+            Ok(mut child) => {
+                // closing stdin ends the input
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(text.as_bytes())?;
+                }
+                std::thread::spawn(move || child.wait());
+
+                return Ok(());
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a Lemmy timestamp, e.g. `2024-02-08T15:20:11.001482Z`, into a [SystemTime].
+///
+/// Lemmy 0.18 omits the UTC offset, timestamps without one are treated as UTC.
+pub fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    let (date, time) = timestamp.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(|n| n.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    // drop the fraction, and any offset
+    let time = time.trim_end_matches('Z');
+    let (time, offset) = match time.find(['+', '-']) {
+        Some(i) => (&time[..i], parse_offset(&time[i..])?),
+        None => (time, 0),
+    };
+    let time = time.split('.').next()?;
+
+    let mut time = time.splitn(3, ':').map(|n| n.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+
+    UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// Parses a UTC offset, e.g. `+02:00`, into seconds.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let (hours, minutes) = offset[1..].split_once(':').unwrap_or((&offset[1..], "0"));
+
+    Some(sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60))
+}
+
+/// Gets the number of days since the Unix epoch for a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Formats the time elapsed from `time` to `now`, e.g. `3h ago`.
+///
+/// Times in the future, e.g. from clock skew, are `just now`.
+pub fn relative_time(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).map_or(0, |d| d.as_secs());
+
+    let (minute, hour, day) = (60, 60 * 60, 24 * 60 * 60);
+
+    match secs {
+        s if s < minute => "just now".into(),
+        s if s < hour => format!("{}m ago", s / minute),
+        s if s < day => format!("{}h ago", s / hour),
+        s if s < 30 * day => format!("{}d ago", s / day),
+        s if s < 365 * day => format!("{}mo ago", s / (30 * day)),
+        s => format!("{}y ago", s / (365 * day)),
+    }
+}
+
+/// Formats a count with thousands separators, e.g. `4,231`.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+
+    formatted
+}
+
+/// Formats a number of bytes in binary units, e.g. `1.5 KiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Display format of timestamps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// Time elapsed, e.g. `3h ago`.
+    #[default]
+    Relative,
+    /// Local date and time, e.g. `2024-02-08 15:20`.
+    Absolute,
+}
+
+/// Formats a Lemmy timestamp in the [TimeFormat], as of `now` for relative times.
+///
+/// Absolute times are in the local timezone. Timestamps that fail to parse are returned as-is.
+pub fn format_timestamp(timestamp: &str, format: TimeFormat, now: SystemTime) -> String {
+    let Some(time) = parse_timestamp(timestamp) else {
+        return timestamp.to_owned();
+    };
+
+    match format {
+        TimeFormat::Relative => relative_time(time, now),
+        TimeFormat::Absolute => chrono::DateTime::<chrono::Local>::from(time)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+    }
+}
+
+/// Gets the host, with any port, of an ActivityPub actor ID, e.g. `lemmy.ml` for
+/// `https://lemmy.ml/u/ferris`.
+///
+/// The scheme is optional, and user info is dropped.
+pub fn actor_host(actor_id: &str) -> Option<&str> {
+    let actor_id = actor_id.trim();
+    let rest = actor_id
+        .split_once("://")
+        .map_or(actor_id, |(_, rest)| rest);
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+
+    (!host.is_empty()).then_some(host)
+}
+
+/// Gets the domain of a URL, without the port, e.g. `blog.rust-lang.org` for
+/// `https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html`.
+///
+/// Bracketed IPv6 hosts are kept as is, e.g. `[::1]`.
+pub fn url_domain(url: &str) -> Option<&str> {
+    let host = actor_host(url)?;
+    let domain = match host.rsplit_once(':') {
+        Some((domain, port))
+            if (!domain.contains(':') || domain.ends_with(']'))
+                && port.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            domain
+        }
+        _ => host,
+    };
+
+    (!domain.is_empty()).then_some(domain)
+}
+
+/// Normalizes a URL for comparing links, e.g. reposts of the same article: the scheme and host
+/// are lowercased, `utm_*` tracking parameters dropped, and trailing slashes stripped from the
+/// path.
+///
+/// URLs without a scheme, or a host, have no normalized form.
+pub fn normalize_url(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if scheme.is_empty() || host.is_empty() {
+        return None;
+    }
+
+    let mut normalized = format!(
+        "{}://{}{}",
+        scheme.to_ascii_lowercase(),
+        host.to_ascii_lowercase(),
+        path.trim_end_matches('/')
+    );
+
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            !param.is_empty()
+                && !param
+                    .get(..4)
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case("utm_"))
+        })
+        .collect();
+    if !params.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links() {
+        let text =
+            "See [the release notes](https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html), \
+            <https://lemmy.ml/post/1>, and https://example.com/a?b=c&d=e.\n\
+            ![image](https://lemmy.ml/pictrs/image/x.png \"title\") \
+            (see https://en.wikipedia.org/wiki/Rust_(programming_language)) \
+            **https://example.org/bold**! ftp://example.com https:// \
+            duplicate: https://lemmy.ml/post/1";
+
+        assert_eq!(
+            extract_links(text),
+            [
+                "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html",
+                "https://lemmy.ml/post/1",
+                "https://example.com/a?b=c&d=e",
+                "https://lemmy.ml/pictrs/image/x.png",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "https://example.org/bold",
+            ]
+        );
+
+        // the link text is not a URL, even if it looks like one
+        assert_eq!(
+            extract_links("[http://a.example](http://b.example/path)"),
+            ["http://a.example", "http://b.example/path"]
+        );
+        assert!(extract_links("no links here").is_empty());
+    }
+
+    #[test]
+    fn test_extract_links_wrapped() {
+        let url = "https://lemmy.ml/pictrs/image/0123456789abcdef0123456789abcdef.png";
+        let text = format!("A long link, {url}, wrapped across lines.");
+
+        // wrapping splits the URL, the raw text keeps it whole
+        let lines = crate::screen::split_width(&text, 30);
+        assert!(lines.iter().all(|l| !l.contains(url)));
+        assert_eq!(extract_links(&text), [url]);
+    }
+
+    #[test]
+    fn test_osc52() {
+        // RFC 4648 test vectors
+        let encoded =
+            ["", "f", "fo", "foo", "foob", "fooba", "foobar"].map(|s| base64(s.as_bytes()));
+        assert_eq!(
+            encoded,
+            ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"]
+        );
+        assert_eq!(base64("🦀".as_bytes()), "8J+mgA==");
+
+        assert_eq!(osc52("foo", false), "\x1b]52;c;Zm9v\x07");
+        assert_eq!(osc52("foo", true), "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\");
+    }
+
+    #[test]
+    fn test_open_in_browser_rejects_non_web_urls() {
+        for url in [
+            "",
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "lemmy.ml/post/1",
+        ] {
+            assert!(open_in_browser(url).is_err(), "{url}");
+            assert!(spawn_viewer("mpv %u", url).is_err(), "{url}");
+        }
+
+        assert!(spawn_viewer("  ", "https://example.com/clip.mp4").is_err());
+        assert!(spawn_viewer("temi-no-such-viewer", "https://example.com/clip.mp4").is_err());
+    }
+
+    #[test]
+    fn test_browser_command() {
+        // a query with `&` is passed as is, without a shell running what follows it
+        let url = "https://example.com/?a=1&calc.exe";
+
+        assert_eq!(
+            browser_command("windows", url),
+            ["rundll32", "url.dll,FileProtocolHandler", url]
+        );
+        assert_eq!(browser_command("macos", url), ["open", url]);
+        assert_eq!(browser_command("linux", url), ["xdg-open", url]);
+    }
+
+    #[test]
+    fn test_viewer_command() {
+        let url = "https://example.com/a b.png";
+
+        assert_eq!(
+            viewer_command("mpv --fs %u", url),
+            ["mpv", "--fs", "https://example.com/a b.png"]
+        );
+        assert_eq!(
+            viewer_command("swayimg", url),
+            ["swayimg", "https://example.com/a b.png"]
+        );
+        assert_eq!(
+            viewer_command("viewer --title=%u  %u", url),
+            [
+                "viewer",
+                "--title=https://example.com/a b.png",
+                "https://example.com/a b.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let secs = |timestamp| {
+            parse_timestamp(timestamp).map(|t| t.duration_since(UNIX_EPOCH).unwrap().as_secs())
+        };
+
+        assert_eq!(secs("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(secs("2024-02-08T15:20:11.001482Z"), Some(1_707_405_611));
+        // Lemmy 0.18, without an offset
+        assert_eq!(secs("2024-02-08T15:20:11.001482"), Some(1_707_405_611));
+        assert_eq!(secs("2024-02-08T17:20:11+02:00"), Some(1_707_405_611));
+        assert_eq!(secs("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+
+        assert_eq!(secs(""), None);
+        assert_eq!(secs("2024-02-08"), None);
+        assert_eq!(secs("2024-13-08T00:00:00Z"), None);
+        assert_eq!(secs("yesterday"), None);
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now = UNIX_EPOCH + Duration::from_secs(400 * 24 * 3600);
+        let ago = |secs| relative_time(now - Duration::from_secs(secs), now);
+
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(59), "just now");
+        assert_eq!(ago(60), "1m ago");
+        assert_eq!(ago(59 * 60 + 59), "59m ago");
+        assert_eq!(ago(3600), "1h ago");
+        assert_eq!(ago(23 * 3600 + 3599), "23h ago");
+        assert_eq!(ago(24 * 3600), "1d ago");
+        assert_eq!(ago(29 * 24 * 3600), "29d ago");
+        assert_eq!(ago(30 * 24 * 3600), "1mo ago");
+        assert_eq!(ago(364 * 24 * 3600), "12mo ago");
+        assert_eq!(ago(365 * 24 * 3600), "1y ago");
+
+        assert_eq!(
+            relative_time(now + Duration::from_secs(60), now),
+            "just now"
+        );
+    }
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(4231), "4,231");
+        assert_eq!(format_count(1_000_000), "1,000,000");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(64 * 1024 * 1024), "64.0 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_707_500_000);
+        let relative = |timestamp| format_timestamp(timestamp, TimeFormat::Relative, now);
+
+        // Lemmy 0.18 timestamps have no offset, Lemmy 0.19 timestamps do
+        assert_eq!(relative("2024-02-08T15:20:11.001482"), "1d ago");
+        assert_eq!(relative("2024-02-08T15:20:11.001482Z"), "1d ago");
+        assert_eq!(relative("2024-02-09T17:20:11+02:00"), "2h ago");
+        assert_eq!(relative("2024-02-09T17:20:11Z"), "13m ago");
+
+        assert_eq!(relative("yesterday"), "yesterday");
+        assert_eq!(relative(""), "");
+
+        let absolute = format_timestamp("2024-02-08T15:20:11Z", TimeFormat::Absolute, now);
+        let local = chrono::NaiveDateTime::parse_from_str(&absolute, "%Y-%m-%d %H:%M").unwrap();
+        let utc = chrono::NaiveDate::from_ymd_opt(2024, 2, 8)
+            .and_then(|d| d.and_hms_opt(15, 20, 0))
+            .unwrap();
+        // local timezones are within a day of UTC
+        assert!((local - utc).num_hours().abs() <= 14);
+    }
+
+    #[test]
+    fn test_actor_host() {
+        assert_eq!(actor_host("https://lemmy.ml/u/ferris"), Some("lemmy.ml"));
+        assert_eq!(
+            actor_host("http://localhost:8536/c/rust"),
+            Some("localhost:8536")
+        );
+        assert_eq!(actor_host("lemmy.world/u/admin"), Some("lemmy.world"));
+        assert_eq!(
+            actor_host("https://sh.itjust.works"),
+            Some("sh.itjust.works")
+        );
+        assert_eq!(
+            actor_host("https://user@example.com/users/admin?x=1"),
+            Some("example.com")
+        );
+        assert_eq!(actor_host(" https://lemmy.ml "), Some("lemmy.ml"));
+
+        assert_eq!(actor_host(""), None);
+        assert_eq!(actor_host("https://"), None);
+        assert_eq!(actor_host("/u/ferris"), None);
+    }
+
+    #[test]
+    fn test_url_domain() {
+        assert_eq!(
+            url_domain("https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html"),
+            Some("blog.rust-lang.org")
+        );
+        assert_eq!(
+            url_domain("https://a.b.example.co.uk/x"),
+            Some("a.b.example.co.uk")
+        );
+        assert_eq!(
+            url_domain("https://www.example.com"),
+            Some("www.example.com")
+        );
+
+        // ports are dropped
+        assert_eq!(
+            url_domain("http://localhost:8536/post/1"),
+            Some("localhost")
+        );
+        assert_eq!(url_domain("https://example.com:/x"), Some("example.com"));
+        assert_eq!(url_domain("http://[::1]:8080/x"), Some("[::1]"));
+        assert_eq!(url_domain("http://[::1]/x"), Some("[::1]"));
+
+        assert_eq!(url_domain(""), None);
+        assert_eq!(url_domain("https://:8080/x"), None);
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        let article = "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html";
+
+        for url in [
+            article,
+            "HTTPS://Blog.Rust-Lang.org/2024/02/08/Rust-1.76.0.html",
+            "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html/",
+            "https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html?utm_source=lemmy",
+            " https://blog.rust-lang.org/2024/02/08/Rust-1.76.0.html?UTM_Medium=a&utm_campaign= ",
+        ] {
+            assert_eq!(normalize_url(url).as_deref(), Some(article), "{url}");
+        }
+
+        // paths are case-sensitive, other parameters are kept
+        assert_eq!(
+            normalize_url("https://example.com/A/?id=1&utm_source=x&b=2").as_deref(),
+            Some("https://example.com/A?id=1&b=2")
+        );
+        assert_eq!(
+            normalize_url("https://example.com/#intro").as_deref(),
+            Some("https://example.com#intro")
+        );
+        assert_eq!(
+            normalize_url("https://example.com//").as_deref(),
+            Some("https://example.com")
+        );
+        assert_ne!(
+            normalize_url("https://example.com/a"),
+            normalize_url("https://example.com/b")
+        );
+
+        assert_eq!(normalize_url(""), None);
+        assert_eq!(normalize_url("example.com/a"), None);
+        assert_eq!(normalize_url("https:///a"), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Writer for API responses captured with the `debug_endpoints` feature.
+//!
+//! Every response is written to a unique file under the debug directory, e.g.
+//! `temi-debug/comment_list.post_id=123.page=2.20240105T120301.json`, and listed in an
+//! `index.json` manifest, so multi-page captures are kept.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Result, Source};
+
+/// Default directory for captured responses, relative to the working directory.
+pub const DEFAULT_DEBUG_DIR: &str = "temi-debug";
+/// Environment variable to override the [DEFAULT_DEBUG_DIR].
+pub const DEBUG_DIR_ENV: &str = "TEMI_DEBUG_DIR";
+/// File name of the manifest listing the captured responses.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Prefix stripped from endpoint paths for file names.
+const API_PREFIX: &str = "/api/v3/";
+
+static DEBUG_WRITER: OnceLock<DebugWriter> = OnceLock::new();
+
+/// Represents an entry in the [INDEX_FILE] manifest.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DebugEntry {
+    /// File name, relative to the debug directory.
+    pub file: String,
+    /// HTTP method, e.g. `GET`.
+    pub method: String,
+    /// Request URL, without authentication parameters.
+    pub url: String,
+    /// Capture time, e.g. `20240105T120301`.
+    pub time: String,
+}
+
+/// Writes captured API responses to unique files in a debug directory.
+#[derive(Debug)]
+pub struct DebugWriter {
+    dir: PathBuf,
+    entries: Mutex<Vec<DebugEntry>>,
+}
+
+impl DebugWriter {
+    /// Creates a new [DebugWriter] for the directory.
+    ///
+    /// Entries from an existing [INDEX_FILE] are kept, so captures from multiple runs are listed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        let dir = dir.into();
+        let entries = std::fs::read(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|index| serde_json::from_slice(&index).ok())
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Creates a new [DebugWriter] for the [DEBUG_DIR_ENV] directory, or the
+    /// [DEFAULT_DEBUG_DIR].
+    pub fn from_env() -> Self {
+        let dir = std::env::var_os(DEBUG_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DEBUG_DIR));
+
+        Self::new(dir)
+    }
+
+    /// Gets the global [DebugWriter], created from the environment on first use.
+    pub fn global() -> &'static Self {
+        DEBUG_WRITER.get_or_init(Self::from_env)
+    }
+
+    /// Gets the debug directory.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    /// Gets the entries in the [INDEX_FILE] manifest.
+    pub fn entries(&self) -> Vec<DebugEntry> {
+        self.entries.lock().map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Writes a response body for the request URL, and updates the [INDEX_FILE] manifest.
+    ///
+    /// Returns the path of the written file.
+    pub fn write(&self, method: &str, url: &str, body: &[u8]) -> Result<PathBuf> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|err| Error::Io(Source::msg(err.to_string())))?;
+
+        std::fs::create_dir_all(self.dir.as_path())?;
+
+        let time = timestamp(SystemTime::now());
+        let stem = file_stem(url, time.as_str());
+
+        // same request in the same second, e.g. a refresh, gets a numbered suffix
+        let mut file = format!("{stem}.json");
+        let mut n = 1;
+        while self.dir.join(file.as_str()).exists() {
+            file = format!("{stem}.{n}.json");
+            n += 1;
+        }
+
+        let path = self.dir.join(file.as_str());
+        std::fs::write(path.as_path(), body)?;
+
+        entries.push(DebugEntry {
+            file,
+            method: method.into(),
+            url: strip_auth(url),
+            time,
+        });
+
+        std::fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_vec_pretty(&*entries)?,
+        )?;
+
+        Ok(path)
+    }
+}
+
+/// Gets the file name, without extension, for a request URL captured at `time`.
+///
+/// The endpoint and query parameters are joined with `.`, e.g.
+/// `comment_list.post_id=123.page=2.20240105T120301`.
+pub fn file_stem(url: &str, time: &str) -> String {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let path = path
+        .split_once("://")
+        .map_or(path, |(_, rest)| rest.find('/').map_or("", |i| &rest[i..]));
+    let endpoint = path
+        .strip_prefix(API_PREFIX)
+        .unwrap_or(path)
+        .trim_matches('/');
+
+    let mut parts = vec![if endpoint.is_empty() {
+        "root".to_owned()
+    } else {
+        sanitize(endpoint)
+    }];
+
+    parts.extend(
+        query
+            .split('&')
+            .filter(|p| !p.is_empty() && !is_auth(p))
+            .map(sanitize),
+    );
+    parts.push(time.to_owned());
+
+    parts.join(".")
+}
+
+/// Formats a [SystemTime] as a compact UTC timestamp, e.g. `20240105T120301`.
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` date.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+fn is_auth(param: &str) -> bool {
+    param.starts_with("auth=")
+}
+
+fn strip_auth(url: &str) -> String {
+    match url.split_once('?') {
+        Some((path, query)) => {
+            let query: Vec<&str> = query.split('&').filter(|p| !is_auth(p)).collect();
+
+            if query.is_empty() {
+                path.to_owned()
+            } else {
+                format!("{path}?{}", query.join("&"))
+            }
+        }
+        None => url.to_owned(),
+    }
+}
+
+/// Replaces characters that are unsafe in file names.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '=' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(
+            file_stem(
+                "https://lemmy.ml/api/v3/comment/list?post_id=123&page=2&auth=secret",
+                "20240105T120301"
+            ),
+            "comment_list.post_id=123.page=2.20240105T120301"
+        );
+        assert_eq!(
+            file_stem("http://127.0.0.1:8536/api/v3/site", "20240105T120301"),
+            "site.20240105T120301"
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_704_456_181);
+
+        assert_eq!(timestamp(time), "20240105T120301");
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101T000000");
+    }
+
+    #[test]
+    fn test_write_unique() {
+        let dir = std::env::temp_dir().join(format!("temi-debug-{}", std::process::id()));
+        let url = "https://lemmy.ml/api/v3/post/list?page=1&auth=secret";
+
+        let writer = DebugWriter::new(dir.as_path());
+        let first = writer.write("GET", url, b"{}").unwrap();
+        let second = writer.write("GET", url, b"{}").unwrap();
+
+        assert_ne!(first, second);
+
+        // manifest is reloaded by new writers
+        let entries = DebugWriter::new(dir.as_path()).entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries
+            .iter()
+            .all(|e| e.url == "https://lemmy.ml/api/v3/post/list?page=1"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}